@@ -1962,7 +1962,8 @@ impl Chain {
         }
 
         metrics::BLOCK_PROCESSED_TOTAL.inc();
-        metrics::BLOCK_PROCESSING_TIME.observe(
+        near_o11y::openmetrics::observe_with_exemplar(
+            &metrics::BLOCK_PROCESSING_TIME,
             (self.clock.now().signed_duration_since(block_start_processing_time))
                 .as_seconds_f64()
                 .max(0.0),
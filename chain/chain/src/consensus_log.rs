@@ -0,0 +1,403 @@
+//! Journal of consensus decisions for post-mortems.
+//!
+//! Block production, approval handling, chunk inclusion and state witness
+//! processing record typed [`ConsensusEvent`]s with [`record`]. A background
+//! thread appends them as JSON lines to rotating files in the directory
+//! configured by the `consensus_log` section of `config.json`, so recording
+//! never blocks consensus on disk IO. The files are synced to disk whenever
+//! the writer catches up with the recorded events, rather than per event. If
+//! the writer falls behind, events are dropped and counted in
+//! `near_consensus_log_dropped_events_total`.
+//!
+//! The journal is disabled by default.
+//!
+//! `neard consensus-log` reads the journal back with [`read_journal`] and
+//! prints a timeline for a range of heights.
+
+use crate::metrics;
+use bytesize::ByteSize;
+use near_primitives::block::ApprovalInner;
+use near_primitives::hash::CryptoHash;
+use near_primitives::network::PeerId;
+use near_primitives::sharding::ChunkHash;
+use near_primitives::types::{AccountId, BlockHeight, ShardId};
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::sync::mpsc::{self, SyncSender};
+
+const FILE_NAME: &str = "consensus.log";
+
+/// Number of events buffered for the writer thread.
+const QUEUE_SIZE: usize = 16 * 1024;
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct ConsensusLogConfig {
+    /// Whether to record the journal. Disabled by default.
+    pub enabled: bool,
+    /// Directory of the journal. Relative paths are relative to the home
+    /// directory.
+    pub dir: PathBuf,
+    /// Size at which the current file is rotated.
+    pub max_file_size: ByteSize,
+    /// Number of rotated files kept next to the current one.
+    pub max_files: usize,
+}
+
+impl Default for ConsensusLogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            dir: PathBuf::from("consensus_log"),
+            max_file_size: ByteSize::mib(64),
+            max_files: 8,
+        }
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalKind {
+    Endorsement,
+    Skip,
+}
+
+impl From<&ApprovalInner> for ApprovalKind {
+    fn from(inner: &ApprovalInner) -> Self {
+        match inner {
+            ApprovalInner::Endorsement(_) => ApprovalKind::Endorsement,
+            ApprovalInner::Skip(_) => ApprovalKind::Skip,
+        }
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum BlockSkipReason {
+    /// This node was the block producer and approvals for the height never
+    /// reached the threshold.
+    NotEnoughApprovals { approvals: usize },
+    /// This node was the block producer and had enough approvals, but the chain
+    /// moved on without its block.
+    NotIncluded,
+    /// Another validator was the block producer and no block for the height
+    /// became part of the chain.
+    NotReceived,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChunkMissReason {
+    /// The chunk wasn't received, or not all of its parts were.
+    NotReceived,
+    /// Chunk validators didn't endorse enough stake for the chunk.
+    InsufficientEndorsement,
+    /// The chunk producer was banned for producing an invalid chunk.
+    BannedProducer,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ConsensusEvent {
+    ApprovalSent {
+        target_height: BlockHeight,
+        kind: ApprovalKind,
+        block_producer: AccountId,
+    },
+    ApprovalReceived {
+        target_height: BlockHeight,
+        kind: ApprovalKind,
+        account_id: AccountId,
+        peer_id: PeerId,
+    },
+    BlockProduced {
+        height: BlockHeight,
+        hash: CryptoHash,
+        approvals: usize,
+        chunks: usize,
+    },
+    BlockSkipped {
+        height: BlockHeight,
+        block_producer: AccountId,
+        reason: BlockSkipReason,
+    },
+    /// Chunk included in a block produced by this node at `height`.
+    ChunkIncluded {
+        height: BlockHeight,
+        shard_id: ShardId,
+        chunk_hash: ChunkHash,
+        chunk_producer: AccountId,
+    },
+    /// No new chunk for the shard in a block produced by this node at `height`.
+    ChunkMissed {
+        height: BlockHeight,
+        shard_id: ShardId,
+        reason: ChunkMissReason,
+    },
+    /// Some chunk validators didn't endorse a chunk considered for a block at
+    /// `height`, whether or not the chunk still got enough endorsed stake.
+    EndorsementMissing {
+        height: BlockHeight,
+        shard_id: ShardId,
+        chunk_hash: ChunkHash,
+        missing_validators: usize,
+        endorsed_stake_ratio: f64,
+    },
+    /// State witness for a chunk created at `height` received when the head of
+    /// the chain was already at `head_height`.
+    WitnessLate {
+        height: BlockHeight,
+        shard_id: ShardId,
+        chunk_producer: AccountId,
+        head_height: BlockHeight,
+    },
+}
+
+impl ConsensusEvent {
+    /// Height the event is about, used to build timelines.
+    pub fn height(&self) -> BlockHeight {
+        match self {
+            ConsensusEvent::ApprovalSent { target_height, .. }
+            | ConsensusEvent::ApprovalReceived { target_height, .. } => *target_height,
+            ConsensusEvent::BlockProduced { height, .. }
+            | ConsensusEvent::BlockSkipped { height, .. }
+            | ConsensusEvent::ChunkIncluded { height, .. }
+            | ConsensusEvent::ChunkMissed { height, .. }
+            | ConsensusEvent::EndorsementMissing { height, .. }
+            | ConsensusEvent::WitnessLate { height, .. } => *height,
+        }
+    }
+}
+
+impl fmt::Display for ConsensusEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConsensusEvent::ApprovalSent { kind, block_producer, .. } => {
+                write!(f, "approval sent: {:?} to {}", kind, block_producer)
+            }
+            ConsensusEvent::ApprovalReceived { kind, account_id, peer_id, .. } => {
+                write!(f, "approval received: {:?} from {} via {}", kind, account_id, peer_id)
+            }
+            ConsensusEvent::BlockProduced { hash, approvals, chunks, .. } => {
+                write!(
+                    f,
+                    "block produced: {} with {} approvals, {} new chunks",
+                    hash, approvals, chunks
+                )
+            }
+            ConsensusEvent::BlockSkipped { block_producer, reason, .. } => {
+                write!(f, "block skipped: producer {}, {:?}", block_producer, reason)
+            }
+            ConsensusEvent::ChunkIncluded { shard_id, chunk_hash, chunk_producer, .. } => {
+                write!(
+                    f,
+                    "chunk included: shard {} chunk {} by {}",
+                    shard_id, chunk_hash.0, chunk_producer
+                )
+            }
+            ConsensusEvent::ChunkMissed { shard_id, reason, .. } => {
+                write!(f, "chunk missed: shard {}, {:?}", shard_id, reason)
+            }
+            ConsensusEvent::EndorsementMissing {
+                shard_id,
+                chunk_hash,
+                missing_validators,
+                endorsed_stake_ratio,
+                ..
+            } => write!(
+                f,
+                "endorsement missing: shard {} chunk {}, {} validators missing, {:.1}% stake endorsed",
+                shard_id,
+                chunk_hash.0,
+                missing_validators,
+                endorsed_stake_ratio * 100.0
+            ),
+            ConsensusEvent::WitnessLate { shard_id, chunk_producer, head_height, .. } => {
+                write!(
+                    f,
+                    "witness late: shard {} by {}, head already at {}",
+                    shard_id, chunk_producer, head_height
+                )
+            }
+        }
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+pub struct ConsensusLogEntry {
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    #[serde(flatten)]
+    pub event: ConsensusEvent,
+}
+
+static CONSENSUS_LOG: OnceLock<SyncSender<ConsensusLogEntry>> = OnceLock::new();
+
+/// Starts writing recorded events to the journal. Until then, or if the
+/// journal is disabled, recorded events are discarded.
+pub fn init(home_dir: &Path, config: &ConsensusLogConfig) -> io::Result<()> {
+    if !config.enabled || CONSENSUS_LOG.get().is_some() {
+        return Ok(());
+    }
+    let mut writer = JournalWriter::open(
+        home_dir.join(&config.dir),
+        config.max_file_size.as_u64(),
+        config.max_files,
+    )?;
+    let (sender, receiver) = mpsc::sync_channel::<ConsensusLogEntry>(QUEUE_SIZE);
+    if CONSENSUS_LOG.set(sender).is_err() {
+        return Ok(());
+    }
+    std::thread::Builder::new().name("consensus_log".to_string()).spawn(move || {
+        while let Ok(entry) = receiver.recv() {
+            // Write everything recorded so far, then sync once for the whole batch.
+            for entry in std::iter::once(entry).chain(receiver.try_iter()) {
+                if let Err(err) = writer.write(&entry) {
+                    tracing::warn!(target: "consensus_log", ?err, "Failed to write consensus event");
+                }
+            }
+            if let Err(err) = writer.sync() {
+                tracing::warn!(target: "consensus_log", ?err, "Failed to sync consensus log");
+            }
+        }
+    })?;
+    Ok(())
+}
+
+/// Records an event in the journal.
+pub fn record(event: ConsensusEvent) {
+    let Some(sender) = CONSENSUS_LOG.get() else {
+        return;
+    };
+    let entry = ConsensusLogEntry { timestamp: chrono::Utc::now(), event };
+    if sender.try_send(entry).is_err() {
+        metrics::CONSENSUS_LOG_DROPPED_EVENTS.inc();
+    }
+}
+
+fn file_path(dir: &Path, index: usize) -> PathBuf {
+    if index == 0 { dir.join(FILE_NAME) } else { dir.join(format!("{}.{}", FILE_NAME, index)) }
+}
+
+struct JournalWriter {
+    dir: PathBuf,
+    max_file_size: u64,
+    max_files: usize,
+    file: File,
+    size: u64,
+}
+
+impl JournalWriter {
+    fn open(dir: PathBuf, max_file_size: u64, max_files: usize) -> io::Result<Self> {
+        std::fs::create_dir_all(&dir)?;
+        let file = OpenOptions::new().create(true).append(true).open(file_path(&dir, 0))?;
+        let size = file.metadata()?.len();
+        Ok(Self { dir, max_file_size, max_files, file, size })
+    }
+
+    fn write(&mut self, entry: &ConsensusLogEntry) -> io::Result<()> {
+        let mut line = serde_json::to_vec(entry)?;
+        line.push(b'\n');
+        if self.size > 0 && self.size + line.len() as u64 > self.max_file_size {
+            self.rotate()?;
+        }
+        // A single write per line, so that a crash cuts off at most the last line.
+        self.file.write_all(&line)?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+
+    /// Syncs the current file to disk. The journal is read after crashes, so
+    /// the events leading to one should be on disk.
+    fn sync(&self) -> io::Result<()> {
+        self.file.sync_data()
+    }
+
+    /// Shifts `consensus.log.N` to `consensus.log.N+1`, dropping the oldest
+    /// file, and starts a new current file.
+    fn rotate(&mut self) -> io::Result<()> {
+        self.sync()?;
+        let oldest = file_path(&self.dir, self.max_files);
+        if oldest.exists() {
+            std::fs::remove_file(&oldest)?;
+        }
+        for index in (0..self.max_files).rev() {
+            let path = file_path(&self.dir, index);
+            if path.exists() {
+                std::fs::rename(&path, file_path(&self.dir, index + 1))?;
+            }
+        }
+        self.file = OpenOptions::new().create(true).append(true).open(file_path(&self.dir, 0))?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+/// Reads all entries of the journal in `dir`, oldest first. Lines that can't
+/// be parsed, like one cut short by a crash, are skipped.
+pub fn read_journal(dir: &Path) -> io::Result<Vec<ConsensusLogEntry>> {
+    let mut indices = vec![];
+    for dir_entry in std::fs::read_dir(dir)? {
+        let name = dir_entry?.file_name();
+        let name = name.to_string_lossy();
+        if name == FILE_NAME {
+            indices.push(0);
+        } else if let Some(index) = name.strip_prefix(FILE_NAME).and_then(|s| s.strip_prefix('.')) {
+            if let Ok(index) = index.parse::<usize>() {
+                indices.push(index);
+            }
+        }
+    }
+    indices.sort_unstable();
+
+    let mut entries = vec![];
+    // Higher indices hold older events.
+    for index in indices.into_iter().rev() {
+        let reader = BufReader::new(File::open(file_path(dir, index))?);
+        for line in reader.lines() {
+            match serde_json::from_str(&line?) {
+                Ok(entry) => entries.push(entry),
+                Err(err) => {
+                    tracing::debug!(target: "consensus_log", ?err, "Skipping malformed entry")
+                }
+            }
+        }
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(height: BlockHeight) -> ConsensusLogEntry {
+        ConsensusLogEntry {
+            timestamp: chrono::Utc::now(),
+            event: ConsensusEvent::BlockSkipped {
+                height,
+                block_producer: "test".parse().unwrap(),
+                reason: BlockSkipReason::NotEnoughApprovals { approvals: 1 },
+            },
+        }
+    }
+
+    #[test]
+    fn test_rotation_keeps_recent_entries_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let line_len = serde_json::to_vec(&entry(10)).unwrap().len() as u64 + 1;
+        // Three entries per file, two rotated files.
+        let mut writer = JournalWriter::open(dir.path().to_path_buf(), 3 * line_len, 2).unwrap();
+        for height in 10..20 {
+            writer.write(&entry(height)).unwrap();
+        }
+        // A partially written line is skipped.
+        writer.file.write_all(b"{\"timestamp\":").unwrap();
+
+        let heights: Vec<_> =
+            read_journal(dir.path()).unwrap().iter().map(|entry| entry.event.height()).collect();
+        assert_eq!(heights, (13..20).collect::<Vec<_>>());
+        assert!(!file_path(dir.path(), 3).exists());
+    }
+}
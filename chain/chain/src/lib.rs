@@ -23,6 +23,7 @@ mod block_processing_utils;
 pub mod blocks_delay_tracker;
 pub mod chain;
 mod chain_update;
+pub mod consensus_log;
 pub mod crypto_hash_timer;
 mod doomslug;
 pub mod flat_storage_init;
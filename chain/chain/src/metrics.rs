@@ -24,6 +24,13 @@ pub static BLOCK_PROCESSING_ATTEMPTS_TOTAL: LazyLock<IntCounter> = LazyLock::new
     )
     .unwrap()
 });
+pub static CONSENSUS_LOG_DROPPED_EVENTS: LazyLock<IntCounter> = LazyLock::new(|| {
+    try_create_int_counter(
+        "near_consensus_log_dropped_events_total",
+        "Number of consensus events not written to the consensus log because the writer fell behind",
+    )
+    .unwrap()
+});
 pub static BLOCK_PROCESSED_TOTAL: LazyLock<IntCounter> = LazyLock::new(|| {
     try_create_int_counter("near_block_processed_total", "Total number of blocks processed")
         .unwrap()
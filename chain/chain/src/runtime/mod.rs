@@ -797,9 +797,10 @@ impl RuntimeAdapter for NightshadeRuntime {
         transactions: SignedValidPeriodTransactions,
     ) -> Result<ApplyChunkResult, Error> {
         let shard_id = chunk.shard_id;
-        let _timer = metrics::APPLYING_CHUNKS_TIME
-            .with_label_values(&[&apply_reason.to_string(), &shard_id.to_string()])
-            .start_timer();
+        let _timer = near_o11y::openmetrics::start_exemplar_timer(
+            metrics::APPLYING_CHUNKS_TIME
+                .with_label_values(&[&apply_reason.to_string(), &shard_id.to_string()]),
+        );
 
         let mut trie = match storage_config.source {
             StorageDataSource::Db => self.get_trie_for_shard(
@@ -286,7 +286,7 @@ mod tests {
     fn create_chunk_header(height: u64, shard_id: ShardId) -> ShardChunkHeader {
         let signer =
             InMemoryValidatorSigner::from_random("test".parse().unwrap(), KeyType::ED25519);
-        ShardChunkHeader::V2(ShardChunkHeaderV2::new(
+        let header = ShardChunkHeaderV2::new(
             CryptoHash::default(),
             CryptoHash::default(),
             CryptoHash::default(),
@@ -301,7 +301,9 @@ mod tests {
             CryptoHash::default(),
             vec![],
             &signer,
-        ))
+        )
+        .unwrap();
+        ShardChunkHeader::V2(header)
     }
 
     #[test]
@@ -1,18 +1,19 @@
 use actix::Message;
 use near_chain_configs::{ClientConfig, ProtocolConfigView};
+use near_primitives::chunk_apply_stats::ChunkApplyStats;
 use near_primitives::hash::CryptoHash;
 use near_primitives::merkle::{MerklePath, PartialMerkleTree};
 use near_primitives::network::PeerId;
 use near_primitives::sharding::{ChunkHash, ShardChunk};
 use near_primitives::types::{
-    AccountId, BlockHeight, BlockReference, EpochId, EpochReference, MaybeBlockId, ShardId,
-    TransactionOrReceiptId,
+    AccountId, BlockHeight, BlockId, BlockReference, EpochId, EpochReference, MaybeBlockId,
+    ShardId, TransactionOrReceiptId,
 };
 use near_primitives::views::validator_stake_view::ValidatorStakeView;
 use near_primitives::views::{
-    BlockView, ChunkView, EpochValidatorInfo, ExecutionOutcomeWithIdView, GasPriceView,
-    LightClientBlockLiteView, LightClientBlockView, MaintenanceWindowsView, QueryRequest,
-    QueryResponse, ReceiptView, SplitStorageInfoView, StateChangesKindsView,
+    BlockView, ChunkView, EpochValidatorForecast, EpochValidatorInfo, ExecutionOutcomeWithIdView,
+    GasPriceView, LightClientBlockLiteView, LightClientBlockView, MaintenanceWindowsView,
+    QueryRequest, QueryResponse, ReceiptView, SplitStorageInfoView, StateChangesKindsView,
     StateChangesRequestView, StateChangesView, StateSyncStatusView, SyncStatusView, TxStatusView,
 };
 pub use near_primitives::views::{StatusResponse, StatusSyncInfo};
@@ -360,6 +361,53 @@ impl From<near_chain_primitives::Error> for GetChunkError {
     }
 }
 
+/// Stats gathered while applying the chunk of a shard in a block.
+#[derive(Debug)]
+pub struct GetChunkApplyStats {
+    pub block_id: BlockId,
+    pub shard_id: ShardId,
+}
+
+impl Message for GetChunkApplyStats {
+    type Result = Result<ChunkApplyStats, GetChunkApplyStatsError>;
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum GetChunkApplyStatsError {
+    #[error("IO Error: {error_message}")]
+    IOError { error_message: String },
+    #[error(
+        "Block either has never been observed on the node or has been garbage collected: {error_message}"
+    )]
+    UnknownBlock { error_message: String },
+    #[error(
+        "Chunk of shard {shard_id} in block {block_hash} was not applied on this node or its stats have been garbage collected"
+    )]
+    UnavailableStats { block_hash: CryptoHash, shard_id: ShardId },
+    // NOTE: Currently, the underlying errors are too broad, and while we tried to handle
+    // expected cases, we cannot statically guarantee that no other errors will be returned
+    // in the future.
+    // TODO #3851: Remove this variant once we can exhaustively match all the underlying errors
+    #[error(
+        "It is a bug if you receive this error type, please, report this incident: https://github.com/near/nearcore/issues/new/choose. Details: {error_message}"
+    )]
+    Unreachable { error_message: String },
+}
+
+impl From<near_chain_primitives::Error> for GetChunkApplyStatsError {
+    fn from(error: near_chain_primitives::Error) -> Self {
+        match error {
+            near_chain_primitives::Error::IOErr(error) => {
+                Self::IOError { error_message: error.to_string() }
+            }
+            near_chain_primitives::Error::DBNotFoundErr(error_message) => {
+                Self::UnknownBlock { error_message }
+            }
+            _ => Self::Unreachable { error_message: error.to_string() },
+        }
+    }
+}
+
 /// Queries client for given path / data.
 #[derive(Clone, Debug)]
 pub struct Query {
@@ -696,6 +744,16 @@ impl Message for GetValidatorOrdered {
     type Result = Result<Vec<ValidatorStakeView>, GetValidatorInfoError>;
 }
 
+/// Forecast of the validator kickouts and rewards at the end of the current epoch.
+#[derive(Debug)]
+pub struct GetValidatorForecast {
+    pub block_id: MaybeBlockId,
+}
+
+impl Message for GetValidatorForecast {
+    type Result = Result<EpochValidatorForecast, GetValidatorInfoError>;
+}
+
 #[derive(Debug)]
 pub struct GetStateChanges {
     pub block_hash: CryptoHash,
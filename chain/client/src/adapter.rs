@@ -25,10 +25,15 @@ pub fn client_sender_for_network(
         tx_status_request: view_client_addr.clone().into_sender(),
         tx_status_response: view_client_addr.clone().into_sender(),
         transaction: rpc_handler.clone().into_sender(),
-        announce_account: view_client_addr.into_sender(),
+        announce_account: view_client_addr.clone().into_sender(),
         chunk_endorsement: rpc_handler.into_sender(),
         epoch_sync_request: client_addr.clone().into_sender(),
         epoch_sync_response: client_addr.clone().into_sender(),
-        optimistic_block_receiver: client_addr.into_sender(),
+        optimistic_block_receiver: client_addr.clone().into_sender(),
+        block_range_request: view_client_addr.clone().into_sender(),
+        block_range_response: client_addr.clone().into_sender(),
+        compact_block: client_addr.clone().into_sender(),
+        compact_block_request: view_client_addr.into_sender(),
+        compact_block_response: client_addr.into_sender(),
     }
 }
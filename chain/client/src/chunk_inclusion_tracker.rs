@@ -1,13 +1,14 @@
 use itertools::Itertools;
 use lru::LruCache;
 use near_async::time::Utc;
+use near_chain::consensus_log::{self, ChunkMissReason, ConsensusEvent};
 use near_chain_primitives::Error;
 use near_o11y::log_assert_fail;
 use near_primitives::block_body::ChunkEndorsementSignatures;
 use near_primitives::hash::CryptoHash;
 use near_primitives::sharding::{ChunkHash, ShardChunkHeader};
 use near_primitives::stateless_validation::validator_assignment::ChunkEndorsementsState;
-use near_primitives::types::{AccountId, EpochId, ShardId};
+use near_primitives::types::{AccountId, BlockHeight, EpochId, ShardId};
 use std::collections::HashMap;
 use std::num::NonZeroUsize;
 
@@ -181,6 +182,19 @@ impl ChunkInclusionTracker {
         chunk_headers_ready_for_inclusion
     }
 
+    /// Returns all the chunk headers received for the given previous block, including the ones
+    /// which are not ready for inclusion. Used to reconstruct the compact blocks.
+    pub fn get_all_chunk_headers(&self, prev_block_hash: &CryptoHash) -> Vec<&ShardChunkHeader> {
+        let Some(entry) = self.prev_block_to_chunk_hash_ready.peek(prev_block_hash) else {
+            return vec![];
+        };
+        entry
+            .values()
+            .filter_map(|chunk_hash| self.chunk_hash_to_chunk_info.get(chunk_hash))
+            .map(|chunk_info| &chunk_info.chunk_header)
+            .collect()
+    }
+
     pub fn num_chunk_headers_ready_for_inclusion(
         &self,
         epoch_id: &EpochId,
@@ -256,6 +270,68 @@ impl ChunkInclusionTracker {
             }
         }
     }
+
+    /// Records in the consensus log which chunks go into the block at `height`
+    /// and why the others are missing.
+    pub fn record_consensus_events(
+        &self,
+        epoch_id: &EpochId,
+        height: BlockHeight,
+        prev_block_hash: &CryptoHash,
+        all_shards: &[ShardId],
+    ) {
+        let maybe_entry = self.prev_block_to_chunk_hash_ready.peek(prev_block_hash);
+        for &shard_id in all_shards {
+            let Some(chunk_info) = maybe_entry
+                .and_then(|entry| entry.get(&shard_id))
+                .and_then(|chunk_hash| self.chunk_hash_to_chunk_info.get(chunk_hash))
+            else {
+                consensus_log::record(ConsensusEvent::ChunkMissed {
+                    height,
+                    shard_id,
+                    reason: ChunkMissReason::NotReceived,
+                });
+                continue;
+            };
+            let chunk_hash = chunk_info.chunk_header.chunk_hash();
+            let stats = &chunk_info.endorsements;
+            if stats.endorsed_validators_count < stats.total_validators_count {
+                consensus_log::record(ConsensusEvent::EndorsementMissing {
+                    height,
+                    shard_id,
+                    chunk_hash: chunk_hash.clone(),
+                    missing_validators: stats.total_validators_count
+                        - stats.endorsed_validators_count,
+                    endorsed_stake_ratio: stats.endorsed_stake as f64
+                        / stats.total_stake.max(1) as f64,
+                });
+            }
+            let event = if self
+                .banned_chunk_producers
+                .contains(&(*epoch_id, chunk_info.chunk_producer.clone()))
+            {
+                ConsensusEvent::ChunkMissed {
+                    height,
+                    shard_id,
+                    reason: ChunkMissReason::BannedProducer,
+                }
+            } else if !stats.is_endorsed {
+                ConsensusEvent::ChunkMissed {
+                    height,
+                    shard_id,
+                    reason: ChunkMissReason::InsufficientEndorsement,
+                }
+            } else {
+                ConsensusEvent::ChunkIncluded {
+                    height,
+                    shard_id,
+                    chunk_hash,
+                    chunk_producer: chunk_info.chunk_producer.clone(),
+                }
+            };
+            consensus_log::record(event);
+        }
+    }
 }
 
 fn record_chunk_excluded_metric(reason: ChunkExclusionReason, shard_id: ShardId) {
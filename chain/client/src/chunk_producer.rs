@@ -308,7 +308,8 @@ impl ChunkProducer {
             bandwidth_requests.cloned().unwrap_or_else(BandwidthRequests::empty),
             &*validator_signer,
             &mut self.reed_solomon_encoder,
-        );
+        )
+        .map_err(|err| Error::ChunkProducer(format!("Failed to sign chunk: {err}")))?;
 
         let encoded_chunk = chunk.to_encoded_shard_chunk();
         span.record("chunk_hash", tracing::field::debug(encoded_chunk.chunk_hash()));
@@ -26,6 +26,7 @@ use near_chain::chain::{
     ApplyChunksDoneMessage, BlockCatchUpRequest, BlockMissingChunks, BlocksCatchUpState,
     VerifyBlockHashAndSignatureResult,
 };
+use near_chain::consensus_log::{self, BlockSkipReason, ConsensusEvent};
 use near_chain::orphan::OrphanMissingChunks;
 use near_chain::resharding::types::ReshardingSender;
 use near_chain::state_snapshot_actor::SnapshotCallbacks;
@@ -44,10 +45,12 @@ use near_epoch_manager::shard_assignment::shard_id_to_uid;
 use near_epoch_manager::shard_tracker::ShardTracker;
 use near_network::types::{AccountKeys, ChainInfo, PeerManagerMessageRequest, SetChainInfo};
 use near_network::types::{
-    HighestHeightPeerInfo, NetworkRequests, PeerManagerAdapter, ReasonForBan,
+    CompactBlockRequest, CompactBlockResponse, HighestHeightPeerInfo, NetworkRequests,
+    PeerManagerAdapter, ReasonForBan,
 };
 use near_primitives::block::{Approval, ApprovalInner, ApprovalMessage, Block, BlockHeader, Tip};
 use near_primitives::block_header::ApprovalType;
+use near_primitives::compact_block::{CompactBlock, PartialBlock};
 use near_primitives::epoch_info::RngSeed;
 use near_primitives::errors::EpochError;
 use near_primitives::hash::CryptoHash;
@@ -79,6 +82,12 @@ use crate::chunk_producer::AdvProduceChunksMode;
 
 const NUM_REBROADCAST_BLOCKS: usize = 30;
 
+/// Max number of compact blocks waiting for the missing chunk headers.
+const NUM_PENDING_COMPACT_BLOCKS: usize = 30;
+
+/// Time to wait for a CompactBlockResponse before requesting the full block instead.
+const COMPACT_BLOCK_RESPONSE_TIMEOUT: Duration = Duration::milliseconds(500);
+
 /// Drop blocks whose height are beyond head + horizon if it is not in the current epoch.
 const BLOCK_HORIZON: u64 = 500;
 
@@ -105,6 +114,22 @@ pub struct CatchupState {
     pub catchup: BlocksCatchUpState,
 }
 
+/// Compact block waiting for the missing chunk headers requested from a peer.
+struct PendingCompactBlock {
+    partial: PartialBlock,
+    requested_from: PeerId,
+    requested_at: Instant,
+    /// Other peers which sent the same compact block, to request the full block from
+    /// if `requested_from` doesn't respond in time or responds with garbage.
+    other_peers: Vec<PeerId>,
+}
+
+impl PendingCompactBlock {
+    fn fallback_peer(&self) -> PeerId {
+        self.other_peers.first().unwrap_or(&self.requested_from).clone()
+    }
+}
+
 pub struct Client {
     /// Adversarial controls - should be enabled only to test disruptive
     /// behavior on chain.
@@ -145,6 +170,8 @@ pub struct Client {
     pub myself_sender: ClientSenderForClient,
     /// Blocks that have been re-broadcast recently. They should not be broadcast again.
     rebroadcasted_blocks: lru::LruCache<CryptoHash, ()>,
+    /// Compact blocks being reconstructed, waiting for the missing chunk headers.
+    pending_compact_blocks: lru::LruCache<CryptoHash, PendingCompactBlock>,
     /// Last time the head was updated, or our head was rebroadcasted. Used to re-broadcast the head
     /// again to prevent network from stalling if a large percentage of the network missed a block
     last_time_head_progress_made: Instant,
@@ -356,6 +383,9 @@ impl Client {
             rebroadcasted_blocks: lru::LruCache::new(
                 NonZeroUsize::new(NUM_REBROADCAST_BLOCKS).unwrap(),
             ),
+            pending_compact_blocks: lru::LruCache::new(
+                NonZeroUsize::new(NUM_PENDING_COMPACT_BLOCKS).unwrap(),
+            ),
             last_time_head_progress_made: clock.now(),
             block_production_info: BlockProductionTracker::new(),
             tier1_accounts_cache: None,
@@ -656,7 +686,8 @@ impl Client {
             &*validator_signer,
             self.clock.now_utc().unix_timestamp_nanos() as u64,
             sandbox_delta_time,
-        );
+        )
+        .map_err(|err| Error::BlockProducer(format!("Failed to sign optimistic block: {err}")))?;
 
         metrics::OPTIMISTIC_BLOCK_PRODUCED_TOTAL.inc();
 
@@ -786,6 +817,8 @@ impl Client {
             .collect();
 
         debug_assert_eq!(approvals_map.len(), 0);
+        let num_approvals = approvals.iter().flatten().count();
+        let num_new_chunks = new_chunks.len();
 
         let next_epoch_id = self
             .epoch_manager
@@ -907,7 +940,8 @@ impl Client {
             self.clock.clone(),
             sandbox_delta_time,
             optimistic_block,
-        );
+        )
+        .map_err(|err| Error::BlockProducer(format!("Failed to sign block: {err}")))?;
 
         // Update latest known even before returning block out, to prevent race conditions.
         self.chain
@@ -915,6 +949,12 @@ impl Client {
             .save_latest_known(LatestKnown { height, seen: block.header().raw_timestamp() })?;
 
         metrics::BLOCK_PRODUCED_TOTAL.inc();
+        consensus_log::record(ConsensusEvent::BlockProduced {
+            height,
+            hash: *block.hash(),
+            approvals: num_approvals,
+            chunks: num_new_chunks,
+        });
 
         Ok(Some(block))
     }
@@ -1361,6 +1401,9 @@ impl Client {
         let tip = self.chain.head()?;
 
         if tip.last_block_hash != self.doomslug.get_tip().0 {
+            if let Err(err) = self.record_skipped_heights(&tip) {
+                debug!(target: "client", ?err, "Failed to record skipped heights");
+            }
             // We need to update the doomslug tip
             let last_final_hash =
                 *self.chain.get_block_header(&tip.last_block_hash)?.last_final_block();
@@ -1375,6 +1418,38 @@ impl Client {
         Ok(())
     }
 
+    /// Records heights skipped between the new head and its previous block in
+    /// the consensus log. Has to be called before the doomslug tip moves to the
+    /// new head, as doomslug forgets approvals for older heights.
+    fn record_skipped_heights(&self, tip: &Tip) -> Result<(), Error> {
+        if tip.prev_block_hash == CryptoHash::default() {
+            return Ok(());
+        }
+        let prev_height = self.chain.get_block_header(&tip.prev_block_hash)?.height();
+        if prev_height + 1 >= tip.height {
+            return Ok(());
+        }
+        let me = self.validator_signer.get().map(|signer| signer.validator_id().clone());
+        for height in prev_height + 1..tip.height {
+            // A block at the skipped height would have been built on top of the
+            // previous block, which determines its epoch.
+            let epoch_id = self.epoch_manager.get_epoch_id_from_prev_block(&tip.prev_block_hash)?;
+            let block_producer = self.epoch_manager.get_block_producer(&epoch_id, height)?;
+            let reason = if Some(&block_producer) == me.as_ref() {
+                let status = self.doomslug.approval_status_at_height(&height);
+                if status.ready_at.is_some() {
+                    BlockSkipReason::NotIncluded
+                } else {
+                    BlockSkipReason::NotEnoughApprovals { approvals: status.approvals.len() }
+                }
+            } else {
+                BlockSkipReason::NotReceived
+            };
+            consensus_log::record(ConsensusEvent::BlockSkipped { height, block_producer, reason });
+        }
+        Ok(())
+    }
+
     #[cfg(feature = "sandbox")]
     pub fn sandbox_update_tip(&mut self, height: BlockHeight) -> Result<(), Error> {
         let tip = self.chain.head()?;
@@ -1420,6 +1495,11 @@ impl Client {
         let next_block_producer =
             self.epoch_manager.get_block_producer(&next_epoch_id, approval.target_height)?;
         let next_block_producer_id = signer.as_ref().map(|x| x.validator_id());
+        consensus_log::record(ConsensusEvent::ApprovalSent {
+            target_height: approval.target_height,
+            kind: (&approval.inner).into(),
+            block_producer: next_block_producer.clone(),
+        });
         if Some(&next_block_producer) == next_block_producer_id {
             self.collect_block_approval(&approval, ApprovalType::SelfApproval, signer);
         } else {
@@ -1943,6 +2023,14 @@ impl Client {
             target_height=target_height,
             approval_type=?approval_type,
             "collect_block_approval");
+        if let ApprovalType::PeerApproval(peer_id) = &approval_type {
+            consensus_log::record(ConsensusEvent::ApprovalReceived {
+                target_height: *target_height,
+                kind: inner.into(),
+                account_id: account_id.clone(),
+                peer_id: peer_id.clone(),
+            });
+        }
         let parent_hash = match inner {
             ApprovalInner::Endorsement(parent_hash) => *parent_hash,
             ApprovalInner::Skip(parent_height) => {
@@ -2205,6 +2293,132 @@ impl Client {
         }
     }
 
+    /// Reconstructs the block from the compact block, using the chunk headers of the previous
+    /// block and the chunk headers received from the chunk producers. Returns the block if
+    /// nothing is missing. Otherwise requests the missing chunk headers from the peer. Falls
+    /// back to requesting the full block if the previous block is unknown.
+    pub fn receive_compact_block(
+        &mut self,
+        compact_block: CompactBlock,
+        peer_id: PeerId,
+    ) -> Option<Block> {
+        let hash = *compact_block.hash();
+        let _span =
+            debug_span!(target: "client", "receive_compact_block", ?hash, ?peer_id).entered();
+        match self.chain.block_exists(&hash) {
+            Ok(false) => {}
+            Ok(true) => return None,
+            Err(err) => {
+                error!(target: "client", ?hash, ?err, "receive_compact_block: failed to check block exists");
+                return None;
+            }
+        }
+        if let Some(pending) = self.pending_compact_blocks.get_mut(&hash) {
+            if pending.requested_from != peer_id && !pending.other_peers.contains(&peer_id) {
+                pending.other_peers.push(peer_id);
+            }
+            return None;
+        }
+        let prev_hash = *compact_block.header.prev_hash();
+        let Ok(prev_block) = self.chain.get_block(&prev_hash) else {
+            debug!(target: "client", ?hash, ?prev_hash, "previous block is unknown, requesting the full block");
+            self.request_block(hash, peer_id);
+            return None;
+        };
+        // The chunk headers received from the chunk producers are not included in any block
+        // yet, so their `height_included` has to be set to match the ones in the block.
+        let height = compact_block.header.height();
+        let new_chunks: Vec<_> = self
+            .chunk_inclusion_tracker
+            .get_all_chunk_headers(&prev_hash)
+            .into_iter()
+            .map(|chunk| {
+                let mut chunk = chunk.clone();
+                *chunk.height_included_mut() = height;
+                chunk
+            })
+            .collect();
+        let partial = compact_block.reconstruct(prev_block.chunks().iter_raw().chain(&new_chunks));
+        if partial.is_complete() {
+            return self.finish_compact_block(partial, peer_id);
+        }
+        let request =
+            CompactBlockRequest { block_hash: hash, chunk_indices: partial.missing_chunks() };
+        debug!(target: "client", ?hash, num_chunks = request.chunk_indices.len(), "requesting the missing chunk headers of the compact block");
+        self.pending_compact_blocks.put(
+            hash,
+            PendingCompactBlock {
+                partial,
+                requested_from: peer_id.clone(),
+                requested_at: self.clock.now(),
+                other_peers: vec![],
+            },
+        );
+        self.network_adapter.send(PeerManagerMessageRequest::NetworkRequests(
+            NetworkRequests::CompactBlockRequest { peer_id, request },
+        ));
+        None
+    }
+
+    /// Adds the chunk headers from the response to the compact block requested from the peer.
+    /// Returns the block if it is complete. Falls back to requesting the full block if the
+    /// response doesn't match the compact block.
+    pub fn receive_compact_block_response(
+        &mut self,
+        response: CompactBlockResponse,
+        peer_id: PeerId,
+    ) -> Option<Block> {
+        let hash = response.block_hash;
+        match self.pending_compact_blocks.peek(&hash) {
+            Some(pending) if pending.requested_from == peer_id => {}
+            _ => {
+                debug!(target: "client", ?hash, ?peer_id, "unexpected CompactBlockResponse");
+                return None;
+            }
+        }
+        let mut pending = self.pending_compact_blocks.pop(&hash).unwrap();
+        if !response.chunks.into_iter().all(|(i, chunk)| pending.partial.add_chunk(i, chunk)) {
+            let fallback_peer = pending.fallback_peer();
+            warn!(target: "client", ?hash, ?peer_id, ?fallback_peer, "CompactBlockResponse doesn't match the compact block, requesting the full block");
+            self.request_block(hash, fallback_peer);
+            return None;
+        }
+        let fallback_peer = pending.fallback_peer();
+        self.finish_compact_block(pending.partial, fallback_peer)
+    }
+
+    /// Requests the full blocks for the compact blocks which didn't get a CompactBlockResponse
+    /// within `COMPACT_BLOCK_RESPONSE_TIMEOUT`.
+    pub fn check_pending_compact_blocks(&mut self) {
+        let now = self.clock.now();
+        let timed_out: Vec<_> = self
+            .pending_compact_blocks
+            .iter()
+            .filter(|(_, pending)| now > pending.requested_at + COMPACT_BLOCK_RESPONSE_TIMEOUT)
+            .map(|(hash, _)| *hash)
+            .collect();
+        for hash in timed_out {
+            let pending = self.pending_compact_blocks.pop(&hash).unwrap();
+            let fallback_peer = pending.fallback_peer();
+            debug!(target: "client", ?hash, requested_from = ?pending.requested_from, ?fallback_peer, "CompactBlockResponse timed out, requesting the full block");
+            self.request_block(hash, fallback_peer);
+        }
+    }
+
+    /// Turns the complete partial block into the block. Requests the full block from the given
+    /// peer if the chunk headers don't match the header.
+    fn finish_compact_block(&self, partial: PartialBlock, peer_id: PeerId) -> Option<Block> {
+        let hash = *partial.compact_block().hash();
+        match partial.into_block() {
+            Ok(block) => Some(block),
+            Err(err) => {
+                warn!(target: "client", ?hash, ?peer_id, %err, "failed to reconstruct the compact block, requesting the full block");
+                self.request_block(hash, peer_id);
+                None
+            }
+        }
+    }
+
     pub fn ban_peer(&self, peer_id: PeerId, ban_reason: ReasonForBan) {
         self.network_adapter.send(PeerManagerMessageRequest::NetworkRequests(
             NetworkRequests::BanPeer { peer_id, ban_reason },
@@ -2337,10 +2551,13 @@ impl Client {
         };
         let tier1_accounts = self.get_tier1_accounts(&tip)?;
         let block = self.chain.get_block(&tip.last_block_hash)?;
+        // Archival nodes serve block ranges of all the blocks they have, i.e. from the tail.
+        let block_range_start = if self.config.archive { Some(self.chain.tail()?) } else { None };
         self.network_adapter.send(SetChainInfo(ChainInfo {
             block,
             tracked_shards,
             tier1_accounts,
+            block_range_start,
         }));
         Ok(())
     }
@@ -20,6 +20,7 @@ use crate::sync::state::chain_requests::{
     ChainFinalizationRequest, ChainSenderForStateSync, StateHeaderValidationRequest,
 };
 use crate::sync_jobs_actor::{ClientSenderForSyncJobs, SyncJobsActor};
+use crate::validator_alerts::ValidatorAlertEngine;
 use crate::{StatusResponse, metrics};
 use actix::Actor;
 use near_async::actix::AddrWithAutoSpanContextExt;
@@ -53,7 +54,8 @@ use near_client_primitives::types::{
 use near_epoch_manager::EpochManagerAdapter;
 use near_epoch_manager::shard_tracker::ShardTracker;
 use near_network::client::{
-    BlockApproval, BlockHeadersResponse, BlockResponse, OptimisticBlockMessage, SetNetworkInfo,
+    BlockApproval, BlockHeadersResponse, BlockRangeResponseMessage, BlockResponse,
+    CompactBlockMessage, CompactBlockResponseMessage, OptimisticBlockMessage, SetNetworkInfo,
     StateResponseReceived,
 };
 use near_network::types::ReasonForBan;
@@ -252,6 +254,10 @@ pub struct ClientActorInner {
     // Last time when log_summary method was called.
     log_summary_timer_next_attempt: near_async::time::Utc,
 
+    /// Evaluates validator alert rules, if configured.
+    validator_alerts: Option<ValidatorAlertEngine>,
+    validator_alerts_timer_next_attempt: near_async::time::Utc,
+
     doomslug_timer_next_attempt: near_async::time::Utc,
     sync_timer_next_attempt: near_async::time::Utc,
     sync_started: bool,
@@ -345,6 +351,8 @@ impl ClientActorInner {
             check_validator_tracked_shards(&client, vs.validator_id())?;
         }
         let info_helper = InfoHelper::new(clock.clone(), telemetry_sender, &client.config);
+        let validator_alerts =
+            client.config.validator_alerts.clone().map(ValidatorAlertEngine::new);
 
         let now = clock.now_utc();
         Ok(ClientActorInner {
@@ -369,6 +377,8 @@ impl ClientActorInner {
             info_helper,
             block_production_next_attempt: now,
             log_summary_timer_next_attempt: now,
+            validator_alerts,
+            validator_alerts_timer_next_attempt: now,
             doomslug_timer_next_attempt: now,
             sync_timer_next_attempt: now,
             sync_started: false,
@@ -548,6 +558,52 @@ impl Handler<BlockResponse> for ClientActorInner {
     }
 }
 
+impl Handler<CompactBlockMessage> for ClientActorInner {
+    fn handle(&mut self, msg: CompactBlockMessage) {
+        let CompactBlockMessage { from_peer, compact_block } = msg;
+        debug!(target: "client", block_height = compact_block.header.height(), block_hash = ?compact_block.hash(), ?from_peer, "CompactBlock");
+        if let Some(block) = self.client.receive_compact_block(compact_block, from_peer.clone()) {
+            self.handle(BlockResponse { block, peer_id: from_peer, was_requested: false });
+        }
+    }
+}
+
+impl Handler<CompactBlockResponseMessage> for ClientActorInner {
+    fn handle(&mut self, msg: CompactBlockResponseMessage) {
+        let CompactBlockResponseMessage { from_peer, response } = msg;
+        debug!(target: "client", block_hash = ?response.block_hash, num_chunks = response.chunks.len(), ?from_peer, "CompactBlockResponse");
+        if let Some(block) = self.client.receive_compact_block_response(response, from_peer.clone())
+        {
+            self.handle(BlockResponse { block, peer_id: from_peer, was_requested: false });
+        }
+    }
+}
+
+impl Handler<BlockRangeResponseMessage> for ClientActorInner {
+    fn handle(&mut self, msg: BlockRangeResponseMessage) {
+        let BlockRangeResponseMessage { from_peer, response } = msg;
+        debug!(target: "client", start_height = response.start_height, num_blocks = response.blocks.len(), last = response.last, ?from_peer, "BlockRangeResponse");
+        if let Err(err) = self.client.sync_handler.block_sync.receive_block_range_response(
+            &self.client.chain,
+            &from_peer,
+            &response,
+        ) {
+            warn!(target: "client", ?from_peer, ?err, "Rejecting invalid block range response");
+            return;
+        }
+        let signer = self.client.validator_signer.get();
+        for block in response.blocks {
+            self.client.receive_block(
+                block,
+                from_peer.clone(),
+                true,
+                Some(self.client.myself_sender.apply_chunks_done.clone()),
+                &signer,
+            );
+        }
+    }
+}
+
 impl Handler<BlockHeadersResponse> for ClientActorInner {
     fn handle(&mut self, msg: BlockHeadersResponse) -> Result<(), ReasonForBan> {
         let BlockHeadersResponse(headers, peer_id) = msg;
@@ -1122,6 +1178,12 @@ impl ClientActorInner {
                 self.client
                     .chunk_inclusion_tracker
                     .record_endorsement_metrics(prev_block_hash, &shard_ids);
+                self.client.chunk_inclusion_tracker.record_consensus_events(
+                    &epoch_id,
+                    height,
+                    prev_block_hash,
+                    &shard_ids,
+                );
                 if let Err(err) = self.produce_block(height, signer) {
                     // If there is an error, report it and let it retry on the next loop step.
                     error!(target: "client", height, "Block production failed: {}", err);
@@ -1243,6 +1305,7 @@ impl ClientActorInner {
 
             delay = core::cmp::min(delay, self.block_production_next_attempt - now)
         }
+        self.client.check_pending_compact_blocks();
 
         self.log_summary_timer_next_attempt = self.run_timer(
             self.client.config.log_summary_period,
@@ -1252,6 +1315,17 @@ impl ClientActorInner {
             "log_summary",
         );
         delay = core::cmp::min(delay, self.log_summary_timer_next_attempt - now);
+
+        if let Some(check_period) = self.validator_alerts.as_ref().map(|e| e.check_period()) {
+            self.validator_alerts_timer_next_attempt = self.run_timer(
+                check_period,
+                self.validator_alerts_timer_next_attempt,
+                ctx,
+                |act, _ctx| act.check_validator_alerts(),
+                "validator_alerts",
+            );
+            delay = core::cmp::min(delay, self.validator_alerts_timer_next_attempt - now);
+        }
         timer.observe_duration();
         delay
     }
@@ -1725,6 +1799,13 @@ impl ClientActorInner {
         )
     }
 
+    /// Evaluate the validator alert rules and report the alerts that fire.
+    fn check_validator_alerts(&mut self) {
+        if let Some(validator_alerts) = &mut self.validator_alerts {
+            validator_alerts.check(self.clock.now_utc(), &self.client);
+        }
+    }
+
     /// Checks if the node is syncing its State and applies special logic in
     /// that case. A node usually ignores blocks that are too far ahead, but in
     /// case of a node syncing its state it is looking for specific blocks:
@@ -746,3 +746,12 @@ pub(crate) static CHUNK_ENDORSEMENTS_REJECTED: LazyLock<IntCounterVec> = LazyLoc
     )
     .unwrap()
 });
+
+pub(crate) static VALIDATOR_ALERTS_TOTAL: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    try_create_int_counter_vec(
+        "near_validator_alerts_total",
+        "Total number of alerts fired by the validator alert engine",
+        &["rule", "severity"],
+    )
+    .unwrap()
+});
@@ -5,6 +5,7 @@ use crate::Client;
 use itertools::Itertools;
 use near_async::futures::{AsyncComputationSpawner, AsyncComputationSpawnerExt};
 use near_async::messaging::{CanSend, Sender};
+use near_chain::consensus_log::{self, ConsensusEvent};
 use near_chain::stateless_validation::chunk_validation;
 use near_chain::stateless_validation::processing_tracker::ProcessingDoneTracker;
 use near_chain::types::RuntimeAdapter;
@@ -225,7 +226,13 @@ pub(crate) fn send_chunk_endorsement_to_block_producers(
         "send_chunk_endorsement",
     );
 
-    let endorsement = ChunkEndorsement::new(epoch_id, chunk_header, signer);
+    let endorsement = match ChunkEndorsement::new(epoch_id, chunk_header, signer) {
+        Ok(endorsement) => endorsement,
+        Err(err) => {
+            tracing::warn!(target: "client", ?chunk_hash, %err, "Failed to sign chunk endorsement");
+            return None;
+        }
+    };
     let mut send_to_itself = None;
     for block_producer in block_producers {
         if &block_producer == signer.validator_id() {
@@ -265,6 +272,16 @@ impl Client {
         );
         let signer = signer.unwrap();
 
+        let head_height = self.chain.head()?.height;
+        if head_height >= witness.chunk_header.height_created() {
+            consensus_log::record(ConsensusEvent::WitnessLate {
+                height: witness.chunk_header.height_created(),
+                shard_id: witness.chunk_header.shard_id(),
+                chunk_producer: witness.chunk_producer.clone(),
+                head_height,
+            });
+        }
+
         // Send the acknowledgement for the state witness back to the chunk producer.
         // This is currently used for network roundtrip time measurement, so we do not need to
         // wait for validation to finish.
@@ -4,17 +4,42 @@ use near_chain::Chain;
 use near_chain::{ChainStoreAccess, check_known};
 use near_client_primitives::types::SyncStatus;
 use near_network::types::PeerManagerMessageRequest;
-use near_network::types::{HighestHeightPeerInfo, NetworkRequests, PeerManagerAdapter};
+use near_network::types::{
+    BlockRangeRequest, BlockRangeResponse, HighestHeightPeerInfo, NetworkRequests,
+    PeerManagerAdapter,
+};
 use near_o11y::log_assert;
 use near_primitives::block::Tip;
 use near_primitives::hash::CryptoHash;
+use near_primitives::network::PeerId;
 use near_primitives::types::{BlockHeight, BlockHeightDelta};
 use rand::seq::IteratorRandom;
+use std::collections::HashMap;
 use tracing::{debug, warn};
 
 /// Expect to receive the requested block in this time.
 const BLOCK_REQUEST_TIMEOUT_MS: i64 = 2_000;
 
+/// Number of blocks requested by a single BlockRangeRequest.
+/// The ranges are aligned to multiples of this number.
+pub const BLOCK_RANGE_REQUEST_COUNT: u64 = 50;
+
+/// Maximum number of blocks sent in response to a single BlockRangeRequest.
+pub const MAX_BLOCK_RANGE_REQUEST_COUNT: u64 = 100;
+
+/// Expect to receive the last part of the response to a BlockRangeRequest in this time,
+/// and to process the received blocks in this time after the last part.
+const BLOCK_RANGE_REQUEST_TIMEOUT_MS: i64 = 10_000;
+
+/// BlockRangeRequest waiting for the response, or for its blocks to be processed.
+struct BlockRangeInFlight {
+    peer_id: PeerId,
+    /// When the range was requested, or when the last part of the response was received.
+    when: Utc,
+    /// Whether the last part of the response was received.
+    received: bool,
+}
+
 #[derive(Clone)]
 pub struct BlockSyncRequest {
     // Head of the chain at the time of the last requests.
@@ -40,6 +65,11 @@ pub struct BlockSync {
 
     /// Whether State Sync should be enabled when a node falls far enough behind.
     state_sync_enabled: bool,
+
+    /// BlockRangeRequests sent to the archival peers, keyed by the start height. A range stays
+    /// here until the head passes it, so that it is not requested again while its blocks are
+    /// being processed.
+    block_ranges_in_flight: HashMap<BlockHeight, BlockRangeInFlight>,
 }
 
 impl BlockSync {
@@ -57,6 +87,7 @@ impl BlockSync {
             block_fetch_horizon,
             archive,
             state_sync_enabled,
+            block_ranges_in_flight: HashMap::new(),
         }
     }
 
@@ -211,8 +242,23 @@ impl BlockSync {
         // [gc_stop_height, header_head.last_block_hash].
         let gc_stop_height = chain.runtime_adapter.get_gc_stop_height(&header_head.last_block_hash);
 
+        // Blocks below this height are requested with BlockRangeRequests.
+        let block_ranges_end = if self.archive {
+            self.request_block_ranges(
+                chain.get_block_header(&reference_hash)?.height() + 1,
+                gc_stop_height,
+                highest_height_peers,
+                max_block_requests,
+            )
+        } else {
+            0
+        };
+
         let mut num_requests = 0;
         for (height, hash) in requests {
+            if height < block_ranges_end {
+                continue;
+            }
             let request_from_archival = self.archive && height < gc_stop_height;
             // Assume that heads of `highest_height_peers` are ahead of the blocks we're requesting.
             let peer = if request_from_archival {
@@ -261,6 +307,125 @@ impl BlockSync {
         Ok(())
     }
 
+    /// Requests the blocks in `[start_height, end_height)` from the archival peers serving
+    /// block ranges. Up to `max_requests` BlockRangeRequests are kept in flight, so that
+    /// the blocks are downloaded while the previous ones are being processed.
+    /// Returns the height below which all the blocks have been requested.
+    fn request_block_ranges(
+        &mut self,
+        start_height: BlockHeight,
+        end_height: BlockHeight,
+        highest_height_peers: &[HighestHeightPeerInfo],
+        max_requests: usize,
+    ) -> BlockHeight {
+        let now = self.clock.now_utc();
+        let timeout = Duration::milliseconds(BLOCK_RANGE_REQUEST_TIMEOUT_MS);
+        self.block_ranges_in_flight.retain(|range_start, request| {
+            range_start + BLOCK_RANGE_REQUEST_COUNT > start_height && now - request.when <= timeout
+        });
+
+        let mut range_start = start_height - start_height % BLOCK_RANGE_REQUEST_COUNT;
+        while range_start < end_height {
+            if !self.block_ranges_in_flight.contains_key(&range_start) {
+                if self.block_ranges_in_flight.len() >= max_requests {
+                    break;
+                }
+                let range_end = range_start + BLOCK_RANGE_REQUEST_COUNT;
+                let peer = highest_height_peers
+                    .iter()
+                    .filter(|p| {
+                        p.archival
+                            && p.block_range_start.is_some_and(|start| start <= range_start)
+                            && p.highest_block_height >= range_end.min(end_height)
+                    })
+                    .choose(&mut rand::thread_rng());
+                let Some(peer) = peer else {
+                    break;
+                };
+                debug!(
+                    target: "sync",
+                    range_start,
+                    peer = ?peer.peer_info.id,
+                    "Block sync: requested block range"
+                );
+                self.network_adapter.send(PeerManagerMessageRequest::NetworkRequests(
+                    NetworkRequests::BlockRangeRequest {
+                        peer_id: peer.peer_info.id.clone(),
+                        request: BlockRangeRequest {
+                            start_height: range_start,
+                            count: BLOCK_RANGE_REQUEST_COUNT,
+                        },
+                    },
+                ));
+                self.block_ranges_in_flight.insert(
+                    range_start,
+                    BlockRangeInFlight {
+                        peer_id: peer.peer_info.id.clone(),
+                        when: now,
+                        received: false,
+                    },
+                );
+            }
+            range_start += BLOCK_RANGE_REQUEST_COUNT;
+        }
+        range_start
+    }
+
+    /// Verifies a part of the response to a BlockRangeRequest sent to `peer_id`: the blocks
+    /// need to be within the requested range, form a chain and the last one needs to be on
+    /// the canonical chain of headers. A chain of blocks ending with a canonical one is
+    /// canonical as a whole, so the blocks can be processed as the requested ones.
+    pub fn receive_block_range_response(
+        &mut self,
+        chain: &Chain,
+        peer_id: &PeerId,
+        response: &BlockRangeResponse,
+    ) -> Result<(), near_chain::Error> {
+        let start_height = response.start_height;
+        match self.block_ranges_in_flight.get(&start_height) {
+            Some(request) if &request.peer_id == peer_id && !request.received => {}
+            _ => {
+                return Err(near_chain::Error::Other(format!(
+                    "block range {start_height} was not requested from {peer_id}"
+                )));
+            }
+        }
+
+        let end_height = start_height + BLOCK_RANGE_REQUEST_COUNT;
+        let mut prev_hash = None;
+        for block in &response.blocks {
+            let height = block.header().height();
+            if height < start_height || height >= end_height {
+                return Err(near_chain::Error::Other(format!(
+                    "block at height {height} is outside of block range {start_height}"
+                )));
+            }
+            if prev_hash.is_some_and(|prev_hash| &prev_hash != block.header().prev_hash()) {
+                return Err(near_chain::Error::Other(format!(
+                    "blocks of block range {start_height} do not form a chain"
+                )));
+            }
+            prev_hash = Some(*block.hash());
+        }
+        if let Some(last) = response.blocks.last() {
+            let canonical = chain.get_block_header_by_height(last.header().height())?;
+            if canonical.hash() != last.hash() {
+                return Err(near_chain::Error::Other(format!(
+                    "block {} of block range {start_height} is not canonical",
+                    last.hash()
+                )));
+            }
+        }
+        if response.last {
+            // The range stays in flight until its blocks are processed.
+            if let Some(request) = self.block_ranges_in_flight.get_mut(&start_height) {
+                request.received = true;
+                request.when = self.clock.now_utc();
+            }
+        }
+        Ok(())
+    }
+
     /// Checks if we should run block sync and ask for more full blocks.
     /// Block sync is due either if the chain head has changed since the last request
     /// or if time since the last request is > BLOCK_REQUEST_TIMEOUT_MS
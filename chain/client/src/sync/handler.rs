@@ -45,7 +45,7 @@ pub struct SyncHandler {
     /// Keeps track of syncing state.
     pub state_sync: StateSync,
     /// Keeps track of syncing block.
-    block_sync: BlockSync,
+    pub block_sync: BlockSync,
 }
 
 /// Request to the client to perform some action to continue syncing.
@@ -152,8 +152,13 @@ impl HeaderSync {
         });
 
         self.syncing_peer = None;
-        // Pick a new random peer to request the next batch of headers.
-        if let Some(peer) = highest_height_peers.choose(&mut thread_rng()).cloned() {
+        // Pick a new random peer to request the next batch of headers,
+        // preferring the peers with a better reputation.
+        if let Some(peer) = highest_height_peers
+            .choose_weighted(&mut thread_rng(), |peer| peer.reputation + 1)
+            .ok()
+            .cloned()
+        {
             let shutdown_height = self.shutdown_height.get().unwrap_or(u64::MAX);
             let highest_height = peer.highest_block_height.min(shutdown_height);
             if highest_height > header_head.height {
@@ -510,6 +515,7 @@ mod test {
                 },
                 tracked_shards: vec![],
                 archival: false,
+                block_range_start: None,
                 last_block: Some(BlockInfo {
                     height: chain2.head().unwrap().height,
                     hash: chain2.head().unwrap().last_block_hash,
@@ -614,6 +620,7 @@ mod test {
                 },
                 tracked_shards: vec![],
                 archival: false,
+                block_range_start: None,
                 last_block: Some(BlockInfo {
                     height: chain2.head().unwrap().height,
                     hash: chain2.head().unwrap().last_block_hash,
@@ -683,6 +690,8 @@ mod test {
                 highest_block_hash: Default::default(),
                 tracked_shards: vec![],
                 archival: false,
+                block_range_start: None,
+                reputation: 0,
             });
             header_sync.syncing_peer.as_mut().unwrap().highest_block_height = highest_height;
         };
@@ -803,6 +812,7 @@ mod test {
                                 this_height,
                                 signer.as_ref(),
                             )
+                            .unwrap()
                             .signature,
                         ))
                     })
@@ -817,7 +827,8 @@ mod test {
                 clock.clock(),
                 None,
                 None,
-            );
+            )
+            .unwrap();
             block_merkle_tree.insert(*block.hash());
             chain2.process_block_header(block.header()).unwrap(); // just to validate
             process_block_sync(
@@ -839,6 +850,7 @@ mod test {
                 },
                 tracked_shards: vec![],
                 archival: false,
+                block_range_start: None,
                 last_block: Some(BlockInfo {
                     height: chain2.head().unwrap().height,
                     hash: chain2.head().unwrap().last_block_hash,
@@ -0,0 +1,618 @@
+//! Validator alert engine.
+//!
+//! Periodically evaluates the performance of a validator in the current epoch against the
+//! rules configured in [`ValidatorAlertsConfig`] and reports the rules that fire to the
+//! configured sinks. The statistics are the same as the ones returned by the `validators`
+//! RPC, and the thresholds are taken from the epoch config, so the predictions match what
+//! the epoch manager computes at the end of the epoch.
+
+use crate::metrics;
+use near_async::time::{Duration, Utc};
+use near_chain_configs::{
+    ValidatorAlertMetric, ValidatorAlertRule, ValidatorAlertSink, ValidatorAlertsConfig,
+};
+use near_epoch_manager::ValidatorOnlineThresholds;
+use near_primitives::epoch_manager::EpochConfig;
+use near_primitives::types::{
+    AccountId, BlockChunkValidatorStats, BlockHeight, BlockHeightDelta, ChunkStats, EpochHeight,
+    ValidatorInfoIdentifier, ValidatorStats,
+};
+use near_primitives::views::CurrentEpochValidatorInfo;
+use std::collections::{HashMap, VecDeque};
+use std::io::Write;
+use tracing::{debug, warn};
+
+#[derive(serde::Serialize, Clone, Copy, Debug, PartialEq, Eq, strum::IntoStaticStr)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum AlertSeverity {
+    /// The validator is underperforming, but may still recover before the end of the epoch.
+    Warning,
+    /// The validator will be kicked out even if it performs perfectly until the end of the epoch.
+    Critical,
+}
+
+/// Alert reported to the sinks, serialized as a single JSON object.
+#[derive(serde::Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct ValidatorAlert {
+    /// Name of the rule that fired.
+    pub rule: &'static str,
+    /// Validator duty the alert is about, if the rule is about a single duty.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metric: Option<ValidatorAlertMetric>,
+    pub severity: AlertSeverity,
+    pub account_id: AccountId,
+    pub epoch_height: EpochHeight,
+    pub block_height: BlockHeight,
+    /// Unix timestamp (in seconds) at which the alert was fired.
+    pub timestamp: i64,
+    pub message: String,
+}
+
+/// Performance of the monitored validator in the current epoch, as of `block_height`.
+struct ValidatorSnapshot {
+    account_id: AccountId,
+    block_height: BlockHeight,
+    epoch_height: EpochHeight,
+    epoch_start_height: BlockHeight,
+    stats: BlockChunkValidatorStats,
+}
+
+impl ValidatorSnapshot {
+    fn new(
+        info: &CurrentEpochValidatorInfo,
+        block_height: BlockHeight,
+        epoch_height: EpochHeight,
+        epoch_start_height: BlockHeight,
+    ) -> Self {
+        Self {
+            account_id: info.account_id.clone(),
+            block_height,
+            epoch_height,
+            epoch_start_height,
+            stats: BlockChunkValidatorStats {
+                block_stats: ValidatorStats {
+                    produced: info.num_produced_blocks,
+                    expected: info.num_expected_blocks,
+                },
+                chunk_stats: ChunkStats::new(
+                    info.num_produced_chunks,
+                    info.num_expected_chunks,
+                    info.num_produced_endorsements,
+                    info.num_expected_endorsements,
+                ),
+            },
+        }
+    }
+}
+
+/// Cumulative stats of the current epoch, sampled at some block height.
+#[derive(Clone)]
+struct StatsSample {
+    block_height: BlockHeight,
+    blocks: ValidatorStats,
+    chunks: ValidatorStats,
+    endorsements: ValidatorStats,
+}
+
+impl StatsSample {
+    fn new(block_height: BlockHeight, stats: &BlockChunkValidatorStats) -> Self {
+        Self {
+            block_height,
+            blocks: stats.block_stats.clone(),
+            chunks: stats.chunk_stats.production_stats().clone(),
+            endorsements: stats.chunk_stats.endorsement_stats().clone(),
+        }
+    }
+
+    fn zero(block_height: BlockHeight) -> Self {
+        let zero = ValidatorStats { produced: 0, expected: 0 };
+        Self { block_height, blocks: zero.clone(), chunks: zero.clone(), endorsements: zero }
+    }
+
+    fn get(&self, metric: ValidatorAlertMetric) -> &ValidatorStats {
+        match metric {
+            ValidatorAlertMetric::Blocks => &self.blocks,
+            ValidatorAlertMetric::Chunks => &self.chunks,
+            ValidatorAlertMetric::Endorsements => &self.endorsements,
+        }
+    }
+}
+
+pub struct ValidatorAlertEngine {
+    config: ValidatorAlertsConfig,
+    http_client: reqwest::Client,
+    /// Epoch the samples belong to. Samples are dropped when the epoch changes.
+    epoch_height: Option<EpochHeight>,
+    /// Samples of the cumulative epoch stats, ordered by block height.
+    samples: VecDeque<StatsSample>,
+    /// Time at which each rule was last reported, used to throttle repeated alerts.
+    last_reported: HashMap<String, Utc>,
+}
+
+impl ValidatorAlertEngine {
+    pub fn new(config: ValidatorAlertsConfig) -> Self {
+        Self {
+            config,
+            http_client: reqwest::Client::new(),
+            epoch_height: None,
+            samples: VecDeque::new(),
+            last_reported: HashMap::new(),
+        }
+    }
+
+    pub fn check_period(&self) -> Duration {
+        self.config.check_period
+    }
+
+    /// Evaluates all rules against the current head of the chain and reports the alerts.
+    pub fn check(&mut self, now: Utc, client: &crate::client::Client) {
+        let _span = tracing::debug_span!(target: "client", "check_validator_alerts").entered();
+        // Computing validator info is expensive while syncing, and the stats are meaningless
+        // until the node catches up anyway.
+        if client.sync_handler.sync_status.is_syncing() {
+            return;
+        }
+        let Some(account_id) =
+            self.config.account_id.clone().or_else(|| {
+                client.validator_signer.get().map(|signer| signer.validator_id().clone())
+            })
+        else {
+            return;
+        };
+        let head = match client.chain.head() {
+            Ok(head) => head,
+            Err(err) => {
+                debug!(target: "client", ?err, "Failed to get head for validator alerts");
+                return;
+            }
+        };
+        let epoch_config = match client.epoch_manager.get_epoch_config(&head.epoch_id) {
+            Ok(epoch_config) => epoch_config,
+            Err(err) => {
+                debug!(target: "client", ?err, "Failed to get epoch config for validator alerts");
+                return;
+            }
+        };
+        let validator_info = match client
+            .epoch_manager
+            .get_validator_info(ValidatorInfoIdentifier::BlockHash(head.last_block_hash))
+        {
+            Ok(validator_info) => validator_info,
+            Err(err) => {
+                debug!(target: "client", ?err, "Failed to get validator info for validator alerts");
+                return;
+            }
+        };
+        // Nothing to monitor if the account is not a validator in the current epoch.
+        let Some(info) =
+            validator_info.current_validators.iter().find(|info| info.account_id == account_id)
+        else {
+            return;
+        };
+        let snapshot = ValidatorSnapshot::new(
+            info,
+            head.height,
+            validator_info.epoch_height,
+            validator_info.epoch_start_height,
+        );
+        let alerts = self.evaluate(now, &snapshot, &epoch_config);
+        let alerts = self.throttle(now, alerts);
+        for alert in &alerts {
+            self.report(alert);
+        }
+    }
+
+    /// Evaluates all rules for the given snapshot, without throttling.
+    fn evaluate(
+        &mut self,
+        now: Utc,
+        snapshot: &ValidatorSnapshot,
+        epoch_config: &EpochConfig,
+    ) -> Vec<ValidatorAlert> {
+        self.record_sample(snapshot);
+        let mut alerts = vec![];
+        let mut fire = |rule: &'static str,
+                        metric: Option<ValidatorAlertMetric>,
+                        severity: AlertSeverity,
+                        message: String| {
+            alerts.push(ValidatorAlert {
+                rule,
+                metric,
+                severity,
+                account_id: snapshot.account_id.clone(),
+                epoch_height: snapshot.epoch_height,
+                block_height: snapshot.block_height,
+                timestamp: now.unix_timestamp(),
+                message,
+            })
+        };
+        for rule in &self.config.rules {
+            match rule {
+                ValidatorAlertRule::ProductionRatio { metric, threshold, window } => {
+                    let Some(stats) = self.window_stats(snapshot, *metric, *window) else {
+                        continue;
+                    };
+                    if stats.less_than(*threshold) {
+                        fire(
+                            "production_ratio",
+                            Some(*metric),
+                            AlertSeverity::Warning,
+                            format!(
+                                "{} ratio over the last {} blocks is {}/{}, below {}%",
+                                metric_name(*metric),
+                                window,
+                                stats.produced,
+                                stats.expected,
+                                threshold,
+                            ),
+                        );
+                    }
+                }
+                ValidatorAlertRule::OnlineRatio => {
+                    let stats = &snapshot.stats;
+                    let nothing_expected = stats.block_stats.expected == 0
+                        && stats.chunk_stats.expected() == 0
+                        && stats.chunk_stats.endorsement_stats().expected == 0;
+                    let thresholds = ValidatorOnlineThresholds::from_epoch_config(epoch_config);
+                    if !nothing_expected && thresholds.is_below_online_min_threshold(stats) {
+                        fire(
+                            "online_ratio",
+                            None,
+                            AlertSeverity::Warning,
+                            format!(
+                                "online ratio is below the minimum threshold of {}, \
+                                no reward will be paid for this epoch",
+                                thresholds.online_min_threshold,
+                            ),
+                        );
+                    }
+                }
+                ValidatorAlertRule::KickoutRisk => {
+                    for (metric, severity, message) in kickout_risks(snapshot, epoch_config) {
+                        fire(
+                            "kickout_risk",
+                            Some(metric),
+                            severity,
+                            format!("{}: {}", metric_name(metric), message),
+                        );
+                    }
+                }
+            }
+        }
+        alerts
+    }
+
+    fn record_sample(&mut self, snapshot: &ValidatorSnapshot) {
+        if self.epoch_height != Some(snapshot.epoch_height) {
+            self.epoch_height = Some(snapshot.epoch_height);
+            self.samples.clear();
+        }
+        if self.samples.back().is_some_and(|last| last.block_height >= snapshot.block_height) {
+            return;
+        }
+        self.samples.push_back(StatsSample::new(snapshot.block_height, &snapshot.stats));
+        // Keep one sample at or before the start of the largest window.
+        let max_window = self
+            .config
+            .rules
+            .iter()
+            .filter_map(|rule| match rule {
+                ValidatorAlertRule::ProductionRatio { window, .. } => Some(*window),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0);
+        let window_start = snapshot.block_height.saturating_sub(max_window);
+        while self.samples.len() > 1 && self.samples[1].block_height <= window_start {
+            self.samples.pop_front();
+        }
+    }
+
+    /// Returns the stats of `metric` accumulated over the last `window` blocks, or `None`
+    /// if the validator wasn't expected to do anything in that window.
+    /// The window is truncated to the start of the epoch, and to the oldest known sample
+    /// if the node started monitoring in the middle of the window.
+    fn window_stats(
+        &self,
+        snapshot: &ValidatorSnapshot,
+        metric: ValidatorAlertMetric,
+        window: BlockHeightDelta,
+    ) -> Option<ValidatorStats> {
+        let current = StatsSample::new(snapshot.block_height, &snapshot.stats);
+        let window_start = snapshot.block_height.saturating_sub(window);
+        let baseline = if snapshot.epoch_start_height >= window_start {
+            StatsSample::zero(snapshot.epoch_start_height)
+        } else {
+            self.samples
+                .iter()
+                .rev()
+                .find(|sample| sample.block_height <= window_start)
+                .or_else(|| self.samples.front())
+                .cloned()?
+        };
+        let current = current.get(metric);
+        let baseline = baseline.get(metric);
+        let stats = ValidatorStats {
+            produced: current.produced.saturating_sub(baseline.produced),
+            expected: current.expected.saturating_sub(baseline.expected),
+        };
+        (stats.expected > 0).then_some(stats)
+    }
+
+    /// Drops the alerts that were already reported within the repeat interval.
+    /// Alerts are identified by rule, metric and severity, so that an escalation
+    /// from warning to critical is reported immediately.
+    fn throttle(&mut self, now: Utc, alerts: Vec<ValidatorAlert>) -> Vec<ValidatorAlert> {
+        alerts
+            .into_iter()
+            .filter(|alert| {
+                let key = format!("{}/{:?}/{:?}", alert.rule, alert.metric, alert.severity);
+                match self.last_reported.get(&key) {
+                    Some(last) if now < *last + self.config.repeat_interval => false,
+                    _ => {
+                        self.last_reported.insert(key, now);
+                        true
+                    }
+                }
+            })
+            .collect()
+    }
+
+    fn report(&self, alert: &ValidatorAlert) {
+        let severity: &'static str = alert.severity.into();
+        metrics::VALIDATOR_ALERTS_TOTAL.with_label_values(&[alert.rule, severity]).inc();
+        let json = match serde_json::to_string(alert) {
+            Ok(json) => json,
+            Err(err) => {
+                warn!(target: "client", ?err, "Failed to serialize validator alert");
+                return;
+            }
+        };
+        for sink in &self.config.sinks {
+            match sink {
+                ValidatorAlertSink::Stdout => println!("{}", json),
+                ValidatorAlertSink::File { path } => {
+                    let result = std::fs::OpenOptions::new()
+                        .create(true)
+                        .append(true)
+                        .open(path)
+                        .and_then(|mut file| writeln!(file, "{}", json));
+                    if let Err(err) = result {
+                        warn!(target: "client", ?err, ?path, "Failed to write validator alert");
+                    }
+                }
+                ValidatorAlertSink::Webhook { url } => {
+                    let request = self
+                        .http_client
+                        .post(url)
+                        .header(reqwest::header::CONTENT_TYPE, "application/json")
+                        .body(json.clone());
+                    let url = url.clone();
+                    near_performance_metrics::actix::spawn("ValidatorAlerts", async move {
+                        let result = request.send().await.and_then(|r| r.error_for_status());
+                        if let Err(err) = result {
+                            warn!(target: "client", ?err, %url, "Failed to send validator alert");
+                        }
+                    });
+                }
+            }
+        }
+    }
+}
+
+fn metric_name(metric: ValidatorAlertMetric) -> &'static str {
+    match metric {
+        ValidatorAlertMetric::Blocks => "block production",
+        ValidatorAlertMetric::Chunks => "chunk production",
+        ValidatorAlertMetric::Endorsements => "chunk endorsement",
+    }
+}
+
+/// Predicts whether the validator will be kicked out at the end of the epoch, using the same
+/// thresholds as the epoch manager. The number of expected blocks, chunks and endorsements
+/// at the end of the epoch is extrapolated from the rate observed so far.
+fn kickout_risks(
+    snapshot: &ValidatorSnapshot,
+    epoch_config: &EpochConfig,
+) -> Vec<(ValidatorAlertMetric, AlertSeverity, String)> {
+    let stats = &snapshot.stats;
+    let elapsed = snapshot.block_height.saturating_sub(snapshot.epoch_start_height) + 1;
+    let epoch_end_height = snapshot.epoch_start_height + epoch_config.epoch_length;
+    let remaining = epoch_end_height.saturating_sub(snapshot.block_height + 1);
+
+    let mut checks = vec![
+        (
+            ValidatorAlertMetric::Blocks,
+            &stats.block_stats,
+            epoch_config.block_producer_kickout_threshold,
+        ),
+        (
+            ValidatorAlertMetric::Chunks,
+            stats.chunk_stats.production_stats(),
+            epoch_config.chunk_producer_kickout_threshold,
+        ),
+    ];
+    // Endorsements only count towards kickouts of validators which don't produce anything.
+    if stats.block_stats.expected == 0 && stats.chunk_stats.expected() == 0 {
+        checks.push((
+            ValidatorAlertMetric::Endorsements,
+            stats.chunk_stats.endorsement_stats(),
+            epoch_config.chunk_validator_only_kickout_threshold,
+        ));
+    }
+
+    let mut risks = vec![];
+    for (metric, stats, threshold) in checks {
+        if stats.expected == 0 || !stats.less_than(threshold) {
+            continue;
+        }
+        let additional = ((stats.expected as u128 * remaining as u128) / elapsed as u128) as u64;
+        let best_case = ValidatorStats {
+            produced: stats.produced + additional,
+            expected: stats.expected + additional,
+        };
+        let (severity, outlook) = if best_case.less_than(threshold) {
+            (AlertSeverity::Critical, "kickout is certain")
+        } else {
+            (AlertSeverity::Warning, "kickout is likely unless performance improves")
+        };
+        risks.push((
+            metric,
+            severity,
+            format!(
+                "produced {}/{} which is below the kickout threshold of {}%, \
+                {} before epoch end at height {}",
+                stats.produced, stats.expected, threshold, outlook, epoch_end_height,
+            ),
+        ));
+    }
+    risks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_primitives::epoch_manager::EpochConfig;
+
+    fn config(rules: Vec<ValidatorAlertRule>) -> ValidatorAlertsConfig {
+        ValidatorAlertsConfig { rules, sinks: vec![], ..Default::default() }
+    }
+
+    fn epoch_config() -> EpochConfig {
+        let mut epoch_config = EpochConfig::minimal();
+        epoch_config.epoch_length = 100;
+        epoch_config.block_producer_kickout_threshold = 80;
+        epoch_config.chunk_producer_kickout_threshold = 80;
+        epoch_config.chunk_validator_only_kickout_threshold = 70;
+        epoch_config.online_min_threshold = num_rational::Rational32::new(90, 100);
+        epoch_config.online_max_threshold = num_rational::Rational32::new(99, 100);
+        epoch_config
+    }
+
+    fn snapshot(
+        block_height: BlockHeight,
+        blocks: (u64, u64),
+        chunks: (u64, u64),
+        endorsements: (u64, u64),
+    ) -> ValidatorSnapshot {
+        ValidatorSnapshot {
+            account_id: "test.near".parse().unwrap(),
+            block_height,
+            epoch_height: 1,
+            epoch_start_height: 0,
+            stats: BlockChunkValidatorStats {
+                block_stats: ValidatorStats { produced: blocks.0, expected: blocks.1 },
+                chunk_stats: ChunkStats::new(chunks.0, chunks.1, endorsements.0, endorsements.1),
+            },
+        }
+    }
+
+    #[test]
+    fn test_production_ratio_window() {
+        let clock = near_async::time::FakeClock::default();
+        let rule = ValidatorAlertRule::ProductionRatio {
+            metric: ValidatorAlertMetric::Endorsements,
+            threshold: 90,
+            window: 10,
+        };
+        let mut engine = ValidatorAlertEngine::new(config(vec![rule]));
+        let epoch_config = epoch_config();
+
+        // Perfect endorsement record over the first 20 blocks.
+        for height in 1..=20 {
+            let alerts = engine.evaluate(
+                clock.now_utc(),
+                &snapshot(height, (0, 0), (0, 0), (height, height)),
+                &epoch_config,
+            );
+            assert!(alerts.is_empty());
+        }
+        // Missing all endorsements for the next 5 blocks: 15/20 over the whole epoch,
+        // but only 5/10 over the window.
+        for height in 21..=25 {
+            let alerts = engine.evaluate(
+                clock.now_utc(),
+                &snapshot(height, (0, 0), (0, 0), (20, height)),
+                &epoch_config,
+            );
+            assert_eq!(alerts.is_empty(), height < 22, "height {}", height);
+        }
+    }
+
+    #[test]
+    fn test_online_ratio() {
+        let clock = near_async::time::FakeClock::default();
+        let mut engine = ValidatorAlertEngine::new(config(vec![ValidatorAlertRule::OnlineRatio]));
+        let epoch_config = epoch_config();
+
+        let alerts = engine.evaluate(
+            clock.now_utc(),
+            &snapshot(10, (10, 10), (10, 10), (0, 0)),
+            &epoch_config,
+        );
+        assert!(alerts.is_empty());
+        let alerts = engine.evaluate(
+            clock.now_utc(),
+            &snapshot(20, (15, 20), (15, 20), (0, 0)),
+            &epoch_config,
+        );
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].rule, "online_ratio");
+    }
+
+    #[test]
+    fn test_kickout_risk() {
+        let clock = near_async::time::FakeClock::default();
+        let mut engine = ValidatorAlertEngine::new(config(vec![ValidatorAlertRule::KickoutRisk]));
+        let epoch_config = epoch_config();
+
+        // Below the threshold at the middle of the epoch, but can still recover.
+        let alerts = engine.evaluate(
+            clock.now_utc(),
+            &snapshot(49, (35, 50), (50, 50), (0, 0)),
+            &epoch_config,
+        );
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].severity, AlertSeverity::Warning);
+        assert!(alerts[0].message.starts_with("block production"));
+
+        // Too many blocks missed to recover before the end of the epoch.
+        let alerts = engine.evaluate(
+            clock.now_utc(),
+            &snapshot(89, (50, 90), (90, 90), (0, 0)),
+            &epoch_config,
+        );
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].severity, AlertSeverity::Critical);
+
+        // Endorsements are ignored for validators which also produce blocks or chunks.
+        let alerts = engine.evaluate(
+            clock.now_utc(),
+            &snapshot(89, (90, 90), (90, 90), (0, 90)),
+            &epoch_config,
+        );
+        assert!(alerts.is_empty());
+        let alerts =
+            engine.evaluate(clock.now_utc(), &snapshot(89, (0, 0), (0, 0), (0, 90)), &epoch_config);
+        assert_eq!(alerts.len(), 1);
+        assert!(alerts[0].message.starts_with("chunk endorsement"));
+    }
+
+    #[test]
+    fn test_throttle() {
+        let clock = near_async::time::FakeClock::default();
+        let mut engine = ValidatorAlertEngine::new(config(vec![ValidatorAlertRule::OnlineRatio]));
+        let epoch_config = epoch_config();
+        let snapshot = snapshot(20, (0, 20), (0, 20), (0, 0));
+
+        let alerts = engine.evaluate(clock.now_utc(), &snapshot, &epoch_config);
+        assert_eq!(engine.throttle(clock.now_utc(), alerts).len(), 1);
+        clock.advance(Duration::minutes(1));
+        let alerts = engine.evaluate(clock.now_utc(), &snapshot, &epoch_config);
+        assert!(engine.throttle(clock.now_utc(), alerts).is_empty());
+        clock.advance(engine.config.repeat_interval);
+        let alerts = engine.evaluate(clock.now_utc(), &snapshot, &epoch_config);
+        assert_eq!(engine.throttle(clock.now_utc(), alerts).len(), 1);
+    }
+}
@@ -19,27 +19,29 @@ use near_chain_configs::{ClientConfig, MutableValidatorSigner, ProtocolConfigVie
 use near_chain_primitives::error::EpochErrorResultToChainError;
 use near_client_primitives::types::{
     Error, GetBlock, GetBlockError, GetBlockProof, GetBlockProofError, GetBlockProofResponse,
-    GetBlockWithMerkleTree, GetChunkError, GetExecutionOutcome, GetExecutionOutcomeError,
-    GetExecutionOutcomesForBlock, GetGasPrice, GetGasPriceError, GetMaintenanceWindows,
-    GetMaintenanceWindowsError, GetNextLightClientBlockError, GetProtocolConfig,
-    GetProtocolConfigError, GetReceipt, GetReceiptError, GetSplitStorageInfo,
-    GetSplitStorageInfoError, GetStateChangesError, GetStateChangesWithCauseInBlock,
-    GetStateChangesWithCauseInBlockForTrackedShards, GetValidatorInfoError, Query, QueryError,
-    TxStatus, TxStatusError,
+    GetBlockWithMerkleTree, GetChunkApplyStats, GetChunkApplyStatsError, GetChunkError,
+    GetExecutionOutcome, GetExecutionOutcomeError, GetExecutionOutcomesForBlock, GetGasPrice,
+    GetGasPriceError, GetMaintenanceWindows, GetMaintenanceWindowsError,
+    GetNextLightClientBlockError, GetProtocolConfig, GetProtocolConfigError, GetReceipt,
+    GetReceiptError, GetSplitStorageInfo, GetSplitStorageInfoError, GetStateChangesError,
+    GetStateChangesWithCauseInBlock, GetStateChangesWithCauseInBlockForTrackedShards,
+    GetValidatorForecast, GetValidatorInfoError, Query, QueryError, TxStatus, TxStatusError,
 };
 use near_epoch_manager::EpochManagerAdapter;
 use near_epoch_manager::shard_assignment::{account_id_to_shard_id, shard_id_to_uid};
 use near_epoch_manager::shard_tracker::ShardTracker;
 use near_network::client::{
-    AnnounceAccountRequest, BlockHeadersRequest, BlockRequest, StateRequestHeader,
-    StateRequestPart, StateResponse, TxStatusRequest, TxStatusResponse,
+    AnnounceAccountRequest, BlockHeadersRequest, BlockRangeRequestMessage, BlockRequest,
+    CompactBlockRequestMessage, StateRequestHeader, StateRequestPart, StateResponse,
+    TxStatusRequest, TxStatusResponse,
 };
 use near_network::types::{
-    NetworkRequests, PeerManagerAdapter, PeerManagerMessageRequest, ReasonForBan,
-    StateResponseInfo, StateResponseInfoV2,
+    BlockRangeResponse, CompactBlockResponse, NetworkRequests, PeerManagerAdapter,
+    PeerManagerMessageRequest, ReasonForBan, StateResponseInfo, StateResponseInfoV2,
 };
 use near_performance_metrics_macros::perf;
 use near_primitives::block::{Block, BlockHeader};
+use near_primitives::chunk_apply_stats::ChunkApplyStats;
 use near_primitives::epoch_info::EpochInfo;
 use near_primitives::errors::EpochError;
 use near_primitives::hash::CryptoHash;
@@ -58,11 +60,11 @@ use near_primitives::types::{
 use near_primitives::validator_signer::ValidatorSigner;
 use near_primitives::views::validator_stake_view::ValidatorStakeView;
 use near_primitives::views::{
-    BlockView, ChunkView, EpochValidatorInfo, ExecutionOutcomeWithIdView, ExecutionStatusView,
-    FinalExecutionOutcomeView, FinalExecutionOutcomeViewEnum, FinalExecutionStatus, GasPriceView,
-    LightClientBlockView, MaintenanceWindowsView, QueryRequest, QueryResponse, ReceiptView,
-    SignedTransactionView, SplitStorageInfoView, StateChangesKindsView, StateChangesView,
-    TxExecutionStatus, TxStatusView,
+    BlockView, ChunkView, EpochValidatorForecast, EpochValidatorInfo, ExecutionOutcomeWithIdView,
+    ExecutionStatusView, FinalExecutionOutcomeView, FinalExecutionOutcomeViewEnum,
+    FinalExecutionStatus, GasPriceView, LightClientBlockView, MaintenanceWindowsView, QueryRequest,
+    QueryResponse, ReceiptView, SignedTransactionView, SplitStorageInfoView, StateChangesKindsView,
+    StateChangesView, TxExecutionStatus, TxStatusView,
 };
 use near_store::{COLD_HEAD_KEY, DBCol, FINAL_HEAD_KEY, HEAD_KEY};
 use parking_lot::{Mutex, RwLock};
@@ -77,6 +79,8 @@ use tracing::{error, info, warn};
 const QUERY_REQUEST_LIMIT: usize = 500;
 /// Waiting time between requests, in ms
 const REQUEST_WAIT_TIME: i64 = 1000;
+/// Max size of the blocks sent in a single part of a block range response.
+const MAX_BLOCK_RANGE_RESPONSE_PART_SIZE: usize = 4 * bytesize::MIB as usize;
 
 /// Request and response manager across all instances of ViewClientActor.
 pub struct ViewClientRequestManager {
@@ -836,6 +840,27 @@ impl Handler<GetChunk> for ViewClientActorInner {
     }
 }
 
+impl Handler<GetChunkApplyStats> for ViewClientActorInner {
+    #[perf]
+    fn handle(
+        &mut self,
+        msg: GetChunkApplyStats,
+    ) -> Result<ChunkApplyStats, GetChunkApplyStatsError> {
+        tracing::debug!(target: "client", ?msg);
+        let _timer = metrics::VIEW_CLIENT_MESSAGE_TIME
+            .with_label_values(&["GetChunkApplyStats"])
+            .start_timer();
+        let block_hash = match msg.block_id {
+            BlockId::Height(height) => *self.chain.get_block_header_by_height(height)?.hash(),
+            BlockId::Hash(hash) => *self.chain.get_block_header(&hash)?.hash(),
+        };
+        self.chain
+            .chain_store()
+            .get_chunk_apply_stats(&block_hash, &msg.shard_id)?
+            .ok_or(GetChunkApplyStatsError::UnavailableStats { block_hash, shard_id: msg.shard_id })
+    }
+}
+
 impl Handler<TxStatus> for ViewClientActorInner {
     #[perf]
     fn handle(&mut self, msg: TxStatus) -> Result<TxStatusView, TxStatusError> {
@@ -909,6 +934,26 @@ impl Handler<GetValidatorOrdered> for ViewClientActorInner {
         })?)
     }
 }
+impl Handler<GetValidatorForecast> for ViewClientActorInner {
+    #[perf]
+    fn handle(
+        &mut self,
+        msg: GetValidatorForecast,
+    ) -> Result<EpochValidatorForecast, GetValidatorInfoError> {
+        tracing::debug!(target: "client", ?msg);
+        let _timer = metrics::VIEW_CLIENT_MESSAGE_TIME
+            .with_label_values(&["GetValidatorForecast"])
+            .start_timer();
+        let block_hash = match msg.block_id {
+            // use header head because this is latest from the perspective of epoch manager
+            None => self.chain.header_head()?.last_block_hash,
+            Some(BlockId::Height(height)) => *self.chain.get_block_header_by_height(height)?.hash(),
+            Some(BlockId::Hash(hash)) => hash,
+        };
+        Ok(self.epoch_manager.get_validator_forecast(&block_hash).into_chain_error()?)
+    }
+}
+
 /// Returns a list of change kinds per account in a store for a given block.
 impl Handler<GetStateChangesInBlock> for ViewClientActorInner {
     #[perf]
@@ -1327,6 +1372,99 @@ impl Handler<BlockHeadersRequest> for ViewClientActorInner {
     }
 }
 
+impl Handler<BlockRangeRequestMessage> for ViewClientActorInner {
+    #[perf]
+    fn handle(&mut self, msg: BlockRangeRequestMessage) {
+        tracing::debug!(target: "client", ?msg);
+        let _timer = metrics::VIEW_CLIENT_MESSAGE_TIME
+            .with_label_values(&["BlockRangeRequest"])
+            .start_timer();
+        let BlockRangeRequestMessage { from_peer, request } = msg;
+        let start_height = request.start_height;
+        let send = |blocks, last| {
+            self.network_adapter.send(PeerManagerMessageRequest::NetworkRequests(
+                NetworkRequests::BlockRangeResponse {
+                    peer_id: from_peer.clone(),
+                    response: BlockRangeResponse { start_height, blocks, last },
+                },
+            ));
+        };
+        let head = match self.chain.head() {
+            Ok(head) => head,
+            Err(err) => {
+                warn!(target: "client", ?err, "Failed to get head for a block range request");
+                send(vec![], true);
+                return;
+            }
+        };
+        let count = request.count.min(sync::block::MAX_BLOCK_RANGE_REQUEST_COUNT);
+        let end_height = start_height.saturating_add(count).min(head.height + 1);
+
+        let mut part = vec![];
+        let mut part_size = 0;
+        let mut prev_hash = None;
+        for height in start_height..end_height {
+            let block = match self
+                .chain
+                .get_block_hash_by_height(height)
+                .and_then(|hash| self.chain.get_block(&hash))
+            {
+                Ok(block) => block,
+                // No block on the canonical chain at this height.
+                Err(near_chain::Error::DBNotFoundErr(_)) => continue,
+                Err(err) => {
+                    warn!(target: "client", height, ?err, "Failed to get a block for a block range request");
+                    break;
+                }
+            };
+            // The canonical chain may change while the blocks are read. End the response
+            // then, so that the blocks sent still form a chain.
+            if prev_hash.is_some_and(|prev_hash| &prev_hash != block.header().prev_hash()) {
+                break;
+            }
+            prev_hash = Some(*block.hash());
+            let size = borsh::object_length(&block).unwrap_or_default();
+            if !part.is_empty() && part_size + size > MAX_BLOCK_RANGE_RESPONSE_PART_SIZE {
+                send(std::mem::take(&mut part), false);
+                part_size = 0;
+            }
+            part_size += size;
+            part.push(block);
+        }
+        send(part, true);
+    }
+}
+
+impl Handler<CompactBlockRequestMessage> for ViewClientActorInner {
+    #[perf]
+    fn handle(&mut self, msg: CompactBlockRequestMessage) {
+        tracing::debug!(target: "client", ?msg);
+        let _timer = metrics::VIEW_CLIENT_MESSAGE_TIME
+            .with_label_values(&["CompactBlockRequest"])
+            .start_timer();
+        let CompactBlockRequestMessage { from_peer, request } = msg;
+        let block = match self.chain.get_block(&request.block_hash) {
+            Ok(block) => block,
+            Err(err) => {
+                warn!(target: "client", block_hash = ?request.block_hash, ?err, "Failed to get a block for a compact block request");
+                return;
+            }
+        };
+        let chunks = block.chunks();
+        let response = CompactBlockResponse {
+            block_hash: request.block_hash,
+            chunks: request
+                .chunk_indices
+                .into_iter()
+                .filter_map(|i| Some((i, chunks.get(i as usize)?.clone())))
+                .collect(),
+        };
+        self.network_adapter.send(PeerManagerMessageRequest::NetworkRequests(
+            NetworkRequests::CompactBlockResponse { peer_id: from_peer, response },
+        ));
+    }
+}
+
 impl Handler<StateRequestHeader> for ViewClientActorInner {
     #[perf]
     fn handle(&mut self, msg: StateRequestHeader) -> Option<StateResponse> {
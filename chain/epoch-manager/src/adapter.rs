@@ -16,7 +16,7 @@ use near_primitives::types::{
     ValidatorInfoIdentifier,
 };
 use near_primitives::version::ProtocolVersion;
-use near_primitives::views::EpochValidatorInfo;
+use near_primitives::views::{EpochValidatorForecast, EpochValidatorInfo};
 use near_store::{ShardUId, StoreUpdate};
 use std::cmp::Ordering;
 use std::collections::{HashMap, HashSet};
@@ -532,6 +532,15 @@ pub trait EpochManagerAdapter: Send + Sync {
         epoch_identifier: ValidatorInfoIdentifier,
     ) -> Result<EpochValidatorInfo, EpochError>;
 
+    /// WARNING: this call may be expensive.
+    ///
+    /// Forecasts the outcome of the epoch containing the given block, as if the
+    /// validators kept the same performance until the end of the epoch.
+    fn get_validator_forecast(
+        &self,
+        block_hash: &CryptoHash,
+    ) -> Result<EpochValidatorForecast, EpochError>;
+
     fn add_validator_proposals(
         &self,
         block_info: BlockInfo,
@@ -933,6 +942,16 @@ impl EpochManagerAdapter for EpochManagerHandle {
         epoch_manager.get_validator_info(epoch_id)
     }
 
+    /// WARNING: this function calls EpochManager::get_epoch_info_aggregator_upto_last
+    /// underneath which can be very expensive.
+    fn get_validator_forecast(
+        &self,
+        block_hash: &CryptoHash,
+    ) -> Result<EpochValidatorForecast, EpochError> {
+        let epoch_manager = self.read();
+        epoch_manager.get_validator_forecast(block_hash)
+    }
+
     fn add_validator_proposals(
         &self,
         block_info: BlockInfo,
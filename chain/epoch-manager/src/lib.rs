@@ -4,6 +4,7 @@ pub use crate::adapter::EpochManagerAdapter;
 use crate::metrics::{PROTOCOL_VERSION_NEXT, PROTOCOL_VERSION_VOTES};
 pub use crate::reward_calculator::NUM_SECONDS_IN_A_YEAR;
 pub use crate::reward_calculator::RewardCalculator;
+pub use crate::reward_calculator::ValidatorOnlineThresholds;
 use epoch_info_aggregator::EpochInfoAggregator;
 use itertools::Itertools;
 use near_cache::SyncLruCache;
@@ -27,20 +28,20 @@ use near_primitives::types::{
 };
 use near_primitives::version::{ProtocolFeature, ProtocolVersion};
 use near_primitives::views::{
-    CurrentEpochValidatorInfo, EpochValidatorInfo, NextEpochValidatorInfo, ValidatorKickoutView,
+    CurrentEpochValidatorInfo, EpochValidatorForecast, EpochValidatorInfo, NextEpochValidatorInfo,
+    ValidatorForecast, ValidatorKickoutView,
 };
 use near_store::adapter::StoreAdapter;
 use near_store::{DBCol, HEADER_HEAD_KEY, Store, StoreUpdate};
 use num_rational::BigRational;
 use parking_lot::{RwLock, RwLockReadGuard, RwLockWriteGuard};
-use reward_calculator::ValidatorOnlineThresholds;
 use shard_assignment::build_assignment_restrictions_v77_to_v78;
 use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::Path;
 use std::sync::Arc;
 use tracing::{debug, warn};
 pub use validator_selection::proposals_to_epoch_info;
-use validator_stats::get_sortable_validator_online_ratio;
+use validator_stats::{get_sortable_validator_online_ratio, get_validator_online_ratio_f64};
 
 mod adapter;
 pub mod epoch_info_aggregator;
@@ -604,9 +605,6 @@ impl EpochManager {
     ) -> Result<(), EpochError> {
         let epoch_summary = self.collect_blocks_info(block_info, last_block_hash)?;
         let epoch_info = self.get_epoch_info(block_info.epoch_id())?;
-        let epoch_protocol_version = epoch_info.protocol_version();
-        let validator_stake =
-            epoch_info.validators_iter().map(|r| r.account_and_stake()).collect::<HashMap<_, _>>();
         let next_epoch_id = self.get_next_epoch_id_from_info(block_info)?;
         let next_epoch_info = self.get_epoch_info(&next_epoch_id)?;
         self.save_epoch_validator_info(store_update, block_info.epoch_id(), &epoch_summary)?;
@@ -614,7 +612,7 @@ impl EpochManager {
         let EpochSummary {
             all_proposals,
             validator_kickout,
-            mut validator_block_chunk_stats,
+            validator_block_chunk_stats,
             next_next_epoch_version,
             ..
         } = epoch_summary;
@@ -626,35 +624,82 @@ impl EpochManager {
             assert!(block_info.timestamp_nanosec() > last_block_in_last_epoch.timestamp_nanosec());
             let epoch_duration =
                 block_info.timestamp_nanosec() - last_block_in_last_epoch.timestamp_nanosec();
-            for (account_id, reason) in &validator_kickout {
-                if matches!(
-                    reason,
-                    ValidatorKickoutReason::NotEnoughBlocks { .. }
-                        | ValidatorKickoutReason::NotEnoughChunks { .. }
-                        | ValidatorKickoutReason::NotEnoughChunkEndorsements { .. }
-                ) {
-                    validator_block_chunk_stats.remove(account_id);
-                }
-            }
-            let epoch_config = self.get_epoch_config(epoch_protocol_version);
-            // If ChunkEndorsementsInBlockHeader feature is enabled, we use the chunk validator kickout threshold
-            // as the cutoff threshold for the endorsement ratio to remap the ratio to 0 or 1.
-            let online_thresholds = ValidatorOnlineThresholds {
-                online_min_threshold: epoch_config.online_min_threshold,
-                online_max_threshold: epoch_config.online_max_threshold,
-                endorsement_cutoff_threshold: Some(
-                    epoch_config.chunk_validator_only_kickout_threshold,
-                ),
-            };
-            self.reward_calculator.calculate_reward(
+            self.compute_validator_rewards(
+                &epoch_info,
+                &validator_kickout,
                 validator_block_chunk_stats,
-                &validator_stake,
                 *block_info.total_supply(),
-                epoch_protocol_version,
                 epoch_duration,
-                online_thresholds,
             )
         };
+        let next_next_epoch_info = self.compute_next_next_epoch_info(
+            &next_epoch_info,
+            next_next_epoch_version,
+            rng_seed,
+            all_proposals,
+            validator_kickout,
+            validator_reward,
+            minted_amount,
+        )?;
+        let next_next_epoch_id = EpochId(*last_block_hash);
+        debug!(target: "epoch_manager", "next next epoch height: {}, id: {:?}, protocol version: {} shard layout: {:?} config: {:?}",
+               next_next_epoch_info.epoch_height(),
+               &next_next_epoch_id,
+               next_next_epoch_info.protocol_version(),
+               self.config.for_protocol_version(next_next_epoch_info.protocol_version()).shard_layout,
+            self.config.for_protocol_version(next_next_epoch_info.protocol_version()));
+        // This epoch info is computed for the epoch after next (T+2),
+        // where epoch_id of it is the hash of last block in this epoch (T).
+        self.save_epoch_info(store_update, &next_next_epoch_id, Arc::new(next_next_epoch_info))?;
+        Ok(())
+    }
+
+    /// Computes the rewards of the validators of the epoch (T) from the stats collected
+    /// during the epoch. Returns the rewards and the total minted amount.
+    fn compute_validator_rewards(
+        &self,
+        epoch_info: &EpochInfo,
+        validator_kickout: &HashMap<AccountId, ValidatorKickoutReason>,
+        mut validator_block_chunk_stats: HashMap<AccountId, BlockChunkValidatorStats>,
+        total_supply: Balance,
+        epoch_duration: u64,
+    ) -> (HashMap<AccountId, Balance>, Balance) {
+        let epoch_protocol_version = epoch_info.protocol_version();
+        let validator_stake =
+            epoch_info.validators_iter().map(|r| r.account_and_stake()).collect::<HashMap<_, _>>();
+        for (account_id, reason) in validator_kickout {
+            if matches!(
+                reason,
+                ValidatorKickoutReason::NotEnoughBlocks { .. }
+                    | ValidatorKickoutReason::NotEnoughChunks { .. }
+                    | ValidatorKickoutReason::NotEnoughChunkEndorsements { .. }
+            ) {
+                validator_block_chunk_stats.remove(account_id);
+            }
+        }
+        let epoch_config = self.get_epoch_config(epoch_protocol_version);
+        let online_thresholds = ValidatorOnlineThresholds::from_epoch_config(&epoch_config);
+        self.reward_calculator.calculate_reward(
+            validator_block_chunk_stats,
+            &validator_stake,
+            total_supply,
+            epoch_protocol_version,
+            epoch_duration,
+            online_thresholds,
+        )
+    }
+
+    /// Computes the epoch info of the epoch after next (T+2) from the outcome of the epoch (T).
+    fn compute_next_next_epoch_info(
+        &self,
+        next_epoch_info: &EpochInfo,
+        next_next_epoch_version: ProtocolVersion,
+        rng_seed: RngSeed,
+        all_proposals: Vec<ValidatorStake>,
+        validator_kickout: HashMap<AccountId, ValidatorKickoutReason>,
+        validator_reward: HashMap<AccountId, Balance>,
+        minted_amount: Balance,
+    ) -> Result<EpochInfo, EpochError> {
         let next_next_epoch_config = self.config.for_protocol_version(next_next_epoch_version);
         let next_epoch_version = next_epoch_info.protocol_version();
         let next_shard_layout = self.config.for_protocol_version(next_epoch_version).shard_layout;
@@ -666,15 +711,15 @@ impl EpochManager {
         let chunk_producer_assignment_restrictions =
             (!next_epoch_v6 && next_next_epoch_v6).then(|| {
                 build_assignment_restrictions_v77_to_v78(
-                    &next_epoch_info,
+                    next_epoch_info,
                     &next_shard_layout,
                     next_next_epoch_config.shard_layout.clone(),
                 )
             });
-        let next_next_epoch_info = match proposals_to_epoch_info(
+        match proposals_to_epoch_info(
             &next_next_epoch_config,
             rng_seed,
-            &next_epoch_info,
+            next_epoch_info,
             all_proposals,
             validator_kickout,
             validator_reward,
@@ -683,32 +728,21 @@ impl EpochManager {
             has_same_shard_layout,
             chunk_producer_assignment_restrictions,
         ) {
-            Ok(next_next_epoch_info) => next_next_epoch_info,
+            Ok(next_next_epoch_info) => Ok(next_next_epoch_info),
             Err(EpochError::ThresholdError { stake_sum, num_seats }) => {
                 warn!(target: "epoch_manager", "Not enough stake for required number of seats (all validators tried to unstake?): amount = {} for {}", stake_sum, num_seats);
-                let mut epoch_info = EpochInfo::clone(&next_epoch_info);
+                let mut epoch_info = next_epoch_info.clone();
                 *epoch_info.epoch_height_mut() += 1;
-                epoch_info
+                Ok(epoch_info)
             }
             Err(EpochError::NotEnoughValidators { num_validators, num_shards }) => {
                 warn!(target: "epoch_manager", "Not enough validators for required number of shards (all validators tried to unstake?): num_validators={} num_shards={}", num_validators, num_shards);
-                let mut epoch_info = EpochInfo::clone(&next_epoch_info);
+                let mut epoch_info = next_epoch_info.clone();
                 *epoch_info.epoch_height_mut() += 1;
-                epoch_info
+                Ok(epoch_info)
             }
-            Err(err) => return Err(err),
-        };
-        let next_next_epoch_id = EpochId(*last_block_hash);
-        debug!(target: "epoch_manager", "next next epoch height: {}, id: {:?}, protocol version: {} shard layout: {:?} config: {:?}",
-               next_next_epoch_info.epoch_height(),
-               &next_next_epoch_id,
-               next_next_epoch_info.protocol_version(),
-               self.config.for_protocol_version(next_next_epoch_info.protocol_version()).shard_layout,
-            self.config.for_protocol_version(next_next_epoch_info.protocol_version()));
-        // This epoch info is computed for the epoch after next (T+2),
-        // where epoch_id of it is the hash of last block in this epoch (T).
-        self.save_epoch_info(store_update, &next_next_epoch_id, Arc::new(next_next_epoch_info))?;
-        Ok(())
+            Err(err) => Err(err),
+        }
     }
 
     pub fn record_block_info(
@@ -1216,6 +1250,35 @@ impl EpochManager {
 
         let next_epoch_info = self.get_epoch_info(&next_epoch_id)?;
         let next_shard_layout = self.get_shard_layout(&next_epoch_id)?;
+        let next_validators =
+            Self::get_next_epoch_validators_info(&next_epoch_info, &next_shard_layout)?;
+        let prev_epoch_kickout = next_epoch_info
+            .validator_kickout()
+            .clone()
+            .into_iter()
+            .collect::<BTreeMap<_, _>>()
+            .into_iter()
+            .map(|(account_id, reason)| ValidatorKickoutView { account_id, reason })
+            .collect();
+
+        Ok(EpochValidatorInfo {
+            current_validators,
+            next_validators,
+            current_fishermen: cur_epoch_info.fishermen_iter().map(Into::into).collect(),
+            next_fishermen: next_epoch_info.fishermen_iter().map(Into::into).collect(),
+            current_proposals: all_proposals,
+            prev_epoch_kickout,
+            epoch_start_height,
+            epoch_height,
+        })
+    }
+
+    /// Returns the validators of the given (future) epoch with the shards they are
+    /// assigned to as chunk producers.
+    fn get_next_epoch_validators_info(
+        next_epoch_info: &EpochInfo,
+        next_shard_layout: &ShardLayout,
+    ) -> Result<Vec<NextEpochValidatorInfo>, EpochError> {
         let mut next_validator_to_shard = (0..next_epoch_info.validators_len())
             .map(|_| HashSet::default())
             .collect::<Vec<HashSet<ShardId>>>();
@@ -1240,24 +1303,116 @@ impl EpochManager {
                 NextEpochValidatorInfo { account_id, public_key, stake, shards }
             })
             .collect();
-        let prev_epoch_kickout = next_epoch_info
-            .validator_kickout()
-            .clone()
-            .into_iter()
-            .collect::<BTreeMap<_, _>>()
-            .into_iter()
-            .map(|(account_id, reason)| ValidatorKickoutView { account_id, reason })
-            .collect();
+        Ok(next_validators)
+    }
 
-        Ok(EpochValidatorInfo {
-            current_validators,
-            next_validators,
-            current_fishermen: cur_epoch_info.fishermen_iter().map(Into::into).collect(),
-            next_fishermen: next_epoch_info.fishermen_iter().map(Into::into).collect(),
-            current_proposals: all_proposals,
-            prev_epoch_kickout,
+    /// Forecasts the outcome of the epoch containing the given block: the online ratio,
+    /// reward and kickout of each validator, and the validators selected for the epoch
+    /// after next. The epoch is finalized as if the validators kept the same performance
+    /// until its end.
+    ///
+    /// WARNING: this call may be expensive, it aggregates all blocks of the epoch
+    /// up to the given one.
+    pub fn get_validator_forecast(
+        &self,
+        block_hash: &CryptoHash,
+    ) -> Result<EpochValidatorForecast, EpochError> {
+        let block_info = self.get_block_info(block_hash)?;
+        let epoch_id = *block_info.epoch_id();
+        let epoch_info = self.get_epoch_info(&epoch_id)?;
+        let next_epoch_info = self.get_epoch_info(&self.get_next_epoch_id(block_hash)?)?;
+        let epoch_config = self.get_epoch_config(epoch_info.protocol_version());
+        let epoch_start_height = self.get_epoch_start_from_epoch_id(&epoch_id)?;
+
+        let EpochSummary {
+            all_proposals,
+            validator_kickout,
+            validator_block_chunk_stats,
+            next_next_epoch_version,
+            ..
+        } = self.collect_blocks_info(&block_info, block_hash)?;
+
+        let online_thresholds = ValidatorOnlineThresholds::from_epoch_config(&epoch_config);
+        let mut current_validators = epoch_info
+            .validators_iter()
+            .map(|info| {
+                let stats = validator_block_chunk_stats.get(info.account_id());
+                let block_stats = stats.map(|stats| &stats.block_stats);
+                let chunk_stats = stats.map(|stats| &stats.chunk_stats);
+                let (account_id, _, stake) = info.destructure();
+                let kickout_reason = validator_kickout.get(&account_id).cloned();
+                ValidatorForecast {
+                    account_id,
+                    stake,
+                    num_produced_blocks: block_stats.map_or(0, |stats| stats.produced),
+                    num_expected_blocks: block_stats.map_or(0, |stats| stats.expected),
+                    num_produced_chunks: chunk_stats.map_or(0, |stats| stats.produced()),
+                    num_expected_chunks: chunk_stats.map_or(0, |stats| stats.expected()),
+                    num_produced_endorsements: chunk_stats
+                        .map_or(0, |stats| stats.endorsement_stats().produced),
+                    num_expected_endorsements: chunk_stats
+                        .map_or(0, |stats| stats.endorsement_stats().expected),
+                    online_ratio: stats.map_or(0.0, |stats| {
+                        get_validator_online_ratio_f64(
+                            stats,
+                            online_thresholds.endorsement_cutoff_threshold,
+                        )
+                    }),
+                    expected_reward: 0,
+                    kickout_reason,
+                }
+            })
+            .collect_vec();
+
+        // Kickouts and rewards only depend on the ratio between produced and expected
+        // blocks, chunks and endorsements, so the stats collected so far are used as is.
+        // Only the duration of the epoch, which determines the total reward, is extrapolated.
+        let last_epoch_last_block_hash =
+            *self.get_block_info(block_info.epoch_first_block())?.prev_hash();
+        let last_block_in_last_epoch = self.get_block_info(&last_epoch_last_block_hash)?;
+        let elapsed_duration = block_info
+            .timestamp_nanosec()
+            .saturating_sub(*last_block_in_last_epoch.timestamp_nanosec());
+        let elapsed_heights =
+            block_info.height().saturating_sub(last_block_in_last_epoch.height()).max(1);
+        let epoch_duration = (elapsed_duration as u128 * epoch_config.epoch_length as u128
+            / elapsed_heights as u128) as u64;
+        let (validator_reward, minted_amount) = self.compute_validator_rewards(
+            &epoch_info,
+            &validator_kickout,
+            validator_block_chunk_stats,
+            *block_info.total_supply(),
+            epoch_duration,
+        );
+        for validator in &mut current_validators {
+            validator.expected_reward =
+                validator_reward.get(&validator.account_id).copied().unwrap_or_default();
+        }
+
+        // The randomness of the last block of the epoch is not known yet, so the hash of
+        // the given block is used instead. It only affects the shard assignment.
+        let next_next_epoch_info = self.compute_next_next_epoch_info(
+            &next_epoch_info,
+            next_next_epoch_version,
+            block_hash.0,
+            all_proposals,
+            validator_kickout,
+            validator_reward,
+            minted_amount,
+        )?;
+        let next_next_shard_layout =
+            self.get_shard_layout_from_protocol_version(next_next_epoch_info.protocol_version());
+        let predicted_validators =
+            Self::get_next_epoch_validators_info(&next_next_epoch_info, &next_next_shard_layout)?;
+
+        Ok(EpochValidatorForecast {
+            epoch_height: epoch_info.epoch_height(),
             epoch_start_height,
-            epoch_height,
+            block_height: block_info.height(),
+            estimated_next_epoch_start_height: epoch_start_height + epoch_config.epoch_length,
+            current_validators,
+            predicted_protocol_version: next_next_epoch_info.protocol_version(),
+            predicted_validators,
         })
     }
 
@@ -4,6 +4,7 @@ use num_rational::Rational32;
 use primitive_types::{U256, U512};
 
 use near_chain_configs::GenesisConfig;
+use near_primitives::epoch_manager::EpochConfig;
 use near_primitives::types::{AccountId, Balance, BlockChunkValidatorStats};
 use near_primitives::version::ProtocolVersion;
 
@@ -26,6 +27,28 @@ pub struct ValidatorOnlineThresholds {
     pub endorsement_cutoff_threshold: Option<u8>,
 }
 
+impl ValidatorOnlineThresholds {
+    /// Thresholds used to compute the rewards at the end of an epoch with the given config.
+    /// The chunk validator kickout threshold is used as the cutoff threshold for the
+    /// endorsement ratio to remap the ratio to 0 or 1.
+    pub fn from_epoch_config(epoch_config: &EpochConfig) -> Self {
+        Self {
+            online_min_threshold: epoch_config.online_min_threshold,
+            online_max_threshold: epoch_config.online_max_threshold,
+            endorsement_cutoff_threshold: Some(epoch_config.chunk_validator_only_kickout_threshold),
+        }
+    }
+
+    /// Returns true if the online ratio of the validator with the given stats is below
+    /// the online minimum threshold, in which case the validator doesn't receive reward.
+    pub fn is_below_online_min_threshold(&self, stats: &BlockChunkValidatorStats) -> bool {
+        let ratio = get_validator_online_ratio(stats, self.endorsement_cutoff_threshold);
+        let online_min_numer = U256::from(*self.online_min_threshold.numer() as u64);
+        let online_min_denom = U256::from(*self.online_min_threshold.denom() as u64);
+        *ratio.numer() * online_min_denom < online_min_numer * *ratio.denom()
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct RewardCalculator {
     pub max_inflation_rate: Rational32,
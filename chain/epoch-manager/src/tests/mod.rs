@@ -344,6 +344,51 @@ fn test_validator_kickout() {
     );
 }
 
+/// The forecast computed in the middle of the epoch should predict the kickout
+/// that happens at the end of the epoch.
+#[test]
+fn test_validator_forecast() {
+    let amount_staked = 1_000_000;
+    let validators =
+        vec![("test1".parse().unwrap(), amount_staked), ("test2".parse().unwrap(), amount_staked)];
+    let epoch_length = 10;
+    let epoch_manager =
+        setup_default_epoch_manager(validators, epoch_length, 1, 2, 90, 60).into_handle();
+    let h = hash_range(8);
+
+    record_block(&mut epoch_manager.write(), CryptoHash::default(), h[0], 0, vec![]);
+    let mut prev_block = h[0];
+    for (i, curr_block) in h.iter().enumerate().skip(1) {
+        let height = i as u64;
+        let epoch_id = epoch_manager.get_epoch_id_from_prev_block(&prev_block).unwrap();
+        let block_producer = epoch_manager.get_block_producer_info(&epoch_id, height).unwrap();
+        // test2 skips all its blocks in the first epoch.
+        if block_producer.account_id() != "test2" {
+            record_block(&mut epoch_manager.write(), prev_block, *curr_block, height, vec![]);
+            prev_block = *curr_block;
+        }
+    }
+
+    let forecast = epoch_manager.get_validator_forecast(&prev_block).unwrap();
+    assert_eq!(
+        forecast.estimated_next_epoch_start_height,
+        forecast.epoch_start_height + epoch_length
+    );
+    let test1 = forecast.current_validators.iter().find(|v| v.account_id == "test1").unwrap();
+    assert_eq!(test1.kickout_reason, None);
+    assert_eq!(test1.num_produced_blocks, test1.num_expected_blocks);
+    let test2 = forecast.current_validators.iter().find(|v| v.account_id == "test2").unwrap();
+    assert!(matches!(
+        test2.kickout_reason,
+        Some(NotEnoughBlocks { produced: 0, expected }) if expected > 0
+    ));
+    assert_eq!(test2.online_ratio, 0.0);
+    assert_eq!(test2.expected_reward, 0);
+    let predicted_validators =
+        forecast.predicted_validators.iter().map(|v| v.account_id.as_str()).collect_vec();
+    assert_eq!(predicted_validators, vec!["test1"]);
+}
+
 #[test]
 fn test_validator_unstake() {
     let store = create_test_store();
@@ -2857,7 +2902,7 @@ fn test_block_and_chunk_producer_not_kicked_out_for_low_endorsements() {
 }
 
 fn test_chunk_header(h: &[CryptoHash], signer: &ValidatorSigner) -> ShardChunkHeader {
-    ShardChunkHeader::V3(ShardChunkHeaderV3::new(
+    let header = ShardChunkHeaderV3::new(
         h[0],
         h[2],
         h[2],
@@ -2874,7 +2919,9 @@ fn test_chunk_header(h: &[CryptoHash], signer: &ValidatorSigner) -> ShardChunkHe
         Default::default(),
         BandwidthRequests::empty(),
         signer,
-    ))
+    )
+    .unwrap();
+    ShardChunkHeader::V3(header)
 }
 
 #[test]
@@ -1,6 +1,7 @@
 use near_primitives::types::{BlockChunkValidatorStats, ValidatorStats};
 use num_bigint::BigUint;
 use num_rational::{BigRational, Ratio};
+use num_traits::ToPrimitive;
 use primitive_types::U256;
 
 /// Computes the overall online (uptime) ratio of the validator.
@@ -108,7 +109,20 @@ pub(crate) fn get_validator_online_ratio(
 /// TODO: Implement `num_integer::Integer` for `U256` and remove this function.
 /// cspell:words bigdenom bignumer
 pub(crate) fn get_sortable_validator_online_ratio(stats: &BlockChunkValidatorStats) -> BigRational {
-    let ratio = get_validator_online_ratio(stats, None);
+    to_big_rational(&get_validator_online_ratio(stats, None))
+}
+
+/// Computes the overall online (uptime) ratio of the validator as a floating point number.
+/// Intended for display purposes only, use `get_validator_online_ratio` for any computation.
+pub(crate) fn get_validator_online_ratio_f64(
+    stats: &BlockChunkValidatorStats,
+    endorsement_cutoff_threshold: Option<u8>,
+) -> f64 {
+    let ratio = get_validator_online_ratio(stats, endorsement_cutoff_threshold);
+    to_big_rational(&ratio).to_f64().unwrap_or_default()
+}
+
+fn to_big_rational(ratio: &Ratio<U256>) -> BigRational {
     let mut bytes: [u8; size_of::<U256>()] = [0; size_of::<U256>()];
     ratio.numer().to_little_endian(&mut bytes);
     let bignumer = BigUint::from_bytes_le(&bytes);
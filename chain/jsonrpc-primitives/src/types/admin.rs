@@ -0,0 +1,74 @@
+use near_primitives::network::PeerId;
+use serde_json::Value;
+use std::net::SocketAddr;
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct RpcAdminPeerRequest {
+    pub peer_id: PeerId,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct RpcAdminConnectPeerRequest {
+    pub peer_id: PeerId,
+    pub addr: SocketAddr,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct RpcAdminBanPeerRequest {
+    pub peer_id: PeerId,
+    /// Name of the `ReasonForBan` variant, `Abusive` if not specified.
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct RpcAdminBlacklistRequest {
+    /// IP address or socket address to add to or remove from the blacklist.
+    pub entry: String,
+    /// Name of the `ReasonForBan` variant used to ban the connected peers matching
+    /// the added entry, `Blacklisted` if not specified.
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct RpcAdminBlacklistResponse {
+    pub blacklist: Vec<String>,
+}
+
+#[derive(thiserror::Error, Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "name", content = "info", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RpcAdminError {
+    #[error("Peer {peer_id} is not connected")]
+    NotConnected { peer_id: PeerId },
+    #[error("Peer {peer_id} has no known address")]
+    UnknownAddress { peer_id: PeerId },
+    #[error("{entry} is not in the blacklist")]
+    NotBlacklisted { entry: String },
+    #[error("Invalid blacklist entry {entry}: {error_message}")]
+    InvalidBlacklistEntry { entry: String, error_message: String },
+    #[error("Unknown ban reason {reason}")]
+    InvalidBanReason { reason: String },
+    #[error("Peer store error: {error_message}")]
+    PeerStoreError { error_message: String },
+    #[error("The node reached its limits. Try again later. More details: {error_message}")]
+    InternalError { error_message: String },
+}
+
+impl From<RpcAdminError> for crate::errors::RpcError {
+    fn from(error: RpcAdminError) -> Self {
+        let error_data = Some(Value::String(error.to_string()));
+
+        let error_data_value = match serde_json::to_value(error) {
+            Ok(value) => value,
+            Err(err) => {
+                return Self::new_internal_error(
+                    None,
+                    format!("Failed to serialize RpcAdminError: {:?}", err),
+                );
+            }
+        };
+
+        Self::new_internal_or_handler_error(error_data, error_data_value)
+    }
+}
@@ -0,0 +1,60 @@
+use serde_json::Value;
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct RpcChunkApplyStatsRequest {
+    pub block_id: near_primitives::types::BlockId,
+    pub shard_id: near_primitives::types::ShardId,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct RpcChunkApplyStatsResponse {
+    #[serde(flatten)]
+    pub stats: near_primitives::chunk_apply_stats::ChunkApplyStats,
+}
+
+#[derive(thiserror::Error, Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "name", content = "info", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RpcChunkApplyStatsError {
+    #[error("The node reached its limits. Try again later. More details: {error_message}")]
+    InternalError { error_message: String },
+    #[error(
+        "Block either has never been observed on the node or has been garbage collected: {error_message}"
+    )]
+    UnknownBlock {
+        #[serde(skip_serializing)]
+        error_message: String,
+    },
+    #[error(
+        "Chunk of shard {shard_id} in block {block_hash} was not applied on this node or its stats have been garbage collected"
+    )]
+    UnavailableStats {
+        block_hash: near_primitives::hash::CryptoHash,
+        shard_id: near_primitives::types::ShardId,
+    },
+}
+
+impl From<RpcChunkApplyStatsError> for crate::errors::RpcError {
+    fn from(error: RpcChunkApplyStatsError) -> Self {
+        let error_data = match &error {
+            RpcChunkApplyStatsError::InternalError { .. } => Some(Value::String(error.to_string())),
+            RpcChunkApplyStatsError::UnknownBlock { error_message } => Some(Value::String(
+                format!("DB Not Found Error: {} \n Cause: Unknown", error_message),
+            )),
+            RpcChunkApplyStatsError::UnavailableStats { .. } => {
+                Some(Value::String(error.to_string()))
+            }
+        };
+
+        let error_data_value = match serde_json::to_value(error) {
+            Ok(value) => value,
+            Err(err) => {
+                return Self::new_internal_error(
+                    None,
+                    format!("Failed to serialize RpcChunkApplyStatsError: {:?}", err),
+                );
+            }
+        };
+
+        Self::new_internal_or_handler_error(error_data, error_data_value)
+    }
+}
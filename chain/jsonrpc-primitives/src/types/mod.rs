@@ -1,5 +1,7 @@
+pub mod admin;
 pub mod blocks;
 pub mod changes;
+pub mod chunk_apply_stats;
 pub mod chunks;
 pub mod client_config;
 pub mod config;
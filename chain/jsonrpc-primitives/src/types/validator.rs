@@ -31,6 +31,18 @@ pub struct RpcValidatorResponse {
     pub validator_info: near_primitives::views::EpochValidatorInfo,
 }
 
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct RpcValidatorForecastRequest {
+    #[serde(default)]
+    pub block_id: near_primitives::types::MaybeBlockId,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct RpcValidatorForecastResponse {
+    #[serde(flatten)]
+    pub forecast: near_primitives::views::EpochValidatorForecast,
+}
+
 impl From<RpcValidatorError> for crate::errors::RpcError {
     fn from(error: RpcValidatorError) -> Self {
         let error_data = match &error {
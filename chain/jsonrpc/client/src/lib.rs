@@ -244,6 +244,32 @@ impl JsonRpcClient {
         call_method(&self.client, &self.server_addr, "EXPERIMENTAL_validators_ordered", request)
     }
 
+    #[allow(non_snake_case)]
+    pub fn EXPERIMENTAL_chunk_apply_stats(
+        &self,
+        request: near_jsonrpc_primitives::types::chunk_apply_stats::RpcChunkApplyStatsRequest,
+    ) -> RpcRequest<near_jsonrpc_primitives::types::chunk_apply_stats::RpcChunkApplyStatsResponse>
+    {
+        call_method(&self.client, &self.server_addr, "EXPERIMENTAL_chunk_apply_stats", request)
+    }
+
+    #[allow(non_snake_case)]
+    pub fn EXPERIMENTAL_validator_forecast(
+        &self,
+        request: near_jsonrpc_primitives::types::validator::RpcValidatorForecastRequest,
+    ) -> RpcRequest<near_jsonrpc_primitives::types::validator::RpcValidatorForecastResponse> {
+        call_method(&self.client, &self.server_addr, "EXPERIMENTAL_validator_forecast", request)
+    }
+
+    #[allow(non_snake_case)]
+    pub fn EXPERIMENTAL_maintenance_windows(
+        &self,
+        request: near_jsonrpc_primitives::types::maintenance::RpcMaintenanceWindowsRequest,
+    ) -> RpcRequest<near_jsonrpc_primitives::types::maintenance::RpcMaintenanceWindowsResponse>
+    {
+        call_method(&self.client, &self.server_addr, "EXPERIMENTAL_maintenance_windows", request)
+    }
+
     #[allow(non_snake_case)]
     pub fn EXPERIMENTAL_receipt(
         &self,
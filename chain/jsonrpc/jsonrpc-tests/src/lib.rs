@@ -41,6 +41,26 @@ pub fn start_all_with_validity_period(
     node_type: NodeType,
     transaction_validity_period: NumBlocks,
     enable_doomslug: bool,
+) -> (Addr<ViewClientActor>, tcp::ListenerAddr, Arc<tempfile::TempDir>) {
+    start_all_with_config(clock, node_type, transaction_validity_period, enable_doomslug, None)
+}
+
+/// Same as `start_all`, but also serves the `/admin` endpoint, guarded by the
+/// given token.
+pub fn start_all_with_admin_auth_token(
+    clock: Clock,
+    node_type: NodeType,
+    admin_auth_token: &str,
+) -> (Addr<ViewClientActor>, tcp::ListenerAddr, Arc<tempfile::TempDir>) {
+    start_all_with_config(clock, node_type, 100, false, Some(admin_auth_token.to_string()))
+}
+
+fn start_all_with_config(
+    clock: Clock,
+    node_type: NodeType,
+    transaction_validity_period: NumBlocks,
+    enable_doomslug: bool,
+    admin_auth_token: Option<String>,
 ) -> (Addr<ViewClientActor>, tcp::ListenerAddr, Arc<tempfile::TempDir>) {
     let actor_handles = setup_no_network_with_validity_period(
         clock,
@@ -56,8 +76,10 @@ pub fn start_all_with_validity_period(
     );
 
     let addr = tcp::ListenerAddr::reserve_for_test();
+    let mut rpc_config = RpcConfig::new(addr);
+    rpc_config.admin_auth_token = admin_auth_token;
     start_http(
-        RpcConfig::new(addr),
+        rpc_config,
         TEST_GENESIS_CONFIG.clone(),
         actor_handles.client_actor.clone().with_auto_span_context().into_multi_sender(),
         actor_handles.view_client_actor.clone().with_auto_span_context().into_multi_sender(),
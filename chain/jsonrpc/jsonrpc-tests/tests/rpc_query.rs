@@ -745,6 +745,63 @@ fn test_query_global_contract_code(deploy_mode: GlobalContractDeployMode) {
     });
 }
 
+/// Requests to the admin endpoint without the right bearer token must be rejected
+/// before they reach the node.
+#[test]
+fn test_admin_rejects_unauthenticated_requests() {
+    init_test_logger();
+
+    run_actix(async {
+        let (_view_client_addr, addr, _runtime_tempdir) =
+            test_utils::start_all_with_admin_auth_token(
+                Clock::real(),
+                test_utils::NodeType::NonValidator,
+                "secret",
+            );
+        actix::spawn(async move {
+            let _runtime_tempdir = _runtime_tempdir;
+            let client = awc::Client::new();
+            let admin_addr = format!("http://{}/admin", addr);
+            let json = serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": "dontcare",
+                "method": "admin_blacklist",
+                "params": serde_json::json!([]),
+            });
+            for authorization in [None, Some("Bearer wrong"), Some("Bearer secre"), Some("secret")]
+            {
+                let mut request = client.post(&admin_addr);
+                if let Some(authorization) = authorization {
+                    request = request.insert_header(("Authorization", authorization));
+                }
+                let response = request.send_json(&json).await.unwrap();
+                assert_eq!(
+                    response.status(),
+                    StatusCode::UNAUTHORIZED,
+                    "{:?} must be rejected",
+                    authorization
+                );
+            }
+            System::current().stop();
+        });
+    });
+}
+
+/// Without a configured token the admin endpoint is not served at all.
+#[test]
+fn test_admin_disabled_without_token() {
+    test_with_client!(test_utils::NodeType::NonValidator, client, async move {
+        let response = client
+            .client
+            .post(format!("{}/admin", client.server_addr))
+            .insert_header(("Authorization", "Bearer "))
+            .send_json(&serde_json::json!({}))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    });
+}
+
 async fn deploy_global_contract(
     client: &JsonRpcClient,
     account: &AccountId,
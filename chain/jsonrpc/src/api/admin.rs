@@ -0,0 +1,74 @@
+use near_async::messaging::AsyncSendError;
+use serde_json::Value;
+
+use near_jsonrpc_primitives::errors::RpcParseError;
+use near_jsonrpc_primitives::types::admin::{
+    RpcAdminBanPeerRequest, RpcAdminBlacklistRequest, RpcAdminConnectPeerRequest, RpcAdminError,
+    RpcAdminPeerRequest,
+};
+use near_network::admin::AdminPeerError;
+use near_network::blacklist;
+use near_network::types::ReasonForBan;
+
+use super::{Params, RpcFrom, RpcRequest};
+
+impl RpcRequest for RpcAdminPeerRequest {
+    fn parse(value: Value) -> Result<Self, RpcParseError> {
+        Params::parse(value)
+    }
+}
+
+impl RpcRequest for RpcAdminConnectPeerRequest {
+    fn parse(value: Value) -> Result<Self, RpcParseError> {
+        Params::parse(value)
+    }
+}
+
+impl RpcRequest for RpcAdminBanPeerRequest {
+    fn parse(value: Value) -> Result<Self, RpcParseError> {
+        Params::parse(value)
+    }
+}
+
+impl RpcRequest for RpcAdminBlacklistRequest {
+    fn parse(value: Value) -> Result<Self, RpcParseError> {
+        Params::parse(value)
+    }
+}
+
+impl RpcFrom<AsyncSendError> for RpcAdminError {
+    fn rpc_from(error: AsyncSendError) -> Self {
+        Self::InternalError { error_message: error.to_string() }
+    }
+}
+
+impl RpcFrom<AdminPeerError> for RpcAdminError {
+    fn rpc_from(error: AdminPeerError) -> Self {
+        match error {
+            AdminPeerError::NotConnected(peer_id) => Self::NotConnected { peer_id },
+            AdminPeerError::UnknownAddress(peer_id) => Self::UnknownAddress { peer_id },
+            AdminPeerError::NotBlacklisted(entry) => {
+                Self::NotBlacklisted { entry: entry.to_string() }
+            }
+            AdminPeerError::PeerStore(error_message) => Self::PeerStoreError { error_message },
+        }
+    }
+}
+
+/// Parses the name of a `ReasonForBan` variant, falling back to `default` if none is given.
+pub(crate) fn parse_ban_reason(
+    reason: Option<String>,
+    default: ReasonForBan,
+) -> Result<ReasonForBan, RpcAdminError> {
+    match reason {
+        None => Ok(default),
+        Some(reason) => reason.parse().map_err(|_| RpcAdminError::InvalidBanReason { reason }),
+    }
+}
+
+pub(crate) fn parse_blacklist_entry(entry: String) -> Result<blacklist::Entry, RpcAdminError> {
+    entry.parse().map_err(|err: std::net::AddrParseError| RpcAdminError::InvalidBlacklistEntry {
+        entry,
+        error_message: err.to_string(),
+    })
+}
@@ -0,0 +1,50 @@
+use near_async::messaging::AsyncSendError;
+use serde_json::Value;
+
+use near_client_primitives::types::GetChunkApplyStatsError;
+use near_jsonrpc_primitives::errors::RpcParseError;
+use near_jsonrpc_primitives::types::chunk_apply_stats::{
+    RpcChunkApplyStatsError, RpcChunkApplyStatsRequest,
+};
+
+use super::{Params, RpcFrom, RpcRequest};
+
+impl RpcRequest for RpcChunkApplyStatsRequest {
+    fn parse(value: Value) -> Result<Self, RpcParseError> {
+        // params can be:
+        // - {"block_id": block_id, "shard_id": shard_id} or
+        // - [block_id, shard_id].
+        Params::new(value)
+            .try_pair(|block_id, shard_id| Ok(Self { block_id, shard_id }))
+            .unwrap_or_parse()
+    }
+}
+
+impl RpcFrom<AsyncSendError> for RpcChunkApplyStatsError {
+    fn rpc_from(error: AsyncSendError) -> Self {
+        Self::InternalError { error_message: error.to_string() }
+    }
+}
+
+impl RpcFrom<GetChunkApplyStatsError> for RpcChunkApplyStatsError {
+    fn rpc_from(error: GetChunkApplyStatsError) -> Self {
+        match error {
+            GetChunkApplyStatsError::IOError { error_message } => {
+                Self::InternalError { error_message }
+            }
+            GetChunkApplyStatsError::UnknownBlock { error_message } => {
+                Self::UnknownBlock { error_message }
+            }
+            GetChunkApplyStatsError::UnavailableStats { block_hash, shard_id } => {
+                Self::UnavailableStats { block_hash, shard_id }
+            }
+            GetChunkApplyStatsError::Unreachable { ref error_message } => {
+                tracing::warn!(target: "jsonrpc", "Unreachable error occurred: {}", error_message);
+                crate::metrics::RPC_UNREACHABLE_ERROR_COUNT
+                    .with_label_values(&["RpcChunkApplyStatsError"])
+                    .inc();
+                Self::InternalError { error_message: error.to_string() }
+            }
+        }
+    }
+}
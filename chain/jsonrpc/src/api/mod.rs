@@ -4,8 +4,10 @@ use serde_json::Value;
 use near_jsonrpc_primitives::errors::RpcParseError;
 use near_jsonrpc_primitives::errors::{RpcError, ServerError};
 
+pub(crate) mod admin;
 mod blocks;
 mod changes;
+mod chunk_apply_stats;
 mod chunks;
 mod client_config;
 mod config;
@@ -11,9 +11,10 @@ use near_async::messaging::{
 };
 use near_chain_configs::GenesisConfig;
 use near_client::{
-    DebugStatus, GetBlock, GetBlockProof, GetChunk, GetClientConfig, GetExecutionOutcome,
-    GetGasPrice, GetMaintenanceWindows, GetNetworkInfo, GetNextLightClientBlock, GetProtocolConfig,
-    GetReceipt, GetStateChanges, GetStateChangesInBlock, GetValidatorInfo, GetValidatorOrdered,
+    DebugStatus, GetBlock, GetBlockProof, GetChunk, GetChunkApplyStats, GetClientConfig,
+    GetExecutionOutcome, GetGasPrice, GetMaintenanceWindows, GetNetworkInfo,
+    GetNextLightClientBlock, GetProtocolConfig, GetReceipt, GetStateChanges,
+    GetStateChangesInBlock, GetValidatorForecast, GetValidatorInfo, GetValidatorOrdered,
     ProcessTxRequest, ProcessTxResponse, Query, Status, TxStatus,
 };
 use near_client_primitives::debug::{DebugBlockStatusQuery, DebugBlocksStartingMode};
@@ -22,6 +23,10 @@ pub use near_jsonrpc_client_internal as client;
 pub use near_jsonrpc_primitives as primitives;
 use near_jsonrpc_primitives::errors::{RpcError, RpcErrorKind};
 use near_jsonrpc_primitives::message::{Message, Request};
+use near_jsonrpc_primitives::types::admin::{
+    RpcAdminBanPeerRequest, RpcAdminBlacklistRequest, RpcAdminBlacklistResponse,
+    RpcAdminConnectPeerRequest, RpcAdminError, RpcAdminPeerRequest,
+};
 use near_jsonrpc_primitives::types::blocks::RpcBlockRequest;
 use near_jsonrpc_primitives::types::config::{RpcProtocolConfigError, RpcProtocolConfigResponse};
 use near_jsonrpc_primitives::types::entity_debug::{EntityDebugHandler, EntityQueryWithParams};
@@ -32,8 +37,10 @@ use near_jsonrpc_primitives::types::split_storage::{
 use near_jsonrpc_primitives::types::transactions::{
     RpcSendTransactionRequest, RpcTransactionResponse,
 };
+use near_network::admin::{AdminPeerRequest, AdminPeerResponse};
 use near_network::debug::GetDebugStatus;
 use near_network::tcp::{self, ListenerAddr};
+use near_network::types::{PeerInfo, ReasonForBan};
 use near_o11y::metrics::{Encoder, TextEncoder, prometheus};
 use near_primitives::hash::CryptoHash;
 use near_primitives::transaction::SignedTransaction;
@@ -98,6 +105,10 @@ pub struct RpcConfig {
     // be read from this directory, instead of the contents compiled into the binary. This allows
     // for quick iterative development.
     pub experimental_debug_pages_src_path: Option<String>,
+    // If provided, the peer management RPC methods (`admin_*`) are served at `/admin`
+    // to the requests with an `Authorization: Bearer <admin_auth_token>` header.
+    #[serde(default)]
+    pub admin_auth_token: Option<String>,
 }
 
 impl Default for RpcConfig {
@@ -110,6 +121,7 @@ impl Default for RpcConfig {
             limits_config: Default::default(),
             enable_debug_rpc: false,
             experimental_debug_pages_src_path: None,
+            admin_auth_token: None,
         }
     }
 }
@@ -253,6 +265,7 @@ pub struct ViewClientSenderForRpc(
     AsyncSender<GetBlock, ActixResult<GetBlock>>,
     AsyncSender<GetBlockProof, ActixResult<GetBlockProof>>,
     AsyncSender<GetChunk, ActixResult<GetChunk>>,
+    AsyncSender<GetChunkApplyStats, ActixResult<GetChunkApplyStats>>,
     AsyncSender<GetExecutionOutcome, ActixResult<GetExecutionOutcome>>,
     AsyncSender<GetGasPrice, ActixResult<GetGasPrice>>,
     AsyncSender<GetMaintenanceWindows, ActixResult<GetMaintenanceWindows>>,
@@ -262,6 +275,7 @@ pub struct ViewClientSenderForRpc(
     AsyncSender<GetSplitStorageInfo, ActixResult<GetSplitStorageInfo>>,
     AsyncSender<GetStateChanges, ActixResult<GetStateChanges>>,
     AsyncSender<GetStateChangesInBlock, ActixResult<GetStateChangesInBlock>>,
+    AsyncSender<GetValidatorForecast, ActixResult<GetValidatorForecast>>,
     AsyncSender<GetValidatorInfo, ActixResult<GetValidatorInfo>>,
     AsyncSender<GetValidatorOrdered, ActixResult<GetValidatorOrdered>>,
     AsyncSender<Query, ActixResult<Query>>,
@@ -279,7 +293,10 @@ pub struct GCSenderForRpc(
 );
 
 #[derive(Clone, near_async::MultiSend, near_async::MultiSenderFrom)]
-pub struct PeerManagerSenderForRpc(AsyncSender<GetDebugStatus, ActixResult<GetDebugStatus>>);
+pub struct PeerManagerSenderForRpc(
+    AsyncSender<AdminPeerRequest, ActixResult<AdminPeerRequest>>,
+    AsyncSender<GetDebugStatus, ActixResult<GetDebugStatus>>,
+);
 
 struct JsonRpcHandler {
     client_sender: ClientSenderForRpc,
@@ -293,6 +310,7 @@ struct JsonRpcHandler {
     enable_debug_rpc: bool,
     debug_pages_src_path: Option<PathBuf>,
     entity_debug_handler: Arc<dyn EntityDebugHandler>,
+    admin_auth_token: Option<String>,
 }
 
 impl JsonRpcHandler {
@@ -306,6 +324,47 @@ impl JsonRpcHandler {
         }
     }
 
+    async fn process_admin(&self, message: Message) -> Message {
+        let id = message.id();
+        match message {
+            Message::Request(request) => {
+                let timer = Instant::now();
+                let method_name = request.method.to_string();
+                let response = match self.process_admin_request_internal(request).await {
+                    Ok(response) => response,
+                    Err(request) => Err(RpcError::method_not_found(request.method)),
+                };
+                metrics::HTTP_RPC_REQUEST_COUNT.with_label_values(&[&method_name]).inc();
+                metrics::RPC_PROCESSING_TIME
+                    .with_label_values(&[&method_name])
+                    .observe(timer.elapsed().as_secs_f64());
+                Message::response(id, response)
+            }
+            _ => Message::error(RpcError::parse_error(
+                "JSON RPC Request format was expected".to_owned(),
+            )),
+        }
+    }
+
+    /// Checks the bearer token of a request to the admin endpoint.
+    fn is_admin_authorized(&self, request: &HttpRequest) -> bool {
+        let Some(token) = &self.admin_auth_token else {
+            return false;
+        };
+        let Some(provided) = request
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+        else {
+            return false;
+        };
+        // Compare in constant time, so that the token can't be guessed byte by byte
+        // from the response times.
+        provided.len() == token.len()
+            && provided.bytes().zip(token.bytes()).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+    }
+
     // `process_request` increments affected metrics but the request processing is done by
     // `process_request_internal`.
     async fn process_request(&self, request: Request) -> Result<Value, RpcError> {
@@ -423,6 +482,9 @@ impl JsonRpcHandler {
             "EXPERIMENTAL_changes_in_block" => {
                 process_method_call(request, |params| self.changes_in_block(params)).await
             }
+            "EXPERIMENTAL_chunk_apply_stats" => {
+                process_method_call(request, |params| self.chunk_apply_stats(params)).await
+            }
             "EXPERIMENTAL_congestion_level" => {
                 process_method_call(request, |params| self.congestion_level(params)).await
             }
@@ -453,6 +515,9 @@ impl JsonRpcHandler {
             "EXPERIMENTAL_validators_ordered" => {
                 process_method_call(request, |params| self.validators_ordered(params)).await
             }
+            "EXPERIMENTAL_validator_forecast" => {
+                process_method_call(request, |params| self.validator_forecast(params)).await
+            }
             "EXPERIMENTAL_maintenance_windows" => {
                 process_method_call(request, |params| self.maintenance_windows(params)).await
             }
@@ -504,6 +569,56 @@ impl JsonRpcHandler {
         })
     }
 
+    /// Handles the peer management requests served at the authenticated `/admin`
+    /// endpoint. Returns `Err(request)` if the method is not an admin one.
+    async fn process_admin_request_internal(
+        &self,
+        request: Request,
+    ) -> Result<Result<Value, RpcError>, Request> {
+        Ok(match request.method.as_ref() {
+            "admin_ban_peer" => {
+                process_method_call(request, |params| self.admin_ban_peer(params)).await
+            }
+            "admin_blacklist" => {
+                process_method_call(request, |_params: ()| self.admin_blacklist()).await
+            }
+            "admin_blacklist_add" => {
+                process_method_call(request, |params| self.admin_blacklist_add(params)).await
+            }
+            "admin_blacklist_remove" => {
+                process_method_call(request, |params| self.admin_blacklist_remove(params)).await
+            }
+            "admin_connect_peer" => {
+                process_method_call(request, |params| self.admin_connect_peer(params)).await
+            }
+            "admin_disconnect_peer" => {
+                process_method_call(request, |params: RpcAdminPeerRequest| {
+                    self.admin_peer_request(AdminPeerRequest::Disconnect(params.peer_id))
+                })
+                .await
+            }
+            "admin_reconnect_peer" => {
+                process_method_call(request, |params: RpcAdminPeerRequest| {
+                    self.admin_peer_request(AdminPeerRequest::Reconnect(params.peer_id))
+                })
+                .await
+            }
+            "admin_remove_peer" => {
+                process_method_call(request, |params: RpcAdminPeerRequest| {
+                    self.admin_peer_request(AdminPeerRequest::Remove(params.peer_id))
+                })
+                .await
+            }
+            "admin_unban_peer" => {
+                process_method_call(request, |params: RpcAdminPeerRequest| {
+                    self.admin_peer_request(AdminPeerRequest::Unban(params.peer_id))
+                })
+                .await
+            }
+            _ => return Err(request),
+        })
+    }
+
     async fn client_send<M, R, F, E>(&self, msg: M) -> Result<R, E>
     where
         ClientSenderForRpc: CanSend<MessageWithCallback<M, Result<R, F>>>,
@@ -948,6 +1063,21 @@ impl JsonRpcHandler {
         Ok(near_jsonrpc_primitives::types::chunks::RpcChunkResponse { chunk_view })
     }
 
+    async fn chunk_apply_stats(
+        &self,
+        request_data: near_jsonrpc_primitives::types::chunk_apply_stats::RpcChunkApplyStatsRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::chunk_apply_stats::RpcChunkApplyStatsResponse,
+        near_jsonrpc_primitives::types::chunk_apply_stats::RpcChunkApplyStatsError,
+    > {
+        let near_jsonrpc_primitives::types::chunk_apply_stats::RpcChunkApplyStatsRequest {
+            block_id,
+            shard_id,
+        } = request_data;
+        let stats = self.view_client_send(GetChunkApplyStats { block_id, shard_id }).await?;
+        Ok(near_jsonrpc_primitives::types::chunk_apply_stats::RpcChunkApplyStatsResponse { stats })
+    }
+
     async fn congestion_level(
         &self,
         request_data: near_jsonrpc_primitives::types::congestion::RpcCongestionLevelRequest,
@@ -1164,6 +1294,76 @@ impl JsonRpcHandler {
         Ok(validators)
     }
 
+    /// Returns the projected online ratio, reward and kickout of each validator at the end of
+    /// the current epoch, and the validators predicted for the epoch after next.
+    async fn validator_forecast(
+        &self,
+        request: near_jsonrpc_primitives::types::validator::RpcValidatorForecastRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::validator::RpcValidatorForecastResponse,
+        near_jsonrpc_primitives::types::validator::RpcValidatorError,
+    > {
+        let near_jsonrpc_primitives::types::validator::RpcValidatorForecastRequest { block_id } =
+            request;
+        let forecast = self.view_client_send(GetValidatorForecast { block_id }).await?;
+        Ok(near_jsonrpc_primitives::types::validator::RpcValidatorForecastResponse { forecast })
+    }
+
+    async fn admin_peer_request(&self, request: AdminPeerRequest) -> Result<(), RpcAdminError> {
+        self.peer_manager_send::<_, _, RpcAdminError>(request)
+            .await?
+            .map_err(RpcAdminError::rpc_from)?;
+        Ok(())
+    }
+
+    async fn admin_connect_peer(
+        &self,
+        request: RpcAdminConnectPeerRequest,
+    ) -> Result<(), RpcAdminError> {
+        let RpcAdminConnectPeerRequest { peer_id, addr } = request;
+        let peer_info = PeerInfo { id: peer_id, addr: Some(addr), account_id: None };
+        self.admin_peer_request(AdminPeerRequest::Connect(peer_info)).await
+    }
+
+    async fn admin_ban_peer(&self, request: RpcAdminBanPeerRequest) -> Result<(), RpcAdminError> {
+        let RpcAdminBanPeerRequest { peer_id, reason } = request;
+        let reason = api::admin::parse_ban_reason(reason, ReasonForBan::Abusive)?;
+        self.admin_peer_request(AdminPeerRequest::Ban { peer_id, reason }).await
+    }
+
+    async fn admin_blacklist(&self) -> Result<RpcAdminBlacklistResponse, RpcAdminError> {
+        let response = self
+            .peer_manager_send::<_, _, RpcAdminError>(AdminPeerRequest::GetBlacklist)
+            .await?
+            .map_err(RpcAdminError::rpc_from)?;
+        let AdminPeerResponse::Blacklist(entries) = response else {
+            return Err(RpcAdminError::InternalError {
+                error_message: format!("Unexpected response {:?}", response),
+            });
+        };
+        let mut blacklist: Vec<_> = entries.iter().map(ToString::to_string).collect();
+        blacklist.sort();
+        Ok(RpcAdminBlacklistResponse { blacklist })
+    }
+
+    async fn admin_blacklist_add(
+        &self,
+        request: RpcAdminBlacklistRequest,
+    ) -> Result<(), RpcAdminError> {
+        let RpcAdminBlacklistRequest { entry, reason } = request;
+        let entry = api::admin::parse_blacklist_entry(entry)?;
+        let reason = api::admin::parse_ban_reason(reason, ReasonForBan::Blacklisted)?;
+        self.admin_peer_request(AdminPeerRequest::BlacklistAdd { entry, reason }).await
+    }
+
+    async fn admin_blacklist_remove(
+        &self,
+        request: RpcAdminBlacklistRequest,
+    ) -> Result<(), RpcAdminError> {
+        let entry = api::admin::parse_blacklist_entry(request.entry)?;
+        self.admin_peer_request(AdminPeerRequest::BlacklistRemove(entry)).await
+    }
+
     /// If experimental_debug_pages_src_path config is set, reads the html file from that
     /// directory. Otherwise, returns None.
     fn read_html_file_override(&self, html_file: &'static str) -> Option<String> {
@@ -1441,6 +1641,17 @@ async fn rpc_handler(
     response.json(message)
 }
 
+async fn admin_rpc_handler(
+    req: HttpRequest,
+    request: web::Json<Message>,
+    handler: web::Data<JsonRpcHandler>,
+) -> HttpResponse {
+    if !handler.is_admin_authorized(&req) {
+        return HttpResponse::Unauthorized().finish();
+    }
+    HttpResponse::Ok().json(handler.process_admin(request.0).await)
+}
+
 async fn status_handler(handler: web::Data<JsonRpcHandler>) -> Result<HttpResponse, HttpError> {
     metrics::HTTP_STATUS_REQUEST_COUNT.inc();
 
@@ -1553,12 +1764,27 @@ async fn network_info_handler(
     }
 }
 
-pub async fn prometheus_handler() -> Result<HttpResponse, HttpError> {
+pub async fn prometheus_handler(request: HttpRequest) -> Result<HttpResponse, HttpError> {
     metrics::PROMETHEUS_REQUEST_COUNT.inc();
 
+    let families = near_o11y::metrics::gather();
+    // Exemplars are only representable in the OpenMetrics format, so serve it
+    // to the scrapers which ask for it if the operator opted in.
+    let wants_openmetrics = near_o11y::metrics::openmetrics_enabled()
+        && request
+            .headers()
+            .get(header::ACCEPT)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.contains("application/openmetrics-text"));
+    if wants_openmetrics {
+        return Ok(HttpResponse::Ok()
+            .content_type(near_o11y::openmetrics::CONTENT_TYPE)
+            .body(near_o11y::openmetrics::encode(&families)));
+    }
+
     let mut buffer = vec![];
     let encoder = TextEncoder::new();
-    encoder.encode(&prometheus::gather(), &mut buffer).unwrap();
+    encoder.encode(&families, &mut buffer).unwrap();
 
     match String::from_utf8(buffer) {
         Ok(text) => Ok(HttpResponse::Ok()
@@ -1577,6 +1803,34 @@ async fn client_config_handler(
     }
 }
 
+/// Longest recording `/debug/span_timing` takes.
+const MAX_SPAN_TIMING_DURATION: Duration = Duration::from_secs(120);
+
+#[derive(serde::Deserialize)]
+struct SpanTimingQuery {
+    /// Duration of the recording in seconds.
+    #[serde(default = "default_span_timing_seconds")]
+    seconds: u64,
+}
+
+fn default_span_timing_seconds() -> u64 {
+    10
+}
+
+/// Records the wall-clock time spent in tracing spans for the requested
+/// duration and returns it as folded stacks, ready to be rendered as a
+/// flamegraph. This is span timing, not CPU sampling.
+async fn span_timing_handler(
+    query: web::Query<SpanTimingQuery>,
+) -> Result<HttpResponse, HttpError> {
+    let duration = Duration::from_secs(query.seconds).min(MAX_SPAN_TIMING_DURATION);
+    let Some(recording) = near_o11y::span_timing::start() else {
+        return Ok(HttpResponse::Conflict().body("Another span timing recording is running"));
+    };
+    sleep(duration).await;
+    Ok(HttpResponse::Ok().insert_header(ContentType::plaintext()).body(recording.finish()))
+}
+
 fn get_cors(cors_allowed_origins: &[String]) -> Cors {
     let mut cors = Cors::permissive();
     if cors_allowed_origins != ["*".to_string()] {
@@ -1672,6 +1926,7 @@ pub fn start_http(
         limits_config,
         enable_debug_rpc,
         experimental_debug_pages_src_path: debug_pages_src_path,
+        admin_auth_token,
     } = config;
     let prometheus_addr = prometheus_addr.filter(|it| it != &addr.to_string());
     let cors_allowed_origins_clone = cors_allowed_origins.clone();
@@ -1690,6 +1945,7 @@ pub fn start_http(
                 enable_debug_rpc,
                 debug_pages_src_path: debug_pages_src_path.clone().map(Into::into),
                 entity_debug_handler: entity_debug_handler.clone(),
+                admin_auth_token: admin_auth_token.clone(),
                 #[cfg(feature = "test_features")]
                 gc_sender: gc_sender.clone(),
             }))
@@ -1709,6 +1965,10 @@ pub fn start_http(
             .service(web::resource("/network_info").route(web::get().to(network_info_handler)))
             .service(web::resource("/metrics").route(web::get().to(prometheus_handler)));
 
+        if admin_auth_token.is_some() {
+            app = app.service(web::resource("/admin").route(web::post().to(admin_rpc_handler)));
+        }
+
         if enable_debug_rpc {
             app = app
                 .service(
@@ -1733,6 +1993,9 @@ pub fn start_http(
                     web::resource("/debug/client_config")
                         .route(web::get().to(client_config_handler)),
                 )
+                .service(
+                    web::resource("/debug/span_timing").route(web::get().to(span_timing_handler)),
+                )
                 .service(debug_html)
                 .service(display_debug_html);
         }
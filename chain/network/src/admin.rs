@@ -0,0 +1,53 @@
+use crate::blacklist;
+use crate::network_protocol::PeerInfo;
+use crate::types::ReasonForBan;
+use ::actix::Message;
+use near_primitives::network::PeerId;
+
+/// Peer management requests sent by the node operator via the admin RPC.
+/// All the changes are applied to the running node and are lost on restart.
+#[derive(Debug)]
+pub enum AdminPeerRequest {
+    /// Adds the peer to the peer store and connects to it.
+    Connect(PeerInfo),
+    /// Drops the TIER2 connection to the peer. The peer may be picked again
+    /// for an outbound connection later on, ban it to prevent that.
+    Disconnect(PeerId),
+    /// Drops the TIER2 connection to the peer and connects to it again.
+    Reconnect(PeerId),
+    /// Drops the connection to the peer, if any, and bans it for `ban_window`.
+    Ban { peer_id: PeerId, reason: ReasonForBan },
+    /// Lifts the ban of the peer.
+    Unban(PeerId),
+    /// Drops the connection to the peer, if any, and removes it from the peer
+    /// store, so that it is not connected to again until it is rediscovered.
+    Remove(PeerId),
+    /// Adds the entry to the blacklist and bans the connected peers which match it.
+    BlacklistAdd { entry: blacklist::Entry, reason: ReasonForBan },
+    /// Removes the entry from the blacklist.
+    BlacklistRemove(blacklist::Entry),
+    /// Returns the current blacklist.
+    GetBlacklist,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum AdminPeerResponse {
+    Done,
+    Blacklist(Vec<blacklist::Entry>),
+}
+
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub enum AdminPeerError {
+    #[error("peer {0} is not connected")]
+    NotConnected(PeerId),
+    #[error("peer {0} has no known address")]
+    UnknownAddress(PeerId),
+    #[error("{0} is not in the blacklist")]
+    NotBlacklisted(blacklist::Entry),
+    #[error("peer store error: {0}")]
+    PeerStore(String),
+}
+
+impl Message for AdminPeerRequest {
+    type Result = Result<AdminPeerResponse, AdminPeerError>;
+}
@@ -43,6 +43,17 @@ impl std::str::FromStr for Entry {
     }
 }
 
+impl std::fmt::Display for Entry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Entry::Ip(ip) => write!(f, "{}", ip.to_canonical()),
+            Entry::IpPort(ip, port) => {
+                write!(f, "{}", net::SocketAddr::new(ip.to_canonical(), *port))
+            }
+        }
+    }
+}
+
 /// A blacklist for socket addresses.  Supports adding individual IP:port tuples
 /// to the blacklist or entire IPs.
 #[derive(Debug, Default, Clone)]
@@ -61,6 +72,20 @@ impl Blacklist {
     pub fn contains(&self, addr: net::SocketAddr) -> bool {
         self.0.contains(&Entry::from_ip(addr.ip())) || self.0.contains(&Entry::from_addr(addr))
     }
+
+    /// Adds the entry to the blacklist. Returns false if it was already there.
+    pub fn insert(&mut self, entry: Entry) -> bool {
+        self.0.insert(entry)
+    }
+
+    /// Removes the entry from the blacklist. Returns false if it wasn't there.
+    pub fn remove(&mut self, entry: &Entry) -> bool {
+        self.0.remove(entry)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Entry> {
+        self.0.iter()
+    }
 }
 
 #[cfg(test)]
@@ -124,4 +149,23 @@ mod test {
         assert!(blacklist.contains(SocketAddr::new(mapped_ip, 42)));
         assert!(!blacklist.contains(SocketAddr::new(mapped_ip, 8080)));
     }
+
+    #[test]
+    fn test_edit_blacklist() {
+        let entry: Entry = "192.0.2.4".parse().unwrap();
+        assert_eq!(entry.to_string(), "192.0.2.4");
+        assert_eq!(entry.to_string().parse::<Entry>().unwrap(), entry);
+        let entry_port: Entry = "[::1]:42".parse().unwrap();
+        assert_eq!(entry_port.to_string(), "[::1]:42");
+
+        let mut blacklist = Blacklist::default();
+        let addr = net::SocketAddr::new(net::Ipv4Addr::new(192, 0, 2, 4).into(), 42);
+        assert!(!blacklist.contains(addr));
+        assert!(blacklist.insert(entry));
+        assert!(!blacklist.insert(entry));
+        assert!(blacklist.contains(addr));
+        assert!(blacklist.remove(&entry));
+        assert!(!blacklist.remove(&entry));
+        assert!(!blacklist.contains(addr));
+    }
 }
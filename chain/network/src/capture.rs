@@ -0,0 +1,353 @@
+//! Capture of the messages received on the peer connections, so that they can be
+//! replayed against another node (see `neard network replay-capture`).
+//!
+//! Capturing is enabled by setting `network.experimental.capture_dir` in the config.
+//! Every connection is written to a separate file in that directory. A capture file
+//! starts with `MAGIC` and is followed by the frames, each of which is encoded as:
+//!     - u32 LE: length of the rest of the frame,
+//!     - i64 LE: reception time as a unix timestamp in nanoseconds,
+//!     - u8: encoding of the message, 0 for Borsh, 1 for Proto and 2 if the message
+//!       could not be parsed,
+//!     - the message, exactly as received on the wire.
+//!
+//! The files are written by a separate thread per connection, so that a slow disk
+//! doesn't stall the `PeerActor`. If the thread falls behind by more than
+//! `WRITE_QUEUE_SIZE` frames, or the file would grow over its size limit, capturing
+//! of the connection stops.
+
+use crate::network_protocol::{Encoding, ParsePeerMessageError, PeerMessage};
+use crate::raw;
+use crate::types::PeerChainInfoV2;
+use near_async::time;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+
+/// Magic bytes identifying the capture files and their format version.
+pub const MAGIC: &[u8; 8] = b"NEARCAP1";
+
+/// Size of the timestamp and encoding fields preceding the message in a frame.
+const FRAME_HEADER_LEN: usize = 9;
+
+/// Number of frames that can wait for the writer thread before capturing stops.
+const WRITE_QUEUE_SIZE: usize = 1024;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+    /// Time at which the message has been received.
+    pub timestamp: time::Utc,
+    /// Encoding the message has been parsed with, `None` if it failed to parse.
+    pub encoding: Option<Encoding>,
+    pub bytes: Vec<u8>,
+}
+
+impl Frame {
+    /// Parses the message. Returns `None` for the frames which failed to parse when
+    /// they were received.
+    pub(crate) fn message(&self) -> Option<Result<PeerMessage, ParsePeerMessageError>> {
+        self.encoding.map(|encoding| PeerMessage::deserialize(encoding, &self.bytes))
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub(crate) enum CaptureError {
+    #[error("the writer thread can't keep up")]
+    QueueFull,
+    #[error("the writer thread has stopped")]
+    Stopped,
+}
+
+/// Writes the frames received on a single connection to a capture file.
+pub(crate) struct CaptureWriter {
+    path: PathBuf,
+    sender: mpsc::SyncSender<Frame>,
+    /// Joined only in tests. Otherwise the thread exits on its own once the writer
+    /// is dropped and the queue is drained.
+    #[allow(dead_code)]
+    thread: std::thread::JoinHandle<io::Result<()>>,
+}
+
+impl CaptureWriter {
+    /// Creates a new capture file in `dir` for the connection with `peer_addr` and
+    /// spawns the thread writing to it. The file won't grow over `max_file_size` bytes.
+    pub fn create(
+        dir: &Path,
+        peer_addr: SocketAddr,
+        now: time::Utc,
+        max_file_size: u64,
+    ) -> io::Result<Self> {
+        std::fs::create_dir_all(dir)?;
+        let peer_addr = peer_addr.to_string().replace([':', '[', ']'], "_");
+        let path = dir.join(format!("{}-{}.cap", now.unix_timestamp_nanos(), peer_addr));
+        let mut file = BufWriter::new(File::create_new(&path)?);
+        file.write_all(MAGIC)?;
+        let (sender, receiver) = mpsc::sync_channel(WRITE_QUEUE_SIZE);
+        let thread = std::thread::Builder::new().name("capture".to_string()).spawn({
+            let path = path.clone();
+            move || {
+                let result = write_frames(file, receiver, max_file_size);
+                if let Err(err) = &result {
+                    tracing::warn!(target: "network", ?err, path = %path.display(), "Failed to write to the capture file, capturing stopped");
+                }
+                result
+            }
+        })?;
+        Ok(Self { path, sender, thread })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Queues the frame to be appended to the file. Never blocks: if the writer
+    /// thread can't keep up or has stopped, an error is returned and the caller is
+    /// expected to drop the writer.
+    pub fn write(&self, frame: Frame) -> Result<(), CaptureError> {
+        self.sender.try_send(frame).map_err(|err| match err {
+            mpsc::TrySendError::Full(_) => CaptureError::QueueFull,
+            mpsc::TrySendError::Disconnected(_) => CaptureError::Stopped,
+        })
+    }
+
+    /// Waits until all the queued frames are written to the file.
+    #[cfg(test)]
+    pub fn finish(self) -> io::Result<()> {
+        drop(self.sender);
+        self.thread.join().unwrap()
+    }
+}
+
+/// Body of the writer thread. The file is flushed whenever the queue gets empty, so
+/// that the capture is complete even if the node crashes.
+fn write_frames(
+    mut file: BufWriter<File>,
+    receiver: mpsc::Receiver<Frame>,
+    max_file_size: u64,
+) -> io::Result<()> {
+    let mut file_size = MAGIC.len() as u64;
+    while let Ok(mut frame) = receiver.recv() {
+        loop {
+            let len = u32::try_from(FRAME_HEADER_LEN + frame.bytes.len())
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "frame too large"))?;
+            file_size += 4 + len as u64;
+            if file_size > max_file_size {
+                tracing::info!(target: "network", max_file_size, "Capture file reached its size limit, capturing stopped");
+                return file.flush();
+            }
+            file.write_all(&len.to_le_bytes())?;
+            file.write_all(&(frame.timestamp.unix_timestamp_nanos() as i64).to_le_bytes())?;
+            file.write_all(&[match frame.encoding {
+                Some(Encoding::Borsh) => 0,
+                Some(Encoding::Proto) => 1,
+                None => 2,
+            }])?;
+            file.write_all(&frame.bytes)?;
+            match receiver.try_recv() {
+                Ok(next) => frame = next,
+                Err(_) => break,
+            }
+        }
+        file.flush()?;
+    }
+    Ok(())
+}
+
+/// Reads the frames of a capture file one by one.
+pub struct CaptureReader {
+    file: BufReader<File>,
+}
+
+impl CaptureReader {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let mut file = BufReader::new(File::open(path)?);
+        let mut magic = [0; MAGIC.len()];
+        file.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid_data(format!("{} is not a capture file", path.display())));
+        }
+        Ok(Self { file })
+    }
+
+    fn read_frame(&mut self) -> io::Result<Option<Frame>> {
+        let mut len = [0; 4];
+        match self.file.read_exact(&mut len) {
+            Ok(()) => {}
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(err) => return Err(err),
+        }
+        let len = u32::from_le_bytes(len) as usize;
+        if len < FRAME_HEADER_LEN {
+            return Err(invalid_data(format!("frame of length {len} is too short")));
+        }
+        let mut frame = vec![0; len];
+        self.file.read_exact(&mut frame)?;
+        let timestamp = i64::from_le_bytes(frame[..8].try_into().unwrap());
+        let timestamp = time::Utc::from_unix_timestamp_nanos(timestamp as i128)
+            .map_err(|err| invalid_data(err.to_string()))?;
+        let encoding = match frame[8] {
+            0 => Some(Encoding::Borsh),
+            1 => Some(Encoding::Proto),
+            2 => None,
+            encoding => return Err(invalid_data(format!("unknown encoding {encoding}"))),
+        };
+        frame.drain(..FRAME_HEADER_LEN);
+        Ok(Some(Frame { timestamp, encoding, bytes: frame }))
+    }
+}
+
+impl Iterator for CaptureReader {
+    type Item = io::Result<Frame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_frame().transpose()
+    }
+}
+
+fn invalid_data(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Returns the chain info advertised by the peer in the captured handshake, if any.
+pub fn handshake_chain_info(
+    frames: impl IntoIterator<Item = io::Result<Frame>>,
+) -> io::Result<Option<PeerChainInfoV2>> {
+    for frame in frames {
+        if let Some(Ok(
+            PeerMessage::Tier1Handshake(handshake) | PeerMessage::Tier2Handshake(handshake),
+        )) = frame?.message()
+        {
+            return Ok(Some(handshake.sender_chain_info));
+        }
+    }
+    Ok(None)
+}
+
+/// How the captured messages are spaced out when replayed.
+#[derive(Clone, Copy, Debug)]
+pub enum Pacing {
+    /// Every message is followed by a ping, and the next message is sent only once
+    /// the node has answered it. The node hence handles the messages one at a time,
+    /// in the captured order, regardless of the network and the machine speed.
+    Lockstep,
+    /// The messages are sent with their captured relative timing, divided by the
+    /// given speed factor. Useful for timing related bugs, but not deterministic.
+    Timed { speed: f64 },
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ReplayStats {
+    /// Number of messages sent to the node.
+    pub sent: usize,
+    /// Number of sent messages which failed to parse when they were captured.
+    pub unparsed: usize,
+    /// Number of handshake messages, which are not replayed.
+    pub skipped: usize,
+}
+
+/// Sends the captured frames over `conn`, which has already performed its own
+/// handshake.
+pub async fn replay(
+    clock: &time::Clock,
+    conn: &mut raw::Connection,
+    frames: impl IntoIterator<Item = io::Result<Frame>>,
+    pacing: Pacing,
+) -> io::Result<ReplayStats> {
+    let mut stats = ReplayStats::default();
+    let start = clock.now();
+    let mut first_timestamp = None;
+    for frame in frames {
+        let frame = frame?;
+        if let Pacing::Timed { speed } = pacing {
+            let first_timestamp = *first_timestamp.get_or_insert(frame.timestamp);
+            let offset = (frame.timestamp - first_timestamp).max(time::Duration::ZERO);
+            if speed > 0.0 {
+                clock.sleep_until(start + offset / speed).await;
+            }
+        }
+        if !conn.send_captured(&frame).await? {
+            stats.skipped += 1;
+            continue;
+        }
+        stats.sent += 1;
+        if frame.encoding.is_none() {
+            stats.unparsed += 1;
+        }
+        if let Pacing::Lockstep = pacing {
+            conn.ping_and_wait(stats.sent as u64).await?;
+        }
+    }
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network_protocol::testonly as data;
+    use crate::testonly::make_rng;
+
+    #[test]
+    fn test_capture_roundtrip() {
+        let mut rng = make_rng(89028037453);
+        let clock = time::FakeClock::default();
+        let chain = data::Chain::make(&clock, &mut rng, 10);
+        let dir = tempfile::tempdir().unwrap();
+        let addr: SocketAddr = "[::1]:24567".parse().unwrap();
+
+        let mut frames: Vec<_> = [Encoding::Proto, Encoding::Borsh]
+            .into_iter()
+            .enumerate()
+            .map(|(i, encoding)| {
+                clock.advance(time::Duration::milliseconds(i as i64 * 10));
+                let msg = PeerMessage::Block(chain.blocks[i].clone());
+                Frame {
+                    timestamp: clock.now_utc(),
+                    encoding: Some(encoding),
+                    bytes: msg.serialize(encoding),
+                }
+            })
+            .collect();
+        frames.push(Frame { timestamp: clock.now_utc(), encoding: None, bytes: vec![1, 2, 3] });
+        let writer = CaptureWriter::create(dir.path(), addr, clock.now_utc(), u64::MAX).unwrap();
+        for frame in &frames {
+            writer.write(frame.clone()).unwrap();
+        }
+        let path = writer.path().to_path_buf();
+        writer.finish().unwrap();
+
+        let read: Vec<_> = CaptureReader::open(&path).unwrap().collect::<io::Result<_>>().unwrap();
+        assert_eq!(read, frames);
+        for (frame, i) in read[..2].iter().zip(0..) {
+            assert_eq!(
+                frame.message().unwrap().unwrap(),
+                PeerMessage::Block(chain.blocks[i].clone())
+            );
+        }
+        assert!(read[2].message().is_none());
+        assert_eq!(handshake_chain_info(read.into_iter().map(Ok)).unwrap(), None);
+    }
+
+    #[test]
+    fn test_capture_size_limit() {
+        let clock = time::FakeClock::default();
+        let dir = tempfile::tempdir().unwrap();
+        let addr: SocketAddr = "[::1]:24567".parse().unwrap();
+        let frame = Frame { timestamp: clock.now_utc(), encoding: None, bytes: vec![0; 100] };
+        let frame_size = (4 + FRAME_HEADER_LEN + frame.bytes.len()) as u64;
+
+        // Room for two frames and a half.
+        let max_file_size = MAGIC.len() as u64 + 5 * frame_size / 2;
+        let writer =
+            CaptureWriter::create(dir.path(), addr, clock.now_utc(), max_file_size).unwrap();
+        for _ in 0..3 {
+            writer.write(frame.clone()).unwrap();
+        }
+        let path = writer.path().to_path_buf();
+        writer.finish().unwrap();
+
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), MAGIC.len() as u64 + 2 * frame_size);
+        let read: Vec<_> = CaptureReader::open(&path).unwrap().collect::<io::Result<_>>().unwrap();
+        assert_eq!(read, vec![frame.clone(), frame]);
+    }
+}
@@ -1,8 +1,12 @@
-use crate::network_protocol::StateResponseInfo;
+use crate::network_protocol::{
+    BlockRangeRequest, BlockRangeResponse, CompactBlockRequest, CompactBlockResponse,
+    StateResponseInfo,
+};
 use crate::types::{NetworkInfo, ReasonForBan};
 use near_async::messaging::{AsyncSender, Sender};
 use near_async::{MultiSend, MultiSendMessage, MultiSenderFrom};
 use near_primitives::block::{Approval, Block, BlockHeader};
+use near_primitives::compact_block::CompactBlock;
 use near_primitives::epoch_sync::CompressedEpochSyncProof;
 use near_primitives::errors::InvalidTxError;
 use near_primitives::hash::CryptoHash;
@@ -141,6 +145,48 @@ pub struct OptimisticBlockMessage {
     pub from_peer: PeerId,
 }
 
+/// A peer requested a range of blocks. The response is sent back in parts via
+/// `NetworkRequests::BlockRangeResponse`.
+#[derive(actix::Message, Debug, Clone, PartialEq, Eq)]
+#[rtype(result = "()")]
+pub struct BlockRangeRequestMessage {
+    pub from_peer: PeerId,
+    pub request: BlockRangeRequest,
+}
+
+/// Part of the response to a BlockRangeRequest sent by this node.
+#[derive(actix::Message, Debug, Clone, PartialEq, Eq)]
+#[rtype(result = "()")]
+pub struct BlockRangeResponseMessage {
+    pub from_peer: PeerId,
+    pub response: BlockRangeResponse,
+}
+
+/// A block relayed by a peer as a compact block.
+#[derive(actix::Message, Debug, Clone, PartialEq, Eq)]
+#[rtype(result = "()")]
+pub struct CompactBlockMessage {
+    pub from_peer: PeerId,
+    pub compact_block: CompactBlock,
+}
+
+/// A peer requested the pieces of a compact block, which it couldn't find locally.
+/// The response is sent back via `NetworkRequests::CompactBlockResponse`.
+#[derive(actix::Message, Debug, Clone, PartialEq, Eq)]
+#[rtype(result = "()")]
+pub struct CompactBlockRequestMessage {
+    pub from_peer: PeerId,
+    pub request: CompactBlockRequest,
+}
+
+/// Response to a CompactBlockRequest sent by this node.
+#[derive(actix::Message, Debug, Clone, PartialEq, Eq)]
+#[rtype(result = "()")]
+pub struct CompactBlockResponseMessage {
+    pub from_peer: PeerId,
+    pub response: CompactBlockResponse,
+}
+
 #[derive(Clone, MultiSend, MultiSenderFrom, MultiSendMessage)]
 #[multi_send_message_derive(Debug)]
 #[multi_send_input_derive(Debug, Clone, PartialEq, Eq)]
@@ -163,4 +209,9 @@ pub struct ClientSenderForNetwork {
     pub epoch_sync_request: Sender<EpochSyncRequestMessage>,
     pub epoch_sync_response: Sender<EpochSyncResponseMessage>,
     pub optimistic_block_receiver: Sender<OptimisticBlockMessage>,
+    pub block_range_request: Sender<BlockRangeRequestMessage>,
+    pub block_range_response: Sender<BlockRangeResponseMessage>,
+    pub compact_block: Sender<CompactBlockMessage>,
+    pub compact_block_request: Sender<CompactBlockRequestMessage>,
+    pub compact_block_response: Sender<CompactBlockResponseMessage>,
 }
@@ -1,9 +1,11 @@
 use crate::blacklist;
 use crate::concurrency::rate;
+use crate::dns_seeds;
 use crate::network_protocol::PeerAddr;
 use crate::network_protocol::PeerInfo;
 use crate::peer_manager::peer_store;
 use crate::rate_limits::messages_limits;
+use crate::rate_limits::send_limits;
 use crate::snapshot_hosts;
 use crate::stun;
 use crate::tcp;
@@ -101,17 +103,30 @@ pub struct Tier1 {
     /// - a node will try to start outbound TIER1 connections iff `enable_outbound` is true.
     pub enable_inbound: bool,
     pub enable_outbound: bool,
+    /// Transport of the TIER1 connections. With QUIC, the node accepts TIER1 connections on
+    /// the UDP port of `node_addr` and establishes outbound TIER1 connections over QUIC to
+    /// the peers which support it, falling back to TCP otherwise.
+    pub transport: tcp::Transport,
 }
 
 #[derive(Clone)]
 pub struct SocketOptions {
     pub recv_buffer_size: Option<u32>,
     pub send_buffer_size: Option<u32>,
+    /// TEST-ONLY: if set, the outbound connections are established over the simulated
+    /// network instead of TCP. See `crate::testonly::sim`.
+    #[cfg(test)]
+    pub(crate) simulated: Option<crate::testonly::sim::Endpoint>,
 }
 
 impl SocketOptions {
     pub fn default() -> SocketOptions {
-        SocketOptions { recv_buffer_size: None, send_buffer_size: None }
+        SocketOptions {
+            recv_buffer_size: None,
+            send_buffer_size: None,
+            #[cfg(test)]
+            simulated: None,
+        }
     }
 }
 
@@ -123,6 +138,10 @@ pub struct NetworkConfig {
     pub validator: ValidatorConfig,
 
     pub peer_store: peer_store::Config,
+    /// DNS seeds from which additional boot nodes are discovered, see `crate::dns_seeds`.
+    pub dns_seeds: Vec<dns_seeds::DnsSeed>,
+    /// Period of re-resolving the DNS seeds.
+    pub dns_seeds_refresh_period: time::Duration,
     pub snapshot_hosts: snapshot_hosts::Config,
     pub whitelist_nodes: Vec<PeerInfo>,
     pub handshake_timeout: time::Duration,
@@ -195,6 +214,19 @@ pub struct NetworkConfig {
 
     /// Configuration of rate limits for incoming messages.
     pub received_messages_rate_limits: messages_limits::Config,
+    /// Configuration of priorities and byte rate limits of outgoing messages.
+    pub sent_messages_limits: send_limits::Config,
+
+    /// If set, the messages received on every connection are written to a separate
+    /// capture file in this directory. See `crate::capture`.
+    pub capture_dir: Option<std::path::PathBuf>,
+    /// Capturing of a connection stops once its capture file reaches this size in bytes.
+    pub capture_max_file_size: u64,
+
+    /// If set, blocks are relayed to the peers which support it as compact blocks
+    /// (see `near_primitives::compact_block`), and the compact blocks received from
+    /// the peers are accepted.
+    pub compact_block_relay: bool,
 
     #[cfg(test)]
     pub(crate) event_sink:
@@ -244,6 +276,9 @@ impl NetworkConfig {
         if let Some(rate_limits) = overrides.received_messages_rate_limits {
             self.received_messages_rate_limits.apply_overrides(rate_limits);
         }
+        if let Some(limits) = overrides.sent_messages_limits {
+            self.sent_messages_limits.apply_overrides(limits);
+        }
     }
 
     pub fn new(
@@ -325,6 +360,8 @@ impl NetworkConfig {
                 ban_window: cfg.ban_window.try_into()?,
                 peer_expiration_duration: cfg.peer_expiration_duration.try_into()?,
             },
+            dns_seeds: cfg.dns_seeds.unwrap_or_default(),
+            dns_seeds_refresh_period: cfg.dns_seeds_refresh_period,
             snapshot_hosts: snapshot_hosts::Config {
                 snapshot_hosts_cache_size: cfg.snapshot_hosts_cache_size,
                 part_selection_cache_batch_size: 10,
@@ -354,6 +391,8 @@ impl NetworkConfig {
             socket_options: SocketOptions {
                 recv_buffer_size: cfg.so_recv_buffer_size,
                 send_buffer_size: cfg.so_send_buffer_size,
+                #[cfg(test)]
+                simulated: None,
             },
             peer_recent_time_window: cfg.peer_recent_time_window.try_into()?,
             safe_set_size: cfg.safe_set_size,
@@ -376,6 +415,7 @@ impl NetworkConfig {
                 advertise_proxies_interval: time::Duration::minutes(15),
                 enable_inbound: cfg.experimental.tier1_enable_inbound,
                 enable_outbound: cfg.experimental.tier1_enable_outbound,
+                transport: cfg.experimental.tier1_transport,
             }),
             inbound_disabled: cfg.experimental.inbound_disabled,
             skip_tombstones: if cfg.experimental.skip_sending_tombstones_seconds > 0 {
@@ -385,6 +425,10 @@ impl NetworkConfig {
             },
             // Use a preset to configure rate limits and override entries with user defined values later.
             received_messages_rate_limits: messages_limits::Config::standard_preset(),
+            sent_messages_limits: send_limits::Config::standard_preset(),
+            capture_dir: cfg.experimental.capture_dir,
+            capture_max_file_size: cfg.experimental.capture_max_file_size_bytes,
+            compact_block_relay: cfg.experimental.compact_block_relay,
             #[cfg(test)]
             event_sink: near_async::messaging::IntoSender::into_sender(
                 near_async::messaging::noop(),
@@ -423,6 +467,8 @@ impl NetworkConfig {
                 peer_expiration_duration: time::Duration::seconds(60 * 60),
                 connect_only_to_boot_nodes: false,
             },
+            dns_seeds: vec![],
+            dns_seeds_refresh_period: time::Duration::minutes(30),
             snapshot_hosts: snapshot_hosts::Config {
                 snapshot_hosts_cache_size: 1000,
                 part_selection_cache_batch_size: 10,
@@ -435,7 +481,7 @@ impl NetworkConfig {
             minimum_outbound_peers: 5,
             ideal_connections_lo: 30,
             ideal_connections_hi: 35,
-            socket_options: SocketOptions { recv_buffer_size: None, send_buffer_size: None },
+            socket_options: SocketOptions::default(),
             peer_recent_time_window: time::Duration::seconds(600),
             safe_set_size: 20,
             archival_peer_connections_lower_bound: 10,
@@ -460,9 +506,14 @@ impl NetworkConfig {
                 advertise_proxies_interval: time::Duration::hours(1000),
                 enable_inbound: true,
                 enable_outbound: true,
+                transport: tcp::Transport::Tcp,
             }),
             skip_tombstones: None,
             received_messages_rate_limits: messages_limits::Config::default(),
+            sent_messages_limits: send_limits::Config::standard_preset(),
+            capture_dir: None,
+            capture_max_file_size: u64::MAX,
+            compact_block_relay: false,
             #[cfg(test)]
             event_sink: near_async::messaging::IntoSender::into_sender(
                 near_async::messaging::noop(),
@@ -503,6 +554,13 @@ impl NetworkConfig {
             );
         }
 
+        if !self.dns_seeds.is_empty() && self.dns_seeds_refresh_period <= time::Duration::ZERO {
+            anyhow::bail!(
+                "dns_seeds_refresh_period({}) has to be positive.",
+                self.dns_seeds_refresh_period
+            );
+        }
+
         if !(self.max_send_peers <= PEERS_RESPONSE_MAX_PEERS) {
             anyhow::bail!(
                 "max_send_peers({}) can be at most {}",
@@ -521,6 +579,9 @@ impl NetworkConfig {
         if let Err(err) = self.received_messages_rate_limits.validate() {
             anyhow::bail!("One or more invalid rate limits: {err:?}");
         }
+        if let Err(err) = self.sent_messages_limits.validate() {
+            anyhow::bail!("One or more invalid sent messages limits: {err:?}");
+        }
 
         Ok(VerifiedConfig { node_id: self.node_id(), inner: self })
     }
@@ -560,9 +621,11 @@ mod test {
     use crate::network_protocol;
     use crate::network_protocol::testonly as data;
     use crate::network_protocol::{AccountData, VersionedAccountData};
+    use crate::peer::stream::MessageClass;
     use crate::rate_limits::messages_limits::{
         RateLimitedPeerMessageKey::BlockHeaders, SingleMessageConfig,
     };
+    use crate::rate_limits::send_limits;
     use crate::tcp;
     use crate::testonly::make_rng;
     use near_async::time;
@@ -716,4 +779,18 @@ mod test {
             .insert(BlockHeaders, SingleMessageConfig::new(1, 4.0, None));
         assert!(nc.verify().is_ok());
     }
+
+    #[test]
+    fn sent_messages_limits_error() {
+        let limit = |bytes_per_second, burst_bytes| {
+            Some(send_limits::ByteRateLimit { bytes_per_second, burst_bytes })
+        };
+        let mut nc = config::NetworkConfig::from_seed("123", tcp::ListenerAddr::reserve_for_test());
+        nc.sent_messages_limits.classes[MessageClass::StateSync].node_limit = limit(0, 1000);
+        assert!(nc.verify().is_err());
+
+        let mut nc = config::NetworkConfig::from_seed("123", tcp::ListenerAddr::reserve_for_test());
+        nc.sent_messages_limits.classes[MessageClass::StateSync].node_limit = limit(1000, 1000);
+        assert!(nc.verify().is_ok());
+    }
 }
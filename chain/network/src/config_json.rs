@@ -1,5 +1,7 @@
+use crate::dns_seeds;
 use crate::network_protocol::PeerAddr;
 use crate::rate_limits::messages_limits;
+use crate::rate_limits::send_limits;
 use crate::stun;
 use near_async::time::Duration;
 
@@ -67,6 +69,10 @@ fn default_snapshot_hosts_cache_size() -> u32 {
 fn default_peer_expiration_duration() -> Duration {
     Duration::seconds(7 * 24 * 60 * 60)
 }
+/// Period of re-resolving the DNS seeds.
+fn default_dns_seeds_refresh_period() -> Duration {
+    Duration::seconds(30 * 60)
+}
 
 /// This is a list of public STUN servers provided by Google,
 /// which are known to have good availability. To avoid trusting
@@ -92,6 +98,16 @@ pub struct Config {
     ///   ed25519:86EtEy7epneKyrcJwSWP7zsisTkfDRH5CFVszt4qiQYw@31.192.22.209:24567
     ///   ed25519:86EtEy7epneKyrcJwSWP7zsisTkfDRH5CFVszt4qiQYw@nearnode.com:24567
     pub boot_nodes: String,
+    /// DNS seeds from which additional boot nodes are discovered, see `crate::dns_seeds`.
+    /// If not set, the default DNS seeds of the chain are used.
+    /// Example:
+    ///   [{"name": "seeds.example.org", "public_key": "ed25519:86EtEy7epneKyrcJwSWP7zsisTkfDRH5CFVszt4qiQYw"}]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dns_seeds: Option<Vec<dns_seeds::DnsSeed>>,
+    /// Period of re-resolving the DNS seeds.
+    #[serde(default = "default_dns_seeds_refresh_period")]
+    #[serde(with = "near_async::time::serde_duration_as_std")]
+    pub dns_seeds_refresh_period: Duration,
     /// Comma separated list of whitelisted nodes. Inbound connections from the nodes on
     /// the whitelist are accepted even if the limit of the inbound connection has been reached.
     /// For each whitelisted node specifying both PeerId and one of IP:port or Host:port is required:
@@ -231,6 +247,10 @@ fn default_tier1_new_connections_per_attempt() -> u64 {
     50
 }
 
+fn default_capture_max_file_size_bytes() -> u64 {
+    1 << 30
+}
+
 #[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
 pub struct ExperimentalConfig {
     // If true - don't allow any inbound connections.
@@ -265,6 +285,22 @@ pub struct ExperimentalConfig {
     #[serde(default = "default_tier1_new_connections_per_attempt")]
     pub tier1_new_connections_per_attempt: u64,
 
+    /// See `near_network::config::Tier1::transport`.
+    #[serde(default)]
+    pub tier1_transport: crate::tcp::Transport,
+
+    /// See `near_network::config::NetworkConfig::capture_dir`.
+    #[serde(default)]
+    pub capture_dir: Option<std::path::PathBuf>,
+
+    /// See `near_network::config::NetworkConfig::capture_max_file_size`.
+    #[serde(default = "default_capture_max_file_size_bytes")]
+    pub capture_max_file_size_bytes: u64,
+
+    /// See `near_network::config::NetworkConfig::compact_block_relay`.
+    #[serde(default)]
+    pub compact_block_relay: bool,
+
     /// See `NetworkConfig`.
     /// Fields set here will override the NetworkConfig fields.
     #[serde(default)]
@@ -287,6 +323,7 @@ pub struct NetworkConfigOverrides {
     pub routing_table_update_rate_limit_burst: Option<u64>,
     pub routing_table_update_rate_limit_qps: Option<f64>,
     pub received_messages_rate_limits: Option<messages_limits::OverrideConfig>,
+    pub sent_messages_limits: Option<send_limits::OverrideConfig>,
 }
 
 impl Default for ExperimentalConfig {
@@ -299,6 +336,10 @@ impl Default for ExperimentalConfig {
             tier1_enable_outbound: default_tier1_enable_outbound(),
             tier1_connect_interval: default_tier1_connect_interval(),
             tier1_new_connections_per_attempt: default_tier1_new_connections_per_attempt(),
+            tier1_transport: Default::default(),
+            capture_dir: None,
+            capture_max_file_size_bytes: default_capture_max_file_size_bytes(),
+            compact_block_relay: false,
             network_config_overrides: Default::default(),
         }
     }
@@ -309,6 +350,8 @@ impl Default for Config {
         Config {
             addr: "0.0.0.0:24567".to_string(),
             boot_nodes: "".to_string(),
+            dns_seeds: None,
+            dns_seeds_refresh_period: default_dns_seeds_refresh_period(),
             whitelist_nodes: "".to_string(),
             max_num_peers: default_max_num_peers(),
             minimum_outbound_peers: default_minimum_outbound_connections(),
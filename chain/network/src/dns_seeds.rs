@@ -0,0 +1,306 @@
+//! Discovery of the boot nodes via DNS seeds.
+//!
+//! A DNS seed is a domain name with TXT records listing the boot nodes of a chain. It lets
+//! the operators of the seed update the boot nodes without every node operator having to
+//! edit `config.json`. The peers obtained from the seeds are used in addition to the static
+//! `boot_nodes` and are re-resolved every `NetworkConfig::dns_seeds_refresh_period`.
+//!
+//! DNS responses are not authenticated, so every record is signed with the key of the seed,
+//! which is configured together with its name (see `DnsSeed`). A record looks like:
+//!     v=near1;chain=<chain_id>;exp=<unix timestamp>;peers=<peer_id>@<ip>:<port>,...;sig=<signature>
+//! where the signature covers everything preceding ";sig=". The chain id prevents a record
+//! of one chain from being served for another one, and the expiration time bounds how long
+//! a stale record can be replayed. TXT records other than the seed records are ignored.
+//!
+//! SRV records are not supported: they carry neither peer ids nor signatures.
+use crate::network_protocol::PeerInfo;
+use futures::FutureExt as _;
+use futures::future::BoxFuture;
+use near_async::time;
+use near_crypto::{PublicKey, SecretKey, Signature};
+use near_primitives::network::PeerId;
+use std::net::SocketAddr;
+
+/// Version prefix of the seed records.
+const RECORD_PREFIX: &str = "v=near1;";
+/// Separator of the signature from the signed part of a record.
+const SIGNATURE_SEPARATOR: &str = ";sig=";
+
+/// A DNS seed: the domain name with the TXT records and the key signing them.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DnsSeed {
+    pub name: String,
+    pub public_key: PublicKey,
+}
+
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub enum ParseRecordError {
+    #[error("not a DNS seed record")]
+    NotSeedRecord,
+    #[error("missing field {0}")]
+    MissingField(&'static str),
+    #[error("invalid field {0}: {1}")]
+    InvalidField(&'static str, String),
+    #[error("invalid signature")]
+    InvalidSignature,
+    #[error("record of chain {0}")]
+    WrongChain(String),
+    #[error("record expired at {0}")]
+    Expired(time::Utc),
+}
+
+/// Source of the TXT records. Abstracted away, so that the tests don't depend on DNS.
+pub trait Resolver: Send + Sync + 'static {
+    /// Returns the TXT records of `name`. The character strings of every record
+    /// are concatenated.
+    fn lookup_txt(&self, name: &str) -> BoxFuture<'static, anyhow::Result<Vec<String>>>;
+}
+
+/// Resolver using the system DNS configuration (i.e. /etc/resolv.conf).
+pub struct SystemResolver(hickory_resolver::TokioAsyncResolver);
+
+impl SystemResolver {
+    pub fn new() -> anyhow::Result<Self> {
+        Ok(Self(hickory_resolver::TokioAsyncResolver::tokio_from_system_conf()?))
+    }
+}
+
+impl Resolver for SystemResolver {
+    fn lookup_txt(&self, name: &str) -> BoxFuture<'static, anyhow::Result<Vec<String>>> {
+        let resolver = self.0.clone();
+        let name = name.to_string();
+        async move {
+            let lookup = resolver.txt_lookup(name).await?;
+            Ok(lookup
+                .iter()
+                .map(|txt| {
+                    txt.txt_data().iter().map(|data| String::from_utf8_lossy(data)).collect()
+                })
+                .collect())
+        }
+        .boxed()
+    }
+}
+
+/// Constructs a seed record of `chain_id` listing `peers`, valid until `expires`.
+/// Used by the operators of the seeds to generate the TXT records.
+pub fn sign_record(
+    key: &SecretKey,
+    chain_id: &str,
+    expires: time::Utc,
+    peers: &[(PeerId, SocketAddr)],
+) -> String {
+    let peers: Vec<_> = peers.iter().map(|(id, addr)| format!("{id}@{addr}")).collect();
+    let signed = format!(
+        "{RECORD_PREFIX}chain={chain_id};exp={};peers={}",
+        expires.unix_timestamp(),
+        peers.join(",")
+    );
+    let signature = key.sign(signed.as_bytes());
+    format!("{signed}{SIGNATURE_SEPARATOR}{signature}")
+}
+
+/// Parses and verifies a TXT record of the seed, returning the peers it lists.
+/// Peers are required to have an IP address, so that resolving them doesn't block.
+pub fn parse_record(
+    seed: &DnsSeed,
+    chain_id: &str,
+    now: time::Utc,
+    record: &str,
+) -> Result<Vec<PeerInfo>, ParseRecordError> {
+    if !record.starts_with(RECORD_PREFIX) {
+        return Err(ParseRecordError::NotSeedRecord);
+    }
+    let (signed, signature) =
+        record.rsplit_once(SIGNATURE_SEPARATOR).ok_or(ParseRecordError::MissingField("sig"))?;
+    let signature: Signature =
+        signature.parse().map_err(|err| ParseRecordError::InvalidField("sig", format!("{err}")))?;
+    if !signature.verify(signed.as_bytes(), &seed.public_key) {
+        return Err(ParseRecordError::InvalidSignature);
+    }
+
+    let mut fields = signed.strip_prefix(RECORD_PREFIX).unwrap_or_default().split(';');
+    let mut field = |name: &'static str| {
+        fields
+            .next()
+            .and_then(|field| field.strip_prefix(name)?.strip_prefix('='))
+            .ok_or(ParseRecordError::MissingField(name))
+    };
+    let chain = field("chain")?;
+    let exp = field("exp")?;
+    let peers = field("peers")?;
+
+    if chain != chain_id {
+        return Err(ParseRecordError::WrongChain(chain.to_string()));
+    }
+    let exp = exp
+        .parse()
+        .ok()
+        .and_then(|exp| time::Utc::from_unix_timestamp(exp).ok())
+        .ok_or_else(|| ParseRecordError::InvalidField("exp", exp.to_string()))?;
+    if exp < now {
+        return Err(ParseRecordError::Expired(exp));
+    }
+    peers
+        .split(',')
+        .filter(|peer| !peer.is_empty())
+        .map(|peer| {
+            let invalid = || ParseRecordError::InvalidField("peers", peer.to_string());
+            let (id, addr) = peer.split_once('@').ok_or_else(invalid)?;
+            Ok(PeerInfo {
+                id: PeerId::new(id.parse().map_err(|_| invalid())?),
+                addr: Some(addr.parse().map_err(|_| invalid())?),
+                account_id: None,
+            })
+        })
+        .collect()
+}
+
+/// Resolves all the seeds and returns the peers from their valid records.
+/// Failures are logged and otherwise ignored, so that a single broken seed
+/// doesn't prevent using the others.
+pub async fn resolve(
+    resolver: &dyn Resolver,
+    seeds: &[DnsSeed],
+    chain_id: &str,
+    now: time::Utc,
+) -> Vec<PeerInfo> {
+    let mut peers = vec![];
+    for seed in seeds {
+        let records = match resolver.lookup_txt(&seed.name).await {
+            Ok(records) => records,
+            Err(err) => {
+                tracing::warn!(target: "network", seed = %seed.name, ?err, "Failed to resolve DNS seed");
+                continue;
+            }
+        };
+        for record in records {
+            match parse_record(seed, chain_id, now, &record) {
+                Ok(record_peers) => peers.extend(record_peers),
+                Err(ParseRecordError::NotSeedRecord) => {}
+                Err(err) => {
+                    tracing::warn!(target: "network", seed = %seed.name, %err, "Invalid DNS seed record");
+                }
+            }
+        }
+    }
+    peers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_crypto::KeyType;
+    use std::collections::HashMap;
+
+    /// Resolver serving the records from memory.
+    struct StubResolver(HashMap<String, Vec<String>>);
+
+    impl Resolver for StubResolver {
+        fn lookup_txt(&self, name: &str) -> BoxFuture<'static, anyhow::Result<Vec<String>>> {
+            let records = self.0.get(name).cloned().ok_or_else(|| anyhow::anyhow!("NXDOMAIN"));
+            async move { records }.boxed()
+        }
+    }
+
+    fn make_seed(name: &str) -> (DnsSeed, SecretKey) {
+        let key = SecretKey::from_seed(KeyType::ED25519, name);
+        (DnsSeed { name: name.to_string(), public_key: key.public_key() }, key)
+    }
+
+    fn make_peer(seed: &str, addr: &str) -> (PeerId, SocketAddr) {
+        (
+            PeerId::new(SecretKey::from_seed(KeyType::ED25519, seed).public_key()),
+            addr.parse().unwrap(),
+        )
+    }
+
+    fn peer_infos(peers: &[(PeerId, SocketAddr)]) -> Vec<PeerInfo> {
+        peers.iter().map(|(id, addr)| PeerInfo::new(id.clone(), *addr)).collect()
+    }
+
+    #[test]
+    fn test_parse_record() {
+        let clock = time::FakeClock::default();
+        let now = clock.now_utc();
+        let exp = time::Utc::from_unix_timestamp(now.unix_timestamp() + 3600).unwrap();
+        let (seed, key) = make_seed("seed.test");
+        let peers = [make_peer("a", "1.2.3.4:24567"), make_peer("b", "[2001:db8::1]:24567")];
+
+        let record = sign_record(&key, "mainnet", exp, &peers);
+        assert_eq!(parse_record(&seed, "mainnet", now, &record), Ok(peer_infos(&peers)));
+        assert_eq!(
+            parse_record(&seed, "testnet", now, &record),
+            Err(ParseRecordError::WrongChain("mainnet".to_string()))
+        );
+        let later = exp + time::Duration::seconds(1);
+        assert_eq!(
+            parse_record(&seed, "mainnet", later, &record),
+            Err(ParseRecordError::Expired(exp))
+        );
+        let (other_seed, _) = make_seed("other.test");
+        assert_eq!(
+            parse_record(&other_seed, "mainnet", now, &record),
+            Err(ParseRecordError::InvalidSignature)
+        );
+        let tampered = record.replace("1.2.3.4", "5.6.7.8");
+        assert_eq!(
+            parse_record(&seed, "mainnet", now, &tampered),
+            Err(ParseRecordError::InvalidSignature)
+        );
+        assert_eq!(
+            parse_record(&seed, "mainnet", now, "v=spf1 -all"),
+            Err(ParseRecordError::NotSeedRecord)
+        );
+        assert_eq!(
+            parse_record(&seed, "mainnet", now, &sign_record(&key, "mainnet", exp, &[])),
+            Ok(vec![])
+        );
+    }
+
+    #[test]
+    fn test_parse_record_invalid_peer() {
+        let clock = time::FakeClock::default();
+        let now = clock.now_utc();
+        let (seed, key) = make_seed("seed.test");
+        let (id, _) = make_peer("a", "1.2.3.4:24567");
+        // Hostnames are not allowed.
+        let signed = format!(
+            "{RECORD_PREFIX}chain=mainnet;exp={};peers={id}@localhost:24567",
+            (now + time::Duration::hours(1)).unix_timestamp()
+        );
+        let record = format!("{signed}{SIGNATURE_SEPARATOR}{}", key.sign(signed.as_bytes()));
+        assert_eq!(
+            parse_record(&seed, "mainnet", now, &record),
+            Err(ParseRecordError::InvalidField("peers", format!("{id}@localhost:24567")))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resolve() {
+        let clock = time::FakeClock::default();
+        let now = clock.now_utc();
+        let exp = now + time::Duration::hours(1);
+        let (seed1, key1) = make_seed("seed1.test");
+        let (seed2, key2) = make_seed("seed2.test");
+        let (missing, _) = make_seed("missing.test");
+        let peers1 = [make_peer("a", "1.2.3.4:24567")];
+        let peers2 = [make_peer("b", "5.6.7.8:24567"), make_peer("c", "9.10.11.12:24567")];
+        let resolver = StubResolver(HashMap::from([
+            (
+                seed1.name.clone(),
+                vec![
+                    "v=spf1 -all".to_string(),
+                    sign_record(&key1, "mainnet", exp, &peers1),
+                    // Signed by the key of another seed.
+                    sign_record(&key2, "mainnet", exp, &peers2),
+                ],
+            ),
+            (seed2.name.clone(), vec![sign_record(&key2, "mainnet", exp, &peers2)]),
+        ]));
+
+        let got = resolve(&resolver, &[seed1, missing, seed2], "mainnet", now).await;
+        let want: Vec<_> = peer_infos(&peers1).into_iter().chain(peer_infos(&peers2)).collect();
+        assert_eq!(got, want);
+    }
+}
@@ -2,6 +2,7 @@
 
 pub use crate::peer_manager::peer_manager_actor::{Event, PeerManagerActor};
 pub use crate::rate_limits::messages_limits::OverrideConfig as MessagesLimitsOverrideConfig;
+pub use crate::rate_limits::send_limits::OverrideConfig as SendLimitsOverrideConfig;
 
 mod accounts_data;
 mod announce_accounts;
@@ -9,6 +10,7 @@ mod network_protocol;
 mod peer;
 mod peer_manager;
 mod private_actix;
+mod quic;
 mod rate_limits;
 mod snapshot_hosts;
 mod stats;
@@ -16,12 +18,15 @@ mod store;
 mod stun;
 
 pub mod actix;
+pub mod admin;
 pub mod blacklist;
+pub mod capture;
 pub mod client;
 pub mod concurrency;
 pub mod config;
 pub mod config_json;
 pub mod debug;
+pub mod dns_seeds;
 pub mod raw;
 pub mod routing;
 pub mod shards_manager;
@@ -11,6 +11,9 @@ impl From<&net::Handshake> for mem::Handshake {
             sender_peer_id: x.sender_peer_id.clone(),
             target_peer_id: x.target_peer_id.clone(),
             sender_listen_port: x.sender_listen_port,
+            sender_quic_port: None,
+            sender_block_range_start: None,
+            sender_compact_block_relay: false,
             sender_chain_info: x.sender_chain_info.clone(),
             partial_edge_info: x.partial_edge_info.clone(),
             owned_account: None,
@@ -248,6 +251,14 @@ impl From<&mem::PeerMessage> for net::PeerMessage {
             mem::PeerMessage::BlockRequest(bh) => net::PeerMessage::BlockRequest(bh),
             mem::PeerMessage::Block(b) => net::PeerMessage::Block(b),
             mem::PeerMessage::OptimisticBlock(ob) => net::PeerMessage::OptimisticBlock(ob),
+            // These messages are not supported, we translate them to an empty RoutingTableUpdate.
+            mem::PeerMessage::BlockRangeRequest(_)
+            | mem::PeerMessage::BlockRangeResponse(_)
+            | mem::PeerMessage::CompactBlock(_)
+            | mem::PeerMessage::CompactBlockRequest(_)
+            | mem::PeerMessage::CompactBlockResponse(_) => {
+                net::PeerMessage::SyncRoutingTable(net::RoutingTableUpdate::default())
+            }
             mem::PeerMessage::Transaction(t) => net::PeerMessage::Transaction(t),
             mem::PeerMessage::Routed(r) => net::PeerMessage::Routed(Box::new(r.msg.clone())),
             mem::PeerMessage::Disconnect(_) => net::PeerMessage::Disconnect,
@@ -43,6 +43,7 @@ use near_crypto::Signature;
 use near_o11y::OpenTelemetrySpanExt;
 use near_primitives::block::{Approval, Block, BlockHeader};
 use near_primitives::challenge::Challenge;
+use near_primitives::compact_block::CompactBlock;
 use near_primitives::epoch_sync::CompressedEpochSyncProof;
 use near_primitives::hash::CryptoHash;
 use near_primitives::merkle::combine_hash;
@@ -361,6 +362,13 @@ pub struct Handshake {
     pub(crate) target_peer_id: PeerId,
     /// Sender's listening addr.
     pub(crate) sender_listen_port: Option<u16>,
+    /// QUIC port of the sender, see `crate::quic`.
+    pub(crate) sender_quic_port: Option<u16>,
+    /// Lowest height of the blocks that the sender serves in response to BlockRangeRequest,
+    /// up to its head. Set only by the archival nodes, which keep the whole history.
+    pub(crate) sender_block_range_start: Option<BlockHeight>,
+    /// Whether the sender accepts blocks relayed as CompactBlock messages.
+    pub(crate) sender_compact_block_relay: bool,
     /// Peer's chain information.
     pub(crate) sender_chain_info: PeerChainInfoV2,
     /// Represents new `edge`. Contains only `none` and `Signature` from the sender.
@@ -403,6 +411,46 @@ pub struct PeersResponse {
     pub direct_peers: Vec<PeerInfo>,
 }
 
+/// Request for the blocks of the canonical chain of the receiver with heights in
+/// `[start_height, start_height + count)`. Used for syncing the historical blocks from
+/// the archival nodes, see `Handshake::sender_block_range_start`.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct BlockRangeRequest {
+    pub start_height: BlockHeight,
+    pub count: u64,
+}
+
+/// Part of the response to a BlockRangeRequest. The response is streamed as a sequence of
+/// parts with the blocks in increasing order of heights. Heights without a block on the
+/// canonical chain are skipped, so the blocks of every part form a chain: each block is
+/// the previous block of the next one. Once the last block of a part is checked to be on
+/// the canonical chain, all the blocks of the part are known to be canonical.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct BlockRangeResponse {
+    /// `start_height` of the request.
+    pub start_height: BlockHeight,
+    pub blocks: Vec<Block>,
+    /// Whether this is the last part of the response. The response may end before
+    /// the end of the requested range, if the sender doesn't have the remaining blocks.
+    pub last: bool,
+}
+
+/// Request for the chunk headers of a CompactBlock, which the sender couldn't find locally,
+/// identified by their indices in the compact block.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct CompactBlockRequest {
+    pub block_hash: CryptoHash,
+    pub chunk_indices: Vec<u64>,
+}
+
+/// Response to a CompactBlockRequest. Contains only the requested chunk headers, together
+/// with their indices in the compact block.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct CompactBlockResponse {
+    pub block_hash: CryptoHash,
+    pub chunks: Vec<(u64, ShardChunkHeader)>,
+}
+
 /// Message sent when gracefully disconnecting from the other peer.
 #[derive(PartialEq, Eq, Clone, Debug)]
 pub struct Disconnect {
@@ -436,6 +484,11 @@ pub enum PeerMessage {
     BlockRequest(CryptoHash),
     Block(Block),
     OptimisticBlock(OptimisticBlock),
+    BlockRangeRequest(BlockRangeRequest),
+    BlockRangeResponse(BlockRangeResponse),
+    CompactBlock(CompactBlock),
+    CompactBlockRequest(CompactBlockRequest),
+    CompactBlockResponse(CompactBlockResponse),
 
     Transaction(SignedTransaction),
     Routed(Box<RoutedMessageV2>),
@@ -925,6 +978,13 @@ impl StateResponseInfo {
         }
     }
 
+    pub fn part_id(&self) -> Option<u64> {
+        match self {
+            Self::V1(info) => info.state_response.part_id(),
+            Self::V2(info) => info.state_response.part_id(),
+        }
+    }
+
     pub fn take_state_response(self) -> ShardStateSyncResponse {
         match self {
             Self::V1(info) => ShardStateSyncResponse::V1(info.state_response),
@@ -71,6 +71,8 @@ pub enum ParseHandshakeError {
     TargetPeerId(ParseRequiredError<ParsePublicKeyError>),
     #[error("sender_listen_port {0}")]
     SenderListenPort(std::num::TryFromIntError),
+    #[error("sender_quic_port {0}")]
+    SenderQuicPort(std::num::TryFromIntError),
     #[error("sender_chain_info {0}")]
     SenderChainInfo(ParseRequiredError<ParsePeerChainInfoV2Error>),
     #[error("partial_edge_info {0}")]
@@ -87,6 +89,9 @@ impl From<&Handshake> for proto::Handshake {
             sender_peer_id: MF::some((&x.sender_peer_id).into()),
             target_peer_id: MF::some((&x.target_peer_id).into()),
             sender_listen_port: x.sender_listen_port.unwrap_or(0).into(),
+            sender_quic_port: x.sender_quic_port.unwrap_or(0).into(),
+            sender_block_range_start: x.sender_block_range_start,
+            sender_compact_block_relay: x.sender_compact_block_relay,
             sender_chain_info: MF::some((&x.sender_chain_info).into()),
             partial_edge_info: MF::some((&x.partial_edge_info).into()),
             owned_account: x.owned_account.as_ref().map(Into::into).into(),
@@ -110,6 +115,13 @@ impl TryFrom<&proto::Handshake> for Handshake {
                     u16::try_from(p.sender_listen_port).map_err(Self::Error::SenderListenPort)?;
                 if port == 0 { None } else { Some(port) }
             },
+            sender_quic_port: {
+                let port =
+                    u16::try_from(p.sender_quic_port).map_err(Self::Error::SenderQuicPort)?;
+                if port == 0 { None } else { Some(port) }
+            },
+            sender_block_range_start: p.sender_block_range_start,
+            sender_compact_block_relay: p.sender_compact_block_relay,
             sender_chain_info: try_from_required(&p.sender_chain_info)
                 .map_err(Self::Error::SenderChainInfo)?,
             partial_edge_info: try_from_required(&p.partial_edge_info)
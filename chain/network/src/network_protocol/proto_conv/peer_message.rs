@@ -4,7 +4,8 @@ use crate::network_protocol::proto::peer_message::Message_type as ProtoMT;
 use crate::network_protocol::proto::{self};
 use crate::network_protocol::state_sync::{SnapshotHostInfo, SyncSnapshotHosts};
 use crate::network_protocol::{
-    AdvertisedPeerDistance, Disconnect, DistanceVector, PeerMessage, PeersRequest, PeersResponse,
+    AdvertisedPeerDistance, BlockRangeRequest, BlockRangeResponse, CompactBlockRequest,
+    CompactBlockResponse, Disconnect, DistanceVector, PeerMessage, PeersRequest, PeersResponse,
     RoutingTableUpdate, SyncAccountsData,
 };
 use crate::network_protocol::{RoutedMessage, RoutedMessageV2};
@@ -13,7 +14,9 @@ use borsh::BorshDeserialize as _;
 use near_async::time::error::ComponentRange;
 use near_primitives::block::{Block, BlockHeader};
 use near_primitives::challenge::Challenge;
+use near_primitives::compact_block::CompactBlock;
 use near_primitives::optimistic_block::{OptimisticBlock, OptimisticBlockInner};
+use near_primitives::sharding::ShardChunkHeader;
 use near_primitives::transaction::SignedTransaction;
 use near_primitives::utils::compression::CompressedData;
 use protobuf::MessageField as MF;
@@ -236,6 +239,122 @@ impl TryFrom<&proto::OptimisticBlock> for OptimisticBlock {
 
 //////////////////////////////////////////
 
+#[derive(thiserror::Error, Debug)]
+pub enum ParseBlockRangeResponseError {
+    #[error("blocks {0}")]
+    Blocks(ParseVecError<ParseBlockError>),
+}
+
+impl From<&BlockRangeResponse> for proto::BlockRangeResponse {
+    fn from(x: &BlockRangeResponse) -> Self {
+        Self {
+            start_height: x.start_height,
+            blocks: x.blocks.iter().map(Into::into).collect(),
+            last: x.last,
+            ..Default::default()
+        }
+    }
+}
+
+impl TryFrom<&proto::BlockRangeResponse> for BlockRangeResponse {
+    type Error = ParseBlockRangeResponseError;
+    fn try_from(x: &proto::BlockRangeResponse) -> Result<Self, Self::Error> {
+        Ok(Self {
+            start_height: x.start_height,
+            blocks: try_from_slice(&x.blocks).map_err(Self::Error::Blocks)?,
+            last: x.last,
+        })
+    }
+}
+
+//////////////////////////////////////////
+
+impl From<&CompactBlock> for proto::CompactBlock {
+    fn from(x: &CompactBlock) -> Self {
+        Self { borsh: borsh::to_vec(&x).unwrap(), ..Default::default() }
+    }
+}
+
+pub type ParseCompactBlockError = std::io::Error;
+
+impl TryFrom<&proto::CompactBlock> for CompactBlock {
+    type Error = ParseCompactBlockError;
+    fn try_from(x: &proto::CompactBlock) -> Result<Self, Self::Error> {
+        Self::try_from_slice(&x.borsh)
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ParseCompactBlockRequestError {
+    #[error("block_hash {0}")]
+    BlockHash(ParseRequiredError<ParseCryptoHashError>),
+}
+
+impl From<&CompactBlockRequest> for proto::CompactBlockRequest {
+    fn from(x: &CompactBlockRequest) -> Self {
+        Self {
+            block_hash: MF::some((&x.block_hash).into()),
+            chunk_indices: x.chunk_indices.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+impl TryFrom<&proto::CompactBlockRequest> for CompactBlockRequest {
+    type Error = ParseCompactBlockRequestError;
+    fn try_from(x: &proto::CompactBlockRequest) -> Result<Self, Self::Error> {
+        Ok(Self {
+            block_hash: try_from_required(&x.block_hash).map_err(Self::Error::BlockHash)?,
+            chunk_indices: x.chunk_indices.clone(),
+        })
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ParseCompactBlockResponseError {
+    #[error("block_hash {0}")]
+    BlockHash(ParseRequiredError<ParseCryptoHashError>),
+    #[error("chunk_headers {0}")]
+    ChunkHeaders(std::io::Error),
+    #[error("got {indices} indices for {values} values")]
+    LengthMismatch { indices: usize, values: usize },
+}
+
+impl From<&CompactBlockResponse> for proto::CompactBlockResponse {
+    fn from(x: &CompactBlockResponse) -> Self {
+        Self {
+            block_hash: MF::some((&x.block_hash).into()),
+            chunk_indices: x.chunks.iter().map(|(i, _)| *i).collect(),
+            chunk_headers: x.chunks.iter().map(|(_, c)| borsh::to_vec(c).unwrap()).collect(),
+            ..Default::default()
+        }
+    }
+}
+
+impl TryFrom<&proto::CompactBlockResponse> for CompactBlockResponse {
+    type Error = ParseCompactBlockResponseError;
+    fn try_from(x: &proto::CompactBlockResponse) -> Result<Self, Self::Error> {
+        if x.chunk_indices.len() != x.chunk_headers.len() {
+            return Err(Self::Error::LengthMismatch {
+                indices: x.chunk_indices.len(),
+                values: x.chunk_headers.len(),
+            });
+        }
+        let chunk_headers = x
+            .chunk_headers
+            .iter()
+            .map(|c| ShardChunkHeader::try_from_slice(c))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(Self::Error::ChunkHeaders)?;
+        Ok(Self {
+            block_hash: try_from_required(&x.block_hash).map_err(Self::Error::BlockHash)?,
+            chunks: x.chunk_indices.iter().copied().zip(chunk_headers).collect(),
+        })
+    }
+}
+
+//////////////////////////////////////////
+
 #[derive(thiserror::Error, Debug)]
 pub enum ParseSyncSnapshotHostsError {
     #[error("hosts {0}")]
@@ -328,6 +447,17 @@ impl From<&PeerMessage> for proto::PeerMessage {
                     ..Default::default()
                 }),
                 PeerMessage::OptimisticBlock(ob) => ProtoMT::OptimisticBlock(ob.into()),
+                PeerMessage::BlockRangeRequest(r) => {
+                    ProtoMT::BlockRangeRequest(proto::BlockRangeRequest {
+                        start_height: r.start_height,
+                        count: r.count,
+                        ..Default::default()
+                    })
+                }
+                PeerMessage::BlockRangeResponse(r) => ProtoMT::BlockRangeResponse(r.into()),
+                PeerMessage::CompactBlock(b) => ProtoMT::CompactBlock(b.into()),
+                PeerMessage::CompactBlockRequest(r) => ProtoMT::CompactBlockRequest(r.into()),
+                PeerMessage::CompactBlockResponse(r) => ProtoMT::CompactBlockResponse(r.into()),
                 PeerMessage::Transaction(t) => ProtoMT::Transaction(proto::SignedTransaction {
                     borsh: borsh::to_vec(&t).unwrap(),
                     ..Default::default()
@@ -433,6 +563,14 @@ pub enum ParsePeerMessageError {
     SyncSnapshotHosts(ParseSyncSnapshotHostsError),
     #[error("optimistic_block: {0}")]
     OptimisticBlock(ParseOptimisticBlockError),
+    #[error("block_range_response: {0}")]
+    BlockRangeResponse(ParseBlockRangeResponseError),
+    #[error("compact_block: {0}")]
+    CompactBlock(ParseCompactBlockError),
+    #[error("compact_block_request: {0}")]
+    CompactBlockRequest(ParseCompactBlockRequestError),
+    #[error("compact_block_response: {0}")]
+    CompactBlockResponse(ParseCompactBlockResponseError),
 }
 
 impl TryFrom<&proto::PeerMessage> for PeerMessage {
@@ -506,6 +644,22 @@ impl TryFrom<&proto::PeerMessage> for PeerMessage {
             ProtoMT::OptimisticBlock(ob) => {
                 PeerMessage::OptimisticBlock(ob.try_into().map_err(Self::Error::OptimisticBlock)?)
             }
+            ProtoMT::BlockRangeRequest(r) => PeerMessage::BlockRangeRequest(BlockRangeRequest {
+                start_height: r.start_height,
+                count: r.count,
+            }),
+            ProtoMT::BlockRangeResponse(r) => PeerMessage::BlockRangeResponse(
+                r.try_into().map_err(Self::Error::BlockRangeResponse)?,
+            ),
+            ProtoMT::CompactBlock(b) => {
+                PeerMessage::CompactBlock(b.try_into().map_err(Self::Error::CompactBlock)?)
+            }
+            ProtoMT::CompactBlockRequest(r) => PeerMessage::CompactBlockRequest(
+                r.try_into().map_err(Self::Error::CompactBlockRequest)?,
+            ),
+            ProtoMT::CompactBlockResponse(r) => PeerMessage::CompactBlockResponse(
+                r.try_into().map_err(Self::Error::CompactBlockResponse)?,
+            ),
             ProtoMT::Transaction(t) => PeerMessage::Transaction(
                 SignedTransaction::try_from_slice(&t.borsh).map_err(Self::Error::Transaction)?,
             ),
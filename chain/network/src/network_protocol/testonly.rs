@@ -66,6 +66,7 @@ pub fn make_block(
         None,
         None,
     )
+    .unwrap()
 }
 
 pub fn make_account_id<R: Rng>(rng: &mut R) -> AccountId {
@@ -263,6 +264,7 @@ impl Chain {
             tracked_shards: Default::default(),
             block: self.blocks.last().unwrap().clone(),
             tier1_accounts: Arc::new(self.get_tier1_accounts()),
+            block_range_start: None,
         }
     }
 
@@ -319,6 +321,9 @@ pub fn make_handshake<R: Rng>(rng: &mut R, chain: &Chain) -> Handshake {
         sender_peer_id: a_id,
         target_peer_id: b_id,
         sender_listen_port: Some(rng.r#gen()),
+        sender_quic_port: None,
+        sender_block_range_start: None,
+        sender_compact_block_relay: false,
         sender_chain_info: chain.get_peer_chain_info(),
         partial_edge_info: make_partial_edge(rng),
         owned_account: None,
@@ -63,6 +63,33 @@ fn serialize_deserialize_protobuf_only() {
     let chain = data::Chain::make(&mut clock, &mut rng, 12);
     let msgs = [
         PeerMessage::Tier1Handshake(data::make_handshake(&mut rng, &chain)),
+        PeerMessage::Tier1Handshake(Handshake {
+            sender_quic_port: Some(24567),
+            ..data::make_handshake(&mut rng, &chain)
+        }),
+        PeerMessage::Tier2Handshake(Handshake {
+            sender_block_range_start: Some(0),
+            ..data::make_handshake(&mut rng, &chain)
+        }),
+        PeerMessage::BlockRangeRequest(BlockRangeRequest { start_height: 3, count: 100 }),
+        PeerMessage::BlockRangeResponse(BlockRangeResponse {
+            start_height: 3,
+            blocks: chain.blocks[3..8].to_vec(),
+            last: true,
+        }),
+        PeerMessage::Tier2Handshake(Handshake {
+            sender_compact_block_relay: true,
+            ..data::make_handshake(&mut rng, &chain)
+        }),
+        PeerMessage::CompactBlock(CompactBlock::new(&chain.blocks[5]).unwrap()),
+        PeerMessage::CompactBlockRequest(CompactBlockRequest {
+            block_hash: *chain.blocks[5].hash(),
+            chunk_indices: vec![0, 2],
+        }),
+        PeerMessage::CompactBlockResponse(CompactBlockResponse {
+            block_hash: *chain.blocks[5].hash(),
+            chunks: vec![(1, chain.blocks[5].chunks()[1].clone())],
+        }),
         PeerMessage::SyncAccountsData(SyncAccountsData {
             accounts_data: (0..4)
                 .map(|_| Arc::new(data::make_signed_account_data(&mut rng, &clock.clock())))
@@ -1,8 +1,11 @@
 use crate::accounts_data::AccountDataError;
+use crate::capture;
 use crate::client::{
-    AnnounceAccountRequest, BlockHeadersRequest, BlockHeadersResponse, BlockRequest, BlockResponse,
-    EpochSyncRequestMessage, EpochSyncResponseMessage, OptimisticBlockMessage, ProcessTxRequest,
-    StateRequestHeader, StateRequestPart, StateResponseReceived,
+    AnnounceAccountRequest, BlockHeadersRequest, BlockHeadersResponse, BlockRangeRequestMessage,
+    BlockRangeResponseMessage, BlockRequest, BlockResponse, CompactBlockMessage,
+    CompactBlockRequestMessage, CompactBlockResponseMessage, EpochSyncRequestMessage,
+    EpochSyncResponseMessage, OptimisticBlockMessage, ProcessTxRequest, StateRequestHeader,
+    StateRequestPart, StateResponseReceived,
 };
 use crate::concurrency::atomic_cell::AtomicCell;
 use crate::concurrency::demux;
@@ -180,6 +183,9 @@ pub(crate) struct PeerActor {
 
     /// Peer status.
     peer_status: PeerStatus,
+    /// Time the last handshake has been sent. Used to measure the round trip
+    /// time to outbound peers.
+    handshake_sent_at: Option<time::Instant>,
     closing_reason: Option<ClosingReason>,
     /// Peer id and info. Present when Ready,
     /// or (for outbound only) when Connecting.
@@ -189,6 +195,10 @@ pub(crate) struct PeerActor {
 
     /// Per-message rate limits for incoming messages.
     received_messages_rate_limits: messages_limits::RateLimits,
+    /// Capture file of the received messages, if `NetworkConfig::capture_dir` is set.
+    capture: Option<capture::CaptureWriter>,
+    /// Transport of the connection.
+    transport: tcp::Transport,
 }
 
 impl Debug for PeerActor {
@@ -215,7 +225,7 @@ impl PeerActor {
     /// actix makes everything complicated.
     pub(crate) async fn spawn_and_handshake(
         clock: time::Clock,
-        stream: tcp::Stream,
+        stream: impl Into<stream::Stream>,
         force_encoding: Option<Encoding>,
         network_state: Arc<NetworkState>,
     ) -> anyhow::Result<actix::Addr<Self>> {
@@ -232,10 +242,11 @@ impl PeerActor {
     /// You can asynchronously await the returned HandshakeSignal.
     pub(crate) fn spawn(
         clock: time::Clock,
-        stream: tcp::Stream,
+        stream: impl Into<stream::Stream>,
         force_encoding: Option<Encoding>,
         network_state: Arc<NetworkState>,
     ) -> anyhow::Result<(actix::Addr<Self>, HandshakeSignal)> {
+        let stream = stream.into();
         #[cfg(test)]
         let stream_id = stream.id();
         #[cfg(test)]
@@ -254,11 +265,11 @@ impl PeerActor {
 
     fn spawn_inner(
         clock: time::Clock,
-        stream: tcp::Stream,
+        stream: stream::Stream,
         force_encoding: Option<Encoding>,
         network_state: Arc<NetworkState>,
     ) -> Result<(actix::Addr<Self>, HandshakeSignal), ClosingReason> {
-        let connecting_status = match &stream.type_ {
+        let connecting_status = match stream.type_() {
             tcp::StreamType::Inbound => ConnectingStatus::Inbound(
                 network_state
                     .inbound_handshake_permits
@@ -309,12 +320,14 @@ impl PeerActor {
         };
         // Override force_encoding for outbound Tier1 and Tier3 connections;
         // Tier1Handshake and Tier3Handshake are supported only with proto encoding.
-        let force_encoding = match &stream.type_ {
+        // QUIC connections are always TIER1, so the inbound ones use proto as well.
+        let force_encoding = match stream.type_() {
             tcp::StreamType::Outbound { tier, .. }
                 if tier == &tcp::Tier::T1 || tier == &tcp::Tier::T3 =>
             {
                 Some(Encoding::Proto)
             }
+            _ if stream.transport() == tcp::Transport::Quic => Some(Encoding::Proto),
             _ => force_encoding,
         };
         let my_node_info = PeerInfo {
@@ -335,10 +348,29 @@ impl PeerActor {
         Ok((
             Self::start_in_arbiter(&actix::Arbiter::new().handle(), move |ctx| {
                 let stream_id = stream.id();
-                let peer_addr = stream.peer_addr;
-                let stream_type = stream.type_.clone();
+                let peer_addr = stream.peer_addr();
+                let stream_type = stream.type_().clone();
+                let transport = stream.transport();
                 let stats = Arc::new(connection::Stats::default());
-                let framed = stream::FramedStream::spawn(ctx, stream, stats.clone());
+                let framed = stream::FramedStream::spawn(
+                    ctx,
+                    &clock,
+                    stream,
+                    stats.clone(),
+                    network_state.send_limits.clone(),
+                );
+                let capture = network_state.config.capture_dir.as_ref().and_then(|dir| {
+                    capture::CaptureWriter::create(
+                        dir,
+                        peer_addr,
+                        clock.now_utc(),
+                        network_state.config.capture_max_file_size,
+                    )
+                        .inspect_err(|err| {
+                            tracing::warn!(target: "network", ?err, %peer_addr, "Failed to create the capture file")
+                        })
+                        .ok()
+                });
                 Self {
                     closing_reason: None,
                     clock,
@@ -350,6 +382,7 @@ impl PeerActor {
                         tcp::StreamType::Outbound { .. } => PeerType::Outbound,
                     },
                     peer_status: PeerStatus::Connecting(send, connecting_status),
+                    handshake_sent_at: None,
                     framed,
                     tracker: Default::default(),
                     stats,
@@ -369,6 +402,8 @@ impl PeerActor {
                     .into(),
                     network_state,
                     received_messages_rate_limits,
+                    capture,
+                    transport,
                 }
             }),
             recv,
@@ -392,15 +427,19 @@ impl PeerActor {
         }
     }
 
-    fn parse_message(&mut self, msg: &[u8]) -> Result<PeerMessage, ParsePeerMessageError> {
+    /// Parses the message, returning it together with the encoding it has been received in.
+    fn parse_message(
+        &mut self,
+        msg: &[u8],
+    ) -> Result<(PeerMessage, Encoding), ParsePeerMessageError> {
         if let Some(e) = self.encoding() {
-            return PeerMessage::deserialize(e, msg);
+            return Ok((PeerMessage::deserialize(e, msg)?, e));
         }
         if let Ok(msg) = PeerMessage::deserialize(Encoding::Proto, msg) {
             self.protocol_buffers_supported = true;
-            return Ok(msg);
+            return Ok((msg, Encoding::Proto));
         }
-        return PeerMessage::deserialize(Encoding::Borsh, msg);
+        return Ok((PeerMessage::deserialize(Encoding::Borsh, msg)?, Encoding::Borsh));
     }
 
     fn send_message_or_log(&self, msg: &PeerMessage) {
@@ -454,7 +493,7 @@ impl PeerActor {
         self.tracker.lock().increment_sent(&self.clock, bytes.len() as u64);
         let bytes_len = bytes.len();
         tracing::trace!(target: "network", msg_len = bytes_len);
-        self.framed.send(stream::Frame(bytes));
+        self.framed.send(stream::MessageClass::of(msg), stream::Frame(bytes));
         metrics::PEER_DATA_SENT_BYTES.inc_by(bytes_len as u64);
         let msg_type = msg.msg_variant();
         metrics::PEER_MESSAGE_SENT_BY_TYPE_TOTAL.with_label_values(&[msg_type]).inc();
@@ -463,12 +502,17 @@ impl PeerActor {
             .inc_by(bytes_len as u64);
     }
 
-    fn send_handshake(&self, spec: HandshakeSpec) {
-        let (height, tracked_shards) =
+    fn send_handshake(&mut self, spec: HandshakeSpec) {
+        self.handshake_sent_at = Some(self.clock.now());
+        let (height, tracked_shards, block_range_start) =
             if let Some(chain_info) = self.network_state.chain_info.load().as_ref() {
-                (chain_info.block.header().height(), chain_info.tracked_shards.clone())
+                (
+                    chain_info.block.header().height(),
+                    chain_info.tracked_shards.clone(),
+                    chain_info.block_range_start,
+                )
             } else {
-                (0, vec![])
+                (0, vec![], None)
             };
         let handshake = Handshake {
             protocol_version: spec.protocol_version,
@@ -476,6 +520,9 @@ impl PeerActor {
             sender_peer_id: self.network_state.config.node_id(),
             target_peer_id: spec.peer_id,
             sender_listen_port: self.network_state.config.node_addr.as_ref().map(|a| a.port()),
+            sender_quic_port: self.network_state.quic.get().map(|q| q.port()),
+            sender_block_range_start: block_range_start,
+            sender_compact_block_relay: self.network_state.config.compact_block_relay,
             sender_chain_info: PeerChainInfoV2 {
                 genesis_id: self.network_state.genesis_id.clone(),
                 // TODO: remove `height` from PeerChainInfo
@@ -560,6 +607,11 @@ impl PeerActor {
                     self.stop(ctx, ClosingReason::HandshakeFailed);
                     return;
                 }
+                if let Some(sent_at) = self.handshake_sent_at {
+                    self.network_state
+                        .peer_store
+                        .record_latency(&spec.peer_id, self.clock.now() - sent_at);
+                }
             }
             ConnectingStatus::Inbound { .. } => {
                 if MIN_SUPPORTED_PROTOCOL_VERSION > handshake.protocol_version
@@ -632,6 +684,16 @@ impl PeerActor {
             }
         }
 
+        // QUIC is supported only by TIER1, see `crate::quic`.
+        if self.transport == tcp::Transport::Quic && tier != tcp::Tier::T1 {
+            tracing::debug!(target: "network", "{:?}: Received {tier:?} handshake over QUIC from {:?}, disconnecting", self.my_node_id(), handshake.sender_peer_id);
+            self.stop(ctx, ClosingReason::HandshakeFailed);
+            return;
+        }
+        if let Some(port) = handshake.sender_quic_port {
+            self.network_state.quic_ports.lock().put(handshake.sender_peer_id.clone(), port);
+        }
+
         // Merge partial edges.
         let nonce = handshake.partial_edge_info.nonce;
         let partial_edge_info = match cs {
@@ -672,6 +734,8 @@ impl PeerActor {
             genesis_id: handshake.sender_chain_info.genesis_id.clone(),
             tracked_shards: handshake.sender_chain_info.tracked_shards.clone(),
             archival: handshake.sender_chain_info.archival,
+            block_range_start: handshake.sender_block_range_start,
+            compact_block_relay: handshake.sender_compact_block_relay,
             last_block: Default::default(),
             peer_type: self.peer_type,
             stats: self.stats.clone(),
@@ -698,6 +762,7 @@ impl PeerActor {
             time::Interval::new(clock.now(), self.network_state.config.peer_stats_period);
         ctx.spawn({
             let conn = conn.clone();
+            let network_state = self.network_state.clone();
             wrap_future(async move {
                 loop {
                     interval.tick(&clock).await;
@@ -707,6 +772,11 @@ impl PeerActor {
                         .received_bytes_per_sec
                         .store(received.bytes_per_min / 60, Ordering::Relaxed);
                     conn.stats.sent_bytes_per_sec.store(sent.bytes_per_min / 60, Ordering::Relaxed);
+                    network_state.peer_store.record_messages(
+                        &conn.peer_info.id,
+                        conn.stats.useful_messages.swap(0, Ordering::Relaxed),
+                        conn.stats.useless_messages.swap(0, Ordering::Relaxed),
+                    );
                 }
             })
         });
@@ -991,6 +1061,7 @@ impl PeerActor {
         msg: PeerMessage,
     ) {
         let _span = tracing::trace_span!(target: "network", "receive_message").entered();
+        self.stats.useful_messages.fetch_add(1, Ordering::Relaxed);
         #[cfg(test)]
         let message_processed_event = {
             let sink = self.network_state.config.event_sink.clone();
@@ -1000,11 +1071,14 @@ impl PeerActor {
                 sink.send(Event::MessageProcessed(tier, msg));
             }
         };
-        let was_requested = match &msg {
-            PeerMessage::Block(block) => {
+        let received_block = match &msg {
+            PeerMessage::Block(block) => Some((*block.hash(), block.header().height())),
+            PeerMessage::CompactBlock(block) => Some((*block.hash(), block.header.height())),
+            _ => None,
+        };
+        let was_requested = match received_block {
+            Some((hash, height)) => {
                 self.network_state.txns_since_last_block.store(0, Ordering::Release);
-                let hash = *block.hash();
-                let height = block.header().height();
                 conn.last_block.rcu(|last_block| {
                     if last_block.is_none() || last_block.unwrap().height <= height {
                         Arc::new(Some(BlockInfo { height, hash }))
@@ -1016,7 +1090,7 @@ impl PeerActor {
                 tracker.push_received(hash);
                 tracker.has_request(&hash)
             }
-            _ => false,
+            None => false,
         };
         let clock = self.clock.clone();
         let network_state = self.network_state.clone();
@@ -1101,6 +1175,15 @@ impl PeerActor {
                     .flatten()
                     .map(|response| PeerMessage::VersionedStateResponse(*response.0)),
                 PeerMessage::VersionedStateResponse(info) => {
+                    if let Some(part_id) = info.part_id() {
+                        if network_state.snapshot_hosts.part_response_received(
+                            info.shard_id(),
+                            part_id,
+                            &peer_id,
+                        ) {
+                            network_state.peer_store.record_state_part(&peer_id, true);
+                        }
+                    }
                     //TODO: Route to state sync actor.
                     network_state
                         .client
@@ -1128,6 +1211,36 @@ impl PeerActor {
                         .send(OptimisticBlockMessage { from_peer: peer_id, optimistic_block: ob });
                     None
                 }
+                PeerMessage::BlockRangeRequest(request) => {
+                    network_state
+                        .client
+                        .send(BlockRangeRequestMessage { from_peer: peer_id, request });
+                    None
+                }
+                PeerMessage::BlockRangeResponse(response) => {
+                    network_state
+                        .client
+                        .send(BlockRangeResponseMessage { from_peer: peer_id, response });
+                    None
+                }
+                PeerMessage::CompactBlock(compact_block) => {
+                    network_state
+                        .client
+                        .send(CompactBlockMessage { from_peer: peer_id, compact_block });
+                    None
+                }
+                PeerMessage::CompactBlockRequest(request) => {
+                    network_state
+                        .client
+                        .send(CompactBlockRequestMessage { from_peer: peer_id, request });
+                    None
+                }
+                PeerMessage::CompactBlockResponse(response) => {
+                    network_state
+                        .client
+                        .send(CompactBlockResponseMessage { from_peer: peer_id, response });
+                    None
+                }
                 msg => {
                     tracing::error!(target: "network", "Peer received unexpected type: {:?}", msg);
                     None
@@ -1404,6 +1517,7 @@ impl PeerActor {
                 if let Some(&t) = self.routed_message_cache.get(&key) {
                     if now <= t + DROP_DUPLICATED_MESSAGES_PERIOD {
                         metrics::MessageDropped::Duplicate.inc(&msg.body);
+                        self.stats.useless_messages.fetch_add(1, Ordering::Relaxed);
                         #[cfg(test)]
                         self.network_state.config.event_sink.send(Event::RoutedMessageDropped);
                         tracing::debug!(target: "network", "Dropping duplicated message from {} to {:?}", msg.author, msg.target);
@@ -1569,7 +1683,8 @@ impl actix::Actor for PeerActor {
         if let PeerStatus::Connecting(_, ConnectingStatus::Outbound { handshake_spec, .. }) =
             &self.peer_status
         {
-            self.send_handshake(handshake_spec.clone());
+            let spec = handshake_spec.clone();
+            self.send_handshake(spec);
         }
         #[cfg(test)]
         self.network_state
@@ -1699,10 +1814,23 @@ impl actix::Handler<stream::Frame> for PeerActor {
             self.tracker.lock().increment_received(&self.clock, msg.len() as u64);
         }
 
-        let mut peer_msg = match self.parse_message(&msg) {
-            Ok(msg) => msg,
+        let parsed = self.parse_message(&msg);
+        if let Some(capture) = &self.capture {
+            let frame = capture::Frame {
+                timestamp: self.clock.now_utc(),
+                encoding: parsed.as_ref().ok().map(|(_, encoding)| *encoding),
+                bytes: msg.clone(),
+            };
+            if let Err(err) = capture.write(frame) {
+                tracing::warn!(target: "network", %err, path = %capture.path().display(), "Capturing stopped");
+                self.capture = None;
+            }
+        }
+        let mut peer_msg = match parsed {
+            Ok((peer_msg, _)) => peer_msg,
             Err(err) => {
                 tracing::debug!(target: "network", "Received invalid data {} from {}: {}", near_fmt::AbbrBytes(&msg), self.peer_info, err);
+                self.stats.useless_messages.fetch_add(1, Ordering::Relaxed);
                 return;
             }
         };
@@ -1718,6 +1846,7 @@ impl actix::Handler<stream::Frame> for PeerActor {
                 .inc_by(msg.len() as u64);
             if !self.received_messages_rate_limits.is_allowed(&peer_msg, now) {
                 metrics::PEER_MESSAGE_RATE_LIMITED_BY_TYPE_TOTAL.with_label_values(&labels).inc();
+                self.stats.useless_messages.fetch_add(1, Ordering::Relaxed);
                 tracing::debug!(target: "network", "Peer {} is being rate limited for message {}", self.peer_info, peer_msg.msg_variant());
                 return;
             }
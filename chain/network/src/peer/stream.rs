@@ -1,15 +1,21 @@
+use crate::network_protocol::{PeerMessage, RoutedMessageBody};
 use crate::peer_manager::connection;
+use crate::quic;
+use crate::rate_limits::send_limits;
 use crate::stats::metrics;
 use crate::tcp;
 use actix::AsyncContext as _;
 use actix::fut::future::wrap_future;
 use bytesize::{GIB, MIB};
+use enum_map::Enum as _;
+use enum_map::EnumMap;
+use futures::FutureExt as _;
+use near_async::time;
 use std::io;
 use std::net::SocketAddr;
 use std::sync::Arc;
 use std::sync::atomic::Ordering;
-use tokio::io::AsyncReadExt as _;
-use tokio::io::AsyncWriteExt as _;
+use tokio::io::{AsyncRead, AsyncReadExt as _, AsyncWrite, AsyncWriteExt as _};
 
 /// Maximum size of network message in encoded format.
 /// We encode length as `u32`, and therefore maximum size can't be larger than `u32::MAX`.
@@ -17,9 +23,6 @@ const NETWORK_MESSAGE_MAX_SIZE_BYTES: usize = 512 * MIB as usize;
 /// Maximum capacity of write buffer in bytes.
 const MAX_WRITE_BUFFER_CAPACITY_BYTES: usize = GIB as usize;
 
-type ReadHalf = tokio::io::ReadHalf<tokio::net::TcpStream>;
-type WriteHalf = tokio::io::WriteHalf<tokio::net::TcpStream>;
-
 #[derive(thiserror::Error, Debug)]
 pub(crate) enum SendError {
     #[error("IO error: {0}")]
@@ -40,6 +43,114 @@ pub(crate) enum RecvError {
 #[rtype(result = "()")]
 pub(crate) struct Frame(pub Vec<u8>);
 
+/// Connection over which the frames are sent.
+pub(crate) enum Stream {
+    Tcp(tcp::Stream),
+    Quic(quic::Stream),
+}
+
+impl From<tcp::Stream> for Stream {
+    fn from(stream: tcp::Stream) -> Self {
+        Self::Tcp(stream)
+    }
+}
+
+impl From<quic::Stream> for Stream {
+    fn from(stream: quic::Stream) -> Self {
+        Self::Quic(stream)
+    }
+}
+
+impl Stream {
+    pub fn type_(&self) -> &tcp::StreamType {
+        match self {
+            Self::Tcp(s) => &s.type_,
+            Self::Quic(s) => &s.type_,
+        }
+    }
+
+    pub fn peer_addr(&self) -> SocketAddr {
+        match self {
+            Self::Tcp(s) => s.peer_addr,
+            Self::Quic(s) => s.peer_addr,
+        }
+    }
+
+    pub fn transport(&self) -> tcp::Transport {
+        match self {
+            Self::Tcp(_) => tcp::Transport::Tcp,
+            Self::Quic(_) => tcp::Transport::Quic,
+        }
+    }
+
+    // TEST-ONLY used in reporting test events.
+    pub fn id(&self) -> tcp::StreamId {
+        match self {
+            Self::Tcp(s) => s.id(),
+            Self::Quic(s) => tcp::StreamId::new(&s.type_, s.local_addr, s.peer_addr),
+        }
+    }
+}
+
+/// Class of a message. It determines the send priority and the byte rate limits of the
+/// message (see `rate_limits::send_limits`) and the QUIC stream it is sent on, so that the
+/// large messages of one class don't delay the messages of the other classes.
+/// Over TCP all the classes share the same stream.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    Hash,
+    enum_map::Enum,
+    strum::IntoStaticStr,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+pub enum MessageClass {
+    /// Handshakes and all the messages not covered by the other classes.
+    Control,
+    Block,
+    Approval,
+    Endorsement,
+    Witness,
+    Chunk,
+    /// State sync, epoch sync and block range responses.
+    StateSync,
+}
+
+impl MessageClass {
+    pub fn of(msg: &PeerMessage) -> Self {
+        let msg = match msg {
+            PeerMessage::Routed(msg) => msg,
+            PeerMessage::Block(_)
+            | PeerMessage::OptimisticBlock(_)
+            | PeerMessage::CompactBlock(_)
+            | PeerMessage::CompactBlockResponse(_) => return Self::Block,
+            PeerMessage::VersionedStateResponse(_)
+            | PeerMessage::EpochSyncResponse(_)
+            | PeerMessage::BlockRangeResponse(_) => return Self::StateSync,
+            _ => return Self::Control,
+        };
+        match &msg.body {
+            RoutedMessageBody::BlockApproval(_) => Self::Approval,
+            RoutedMessageBody::VersionedChunkEndorsement(_) => Self::Endorsement,
+            RoutedMessageBody::PartialEncodedStateWitness(_)
+            | RoutedMessageBody::PartialEncodedStateWitnessForward(_)
+            | RoutedMessageBody::ChunkStateWitnessAck(_)
+            | RoutedMessageBody::ChunkContractAccesses(_)
+            | RoutedMessageBody::ContractCodeRequest(_)
+            | RoutedMessageBody::ContractCodeResponse(_) => Self::Witness,
+            RoutedMessageBody::VersionedPartialEncodedChunk(_)
+            | RoutedMessageBody::PartialEncodedChunkForward(_)
+            | RoutedMessageBody::PartialEncodedChunkRequest(_)
+            | RoutedMessageBody::PartialEncodedChunkResponse(_) => Self::Chunk,
+            _ => Self::Control,
+        }
+    }
+}
+
 /// Stream critical error.
 /// Actor is responsible for calling ctx.stop() after receiving stream::Error.
 /// Actor might receive more than 1 stream::Error, but should call ctx.stop() just after the
@@ -57,7 +168,8 @@ pub(crate) enum Error {
 }
 
 pub(crate) struct FramedStream<Actor: actix::Actor> {
-    queue_send: tokio::sync::mpsc::UnboundedSender<Frame>,
+    /// Send queue of every message class. Over TCP they are all the same queue.
+    queue_send: EnumMap<MessageClass, tokio::sync::mpsc::UnboundedSender<(MessageClass, Frame)>>,
     stats: Arc<connection::Stats>,
     send_buf_size_metric: Arc<metrics::IntGaugeGuard>,
     addr: actix::Addr<Actor>,
@@ -71,32 +183,71 @@ where
 {
     pub fn spawn(
         ctx: &mut actix::Context<Actor>,
-        stream: tcp::Stream,
+        clock: &time::Clock,
+        stream: Stream,
         stats: Arc<connection::Stats>,
+        send_limits: Arc<send_limits::SendLimits>,
     ) -> Self {
-        let (tcp_recv, tcp_send) = tokio::io::split(stream.stream);
-        let (queue_send, queue_recv) = tokio::sync::mpsc::unbounded_channel();
+        let peer_addr = stream.peer_addr();
         let send_buf_size_metric = Arc::new(metrics::MetricGuard::new(
             &*metrics::PEER_DATA_WRITE_BUFFER_SIZE,
-            vec![stream.peer_addr.to_string()],
+            vec![peer_addr.to_string()],
         ));
+        let (queue_send, send_loop, recv_loop) = match stream {
+            Stream::Tcp(stream) => {
+                let (tcp_recv, tcp_send) = tokio::io::split(stream.stream);
+                let (queue_send, queue_recv) = tokio::sync::mpsc::unbounded_channel();
+                (
+                    EnumMap::from_fn(|_| queue_send.clone()),
+                    Self::run_send_loop(
+                        tcp_send,
+                        queue_recv,
+                        send_limits::Scheduler::new(send_limits, clock.now()),
+                        clock.clone(),
+                        stats.clone(),
+                        send_buf_size_metric.clone(),
+                    )
+                    .boxed_local(),
+                    Self::run_recv_loop(peer_addr, tcp_recv, ctx.address(), stats.clone(), None)
+                        .boxed_local(),
+                )
+            }
+            Stream::Quic(stream) => {
+                let mut queues_send = vec![];
+                let mut queues_recv = vec![];
+                for _ in 0..MessageClass::LENGTH {
+                    let (queue_send, queue_recv) = tokio::sync::mpsc::unbounded_channel();
+                    queues_send.push(queue_send);
+                    queues_recv.push(queue_recv);
+                }
+                (
+                    EnumMap::from_array(queues_send.try_into().unwrap()),
+                    Self::run_quic_send_loops(
+                        stream.conn.clone(),
+                        queues_recv,
+                        send_limits,
+                        clock.clone(),
+                        stats.clone(),
+                        send_buf_size_metric.clone(),
+                    )
+                    .boxed_local(),
+                    Self::run_quic_recv_loops(peer_addr, stream.conn, ctx.address(), stats.clone())
+                        .boxed_local(),
+                )
+            }
+        };
         ctx.spawn(wrap_future({
             let addr = ctx.address();
-            let stats = stats.clone();
-            let m = send_buf_size_metric.clone();
             async move {
-                if let Err(err) = Self::run_send_loop(tcp_send, queue_recv, stats, m).await {
+                if let Err(err) = send_loop.await {
                     addr.do_send(Error::Send(SendError::IO(err)));
                 }
             }
         }));
         ctx.spawn(wrap_future({
             let addr = ctx.address();
-            let stats = stats.clone();
             async move {
-                if let Err(err) =
-                    Self::run_recv_loop(stream.peer_addr, tcp_recv, addr.clone(), stats).await
-                {
+                if let Err(err) = recv_loop.await {
                     addr.do_send(Error::Recv(err));
                 }
             }
@@ -104,11 +255,11 @@ where
         Self { queue_send, stats, send_buf_size_metric, addr: ctx.address() }
     }
 
-    /// Pushes `msg` to the send queue.
+    /// Pushes `msg` to the send queue of its class, see `send_limits::Scheduler`.
     /// Silently drops message if the connection has been closed.
     /// If the message is too large, it will be silently dropped inside run_send_loop.
     /// Emits a critical error to Actor if send queue is full.
-    pub fn send(&self, frame: Frame) {
+    pub fn send(&self, class: MessageClass, frame: Frame) {
         let msg = &frame.0;
         let mut buf_size =
             self.stats.bytes_to_send.fetch_add(msg.len() as u64, Ordering::Acquire) as usize;
@@ -125,7 +276,7 @@ where
                 want_max_bytes: MAX_WRITE_BUFFER_CAPACITY_BYTES,
             }));
         }
-        let _ = self.queue_send.send(frame);
+        let _ = self.queue_send[class].send((class, frame));
     }
 
     /// Event loop receiving and processing messages.
@@ -136,11 +287,13 @@ where
     /// For each message it allocates a Vec with exact size of the message.
     // TODO(gprusak): once borsh support is dropped, we can parse a proto
     // directly from the stream.
+    /// `first_frame_delivered` is notified once the first message has been processed.
     async fn run_recv_loop(
         peer_addr: SocketAddr,
-        read: ReadHalf,
+        read: impl AsyncRead + Unpin,
         addr: actix::Addr<Actor>,
         stats: Arc<connection::Stats>,
+        mut first_frame_delivered: Option<tokio::sync::oneshot::Sender<()>>,
     ) -> Result<(), RecvError> {
         const READ_BUFFER_CAPACITY: usize = 8 * 1024;
         let mut read = tokio::io::BufReader::with_capacity(READ_BUFFER_CAPACITY, read);
@@ -173,19 +326,116 @@ where
                 // so we should just close the stream.
                 return Ok(());
             }
+            if let Some(delivered) = first_frame_delivered.take() {
+                let _ = delivered.send(());
+            }
+        }
+    }
+
+    /// Accepts the QUIC streams opened by `run_quic_send_loops` and runs a recv loop on each.
+    /// The first message of the `Control` stream, i.e. the handshake, is processed before any
+    /// message of the other streams: the peer may send messages of the other classes as soon as
+    /// it has sent its handshake, while they are rejected until we process it.
+    async fn run_quic_recv_loops(
+        peer_addr: SocketAddr,
+        conn: quinn::Connection,
+        addr: actix::Addr<Actor>,
+        stats: Arc<connection::Stats>,
+    ) -> Result<(), RecvError> {
+        let (handshake_send, handshake_recv) = tokio::sync::oneshot::channel();
+        let control = Self::accept_quic_stream(&conn, MessageClass::Control).await?;
+        let control = Self::run_recv_loop(
+            peer_addr,
+            control,
+            addr.clone(),
+            stats.clone(),
+            Some(handshake_send),
+        );
+        let others = async {
+            // Control stream has been closed before delivering a message,
+            // its recv loop reports the error, if any.
+            if handshake_recv.await.is_err() {
+                return Ok(());
+            }
+            let mut loops = vec![];
+            for class in 1..MessageClass::LENGTH {
+                let read = Self::accept_quic_stream(&conn, MessageClass::from_usize(class)).await?;
+                loops.push(Self::run_recv_loop(peer_addr, read, addr.clone(), stats.clone(), None));
+            }
+            futures::future::try_join_all(loops).await?;
+            Ok::<_, RecvError>(())
+        };
+        tokio::try_join!(control, others)?;
+        Ok(())
+    }
+
+    /// Accepts the next QUIC stream, which is expected to carry the messages of `class`.
+    async fn accept_quic_stream(
+        conn: &quinn::Connection,
+        class: MessageClass,
+    ) -> Result<quinn::RecvStream, RecvError> {
+        let mut read = conn.accept_uni().await.map_err(|err| RecvError::IO(err.into()))?;
+        let got = read.read_u8().await.map_err(RecvError::IO)? as usize;
+        if got != class.into_usize() {
+            return Err(RecvError::IO(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("got stream of class {got}, want {class:?}"),
+            )));
         }
+        Ok(read)
     }
+
+    /// Opens a QUIC stream for every message class, in the order of the classes,
+    /// and runs a send loop on each. Every stream starts with the index of its class.
+    /// The priorities of the classes are applied by QUIC to the streams.
+    async fn run_quic_send_loops(
+        conn: quinn::Connection,
+        queues_recv: Vec<tokio::sync::mpsc::UnboundedReceiver<(MessageClass, Frame)>>,
+        send_limits: Arc<send_limits::SendLimits>,
+        clock: time::Clock,
+        stats: Arc<connection::Stats>,
+        buf_size_metric: Arc<metrics::IntGaugeGuard>,
+    ) -> io::Result<()> {
+        let mut loops = vec![];
+        for (class, queue_recv) in queues_recv.into_iter().enumerate() {
+            let mut send = conn.open_uni().await?;
+            let _ = send.set_priority(send_limits.priority(MessageClass::from_usize(class)).into());
+            send.write_u8(class as u8).await?;
+            loops.push(Self::run_send_loop(
+                send,
+                queue_recv,
+                send_limits::Scheduler::new(send_limits.clone(), clock.now()),
+                clock.clone(),
+                stats.clone(),
+                buf_size_metric.clone(),
+            ));
+        }
+        futures::future::try_join_all(loops).await?;
+        Ok(())
+    }
+
+    /// Writes the queued frames in the order determined by `scheduler`.
     async fn run_send_loop(
-        tcp_send: WriteHalf,
-        mut queue_recv: tokio::sync::mpsc::UnboundedReceiver<Frame>,
+        write: impl AsyncWrite + Unpin,
+        mut queue_recv: tokio::sync::mpsc::UnboundedReceiver<(MessageClass, Frame)>,
+        mut scheduler: send_limits::Scheduler,
+        clock: time::Clock,
         stats: Arc<connection::Stats>,
         buf_size_metric: Arc<metrics::IntGaugeGuard>,
     ) -> io::Result<()> {
         const WRITE_BUFFER_CAPACITY: usize = 8 * 1024;
-        let mut writer = tokio::io::BufWriter::with_capacity(WRITE_BUFFER_CAPACITY, tcp_send);
-        while let Some(Frame(mut msg)) = queue_recv.recv().await {
+        let mut writer = tokio::io::BufWriter::with_capacity(WRITE_BUFFER_CAPACITY, write);
+        loop {
             // Try writing a batch of messages and flush once at the end.
-            loop {
+            let retry_at = loop {
+                while let Ok((class, frame)) = queue_recv.try_recv() {
+                    scheduler.push(class, frame, clock.now());
+                }
+                let msg = match scheduler.pop(clock.now()) {
+                    send_limits::Pop::Frame(_, Frame(msg)) => msg,
+                    send_limits::Pop::Throttled(retry_at) => break Some(retry_at),
+                    send_limits::Pop::Empty => break None,
+                };
                 // TODO(gprusak): sending a too large message should probably be treated as a bug,
                 // since dropping messages may lead to hard-to-debug high-level issues.
                 if msg.len() > NETWORK_MESSAGE_MAX_SIZE_BYTES {
@@ -197,11 +447,7 @@ where
                 stats.messages_to_send.fetch_sub(1, Ordering::Release);
                 stats.bytes_to_send.fetch_sub(msg.len() as u64, Ordering::Release);
                 buf_size_metric.sub(msg.len() as i64);
-                msg = match queue_recv.try_recv() {
-                    Ok(Frame(it)) => it,
-                    Err(_) => break,
-                };
-            }
+            };
             // This is an unconditional flush, which means that even if new messages
             // will be added to the queue in the meantime, we will wait for the buffer
             // to be flushed before sending them. This is suboptimal in case messages are small
@@ -209,7 +455,19 @@ where
             // we would need to put writer.flush() and queue_recv.recv() into a tokio::select
             // and make sure that both are cancellation-safe.
             writer.flush().await?;
+            // Wait for a new message or, if some messages are over the byte rate limits,
+            // until they are within the limits.
+            let next = match retry_at {
+                None => queue_recv.recv().await,
+                Some(retry_at) => tokio::select! {
+                    next = queue_recv.recv() => next,
+                    () = clock.sleep_until(retry_at) => continue,
+                },
+            };
+            let Some((class, frame)) = next else {
+                return Ok(());
+            };
+            scheduler.push(class, frame, clock.now());
         }
-        Ok(())
     }
 }
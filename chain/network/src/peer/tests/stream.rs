@@ -1,11 +1,16 @@
 use crate::actix::ActixSystem;
 use crate::network_protocol::testonly as data;
 use crate::peer::stream;
+use crate::quic;
+use crate::rate_limits::send_limits;
 use crate::tcp;
 use crate::testonly::make_rng;
 use actix::Actor as _;
 use actix::ActorContext as _;
+use enum_map::Enum as _;
+use near_async::time;
 use rand::Rng as _;
+use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::mpsc;
 
@@ -20,12 +25,12 @@ impl actix::Actor for Actor {
 
 #[derive(actix::Message)]
 #[rtype("()")]
-struct SendFrame(stream::Frame);
+struct SendFrame(stream::MessageClass, stream::Frame);
 
 impl actix::Handler<SendFrame> for Actor {
     type Result = ();
-    fn handle(&mut self, SendFrame(frame): SendFrame, _ctx: &mut Self::Context) {
-        self.stream.send(frame);
+    fn handle(&mut self, SendFrame(class, frame): SendFrame, _ctx: &mut Self::Context) {
+        self.stream.send(class, frame);
     }
 }
 
@@ -49,13 +54,19 @@ struct Handler {
 }
 
 impl Actor {
-    async fn spawn(s: tcp::Stream) -> Handler {
+    async fn spawn(s: stream::Stream) -> Handler {
         let (queue_send, queue_recv) = mpsc::unbounded_channel();
         Handler {
             queue_recv,
             system: ActixSystem::spawn(|| {
                 Actor::create(|ctx| {
-                    let stream = stream::FramedStream::spawn(ctx, s, Arc::default());
+                    let clock = time::Clock::real();
+                    let send_limits = Arc::new(send_limits::SendLimits::new(
+                        send_limits::Config::standard_preset(),
+                        clock.now(),
+                    ));
+                    let stream =
+                        stream::FramedStream::spawn(ctx, &clock, s, Arc::default(), send_limits);
                     Self { stream, queue_send }
                 })
             })
@@ -68,8 +79,8 @@ impl Actor {
 async fn send_recv() {
     let mut rng = make_rng(98324532);
     let (s1, s2) = tcp::Stream::loopback(data::make_peer_id(&mut rng), tcp::Tier::T2).await;
-    let a1 = Actor::spawn(s1).await;
-    let mut a2 = Actor::spawn(s2).await;
+    let a1 = Actor::spawn(s1.into()).await;
+    let mut a2 = Actor::spawn(s2.into()).await;
 
     for _ in 0..5 {
         let n = rng.gen_range(1..10);
@@ -82,7 +93,11 @@ async fn send_recv() {
             })
             .collect();
         for msg in &msgs {
-            a1.system.addr.send(SendFrame(msg.clone())).await.unwrap();
+            a1.system
+                .addr
+                .send(SendFrame(stream::MessageClass::Control, msg.clone()))
+                .await
+                .unwrap();
         }
         for want in &msgs {
             let got = a2.queue_recv.recv().await.unwrap();
@@ -90,3 +105,47 @@ async fn send_recv() {
         }
     }
 }
+
+#[tokio::test]
+async fn send_recv_quic() {
+    let mut rng = make_rng(5637829);
+    let (s1, s2) = quic::Stream::loopback(data::make_peer_id(&mut rng)).await;
+    let a1 = Actor::spawn(s1.into()).await;
+    let mut a2 = Actor::spawn(s2.into()).await;
+
+    // Messages of the other classes are delivered only after the first Control message.
+    let first = stream::Frame(vec![stream::MessageClass::Control.into_usize() as u8]);
+    a1.system.addr.send(SendFrame(stream::MessageClass::Control, first.clone())).await.unwrap();
+    assert_eq!(a2.queue_recv.recv().await.unwrap(), first);
+
+    for _ in 0..5 {
+        let n = rng.gen_range(1..30);
+        // Every message starts with the index of its class.
+        let msgs: Vec<_> = (0..n)
+            .map(|_| {
+                let class = stream::MessageClass::from_usize(
+                    rng.gen_range(0..stream::MessageClass::LENGTH),
+                );
+                let size = rng.gen_range(1..10000);
+                let mut msg = vec![0; size];
+                rng.fill(&mut msg[..]);
+                msg[0] = class.into_usize() as u8;
+                (class, stream::Frame(msg))
+            })
+            .collect();
+        for (class, msg) in &msgs {
+            a1.system.addr.send(SendFrame(*class, msg.clone())).await.unwrap();
+        }
+        // The order is preserved only within a class.
+        let mut want = HashMap::<u8, Vec<stream::Frame>>::new();
+        for (_, msg) in &msgs {
+            want.entry(msg.0[0]).or_default().push(msg.clone());
+        }
+        let mut got = HashMap::<u8, Vec<stream::Frame>>::new();
+        for _ in 0..n {
+            let msg = a2.queue_recv.recv().await.unwrap();
+            got.entry(msg.0[0]).or_default().push(msg);
+        }
+        assert_eq!(got, want);
+    }
+}
@@ -17,7 +17,7 @@ use near_crypto::PublicKey;
 use near_o11y::WithSpanContextExt;
 use near_primitives::genesis::GenesisId;
 use near_primitives::network::PeerId;
-use near_primitives::types::ShardId;
+use near_primitives::types::{BlockHeight, ShardId};
 use std::collections::{HashMap, hash_map::Entry};
 use std::fmt;
 use std::future::Future;
@@ -54,6 +54,11 @@ impl tcp::Tier {
             | PeerMessage::BlockHeaders(..)
             | PeerMessage::BlockRequest(..)
             | PeerMessage::Block(..)
+            | PeerMessage::BlockRangeRequest(..)
+            | PeerMessage::BlockRangeResponse(..)
+            | PeerMessage::CompactBlock(..)
+            | PeerMessage::CompactBlockRequest(..)
+            | PeerMessage::CompactBlockResponse(..)
             | PeerMessage::Transaction(..)
             | PeerMessage::Disconnect(..)
             | PeerMessage::Challenge(..)
@@ -122,6 +127,13 @@ pub(crate) struct Stats {
     pub messages_to_send: AtomicU64,
     /// Number of bytes (sum of message sizes) in the buffer to send.
     pub bytes_to_send: AtomicU64,
+
+    /// Number of received messages delivered to the client, since the last
+    /// report to the PeerStore.
+    pub useful_messages: AtomicU64,
+    /// Number of received messages which were malformed, rate limited or
+    /// duplicated, since the last report to the PeerStore.
+    pub useless_messages: AtomicU64,
 }
 
 /// Contains information relevant to a connected peer.
@@ -141,6 +153,10 @@ pub(crate) struct Connection {
     pub tracked_shards: Vec<ShardId>,
     /// Denote if a node is running in archival mode or not.
     pub archival: bool,
+    /// Lowest height of the blocks served by the peer in response to BlockRangeRequest.
+    pub block_range_start: Option<BlockHeight>,
+    /// Whether the peer accepts blocks relayed as CompactBlock messages.
+    pub compact_block_relay: bool,
     pub last_block: ArcSwap<Option<BlockInfo>>,
 
     /// Who started connection. Inbound (other) or Outbound (us).
@@ -180,6 +196,7 @@ impl Connection {
             last_block: *self.last_block.load().as_ref(),
             tracked_shards: self.tracked_shards.clone(),
             archival: self.archival,
+            block_range_start: self.block_range_start,
         };
         FullPeerInfo { peer_info: self.peer_info.clone(), chain_info }
     }
@@ -552,4 +569,14 @@ impl Pool {
             peer.send_message(msg.clone());
         }
     }
+
+    /// Broadcast a block to all ready peers. The peers which accept compact blocks
+    /// get `compact_block` instead of `block`.
+    pub fn broadcast_block(&self, block: Arc<PeerMessage>, compact_block: Arc<PeerMessage>) {
+        metrics::BROADCAST_MESSAGES.with_label_values(&[block.msg_variant()]).inc();
+        for peer in self.load().ready.values() {
+            let msg = if peer.compact_block_relay { &compact_block } else { &block };
+            peer.send_message(msg.clone());
+        }
+    }
 }
@@ -17,6 +17,7 @@ use crate::peer_manager::connection;
 use crate::peer_manager::connection_store;
 use crate::peer_manager::peer_store;
 use crate::private_actix::RegisterPeerError;
+use crate::rate_limits::send_limits;
 #[cfg(feature = "distance_vector_routing")]
 use crate::routing::NetworkTopologyChange;
 use crate::routing::route_back_cache::RouteBackCache;
@@ -61,6 +62,10 @@ pub(crate) const LIMIT_PENDING_PEERS: usize = 60;
 /// production of 1 block should fit).
 const RECENT_ROUTED_MESSAGES_CACHE_SIZE: usize = 10000;
 
+/// Size of LRU cache of the QUIC ports advertised by the peers.
+/// It should be large enough to fit all the TIER1 peers.
+const QUIC_PORTS_CACHE_SIZE: usize = 10000;
+
 /// How long a peer has to be unreachable, until we prune it from the in-memory graph.
 const PRUNE_UNREACHABLE_PEERS_AFTER: time::Duration = time::Duration::hours(1);
 
@@ -132,6 +137,8 @@ pub(crate) struct NetworkState {
     pub snapshot_hosts: Arc<SnapshotHostsCache>,
     /// Connection store that provides read/write access to stored connections.
     pub connection_store: connection_store::ConnectionStore,
+    /// Store in which the peer reputations of `peer_store` are persisted.
+    reputation_store: Mutex<store::Store>,
     /// List of peers to which we should re-establish a connection
     pub pending_reconnect: Mutex<Vec<PeerInfo>>,
     /// A graph of the whole NEAR network.
@@ -153,6 +160,14 @@ pub(crate) struct NetworkState {
     /// TODO(gprusak): consider removing it altogether.
     pub tier1_route_back: Mutex<RouteBackCache>,
 
+    /// QUIC endpoint of the TIER1 connections, set by PeerManagerActor at startup
+    /// if `config.tier1.transport` is QUIC.
+    pub quic: std::sync::OnceLock<crate::quic::Endpoint>,
+    /// QUIC ports advertised by the peers in their handshakes, see `crate::quic`.
+    pub quic_ports: Mutex<lru::LruCache<PeerId, u16>>,
+    /// Node-wide state of the outbound messages scheduling.
+    pub send_limits: Arc<send_limits::SendLimits>,
+
     /// Shared counter across all PeerActors, which counts number of `RoutedMessageBody::ForwardTx`
     /// messages since last block.
     pub txns_since_last_block: AtomicUsize,
@@ -188,6 +203,7 @@ impl NetworkState {
         partial_witness_adapter: PartialWitnessSenderForNetwork,
         whitelist_nodes: Vec<WhitelistNode>,
     ) -> Self {
+        peer_store.load_reputations(store.get_peer_reputations());
         Self {
             runtime: Runtime::new(),
             graph: Arc::new(crate::routing::Graph::new(crate::routing::GraphConfig {
@@ -214,6 +230,7 @@ impl NetworkState {
             peer_store,
             snapshot_hosts: Arc::new(SnapshotHostsCache::new(config.snapshot_hosts.clone())),
             connection_store: connection_store::ConnectionStore::new(store.clone()).unwrap(),
+            reputation_store: Mutex::new(store.clone()),
             pending_reconnect: Mutex::new(Vec::<PeerInfo>::new()),
             accounts_data: Arc::new(AccountDataCache::new()),
             account_announcements: Arc::new(AnnounceAccountCache::new(store)),
@@ -222,6 +239,14 @@ impl NetworkState {
             recent_routed_messages: Mutex::new(lru::LruCache::new(
                 NonZeroUsize::new(RECENT_ROUTED_MESSAGES_CACHE_SIZE).unwrap(),
             )),
+            quic: std::sync::OnceLock::new(),
+            send_limits: Arc::new(send_limits::SendLimits::new(
+                config.sent_messages_limits.clone(),
+                clock.now(),
+            )),
+            quic_ports: Mutex::new(lru::LruCache::new(
+                NonZeroUsize::new(QUIC_PORTS_CACHE_SIZE).unwrap(),
+            )),
             txns_since_last_block: AtomicUsize::new(0),
             whitelist_nodes,
             add_edges_demux: demux::Demux::new(config.routing_table_update_rate_limit),
@@ -995,6 +1020,14 @@ impl NetworkState {
         self.connection_store.update(clock, &self.tier2.load());
     }
 
+    /// Persists the peer reputations, so that they survive a restart of the node.
+    pub fn save_peer_reputations(&self) {
+        let reputations = self.peer_store.reputations();
+        if let Err(err) = self.reputation_store.lock().set_peer_reputations(&reputations) {
+            tracing::error!(target: "network", ?err, "Failed to save the peer reputations");
+        }
+    }
+
     /// Clears pending_reconnect and returns the cleared values
     pub fn poll_pending_reconnect(&self) -> Vec<PeerInfo> {
         let mut pending_reconnect = self.pending_reconnect.lock();
@@ -38,6 +38,35 @@ impl super::NetworkState {
         Some(FrozenValidatorConfig { signer, proxies: &self.config.validator.proxies })
     }
 
+    /// Establishes an outbound TIER1 connection to the peer. Uses QUIC if it is enabled
+    /// and the peer has advertised its QUIC port, falling back to TCP otherwise.
+    async fn tier1_connect(
+        self: &Arc<Self>,
+        clock: &time::Clock,
+        peer_info: &PeerInfo,
+    ) -> anyhow::Result<actix::Addr<PeerActor>> {
+        let quic_port = self.quic_ports.lock().get(&peer_info.id).copied();
+        if let (Some(quic), Some(port)) = (self.quic.get(), quic_port) {
+            match quic.connect(peer_info, port).await {
+                Ok(stream) => {
+                    return PeerActor::spawn_and_handshake(
+                        clock.clone(),
+                        stream,
+                        None,
+                        self.clone(),
+                    )
+                    .await;
+                }
+                Err(err) => {
+                    tracing::debug!(target:"network", ?err, "QUIC connection to {} failed, falling back to TCP", peer_info.id);
+                }
+            }
+        }
+        let stream =
+            tcp::Stream::connect(peer_info, tcp::Tier::T1, &self.config.socket_options).await?;
+        PeerActor::spawn_and_handshake(clock.clone(), stream, None, self.clone()).await
+    }
+
     async fn tier1_connect_to_my_proxies(
         self: &Arc<Self>,
         clock: &time::Clock,
@@ -52,19 +81,16 @@ impl super::NetworkState {
                 continue;
             }
             handles.push(async move {
-                let res = async {
-                    let stream = tcp::Stream::connect(
+                let res = self
+                    .tier1_connect(
+                        clock,
                         &PeerInfo {
                             id: proxy.peer_id.clone(),
                             addr: Some(proxy.addr),
                             account_id: None,
                         },
-                        tcp::Tier::T1,
-                        &self.config.socket_options,
                     )
-                    .await?;
-                    anyhow::Ok(PeerActor::spawn_and_handshake(clock.clone(), stream, None, self.clone()).await?)
-                }.await;
+                    .await;
                 if let Err(err) = res {
                     tracing::warn!(target:"network", ?err, "failed to establish connection to TIER1 proxy {:?}",proxy);
                 }
@@ -331,18 +357,15 @@ impl super::NetworkState {
                 if let Some(proxy) = proxy {
                     let proxy = (*proxy).clone();
                     handles.push(async move {
-                        let stream = tcp::Stream::connect(
+                        self.tier1_connect(
+                            clock,
                             &PeerInfo {
                                 id: proxy.peer_id,
                                 addr: Some(proxy.addr),
                                 account_id: None,
                             },
-                            tcp::Tier::T1,
-                            &self.config.socket_options,
                         )
-                        .await?;
-                        PeerActor::spawn_and_handshake(clock.clone(), stream, None, self.clone())
-                            .await
+                        .await
                     });
                 }
             }
@@ -1,6 +1,9 @@
+use crate::admin::{AdminPeerError, AdminPeerRequest, AdminPeerResponse};
+use crate::blacklist;
 use crate::client::{ClientSenderForNetwork, SetNetworkInfo, StateRequestHeader, StateRequestPart};
 use crate::config;
 use crate::debug::{DebugStatus, GetDebugStatus};
+use crate::dns_seeds;
 use crate::network_protocol;
 use crate::network_protocol::SyncSnapshotHosts;
 use crate::network_protocol::{
@@ -11,6 +14,7 @@ use crate::peer::peer_actor::PeerActor;
 use crate::peer_manager::connection;
 use crate::peer_manager::network_state::{NetworkState, WhitelistNode};
 use crate::peer_manager::peer_store;
+use crate::quic;
 use crate::shards_manager::ShardsManagerRequestFromNetwork;
 use crate::state_witness::PartialWitnessSenderForNetwork;
 use crate::stats::metrics;
@@ -30,6 +34,7 @@ use near_async::messaging::{SendAsync, Sender};
 use near_async::time;
 use near_o11y::{WithSpanContext, handler_debug_span, handler_trace_span};
 use near_performance_metrics_macros::perf;
+use near_primitives::compact_block::CompactBlock;
 use near_primitives::genesis::GenesisId;
 use near_primitives::network::{AnnounceAccount, PeerId};
 use near_primitives::views::{
@@ -85,6 +90,8 @@ const PREFER_PREVIOUSLY_CONNECTED_PEER: f64 = 0.6;
 
 /// How often to update the connections in storage.
 pub(crate) const UPDATE_CONNECTION_STORE_INTERVAL: time::Duration = time::Duration::minutes(1);
+/// How often to persist the peer reputations.
+pub(crate) const SAVE_PEER_REPUTATIONS_INTERVAL: time::Duration = time::Duration::minutes(1);
 /// How often to poll the NetworkState for closed connections we'd like to re-establish.
 pub(crate) const POLL_CONNECTION_STORE_INTERVAL: time::Duration = time::Duration::minutes(1);
 
@@ -187,6 +194,46 @@ impl actix::Actor for PeerManagerActor {
             }
         }));
 
+        // Periodically persist the peer reputations.
+        let clock = self.clock.clone();
+        let state = self.state.clone();
+        ctx.spawn(wrap_future(async move {
+            let mut interval = time::Interval::new(clock.now(), SAVE_PEER_REPUTATIONS_INTERVAL);
+            loop {
+                interval.tick(&clock).await;
+                state.save_peer_reputations();
+            }
+        }));
+
+        // Periodically resolve the DNS seeds and replace the discovered boot nodes in the peer store.
+        if !self.state.config.dns_seeds.is_empty() {
+            match dns_seeds::SystemResolver::new() {
+                Ok(resolver) => {
+                    let clock = self.clock.clone();
+                    let state = self.state.clone();
+                    ctx.spawn(wrap_future(async move {
+                        let mut interval =
+                            time::Interval::new(clock.now(), state.config.dns_seeds_refresh_period);
+                        loop {
+                            interval.tick(&clock).await;
+                            let peers = dns_seeds::resolve(
+                                &resolver,
+                                &state.config.dns_seeds,
+                                &state.genesis_id.chain_id,
+                                clock.now_utc(),
+                            )
+                            .await;
+                            tracing::debug!(target: "network", peers = peers.len(), "Resolved DNS seeds");
+                            state.peer_store.set_dns_boot_nodes(&clock, peers.into_iter());
+                        }
+                    }));
+                }
+                Err(err) => {
+                    tracing::warn!(target: "network", ?err, "Failed to create DNS resolver, DNS seeds are disabled");
+                }
+            }
+        }
+
         // Periodically prints bandwidth stats for each peer.
         self.report_bandwidth_stats_trigger(ctx, REPORT_BANDWIDTH_STATS_TRIGGER_INTERVAL);
 
@@ -288,6 +335,44 @@ impl PeerManagerActor {
                             }
                         }
                     });
+                    // Start the QUIC endpoint on the UDP port of the same number, if enabled.
+                    if state.config.tier1.as_ref().is_some_and(|t| t.transport == tcp::Transport::Quic) {
+                        match quic::Endpoint::new(**server_addr) {
+                            Ok(endpoint) => {
+                                let _ = state.quic.set(endpoint);
+                            }
+                            Err(err) => {
+                                tracing::warn!(target: "network", ?err, "failed to start the QUIC endpoint, TIER1 will use TCP only");
+                            }
+                        }
+                    }
+                    if state.quic.get().is_some() {
+                        arbiter.spawn({
+                            let arbiter = arbiter.clone();
+                            let clock = clock.clone();
+                            let state = state.clone();
+                            async move {
+                                let endpoint = state.quic.get().unwrap();
+                                while let Some(conn) = endpoint.accept().await {
+                                    let clock = clock.clone();
+                                    let state = state.clone();
+                                    arbiter.spawn(async move {
+                                        match conn.await {
+                                            Ok(stream) => {
+                                                tracing::debug!(target: "network", from = ?stream.peer_addr, "got new QUIC connection");
+                                                if let Err(err) = PeerActor::spawn(clock, stream, None, state) {
+                                                    tracing::info!(target:"network", ?err, "PeerActor::spawn()");
+                                                }
+                                            }
+                                            Err(err) => {
+                                                tracing::debug!(target: "network", ?err, "failed to accept a QUIC connection");
+                                            }
+                                        }
+                                    });
+                                }
+                            }
+                        });
+                    }
                 }
                 if let Some(cfg) = state.config.tier1.clone() {
                     // Connect to TIER1 proxies and broadcast the list those connections periodically.
@@ -425,6 +510,10 @@ impl PeerManagerActor {
             .ready
             .values()
             .filter_map(|p| p.full_peer_info().into())
+            .map(|mut info: HighestHeightPeerInfo| {
+                info.reputation = self.state.peer_store.reputation_score(&info.peer_info.id);
+                info
+            })
             .collect();
 
         // This finds max height among peers, and returns one peer close to such height.
@@ -549,8 +638,14 @@ impl PeerManagerActor {
         }
 
         // Build valid candidate list to choose the peer to be removed. All peers outside the safe set.
-        let candidates = tier2.ready.values().filter(|p| !safe_set.contains(&p.peer_info.id));
-        if let Some(p) = candidates.choose(&mut rand::thread_rng()) {
+        let mut candidates: Vec<_> =
+            tier2.ready.values().filter(|p| !safe_set.contains(&p.peer_info.id)).collect();
+        // Remove the peer with the worst reputation, breaking ties at random.
+        candidates.shuffle(&mut rand::thread_rng());
+        if let Some(p) = candidates
+            .into_iter()
+            .min_by_key(|p| self.state.peer_store.reputation_score(&p.peer_info.id))
+        {
             tracing::debug!(target: "network", id = ?p.peer_info.id,
                 tier2_len = tier2.ready.len(),
                 ideal_connections_hi = self.state.config.ideal_connections_hi,
@@ -780,7 +875,19 @@ impl PeerManagerActor {
         metrics::REQUEST_COUNT_BY_TYPE_TOTAL.with_label_values(&[msg.as_ref()]).inc();
         match msg {
             NetworkRequests::Block { block } => {
-                self.state.tier2.broadcast_message(Arc::new(PeerMessage::Block(block)));
+                let compact_block = if self.state.config.compact_block_relay {
+                    CompactBlock::new(&block)
+                } else {
+                    None
+                };
+                let block = Arc::new(PeerMessage::Block(block));
+                match compact_block {
+                    Some(compact_block) => self
+                        .state
+                        .tier2
+                        .broadcast_block(block, Arc::new(PeerMessage::CompactBlock(compact_block))),
+                    None => self.state.tier2.broadcast_message(block),
+                }
                 NetworkResponses::NoResponse
             }
             NetworkRequests::OptimisticBlock { chunk_producers, optimistic_block } => {
@@ -811,6 +918,50 @@ impl PeerManagerActor {
                     NetworkResponses::RouteNotFound
                 }
             }
+            NetworkRequests::BlockRangeRequest { peer_id, request } => {
+                if self
+                    .state
+                    .tier2
+                    .send_message(peer_id, Arc::new(PeerMessage::BlockRangeRequest(request)))
+                {
+                    NetworkResponses::NoResponse
+                } else {
+                    NetworkResponses::RouteNotFound
+                }
+            }
+            NetworkRequests::BlockRangeResponse { peer_id, response } => {
+                if self
+                    .state
+                    .tier2
+                    .send_message(peer_id, Arc::new(PeerMessage::BlockRangeResponse(response)))
+                {
+                    NetworkResponses::NoResponse
+                } else {
+                    NetworkResponses::RouteNotFound
+                }
+            }
+            NetworkRequests::CompactBlockRequest { peer_id, request } => {
+                if self
+                    .state
+                    .tier2
+                    .send_message(peer_id, Arc::new(PeerMessage::CompactBlockRequest(request)))
+                {
+                    NetworkResponses::NoResponse
+                } else {
+                    NetworkResponses::RouteNotFound
+                }
+            }
+            NetworkRequests::CompactBlockResponse { peer_id, response } => {
+                if self
+                    .state
+                    .tier2
+                    .send_message(peer_id, Arc::new(PeerMessage::CompactBlockResponse(response)))
+                {
+                    NetworkResponses::NoResponse
+                } else {
+                    NetworkResponses::RouteNotFound
+                }
+            }
             NetworkRequests::BlockHeadersRequest { hashes, peer_id } => {
                 if self
                     .state
@@ -883,12 +1034,21 @@ impl PeerManagerActor {
                     return NetworkResponses::MyPublicAddrNotKnown;
                 };
 
+                // The part is requested again, so the host we have requested it from
+                // previously has failed to serve it.
+                if let Some(failed_host) =
+                    self.state.snapshot_hosts.take_pending_part_request(shard_id, part_id)
+                {
+                    self.state.peer_store.record_state_part(&failed_host, false);
+                }
+
                 // Select a peer which has advertised availability of the desired
                 // state snapshot.
                 let Some(peer_id) = self.state.snapshot_hosts.select_host_for_part(
                     &sync_prev_prev_hash,
                     shard_id,
                     part_id,
+                    |peer_id| self.state.peer_store.reputation_score(peer_id),
                 ) else {
                     tracing::debug!(target: "network", ?shard_id, ?sync_hash, ?part_id, "no snapshot hosts available");
                     return NetworkResponses::NoDestinationsAvailable;
@@ -1241,6 +1401,91 @@ impl PeerManagerActor {
             }
         }
     }
+
+    fn handle_admin_peer_request(
+        &mut self,
+        msg: AdminPeerRequest,
+        ctx: &mut actix::Context<Self>,
+    ) -> Result<AdminPeerResponse, AdminPeerError> {
+        let tier2 = self.state.tier2.load();
+        let connection = |peer_id: &PeerId| {
+            tier2
+                .ready
+                .get(peer_id)
+                .cloned()
+                .ok_or_else(|| AdminPeerError::NotConnected(peer_id.clone()))
+        };
+        match msg {
+            AdminPeerRequest::Connect(peer_info) => {
+                if peer_info.addr.is_none() {
+                    return Err(AdminPeerError::UnknownAddress(peer_info.id));
+                }
+                self.state.peer_store.add_direct_peer(&self.clock, peer_info.clone());
+                self.spawn_admin_connect(ctx, peer_info);
+            }
+            AdminPeerRequest::Disconnect(peer_id) => connection(&peer_id)?.stop(None),
+            AdminPeerRequest::Reconnect(peer_id) => {
+                let conn = connection(&peer_id)?;
+                if conn.peer_info.addr.is_none() {
+                    return Err(AdminPeerError::UnknownAddress(peer_id));
+                }
+                conn.stop(None);
+                // The first attempts may fail until the connection is removed from the pool.
+                self.spawn_admin_connect(ctx, conn.peer_info.clone());
+            }
+            AdminPeerRequest::Ban { peer_id, reason } => match tier2.ready.get(&peer_id) {
+                // The peer gets banned in the peer store once the connection is closed.
+                Some(conn) => conn.stop(Some(reason)),
+                None => self
+                    .state
+                    .peer_store
+                    .peer_ban(&self.clock, &peer_id, reason)
+                    .map_err(|err| AdminPeerError::PeerStore(err.to_string()))?,
+            },
+            AdminPeerRequest::Unban(peer_id) => self
+                .state
+                .peer_store
+                .peer_unban(&peer_id)
+                .map_err(|err| AdminPeerError::PeerStore(err.to_string()))?,
+            AdminPeerRequest::Remove(peer_id) => {
+                self.state
+                    .peer_store
+                    .remove_peer(&peer_id)
+                    .map_err(|err| AdminPeerError::PeerStore(err.to_string()))?;
+                if let Some(conn) = tier2.ready.get(&peer_id) {
+                    conn.stop(None);
+                }
+            }
+            AdminPeerRequest::BlacklistAdd { entry, reason } => {
+                self.state.peer_store.blacklist_add(entry);
+                let added: blacklist::Blacklist = [entry].into_iter().collect();
+                for conn in tier2.ready.values() {
+                    if conn.peer_info.addr.is_some_and(|addr| added.contains(addr)) {
+                        conn.stop(Some(reason));
+                    }
+                }
+            }
+            AdminPeerRequest::BlacklistRemove(entry) => {
+                if !self.state.peer_store.blacklist_remove(&entry) {
+                    return Err(AdminPeerError::NotBlacklisted(entry));
+                }
+            }
+            AdminPeerRequest::GetBlacklist => {
+                return Ok(AdminPeerResponse::Blacklist(self.state.peer_store.blacklist()));
+            }
+        }
+        Ok(AdminPeerResponse::Done)
+    }
+
+    fn spawn_admin_connect(&self, ctx: &mut actix::Context<Self>, peer_info: PeerInfo) {
+        ctx.spawn(wrap_future({
+            let state = self.state.clone();
+            let clock = self.clock.clone();
+            async move {
+                state.reconnect(clock, peer_info, MAX_RECONNECT_ATTEMPTS).await;
+            }
+        }));
+    }
 }
 
 impl actix::Handler<WithSpanContext<SetChainInfo>> for PeerManagerActor {
@@ -1384,6 +1629,15 @@ impl actix::Handler<WithSpanContext<Tier3Request>> for PeerManagerActor {
     }
 }
 
+impl actix::Handler<AdminPeerRequest> for PeerManagerActor {
+    type Result = Result<AdminPeerResponse, AdminPeerError>;
+    #[perf]
+    fn handle(&mut self, msg: AdminPeerRequest, ctx: &mut actix::Context<Self>) -> Self::Result {
+        tracing::info!(target: "network", ?msg, "Admin peer request");
+        self.handle_admin_peer_request(msg, ctx)
+    }
+}
+
 impl actix::Handler<GetDebugStatus> for PeerManagerActor {
     type Result = DebugStatus;
     #[perf]
@@ -1401,6 +1655,7 @@ impl actix::Handler<GetDebugStatus> for PeerManagerActor {
                         addr: format!("{:?}", known_peer_state.peer_info.addr),
                         first_seen: known_peer_state.first_seen.unix_timestamp(),
                         last_seen: known_peer_state.last_seen.unix_timestamp(),
+                        reputation: self.state.peer_store.reputation_score(peer_id),
                         last_attempt: known_peer_state.last_outbound_attempt.clone().map(
                             |(attempt_time, attempt_result)| {
                                 let foo = match attempt_result {
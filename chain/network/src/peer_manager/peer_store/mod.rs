@@ -5,7 +5,9 @@
 //!     - Select peers to which we may try to connect directly (see PeerStore::unconnected_peer).
 //!
 //! Contents of the PeerStore are not persisted to the database. Upon starting a node,
-//! the PeerStore is initialized from the boot nodes in its config.
+//! the PeerStore is initialized from the boot nodes in its config. The only exception
+//! are the peer reputations (see the `reputation` module), which are loaded from and
+//! periodically saved to the database by the PeerManagerActor.
 
 use crate::blacklist;
 use crate::network_protocol::PeerInfo;
@@ -17,17 +19,23 @@ use lru::LruCache;
 use near_async::time;
 use near_primitives::network::PeerId;
 use parking_lot::Mutex;
-use rand::seq::IteratorRandom;
+use rand::seq::{IteratorRandom, SliceRandom};
 use rand::thread_rng;
+use reputation::PeerReputation;
 use std::net::SocketAddr;
 use std::num::NonZeroUsize;
 use std::ops::Not;
 
+pub(crate) mod reputation;
 #[cfg(test)]
 mod testonly;
 #[cfg(test)]
 mod tests;
 
+/// Number of random candidates considered when picking a peer to connect to.
+/// The one with the best reputation among them is picked.
+const REPUTATION_SAMPLE_SIZE: usize = 8;
+
 /// Level of trust we have about a new (PeerId, Addr) pair.
 #[derive(Eq, PartialEq, Debug, Clone, Copy)]
 enum TrustLevel {
@@ -78,6 +86,8 @@ pub struct Config {
 struct Inner {
     config: Config,
     boot_nodes: HashSet<PeerId>,
+    // Boot nodes discovered from the DNS seeds, replaced on every refresh.
+    dns_boot_nodes: HashSet<PeerId>,
     // LruCache of the known peer states. Be sure to use peek/peek_mut to access information.
     // Using the get/put methods modifies the cache order.
     peer_states: LruCache<PeerId, KnownPeerState>,
@@ -85,9 +95,17 @@ struct Inner {
     // It can happens that some peers don't have known address, so
     // they will not be present in this list, otherwise they will be present.
     addr_peers: HashMap<SocketAddr, VerifiedPeer>,
+    // Reputations of the peers. They are kept separately from peer_states, so that
+    // a peer which has expired from peer_states doesn't get a clean slate when
+    // it is rediscovered.
+    reputations: LruCache<PeerId, PeerReputation>,
 }
 
 impl Inner {
+    fn is_boot_node(&self, peer_id: &PeerId) -> bool {
+        self.boot_nodes.contains(peer_id) || self.dns_boot_nodes.contains(peer_id)
+    }
+
     /// Adds a peer which proved to have secret key associated with the ID.
     ///
     /// The host have sent us a message signed with a secret key corresponding
@@ -182,6 +200,31 @@ impl Inner {
             .collect()
     }
 
+    /// Picks a random peer matching the filter, preferring the ones with a better reputation.
+    fn find_best_peer<F>(&self, filter: F) -> Option<PeerInfo>
+    where
+        F: FnMut(&&KnownPeerState) -> bool,
+    {
+        let mut candidates = (self.peer_states.iter().map(|(_, v)| v))
+            .filter(filter)
+            .choose_multiple(&mut thread_rng(), REPUTATION_SAMPLE_SIZE);
+        // `max_by_key` returns the last of the equally scored candidates,
+        // shuffle them so that the ties are broken at random.
+        candidates.shuffle(&mut thread_rng());
+        candidates
+            .into_iter()
+            .max_by_key(|kps| self.reputation_score(&kps.peer_info.id))
+            .map(|kps| kps.peer_info.clone())
+    }
+
+    fn reputation_score(&self, peer_id: &PeerId) -> u32 {
+        self.reputations.peek(peer_id).map_or(reputation::NEUTRAL_SCORE, |r| r.score())
+    }
+
+    fn reputation_mut(&mut self, peer_id: &PeerId) -> &mut PeerReputation {
+        self.reputations.get_or_insert_mut(peer_id.clone(), PeerReputation::default)
+    }
+
     /// Create new pair between peer_info.id and peer_addr removing
     /// old pairs if necessary.
     fn update_peer_info(
@@ -257,6 +300,14 @@ impl Inner {
         }
     }
 
+    /// Decays the reputation penalties of the peers which weren't banned recently.
+    fn decay_ban_penalties(&mut self, now: time::Utc) {
+        let ban_window = self.config.ban_window;
+        for (_, reputation) in &mut self.reputations {
+            reputation.decay_ban_penalty(now, ban_window);
+        }
+    }
+
     /// Update the 'last_seen' time for all the peers that we're currently connected to.
     fn update_last_seen(&mut self, now: time::Utc) {
         let mut connected_peer_ids = vec![];
@@ -277,10 +328,12 @@ impl Inner {
     /// * it unbans a peer if config.ban_window has passed
     /// * it updates KnownPeerStatus.last_seen of the connected peers
     /// * it removes peers which were not seen for config.peer_expiration_duration
+    /// * it decays the reputation penalties of the banned peers
     /// This function should be called periodically.
     pub fn update(&mut self, clock: &time::Clock) {
         let now = clock.now_utc();
         self.unban(now);
+        self.decay_ban_penalties(now);
         self.update_last_seen(now);
         self.remove_expired(now);
     }
@@ -337,8 +390,16 @@ impl PeerStore {
             }
         }
 
-        let inner =
-            Inner { config, boot_nodes, peer_states: peer_id_2_state, addr_peers: addr_2_peer };
+        let reputations =
+            LruCache::new(NonZeroUsize::new(config.peer_states_cache_size as usize).unwrap());
+        let inner = Inner {
+            config,
+            boot_nodes,
+            dns_boot_nodes: HashSet::new(),
+            peer_states: peer_id_2_state,
+            addr_peers: addr_2_peer,
+            reputations,
+        };
         Ok(PeerStore(Mutex::new(inner)))
     }
 
@@ -346,6 +407,20 @@ impl PeerStore {
         self.0.lock().config.blacklist.contains(*addr)
     }
 
+    /// Adds the entry to the blacklist. Returns false if it was already there.
+    pub fn blacklist_add(&self, entry: blacklist::Entry) -> bool {
+        self.0.lock().config.blacklist.insert(entry)
+    }
+
+    /// Removes the entry from the blacklist. Returns false if it wasn't there.
+    pub fn blacklist_remove(&self, entry: &blacklist::Entry) -> bool {
+        self.0.lock().config.blacklist.remove(entry)
+    }
+
+    pub fn blacklist(&self) -> Vec<blacklist::Entry> {
+        self.0.lock().config.blacklist.iter().copied().collect()
+    }
+
     pub fn len(&self) -> usize {
         self.0.lock().peer_states.len()
     }
@@ -419,8 +494,9 @@ impl PeerStore {
     ) -> anyhow::Result<()> {
         tracing::warn!(target: "network", "Banning peer {} for {:?}", peer_id, ban_reason);
         let mut inner = self.0.lock();
+        let now = clock.now_utc();
+        inner.reputation_mut(peer_id).record_ban(now, ban_reason);
         if let Some(peer_state) = inner.peer_states.get_mut(peer_id) {
-            let now = clock.now_utc();
             peer_state.last_seen = now;
             peer_state.status = KnownPeerStatus::Banned(ban_reason, now);
         } else {
@@ -429,8 +505,33 @@ impl PeerStore {
         Ok(())
     }
 
+    /// Lifts the ban of the peer before its `ban_window` has passed.
+    pub fn peer_unban(&self, peer_id: &PeerId) -> anyhow::Result<()> {
+        tracing::info!(target: "network", "Unbanning peer {}", peer_id);
+        let mut inner = self.0.lock();
+        if !inner.peer_states.peek(peer_id).is_some_and(|s| s.status.is_banned()) {
+            bail!("Peer {} is not banned", peer_id);
+        }
+        inner.peer_unban(peer_id)
+    }
+
+    /// Removes the peer from the store, so that it is not picked for outbound
+    /// connections until we learn about it again. Banned peers can't be removed,
+    /// as that would lift the ban.
+    pub fn remove_peer(&self, peer_id: &PeerId) -> anyhow::Result<()> {
+        tracing::info!(target: "network", "Removing peer {}", peer_id);
+        let mut inner = self.0.lock();
+        match inner.peer_states.peek(peer_id) {
+            None => bail!("Peer {} is missing in the peer store", peer_id),
+            Some(state) if state.status.is_banned() => bail!("Peer {} is banned", peer_id),
+            Some(_) => inner.delete_peers(std::slice::from_ref(peer_id)),
+        }
+        Ok(())
+    }
+
     /// Return unconnected or peers with unknown status that we can try to connect to.
-    /// Peers with unknown addresses are filtered out.
+    /// Peers with unknown addresses are filtered out. Among a random sample of the
+    /// candidates, the one with the best reputation is returned.
     pub fn unconnected_peer(
         &self,
         ignore_fn: impl Fn(&KnownPeerState) -> bool,
@@ -438,36 +539,26 @@ impl PeerStore {
     ) -> Option<PeerInfo> {
         let inner = self.0.lock();
         if prefer_previously_connected_peer {
-            let preferred_peer = inner.find_peers(
-                |p| {
-                    (p.status == KnownPeerStatus::NotConnected)
-                        && !ignore_fn(p)
-                        && p.peer_info.addr.is_some()
-                        // if we're connecting only to the boot nodes - filter out the nodes that are not boot nodes.
-                        && (!inner.config.connect_only_to_boot_nodes || inner.boot_nodes.contains(&p.peer_info.id))
-                },
-                1,
-            )
-            .get(0)
-            .cloned();
+            let preferred_peer = inner.find_best_peer(|p| {
+                (p.status == KnownPeerStatus::NotConnected)
+                    && !ignore_fn(p)
+                    && p.peer_info.addr.is_some()
+                    // if we're connecting only to the boot nodes - filter out the nodes that are not boot nodes.
+                    && (!inner.config.connect_only_to_boot_nodes || inner.is_boot_node(&p.peer_info.id))
+            });
             // If we found a preferred peer - return it.
             if preferred_peer.is_some() {
                 return preferred_peer;
             };
             // otherwise, pick a peer from the wider pool below.
         }
-        inner.find_peers(
-            |p| {
-                (p.status == KnownPeerStatus::NotConnected || p.status == KnownPeerStatus::Unknown)
-                    && !ignore_fn(p)
-                    && p.peer_info.addr.is_some()
-                    // If we're connecting only to the boot nodes - filter out the nodes that are not boot nodes.
-                    && (!inner.config.connect_only_to_boot_nodes || inner.boot_nodes.contains(&p.peer_info.id))
-            },
-            1,
-        )
-        .get(0)
-        .cloned()
+        inner.find_best_peer(|p| {
+            (p.status == KnownPeerStatus::NotConnected || p.status == KnownPeerStatus::Unknown)
+                && !ignore_fn(p)
+                && p.peer_info.addr.is_some()
+                // If we're connecting only to the boot nodes - filter out the nodes that are not boot nodes.
+                && (!inner.config.connect_only_to_boot_nodes || inner.is_boot_node(&p.peer_info.id))
+        })
     }
 
     /// Return healthy known peers up to given amount.
@@ -504,6 +595,40 @@ impl PeerStore {
         }
     }
 
+    /// Replaces the boot nodes discovered from the DNS seeds (see `crate::dns_seeds`).
+    ///
+    /// The peers are allowed by `connect_only_to_boot_nodes`, like the boot nodes from
+    /// the config. Their addresses haven't been verified, so they are added as indirect
+    /// peers. The peers which are no longer listed by the seeds stop being boot nodes,
+    /// and are forgotten unless we have ever connected to them.
+    pub fn set_dns_boot_nodes(&self, clock: &time::Clock, peers: impl Iterator<Item = PeerInfo>) {
+        let mut inner = self.0.lock();
+        let mut dns_boot_nodes = HashSet::new();
+        for peer_info in peers {
+            let Some(addr) = peer_info.addr else { continue };
+            if inner.config.blacklist.contains(addr) {
+                tracing::debug!(target: "network", ?peer_info, "Ignored blacklisted boot node");
+                continue;
+            }
+            dns_boot_nodes.insert(peer_info.id.clone());
+            inner.add_peer(clock, peer_info, TrustLevel::Indirect);
+        }
+        let removed: Vec<_> = inner
+            .dns_boot_nodes
+            .difference(&dns_boot_nodes)
+            .filter(|peer_id| {
+                !inner.boot_nodes.contains(peer_id)
+                    && inner
+                        .peer_states
+                        .peek(peer_id)
+                        .is_some_and(|state| state.status == KnownPeerStatus::Unknown)
+            })
+            .cloned()
+            .collect();
+        inner.delete_peers(&removed);
+        inner.dns_boot_nodes = dns_boot_nodes;
+    }
+
     /// Adds a peer we’ve connected to but haven’t verified ID yet.
     ///
     /// We've connected to the host (thus know that the address is correct) and
@@ -518,4 +643,42 @@ impl PeerStore {
     pub fn load(&self) -> HashMap<PeerId, KnownPeerState> {
         self.0.lock().peer_states.iter().map(|(k, v)| (k.clone(), v.clone())).collect()
     }
+
+    /// Returns the reputation score of the peer, see [`PeerReputation::score`].
+    pub fn reputation_score(&self, peer_id: &PeerId) -> u32 {
+        self.0.lock().reputation_score(peer_id)
+    }
+
+    /// Records the round trip time of a handshake with the peer.
+    pub fn record_latency(&self, peer_id: &PeerId, latency: time::Duration) {
+        self.0.lock().reputation_mut(peer_id).record_latency(latency)
+    }
+
+    /// Records the numbers of useful and useless messages received from the peer.
+    pub fn record_messages(&self, peer_id: &PeerId, useful: u64, useless: u64) {
+        if useful == 0 && useless == 0 {
+            return;
+        }
+        self.0.lock().reputation_mut(peer_id).record_messages(useful, useless)
+    }
+
+    /// Records whether the peer has served a state part requested from it.
+    pub fn record_state_part(&self, peer_id: &PeerId, served: bool) {
+        self.0.lock().reputation_mut(peer_id).record_state_part(served)
+    }
+
+    /// Returns the reputations of all the peers, to be persisted, starting with the least
+    /// recently used one.
+    pub fn reputations(&self) -> Vec<(PeerId, PeerReputation)> {
+        self.0.lock().reputations.iter().rev().map(|(k, v)| (k.clone(), v.clone())).collect()
+    }
+
+    /// Loads the reputations persisted by a previous run of the node. They are inserted in
+    /// the given order, so the last one becomes the most recently used.
+    pub fn load_reputations(&self, reputations: Vec<(PeerId, PeerReputation)>) {
+        let mut inner = self.0.lock();
+        for (peer_id, reputation) in reputations {
+            inner.reputations.put(peer_id, reputation);
+        }
+    }
 }
@@ -0,0 +1,146 @@
+//! Graded reputation of the peers, based on the observed quality of their service.
+//!
+//! Unlike bans, which make us ignore a misbehaving peer for a fixed `ban_window`,
+//! the reputation of a peer is accumulated over its whole lifetime and persisted
+//! across restarts (see `DBCol::PeerReputations`). It combines:
+//!     - the round trip time of the handshake with the peer,
+//!     - the ratio of useful messages received from the peer,
+//!     - the ratio of state parts that the peer has served to us,
+//!     - a penalty for each ban, weighted by the severity of the `ReasonForBan`.
+//!
+//! The resulting score is used to select the peers to connect to, the connections
+//! to drop, the hosts to request state parts from and the peers to sync headers from.
+
+use crate::types::ReasonForBan;
+use near_async::time;
+
+/// Maximal reputation score.
+pub(crate) const MAX_SCORE: u32 = 1000;
+/// Score of a peer that we know nothing about.
+pub(crate) const NEUTRAL_SCORE: u32 = MAX_SCORE / 2;
+
+/// Handshake round trip time at and below which the latency score is maximal.
+const TARGET_LATENCY: time::Duration = time::Duration::milliseconds(100);
+
+/// Once the number of the recorded messages exceeds this limit, the counters are halved,
+/// so that the ratio reflects the recent behavior of the peer.
+const MESSAGES_WINDOW: u64 = 10_000;
+/// Same as `MESSAGES_WINDOW`, but for the state part requests.
+const STATE_PARTS_WINDOW: u64 = 100;
+
+/// Weights of the latency, messages and state parts components of the score.
+const LATENCY_WEIGHT: u32 = 1;
+const MESSAGES_WEIGHT: u32 = 2;
+const STATE_PARTS_WEIGHT: u32 = 2;
+
+/// Penalty subtracted from the score of a peer banned for the given reason.
+pub(crate) fn ban_severity(reason: ReasonForBan) -> u32 {
+    match reason {
+        ReasonForBan::None => 0,
+        // The peer is slow or lagging behind rather than malicious.
+        ReasonForBan::ProvidedNotEnoughHeaders => 200,
+        ReasonForBan::BadHandshake
+        | ReasonForBan::Abusive
+        | ReasonForBan::InvalidPeerId
+        | ReasonForBan::InvalidDistanceVector => 400,
+        // The peer has sent us provably invalid data.
+        ReasonForBan::BadBlock
+        | ReasonForBan::BadBlockHeader
+        | ReasonForBan::HeightFraud
+        | ReasonForBan::BadBlockApproval
+        | ReasonForBan::InvalidSignature
+        | ReasonForBan::InvalidHash
+        | ReasonForBan::InvalidEdge
+        | ReasonForBan::BadChunkStateWitness => 600,
+        ReasonForBan::Blacklisted => MAX_SCORE,
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct PeerReputation {
+    /// Moving average of the handshake round trip time, if it has been measured.
+    pub latency: Option<time::Duration>,
+    /// Number of received messages which were delivered to the client.
+    pub useful_messages: u64,
+    /// Number of received messages which were malformed, rate limited or duplicated.
+    pub useless_messages: u64,
+    /// Number of state parts requested from the peer which it has served.
+    pub state_parts_served: u64,
+    /// Number of state parts requested from the peer which we had to request elsewhere.
+    pub state_parts_failed: u64,
+    /// Penalty accumulated from the bans. It is halved for every `ban_window`
+    /// which passes without a ban.
+    pub ban_penalty: u32,
+    /// Time of the last change of `ban_penalty`.
+    pub ban_penalty_updated: Option<time::Utc>,
+}
+
+impl PeerReputation {
+    pub fn record_latency(&mut self, latency: time::Duration) {
+        self.latency = Some(match self.latency {
+            Some(avg) => avg * 7 / 8 + latency / 8,
+            None => latency,
+        });
+    }
+
+    pub fn record_messages(&mut self, useful: u64, useless: u64) {
+        self.useful_messages = self.useful_messages.saturating_add(useful);
+        self.useless_messages = self.useless_messages.saturating_add(useless);
+        while self.useful_messages + self.useless_messages > MESSAGES_WINDOW {
+            self.useful_messages /= 2;
+            self.useless_messages /= 2;
+        }
+    }
+
+    pub fn record_state_part(&mut self, served: bool) {
+        if served {
+            self.state_parts_served += 1;
+        } else {
+            self.state_parts_failed += 1;
+        }
+        if self.state_parts_served + self.state_parts_failed > STATE_PARTS_WINDOW {
+            self.state_parts_served /= 2;
+            self.state_parts_failed /= 2;
+        }
+    }
+
+    pub fn record_ban(&mut self, now: time::Utc, reason: ReasonForBan) {
+        self.ban_penalty = self.ban_penalty.saturating_add(ban_severity(reason)).min(MAX_SCORE);
+        self.ban_penalty_updated = Some(now);
+    }
+
+    /// Halves the ban penalty for every `ban_window` passed since its last change.
+    pub fn decay_ban_penalty(&mut self, now: time::Utc, ban_window: time::Duration) {
+        let Some(mut updated) = self.ban_penalty_updated else {
+            return;
+        };
+        while self.ban_penalty > 0 && updated + ban_window <= now {
+            self.ban_penalty /= 2;
+            updated += ban_window;
+        }
+        self.ban_penalty_updated = if self.ban_penalty > 0 { Some(updated) } else { None };
+    }
+
+    /// Score of the peer in range `[0, MAX_SCORE]`, higher is better.
+    pub fn score(&self) -> u32 {
+        let weighted = LATENCY_WEIGHT * self.latency_score()
+            + MESSAGES_WEIGHT * ratio_score(self.useful_messages, self.useless_messages)
+            + STATE_PARTS_WEIGHT * ratio_score(self.state_parts_served, self.state_parts_failed);
+        let score = weighted / (LATENCY_WEIGHT + MESSAGES_WEIGHT + STATE_PARTS_WEIGHT);
+        score.saturating_sub(self.ban_penalty)
+    }
+
+    fn latency_score(&self) -> u32 {
+        match self.latency {
+            None => NEUTRAL_SCORE,
+            Some(latency) if latency <= TARGET_LATENCY => MAX_SCORE,
+            Some(latency) => (MAX_SCORE as f64 * (TARGET_LATENCY / latency)) as u32,
+        }
+    }
+}
+
+/// Laplace smoothed ratio of the good outcomes, so that a peer with no history
+/// gets the neutral score and a few observations don't move it to the extremes.
+fn ratio_score(good: u64, bad: u64) -> u32 {
+    ((good + 1) * MAX_SCORE as u64 / (good + bad + 2)) as u32
+}
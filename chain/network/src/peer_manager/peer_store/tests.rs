@@ -192,6 +192,55 @@ fn test_unconnected_peer_only_boot_nodes() {
     }
 }
 
+#[test]
+fn test_set_dns_boot_nodes() {
+    let clock = time::FakeClock::default();
+    let peer_info_a = gen_peer_info(0);
+    let peer_info_b = gen_peer_info(1);
+    let peer_info_c = gen_peer_info(2);
+    let peer_info_blacklisted = gen_peer_info(3);
+    let blacklist = [blacklist::Entry::from_addr(peer_info_blacklisted.addr.unwrap())];
+
+    let peer_store = PeerStore::new(
+        &clock.clock(),
+        make_config(&[peer_info_a.clone()], blacklist.into_iter().collect(), true),
+    )
+    .unwrap();
+    peer_store.peer_connected(&clock.clock(), &peer_info_a);
+    assert_eq!(peer_store.unconnected_peer(|_| false, false), None);
+
+    // Discovered boot nodes are connected to even with connect_only_to_boot_nodes.
+    peer_store.set_dns_boot_nodes(
+        &clock.clock(),
+        [peer_info_b.clone(), peer_info_blacklisted.clone()].into_iter(),
+    );
+    assert!(check_exist(
+        &peer_store,
+        &peer_info_b.id,
+        Some((peer_info_b.addr.unwrap(), TrustLevel::Indirect))
+    ));
+    assert!(!peer_store.load().contains_key(&peer_info_blacklisted.id));
+    assert_eq!(peer_store.unconnected_peer(|_| false, false), Some(peer_info_b.clone()));
+
+    // The peers which are no longer listed are forgotten.
+    peer_store.set_dns_boot_nodes(&clock.clock(), [peer_info_c.clone()].into_iter());
+    assert!(!peer_store.load().contains_key(&peer_info_b.id));
+    assert_eq!(peer_store.unconnected_peer(|_| false, false), Some(peer_info_c.clone()));
+
+    // Unless we have connected to them, but then they are no longer boot nodes.
+    peer_store.peer_connected(&clock.clock(), &peer_info_c);
+    peer_store.peer_disconnected(&clock.clock(), &peer_info_c.id).unwrap();
+    peer_store.set_dns_boot_nodes(&clock.clock(), std::iter::empty());
+    assert!(peer_store.load().contains_key(&peer_info_c.id));
+    assert_eq!(peer_store.unconnected_peer(|_| false, false), None);
+
+    // The boot nodes from the config stay boot nodes.
+    peer_store.peer_disconnected(&clock.clock(), &peer_info_a.id).unwrap();
+    peer_store.set_dns_boot_nodes(&clock.clock(), [peer_info_a.clone()].into_iter());
+    peer_store.set_dns_boot_nodes(&clock.clock(), std::iter::empty());
+    assert_eq!(peer_store.unconnected_peer(|_| false, false), Some(peer_info_a));
+}
+
 fn check_exist(
     peer_store: &PeerStore,
     peer_id: &PeerId,
@@ -482,3 +531,128 @@ fn test_lru_ignore_duplicate_peers() {
     peer_store.add_indirect_peers(&clock.clock(), peer_infos[10..].iter().cloned());
     assert_peers_in_cache(&peer_store, &peer_ids[5..], &peer_addresses[5..]);
 }
+
+#[test]
+fn test_unconnected_peer_prefers_reputation() {
+    let clock = time::FakeClock::default();
+    let peer_info_good = gen_peer_info(0);
+    let peer_info_bad = gen_peer_info(1);
+    let boot_nodes = vec![peer_info_good.clone(), peer_info_bad.clone()];
+
+    let peer_store =
+        PeerStore::new(&clock.clock(), make_config(&boot_nodes, Blacklist::default(), false))
+            .unwrap();
+    peer_store.record_messages(&peer_info_good.id, 1000, 0);
+    peer_store.record_messages(&peer_info_bad.id, 0, 1000);
+    assert!(
+        peer_store.reputation_score(&peer_info_good.id)
+            > peer_store.reputation_score(&peer_info_bad.id)
+    );
+
+    for _ in 0..10 {
+        assert_eq!(peer_store.unconnected_peer(|_| false, false), Some(peer_info_good.clone()));
+    }
+}
+
+#[test]
+fn test_load_reputations_keeps_lru_order() {
+    let clock = time::FakeClock::default();
+    let peer_infos: Vec<_> = (0..3).map(gen_peer_info).collect();
+    let peer_store =
+        PeerStore::new(&clock.clock(), make_config(&[], Blacklist::default(), false)).unwrap();
+    for (i, peer_info) in peer_infos.iter().enumerate() {
+        peer_store.record_messages(&peer_info.id, i as u64 + 1, 0);
+    }
+    let reputations = peer_store.reputations();
+    let peer_ids: Vec<_> = reputations.iter().map(|(peer_id, _)| peer_id.clone()).collect();
+    assert_eq!(peer_ids, peer_infos.iter().map(|p| p.id.clone()).collect::<Vec<_>>());
+
+    let restarted =
+        PeerStore::new(&clock.clock(), make_config(&[], Blacklist::default(), false)).unwrap();
+    restarted.load_reputations(reputations.clone());
+    assert_eq!(restarted.reputations(), reputations);
+}
+
+#[test]
+fn test_ban_penalty_decays() {
+    let clock = time::FakeClock::default();
+    let peer_info = gen_peer_info(0);
+    let config = make_config(&[peer_info.clone()], Blacklist::default(), false);
+    let ban_window = config.ban_window;
+
+    let peer_store = PeerStore::new(&clock.clock(), config).unwrap();
+    let initial_score = peer_store.reputation_score(&peer_info.id);
+    peer_store.peer_ban(&clock.clock(), &peer_info.id, ReasonForBan::Abusive).unwrap();
+    let banned_score = peer_store.reputation_score(&peer_info.id);
+    assert!(banned_score < initial_score);
+
+    // The penalty is halved for every ban window, and outlives the ban itself.
+    clock.advance(ban_window);
+    peer_store.update(&clock.clock());
+    assert!(!peer_store.is_banned(&peer_info.id));
+    let decayed_score = peer_store.reputation_score(&peer_info.id);
+    assert!(banned_score < decayed_score && decayed_score < initial_score);
+
+    clock.advance(ban_window * 10);
+    peer_store.update(&clock.clock());
+    assert_eq!(peer_store.reputation_score(&peer_info.id), initial_score);
+}
+
+#[test]
+fn test_peer_unban() {
+    let clock = time::FakeClock::default();
+    let peer_info = gen_peer_info(0);
+
+    let peer_store = PeerStore::new(
+        &clock.clock(),
+        make_config(&[peer_info.clone()], Blacklist::default(), false),
+    )
+    .unwrap();
+    assert!(peer_store.peer_unban(&peer_info.id).is_err());
+    peer_store.peer_ban(&clock.clock(), &peer_info.id, ReasonForBan::Abusive).unwrap();
+    assert!(peer_store.is_banned(&peer_info.id));
+    peer_store.peer_unban(&peer_info.id).unwrap();
+    assert!(!peer_store.is_banned(&peer_info.id));
+}
+
+#[test]
+fn test_remove_peer() {
+    let clock = time::FakeClock::default();
+    let peer_info = gen_peer_info(0);
+    let banned_peer_info = gen_peer_info(1);
+
+    let peer_store = PeerStore::new(
+        &clock.clock(),
+        make_config(&[peer_info.clone(), banned_peer_info.clone()], Blacklist::default(), false),
+    )
+    .unwrap();
+    peer_store.peer_ban(&clock.clock(), &banned_peer_info.id, ReasonForBan::Abusive).unwrap();
+    assert!(peer_store.remove_peer(&banned_peer_info.id).is_err());
+    assert!(peer_store.is_banned(&banned_peer_info.id));
+
+    peer_store.remove_peer(&peer_info.id).unwrap();
+    assert!(peer_store.get_peer_state(&peer_info.id).is_none());
+    assert_eq!(peer_store.unconnected_peer(|_| false, false), None);
+    assert!(peer_store.remove_peer(&peer_info.id).is_err());
+}
+
+#[test]
+fn test_edit_blacklist() {
+    let clock = time::FakeClock::default();
+    let peer_info = gen_peer_info(0);
+    let addr = peer_info.addr.unwrap();
+
+    let peer_store = PeerStore::new(
+        &clock.clock(),
+        make_config(&[peer_info.clone()], Blacklist::default(), false),
+    )
+    .unwrap();
+    let entry = blacklist::Entry::from_ip(addr.ip());
+    assert!(!peer_store.is_blacklisted(&addr));
+    assert!(peer_store.blacklist_add(entry));
+    assert!(peer_store.is_blacklisted(&addr));
+    assert_eq!(peer_store.blacklist(), vec![entry]);
+    assert!(peer_store.blacklist_remove(&entry));
+    assert!(!peer_store.is_blacklisted(&addr));
+    assert!(!peer_store.blacklist_remove(&entry));
+}
@@ -184,9 +184,8 @@ impl ActorHandler {
     pub async fn send_outbound_connect(&self, peer_info: &PeerInfo, tier: tcp::Tier) {
         let addr = self.actix.addr.clone();
         let peer_info = peer_info.clone();
-        let stream = tcp::Stream::connect(&peer_info, tier, &config::SocketOptions::default())
-            .await
-            .unwrap();
+        let stream =
+            tcp::Stream::connect(&peer_info, tier, &self.cfg.socket_options).await.unwrap();
         addr.do_send(PeerManagerMessageRequest::OutboundTcpConnect(stream).with_span_context());
     }
 
@@ -198,10 +197,9 @@ impl ActorHandler {
         let addr = self.actix.addr.clone();
         let events = self.events.clone();
         let peer_info = peer_info.clone();
+        let socket_options = self.cfg.socket_options.clone();
         async move {
-            let stream = tcp::Stream::connect(&peer_info, tier, &config::SocketOptions::default())
-                .await
-                .unwrap();
+            let stream = tcp::Stream::connect(&peer_info, tier, &socket_options).await.unwrap();
             let mut events = events.from_now();
             let stream_id = stream.id();
             addr.do_send(PeerManagerMessageRequest::OutboundTcpConnect(stream).with_span_context());
@@ -99,6 +99,9 @@ async fn loop_connection() {
             sender_peer_id: pm.cfg.node_id(),
             target_peer_id: pm.cfg.node_id(),
             sender_listen_port: Some(port),
+            sender_quic_port: None,
+            sender_block_range_start: None,
+            sender_compact_block_relay: false,
             sender_chain_info: chain.get_peer_chain_info(),
             partial_edge_info: PartialEdgeInfo::new(
                 &pm.cfg.node_id(),
@@ -161,6 +164,9 @@ async fn owned_account_mismatch() {
             sender_peer_id: cfg.node_id(),
             target_peer_id: pm.cfg.node_id(),
             sender_listen_port: Some(port),
+            sender_quic_port: None,
+            sender_block_range_start: None,
+            sender_compact_block_relay: false,
             sender_chain_info: chain.get_peer_chain_info(),
             partial_edge_info: PartialEdgeInfo::new(
                 &cfg.node_id(),
@@ -289,6 +295,9 @@ async fn invalid_edge() {
                 sender_peer_id: cfg.node_id(),
                 target_peer_id: pm.cfg.node_id(),
                 sender_listen_port: Some(port),
+                sender_quic_port: None,
+                sender_block_range_start: None,
+                sender_compact_block_relay: false,
                 sender_chain_info: chain.get_peer_chain_info(),
                 partial_edge_info: edge.clone(),
                 owned_account: Some(
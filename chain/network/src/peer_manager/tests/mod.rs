@@ -3,6 +3,7 @@ mod connection_pool;
 mod fuzzers;
 mod nonce;
 mod routing;
+mod sim;
 mod snapshot_hosts;
 mod tier1;
 mod tier2;
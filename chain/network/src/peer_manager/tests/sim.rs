@@ -0,0 +1,150 @@
+use super::routing::{wait_for_ping, wait_for_pong};
+use super::tier1::{establish_connections, send_and_recv_tier1_message};
+use crate::config::{NetworkConfig, ValidatorProxies};
+use crate::network_protocol::testonly as data;
+use crate::network_protocol::{PeerAddr, Ping, Pong};
+use crate::peer_manager::testonly::{make_chain_info, start as start_pm};
+use crate::tcp;
+use crate::testonly::sim;
+use crate::testonly::{Rng, abort_on_panic, make_rng};
+use near_async::time;
+use near_store::db::TestDB;
+use rand::Rng as _;
+use std::sync::Arc;
+
+fn make_config(
+    network: &Arc<sim::Network>,
+    chain: &data::Chain,
+    rng: &mut Rng,
+    name: &str,
+) -> NetworkConfig {
+    let mut cfg = chain.make_config(rng);
+    network.configure(name, &mut cfg);
+    cfg
+}
+
+// test that a routed message sent across a network partition is delivered once it heals
+#[tokio::test]
+async fn ping_across_healing_partition() {
+    abort_on_panic();
+    let mut rng = make_rng(921853233);
+    let rng = &mut rng;
+    let mut clock = time::FakeClock::default();
+    let chain = Arc::new(data::Chain::make(&mut clock, rng, 10));
+
+    let start = clock.now();
+    let topology = r#"{
+        "nodes": ["a", "b", "c"],
+        "default_link": {"latency_ms": 50, "bandwidth_bytes_per_sec": 1000000},
+        "links": [{"nodes": ["b", "c"], "latency_ms": 150, "loss": 0.05}],
+        "partitions": [{"start_ms": 10000, "end_ms": 20000, "groups": [["a"], ["b", "c"]]}]
+    }"#
+    .parse()
+    .unwrap();
+    let network = sim::Network::new(&clock, topology, rng.r#gen());
+
+    tracing::info!(target:"test", "start three nodes");
+    let cfg_a = make_config(&network, &chain, rng, "a");
+    let cfg_b = make_config(&network, &chain, rng, "b");
+    let cfg_c = make_config(&network, &chain, rng, "c");
+    let pm_a = start_pm(clock.clock(), TestDB::new(), cfg_a, chain.clone()).await;
+    let pm_b = start_pm(clock.clock(), TestDB::new(), cfg_b, chain.clone()).await;
+    let pm_c = start_pm(clock.clock(), TestDB::new(), cfg_c, chain.clone()).await;
+
+    let id_a = pm_a.cfg.node_id();
+    let id_b = pm_b.cfg.node_id();
+    let id_c = pm_c.cfg.node_id();
+
+    tracing::info!(target:"test", "connect nodes in a line");
+    network
+        .run(async {
+            pm_a.connect_to(&pm_b.peer_info(), tcp::Tier::T2).await;
+            pm_b.connect_to(&pm_c.peer_info(), tcp::Tier::T2).await;
+            pm_a.wait_for_routing_table(&[
+                (id_b.clone(), vec![id_b.clone()]),
+                (id_c.clone(), vec![id_b.clone()]),
+            ])
+            .await;
+            pm_c.wait_for_routing_table(&[
+                (id_a.clone(), vec![id_b.clone()]),
+                (id_b.clone(), vec![id_b.clone()]),
+            ])
+            .await;
+        })
+        .await;
+
+    tracing::info!(target:"test", "send ping from {id_a} to {id_c} during the partition");
+    clock.advance_until(start + time::Duration::seconds(15));
+    let mut ev_a = pm_a.events.from_now();
+    let mut ev_c = pm_c.events.from_now();
+    pm_a.send_ping(&clock.clock(), 0, id_c.clone()).await;
+
+    tracing::info!(target:"test", "await ping at {id_c} after the partition heals");
+    network.run(wait_for_ping(&mut ev_c, Ping { nonce: 0, source: id_a.clone() })).await;
+    assert!(clock.now() >= start + time::Duration::seconds(20));
+    network.run(wait_for_pong(&mut ev_a, Pong { nonce: 0, source: id_c.clone() })).await;
+}
+
+// test that TIER1 messages between validators are delivered over their proxies,
+// which are reachable only over slow and lossy links
+#[tokio::test]
+async fn tier1_message_over_lossy_proxies() {
+    abort_on_panic();
+    let mut rng = make_rng(921853233);
+    let rng = &mut rng;
+    let mut clock = time::FakeClock::default();
+    let chain = Arc::new(data::Chain::make(&mut clock, rng, 10));
+
+    let topology = r#"{
+        "nodes": ["v0", "v1", "p0", "p1", "hub"],
+        "default_link": {"latency_ms": 50, "bandwidth_bytes_per_sec": 1000000},
+        "links": [
+            {"nodes": ["v0", "p1"], "latency_ms": 200, "loss": 0.1},
+            {"nodes": ["v1", "p0"], "latency_ms": 200, "loss": 0.1}
+        ]
+    }"#
+    .parse()
+    .unwrap();
+    let network = sim::Network::new(&clock, topology, rng.r#gen());
+
+    tracing::info!(target:"test", "start validators v0 and v1 with proxies p0 and p1");
+    let cfg_p0 = make_config(&network, &chain, rng, "p0");
+    let cfg_p1 = make_config(&network, &chain, rng, "p1");
+    let mut cfg_v0 = make_config(&network, &chain, rng, "v0");
+    let mut cfg_v1 = make_config(&network, &chain, rng, "v1");
+    let cfg_hub = make_config(&network, &chain, rng, "hub");
+    for (cfg, proxy) in [(&mut cfg_v0, &cfg_p0), (&mut cfg_v1, &cfg_p1)] {
+        cfg.validator.proxies = ValidatorProxies::Static(vec![PeerAddr {
+            peer_id: proxy.node_id(),
+            addr: **proxy.node_addr.as_ref().unwrap(),
+        }]);
+    }
+    let p0 = start_pm(clock.clock(), TestDB::new(), cfg_p0, chain.clone()).await;
+    let p1 = start_pm(clock.clock(), TestDB::new(), cfg_p1, chain.clone()).await;
+    let v0 = start_pm(clock.clock(), TestDB::new(), cfg_v0, chain.clone()).await;
+    let v1 = start_pm(clock.clock(), TestDB::new(), cfg_v1, chain.clone()).await;
+    let hub = start_pm(clock.clock(), TestDB::new(), cfg_hub, chain.clone()).await;
+    let all = [&v0, &v1, &p0, &p1, &hub];
+
+    tracing::info!(target:"test", "connect all nodes to the hub and establish TIER1 connections");
+    let chain_info = make_chain_info(&chain, &[&v0.cfg, &v1.cfg]);
+    network
+        .run(async {
+            for pm in &all[..4] {
+                pm.connect_to(&hub.peer_info(), tcp::Tier::T2).await;
+            }
+            for pm in &all {
+                pm.set_chain_info(chain_info.clone()).await;
+            }
+            establish_connections(&clock.clock(), &all).await;
+        })
+        .await;
+
+    tracing::info!(target:"test", "send TIER1 messages between the validators");
+    network
+        .run(async {
+            send_and_recv_tier1_message(rng, &clock.clock(), &v0, &v1, tcp::Tier::T1).await;
+            send_and_recv_tier1_message(rng, &clock.clock(), &v1, &v0, tcp::Tier::T1).await;
+        })
+        .await;
+}
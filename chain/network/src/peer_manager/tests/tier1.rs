@@ -29,7 +29,10 @@ fn make_block_approval(rng: &mut Rng, signer: &ValidatorSigner) -> Approval {
     }
 }
 
-async fn establish_connections(clock: &time::Clock, pms: &[&peer_manager::testonly::ActorHandler]) {
+pub async fn establish_connections(
+    clock: &time::Clock,
+    pms: &[&peer_manager::testonly::ActorHandler],
+) {
     // Make TIER1 validators connect to proxies.
     let mut data = HashSet::new();
     for pm in pms {
@@ -68,7 +71,7 @@ async fn send_tier1_message(
 
 // Sends a routed TIER1 message from `from` to `to`, then waits until `to` receives it.
 // `recv_tier` specifies over which network the message is expected to be actually delivered.
-async fn send_and_recv_tier1_message(
+pub async fn send_and_recv_tier1_message(
     rng: &mut Rng,
     clock: &time::Clock,
     from: &peer_manager::testonly::ActorHandler,
@@ -1,4 +1,5 @@
 use crate::broadcast;
+use crate::client::ClientSenderForNetworkInput;
 use crate::network_protocol::testonly as data;
 use crate::peer_manager::connection_store::STORED_CONNECTIONS_MIN_DURATION;
 use crate::peer_manager::network_state::RECONNECT_ATTEMPT_INTERVAL;
@@ -10,8 +11,11 @@ use crate::peer_manager::testonly::start as start_pm;
 use crate::tcp;
 use crate::testonly::AsSet;
 use crate::testonly::make_rng;
+use crate::types::{NetworkRequests, PeerManagerMessageRequest};
 use near_async::time;
+use near_o11y::WithSpanContextExt;
 use near_o11y::testonly::init_test_logger;
+use near_primitives::compact_block::CompactBlock;
 use near_primitives::network::PeerId;
 use near_store::db::TestDB;
 use std::sync::Arc;
@@ -295,3 +299,61 @@ async fn test_reconnect_after_restart_outbound_side_multi() {
     pm0.wait_for_direct_connection(id3.clone()).await;
     pm0.wait_for_direct_connection(id4.clone()).await;
 }
+
+#[tokio::test]
+async fn test_compact_block_relay() {
+    init_test_logger();
+    let mut rng = make_rng(921853233);
+    let rng = &mut rng;
+    let mut clock = time::FakeClock::default();
+    let chain = Arc::new(data::Chain::make(&mut clock, rng, 10));
+
+    let mut cfgs: Vec<_> = (0..3).map(|_| chain.make_config(rng)).collect();
+    cfgs[0].compact_block_relay = true;
+    cfgs[1].compact_block_relay = true;
+    let mut pms = vec![];
+    for cfg in cfgs {
+        pms.push(start_pm(clock.clock(), TestDB::new(), cfg, chain.clone()).await);
+    }
+    let (pm0, pm1, pm2) = (&pms[0], &pms[1], &pms[2]);
+
+    tracing::info!(target:"test", "connect pm0 to pm1 and pm2");
+    pm0.connect_to(&pm1.peer_info(), tcp::Tier::T2).await;
+    pm0.connect_to(&pm2.peer_info(), tcp::Tier::T2).await;
+
+    tracing::info!(target:"test", "broadcast a block from pm0");
+    let block = chain.blocks[5].clone();
+    let mut events1 = pm1.events.from_now();
+    let mut events2 = pm2.events.from_now();
+    pm0.actix
+        .addr
+        .send(
+            PeerManagerMessageRequest::NetworkRequests(NetworkRequests::Block {
+                block: block.clone(),
+            })
+            .with_span_context(),
+        )
+        .await
+        .unwrap();
+
+    tracing::info!(target:"test", "pm1 gets a compact block, pm2 doesn't accept them");
+    let got = events1
+        .recv_until(|ev| match ev {
+            Event::Client(ClientSenderForNetworkInput::_compact_block(msg)) => {
+                Some(msg.compact_block)
+            }
+            _ => None,
+        })
+        .await;
+    assert_eq!(got, CompactBlock::new(&block).unwrap());
+    events2
+        .recv_until(|ev| match ev {
+            Event::Client(ClientSenderForNetworkInput::_block(msg))
+                if msg.block.hash() == block.hash() =>
+            {
+                Some(())
+            }
+            _ => None,
+        })
+        .await;
+}
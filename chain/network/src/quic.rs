@@ -0,0 +1,207 @@
+//! QUIC transport of the TIER1 connections.
+//!
+//! TIER1 connections carry the latency sensitive consensus messages. Over TCP a large message,
+//! like a state witness part or a chunk, delays all the messages queued after it, including
+//! the tiny approvals and endorsements (head-of-line blocking). Over QUIC every
+//! `stream::MessageClass` is sent on a separate unidirectional stream, so that the classes
+//! don't block each other. QUIC connections also survive a change of the client address
+//! (connection migration).
+//!
+//! The QUIC endpoint is bound to the UDP port with the same number as the TCP listener
+//! (`NetworkConfig::node_addr`) and is enabled by `Tier1::transport`. Nodes advertise it in
+//! the `Handshake` (`sender_quic_port`), on any tier. Outbound TIER1 connections to the peers
+//! which advertised the QUIC port are established over QUIC, falling back to TCP on failure.
+//!
+//! The TLS certificates are self signed and are not verified: TLS only encrypts the traffic,
+//! the peers authenticate each other with the `Handshake`, as they do over TCP.
+use crate::network_protocol::PeerInfo;
+use crate::tcp;
+use anyhow::{Context as _, anyhow};
+use quinn::crypto::rustls::{QuicClientConfig, QuicServerConfig};
+use quinn::rustls;
+use rustls::pki_types::{CertificateDer, PrivatePkcs8KeyDer, ServerName, UnixTime};
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+/// ALPN protocol identifier of the TIER1 connections.
+const ALPN: &[u8] = b"near-tier1";
+/// Server name of the self signed certificates. It is not verified.
+const SERVER_NAME: &str = "near";
+/// Same as the TCP connect timeout, see `tcp::Stream::connect`.
+const CONNECT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(1);
+/// Connections without any traffic for that long are closed.
+const MAX_IDLE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(60);
+/// Interval of the keep-alive packets, which keep the idle connections (and the NAT mappings)
+/// open.
+const KEEP_ALIVE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(15);
+
+/// QUIC endpoint, accepting inbound and establishing outbound TIER1 connections.
+pub(crate) struct Endpoint {
+    endpoint: quinn::Endpoint,
+    local_addr: SocketAddr,
+}
+
+/// A QUIC connection, equivalent of `tcp::Stream`.
+pub(crate) struct Stream {
+    pub(crate) conn: quinn::Connection,
+    pub(crate) type_: tcp::StreamType,
+    pub(crate) local_addr: SocketAddr,
+    pub(crate) peer_addr: SocketAddr,
+}
+
+impl Endpoint {
+    /// Binds the endpoint to the UDP `addr`. Has to be called within a tokio runtime.
+    pub fn new(addr: SocketAddr) -> anyhow::Result<Self> {
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let transport = Arc::new(transport_config()?);
+
+        let cert = rcgen::generate_simple_self_signed(vec![SERVER_NAME.to_string()])
+            .context("generate_simple_self_signed()")?;
+        let key = PrivatePkcs8KeyDer::from(cert.key_pair.serialize_der());
+        let mut server_tls = rustls::ServerConfig::builder_with_provider(provider.clone())
+            .with_protocol_versions(&[&rustls::version::TLS13])?
+            .with_no_client_auth()
+            .with_single_cert(vec![cert.cert.der().clone()], key.into())?;
+        server_tls.alpn_protocols = vec![ALPN.to_vec()];
+        let mut server_config =
+            quinn::ServerConfig::with_crypto(Arc::new(QuicServerConfig::try_from(server_tls)?));
+        server_config.migration(true).transport_config(transport.clone());
+
+        let mut client_tls = rustls::ClientConfig::builder_with_provider(provider.clone())
+            .with_protocol_versions(&[&rustls::version::TLS13])?
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(SkipServerVerification(provider)))
+            .with_no_client_auth();
+        client_tls.alpn_protocols = vec![ALPN.to_vec()];
+        let mut client_config =
+            quinn::ClientConfig::new(Arc::new(QuicClientConfig::try_from(client_tls)?));
+        client_config.transport_config(transport);
+
+        let mut endpoint = quinn::Endpoint::server(server_config, addr)
+            .with_context(|| format!("failed to bind QUIC endpoint to {addr}"))?;
+        endpoint.set_default_client_config(client_config);
+        let local_addr = endpoint.local_addr()?;
+        Ok(Self { endpoint, local_addr })
+    }
+
+    /// UDP port which the endpoint is bound to.
+    pub fn port(&self) -> u16 {
+        self.local_addr.port()
+    }
+
+    /// Establishes an outbound TIER1 connection to the QUIC endpoint at `port`
+    /// of the IP of `peer_info`.
+    pub async fn connect(&self, peer_info: &PeerInfo, port: u16) -> anyhow::Result<Stream> {
+        let mut addr = peer_info
+            .addr
+            .ok_or_else(|| anyhow!("Trying to connect to peer with no public address"))?;
+        addr.set_port(port);
+        let conn = tokio::time::timeout(CONNECT_TIMEOUT, self.endpoint.connect(addr, SERVER_NAME)?)
+            .await?
+            .context("quinn::Endpoint::connect()")?;
+        Ok(Stream {
+            peer_addr: conn.remote_address(),
+            conn,
+            type_: tcp::StreamType::Outbound { peer_id: peer_info.id.clone(), tier: tcp::Tier::T1 },
+            local_addr: self.local_addr,
+        })
+    }
+
+    /// Waits for the next inbound connection and returns a future completing its QUIC
+    /// handshake, so that the slow handshakes don't block accepting the other connections.
+    /// Returns None once the endpoint is closed.
+    pub async fn accept(
+        &self,
+    ) -> Option<impl Future<Output = anyhow::Result<Stream>> + Send + 'static> {
+        let incoming = self.endpoint.accept().await?;
+        let local_addr = self.local_addr;
+        Some(async move {
+            let conn = incoming.await.context("quinn::Incoming")?;
+            Ok(Stream {
+                peer_addr: conn.remote_address(),
+                conn,
+                type_: tcp::StreamType::Inbound,
+                local_addr,
+            })
+        })
+    }
+}
+
+impl Stream {
+    /// Establishes a loopback QUIC connection between two fresh endpoints.
+    /// Returns a pair of streams: (outbound,inbound).
+    #[cfg(test)]
+    pub async fn loopback(peer_id: near_primitives::network::PeerId) -> (Stream, Stream) {
+        let outbound = Endpoint::new("[::1]:0".parse().unwrap()).unwrap();
+        let inbound = Endpoint::new("[::1]:0".parse().unwrap()).unwrap();
+        let peer_info = PeerInfo { id: peer_id, addr: Some(inbound.local_addr), account_id: None };
+        let (outbound, inbound) =
+            tokio::join!(outbound.connect(&peer_info, inbound.port()), async {
+                inbound.accept().await.unwrap().await
+            });
+        (outbound.unwrap(), inbound.unwrap())
+    }
+}
+
+fn transport_config() -> anyhow::Result<quinn::TransportConfig> {
+    let mut config = quinn::TransportConfig::default();
+    config
+        .max_idle_timeout(Some(MAX_IDLE_TIMEOUT.try_into()?))
+        .keep_alive_interval(Some(KEEP_ALIVE_INTERVAL))
+        // Every side opens exactly one stream per message class, see `stream::FramedStream`.
+        .max_concurrent_uni_streams(quinn::VarInt::from_u32(
+            <crate::peer::stream::MessageClass as enum_map::Enum>::LENGTH as u32,
+        ))
+        .max_concurrent_bidi_streams(quinn::VarInt::from_u32(0));
+    Ok(config)
+}
+
+/// Accepts any server certificate, see the module comment.
+#[derive(Debug)]
+struct SkipServerVerification(Arc<rustls::crypto::CryptoProvider>);
+
+impl rustls::client::danger::ServerCertVerifier for SkipServerVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp: &[u8],
+        _now: UnixTime,
+    ) -> Result<rustls::client::danger::ServerCertVerified, rustls::Error> {
+        Ok(rustls::client::danger::ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<rustls::SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
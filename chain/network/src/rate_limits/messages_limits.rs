@@ -179,6 +179,9 @@ pub enum RateLimitedPeerMessageKey {
     PartialEncodedContractDeploys,
     EpochSyncRequest,
     OptimisticBlock,
+    BlockRangeRequest,
+    CompactBlock,
+    CompactBlockRequest,
 }
 
 /// Given a `PeerMessage` returns a tuple containing the `RateLimitedPeerMessageKey`
@@ -201,6 +204,11 @@ fn get_key_and_token_cost(message: &PeerMessage) -> Option<(RateLimitedPeerMessa
         PeerMessage::BlockRequest(_) => Some((BlockRequest, 1)),
         PeerMessage::Block(_) => Some((Block, 1)),
         PeerMessage::OptimisticBlock(_) => Some((OptimisticBlock, 1)),
+        PeerMessage::BlockRangeRequest(_) => Some((BlockRangeRequest, 1)),
+        PeerMessage::BlockRangeResponse(_) => None,
+        PeerMessage::CompactBlock(_) => Some((CompactBlock, 1)),
+        PeerMessage::CompactBlockRequest(_) => Some((CompactBlockRequest, 1)),
+        PeerMessage::CompactBlockResponse(_) => None,
         PeerMessage::Transaction(_) => Some((Transaction, 1)),
         PeerMessage::Routed(msg) => match msg.body {
             RoutedMessageBody::BlockApproval(_) => Some((BlockApproval, 1)),
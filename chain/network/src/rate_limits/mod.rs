@@ -1,2 +1,3 @@
 pub mod messages_limits;
+pub mod send_limits;
 pub mod token_bucket;
@@ -0,0 +1,342 @@
+//! Scheduling of the outbound messages: priorities and byte rate limits per message class.
+//!
+//! Messages queued for a connection are sent in the order of the priority of their class
+//! (see `stream::MessageClass`), so that a burst of large low priority messages, like state
+//! sync responses, doesn't delay the blocks and approvals queued after it.
+//! Additionally, the bytes sent of every class can be limited per peer and node-wide.
+//! Messages of a class over its limits wait in the queue, while the other classes are sent.
+
+use super::token_bucket::TokenBucket;
+use crate::peer::stream::{Frame, MessageClass};
+use crate::stats::metrics;
+use enum_map::EnumMap;
+use near_async::time::{Duration, Instant};
+use parking_lot::Mutex;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+
+/// Limit of `bytes_per_second` on average, with bursts of up to `burst_bytes`.
+#[derive(Clone, serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+pub struct ByteRateLimit {
+    pub bytes_per_second: u32,
+    pub burst_bytes: u32,
+}
+
+impl ByteRateLimit {
+    fn bucket(&self, start_time: Instant) -> TokenBucket {
+        // Cannot fail, since the refill rate is non-negative and finite.
+        TokenBucket::new(
+            self.burst_bytes,
+            self.burst_bytes,
+            self.bytes_per_second as f32,
+            start_time,
+        )
+        .unwrap()
+    }
+}
+
+/// Send configuration of a single message class.
+#[derive(Clone, serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+pub struct ClassConfig {
+    /// Messages of the classes with higher priority are sent first.
+    /// Classes of the same priority are sent in the order of `MessageClass` variants.
+    pub priority: u8,
+    /// Limit of the bytes sent to a single peer.
+    #[serde(default)]
+    pub per_peer_limit: Option<ByteRateLimit>,
+    /// Limit of the bytes sent to all the peers together.
+    #[serde(default)]
+    pub node_limit: Option<ByteRateLimit>,
+}
+
+/// Outbound messages scheduling configuration.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Config {
+    pub classes: EnumMap<MessageClass, ClassConfig>,
+}
+
+/// Struct to manage user defined overrides for [Config].
+/// The configuration of every class present in `classes` is replaced.
+#[derive(serde::Serialize, serde::Deserialize, Default, Clone, Debug)]
+pub struct OverrideConfig {
+    pub classes: HashMap<MessageClass, ClassConfig>,
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum ConfigError {
+    #[error("{0:?}: byte rate limit has to be positive, got {1:?}")]
+    NonPositiveLimit(MessageClass, ByteRateLimit),
+}
+
+impl Config {
+    /// Validates this configuration object.
+    ///
+    /// # Errors
+    ///
+    /// If at least one error is present, returns the list of all configuration errors.
+    pub fn validate(&self) -> Result<(), Vec<ConfigError>> {
+        let mut errors = Vec::new();
+        for (class, config) in &self.classes {
+            for limit in [&config.per_peer_limit, &config.node_limit].into_iter().flatten() {
+                if limit.bytes_per_second == 0 || limit.burst_bytes == 0 {
+                    errors.push(ConfigError::NonPositiveLimit(class, limit.clone()));
+                }
+            }
+        }
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+
+    /// Returns the preset valid for any type of node: consensus messages go first,
+    /// state sync responses go last and nothing is limited.
+    pub fn standard_preset() -> Self {
+        let class = |priority| ClassConfig { priority, per_peer_limit: None, node_limit: None };
+        Self {
+            classes: EnumMap::from_fn(|c| match c {
+                MessageClass::Approval | MessageClass::Endorsement => class(6),
+                MessageClass::Block => class(5),
+                MessageClass::Witness | MessageClass::Chunk => class(4),
+                MessageClass::Control => class(3),
+                MessageClass::StateSync => class(0),
+            }),
+        }
+    }
+
+    /// Applies configuration overrides to `self`.
+    pub fn apply_overrides(&mut self, overrides: OverrideConfig) {
+        for (class, config) in overrides.classes {
+            self.classes[class] = config;
+        }
+    }
+}
+
+/// State of the outbound messages scheduling shared by all the connections of the node.
+pub struct SendLimits {
+    config: Config,
+    /// Classes in the order of decreasing priority.
+    order: Vec<MessageClass>,
+    node_buckets: EnumMap<MessageClass, Option<Mutex<TokenBucket>>>,
+}
+
+impl SendLimits {
+    pub fn new(config: Config, start_time: Instant) -> Self {
+        let mut order: Vec<_> = config.classes.iter().map(|(class, _)| class).collect();
+        order.sort_by_key(|class| std::cmp::Reverse(config.classes[*class].priority));
+        let node_buckets = EnumMap::from_fn(|class| {
+            config.classes[class]
+                .node_limit
+                .as_ref()
+                .map(|limit| Mutex::new(limit.bucket(start_time)))
+        });
+        Self { config, order, node_buckets }
+    }
+
+    pub fn priority(&self, class: MessageClass) -> u8 {
+        self.config.classes[class].priority
+    }
+}
+
+/// Result of [Scheduler::pop].
+#[derive(Debug, PartialEq)]
+pub(crate) enum Pop {
+    Frame(MessageClass, Frame),
+    /// All the queued frames are over the byte rate limits, retry at the given time.
+    Throttled(Instant),
+    Empty,
+}
+
+/// Send queue of a single connection.
+pub(crate) struct Scheduler {
+    limits: Arc<SendLimits>,
+    queues: EnumMap<MessageClass, VecDeque<(Instant, Frame)>>,
+    peer_buckets: EnumMap<MessageClass, Option<TokenBucket>>,
+}
+
+impl Scheduler {
+    pub fn new(limits: Arc<SendLimits>, start_time: Instant) -> Self {
+        let peer_buckets = EnumMap::from_fn(|class| {
+            limits.config.classes[class].per_peer_limit.as_ref().map(|l| l.bucket(start_time))
+        });
+        Self { limits, queues: EnumMap::default(), peer_buckets }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queues.values().all(|q| q.is_empty())
+    }
+
+    pub fn push(&mut self, class: MessageClass, frame: Frame, now: Instant) {
+        let label: &str = class.into();
+        metrics::PEER_SEND_QUEUE_BY_CLASS_BYTES
+            .with_label_values(&[label])
+            .add(frame.0.len() as i64);
+        self.queues[class].push_back((now, frame));
+    }
+
+    /// Pops the first frame of the highest priority class which is within the byte rate limits.
+    pub fn pop(&mut self, now: Instant) -> Pop {
+        let limits = self.limits.clone();
+        let mut retry_at = None;
+        for &class in &limits.order {
+            let Some(len) = self.queues[class].front().map(|(_, frame)| frame.0.len()) else {
+                continue;
+            };
+            let label: &str = class.into();
+            if let Err(wait) = self.acquire(class, len, now) {
+                metrics::PEER_SEND_THROTTLED_BY_CLASS_TOTAL.with_label_values(&[label]).inc();
+                retry_at = Some(retry_at.map_or(now + wait, |t: Instant| t.min(now + wait)));
+                continue;
+            }
+            let (queued_at, frame) = self.queues[class].pop_front().unwrap();
+            metrics::PEER_SEND_QUEUE_BY_CLASS_BYTES.with_label_values(&[label]).sub(len as i64);
+            metrics::PEER_SEND_QUEUE_DELAY_BY_CLASS
+                .with_label_values(&[label])
+                .observe((now - queued_at).as_seconds_f64());
+            metrics::PEER_MESSAGE_SENT_BY_CLASS_BYTES
+                .with_label_values(&[label])
+                .inc_by(len as u64);
+            metrics::PEER_MESSAGE_SENT_BY_CLASS_TOTAL.with_label_values(&[label]).inc();
+            return Pop::Frame(class, frame);
+        }
+        match retry_at {
+            Some(t) => Pop::Throttled(t),
+            None => Pop::Empty,
+        }
+    }
+
+    /// Acquires the tokens for sending `len` bytes of `class` from both the per peer and the
+    /// node-wide bucket. If either doesn't have enough tokens, acquires none and returns
+    /// how long to wait.
+    fn acquire(&mut self, class: MessageClass, len: usize, now: Instant) -> Result<(), Duration> {
+        let mut node_bucket = self.limits.node_buckets[class].as_ref().map(|b| b.lock());
+        let buckets = self.peer_buckets[class].iter_mut().chain(node_bucket.as_deref_mut());
+        let mut acquire = vec![];
+        let mut wait = Duration::ZERO;
+        for bucket in buckets {
+            // Frames larger than the burst are sent once the bucket is full.
+            let tokens = u32::try_from(len).unwrap_or(u32::MAX).min(bucket.maximum_size());
+            // Never None, since `tokens` doesn't exceed the maximum size and the refill rate
+            // is validated to be positive.
+            wait = wait.max(bucket.time_until_available(tokens, now).unwrap_or(Duration::ZERO));
+            acquire.push((bucket, tokens));
+        }
+        if wait > Duration::ZERO {
+            return Err(wait);
+        }
+        for (bucket, tokens) in acquire {
+            bucket.acquire(tokens, now);
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Scheduler {
+    fn drop(&mut self) {
+        for (class, queue) in &self.queues {
+            let label: &str = class.into();
+            let len: usize = queue.iter().map(|(_, frame)| frame.0.len()).sum();
+            metrics::PEER_SEND_QUEUE_BY_CLASS_BYTES.with_label_values(&[label]).sub(len as i64);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_async::time::FakeClock;
+
+    fn frame(len: usize) -> Frame {
+        Frame(vec![0; len])
+    }
+
+    fn limit(bytes_per_second: u32, burst_bytes: u32) -> Option<ByteRateLimit> {
+        Some(ByteRateLimit { bytes_per_second, burst_bytes })
+    }
+
+    #[test]
+    fn priorities() {
+        let clock = FakeClock::default();
+        let limits = Arc::new(SendLimits::new(Config::standard_preset(), clock.now()));
+        let mut scheduler = Scheduler::new(limits, clock.now());
+        assert_eq!(scheduler.pop(clock.now()), Pop::Empty);
+
+        scheduler.push(MessageClass::StateSync, frame(1), clock.now());
+        scheduler.push(MessageClass::Control, frame(2), clock.now());
+        scheduler.push(MessageClass::Block, frame(3), clock.now());
+        scheduler.push(MessageClass::Approval, frame(4), clock.now());
+        scheduler.push(MessageClass::Approval, frame(5), clock.now());
+        let mut got = vec![];
+        while let Pop::Frame(class, frame) = scheduler.pop(clock.now()) {
+            got.push((class, frame.0.len()));
+        }
+        assert_eq!(
+            got,
+            vec![
+                (MessageClass::Approval, 4),
+                (MessageClass::Approval, 5),
+                (MessageClass::Block, 3),
+                (MessageClass::Control, 2),
+                (MessageClass::StateSync, 1),
+            ]
+        );
+        assert!(scheduler.is_empty());
+    }
+
+    #[test]
+    fn byte_rate_limits() {
+        let clock = FakeClock::default();
+        let mut config = Config::standard_preset();
+        config.classes[MessageClass::StateSync].priority = 10;
+        config.classes[MessageClass::StateSync].per_peer_limit = limit(1000, 1000);
+        config.classes[MessageClass::StateSync].node_limit = limit(500, 2000);
+        assert_eq!(config.validate(), Ok(()));
+        let limits = Arc::new(SendLimits::new(config, clock.now()));
+        let mut s1 = Scheduler::new(limits.clone(), clock.now());
+        let mut s2 = Scheduler::new(limits, clock.now());
+
+        // The per peer limit allows a burst of 1000 bytes.
+        s1.push(MessageClass::StateSync, frame(1000), clock.now());
+        s1.push(MessageClass::StateSync, frame(500), clock.now());
+        s1.push(MessageClass::Control, frame(10), clock.now());
+        assert!(matches!(s1.pop(clock.now()), Pop::Frame(MessageClass::StateSync, _)));
+        // The throttled class doesn't block the classes of lower priority.
+        assert!(matches!(s1.pop(clock.now()), Pop::Frame(MessageClass::Control, _)));
+        assert_eq!(s1.pop(clock.now()), Pop::Throttled(clock.now() + Duration::milliseconds(500)));
+        clock.advance(Duration::milliseconds(500));
+        assert!(matches!(s1.pop(clock.now()), Pop::Frame(MessageClass::StateSync, _)));
+
+        // The node-wide limit is shared: 750 bytes are left out of the 2000 bytes burst.
+        s2.push(MessageClass::StateSync, frame(1000), clock.now());
+        assert_eq!(s2.pop(clock.now()), Pop::Throttled(clock.now() + Duration::milliseconds(500)));
+        clock.advance(Duration::milliseconds(500));
+        assert!(matches!(s2.pop(clock.now()), Pop::Frame(MessageClass::StateSync, _)));
+
+        // Frames larger than the burst are sent once the bucket is full.
+        clock.advance(Duration::seconds(10));
+        s1.push(MessageClass::StateSync, frame(5000), clock.now());
+        assert!(matches!(s1.pop(clock.now()), Pop::Frame(MessageClass::StateSync, _)));
+    }
+
+    #[test]
+    fn configuration() {
+        let mut config = Config::standard_preset();
+        config.classes[MessageClass::Block].per_peer_limit = limit(0, 10);
+        config.classes[MessageClass::Chunk].node_limit = limit(10, 0);
+        assert_eq!(config.validate().unwrap_err().len(), 2);
+
+        let overrides: OverrideConfig = serde_json::from_value(serde_json::json!({"classes": {
+            "StateSync": {
+                "priority": 7,
+                "node_limit": {"bytes_per_second": 1000000, "burst_bytes": 5000000},
+            },
+        }}))
+        .unwrap();
+        let mut config = Config::standard_preset();
+        config.apply_overrides(overrides);
+        assert_eq!(config.validate(), Ok(()));
+        assert_eq!(config.classes[MessageClass::StateSync].priority, 7);
+        assert_eq!(config.classes[MessageClass::StateSync].node_limit, limit(1000000, 5000000));
+        assert_eq!(
+            config.classes[MessageClass::Block],
+            Config::standard_preset().classes[MessageClass::Block]
+        );
+    }
+}
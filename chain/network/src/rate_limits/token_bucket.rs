@@ -8,9 +8,9 @@
 //! in order to be allowed to pass.
 //! If there aren't enough tokens in the bucket, the traffic might be stopped
 //! or delayed. However, this module responsibility stops at telling
-//! whether or not the incoming messages are allowed.
+//! whether or not the messages are allowed, and when they will be.
 
-use near_async::time::Instant;
+use near_async::time::{Duration, Instant};
 
 #[derive(thiserror::Error, Debug, PartialEq)]
 pub enum TokenBucketError {
@@ -80,6 +80,29 @@ impl TokenBucket {
         }
     }
 
+    /// Returns how long it will take until `tokens` tokens can be acquired, zero if they can be
+    /// acquired already.
+    ///
+    /// Returns `None` if the tokens will never be available, i.e. if `tokens` is larger than
+    /// `maximum_size` or `refill_rate` is zero.
+    pub fn time_until_available(&mut self, tokens: u32, now: Instant) -> Option<Duration> {
+        self.refill(now);
+        let tokens = to_tokens_with_parts(tokens);
+        if self.size >= tokens {
+            return Some(Duration::ZERO);
+        }
+        if tokens > to_tokens_with_parts(self.maximum_size) || self.refill_rate == 0.0 {
+            return None;
+        }
+        let missing = (tokens - self.size) as f64 / TOKEN_PARTS_NUMBER as f64;
+        Some(Duration::seconds_f64(missing / self.refill_rate as f64))
+    }
+
+    /// Maximum number of tokens the bucket can hold.
+    pub fn maximum_size(&self) -> u32 {
+        self.maximum_size
+    }
+
     /// Refills the bucket with the right number of tokens according to
     /// the `refill_rate` and the new current time `now`.
     ///
@@ -195,6 +218,24 @@ mod tests {
         assert!(bucket.acquire(1, now + Duration::milliseconds(500)));
     }
 
+    #[test]
+    fn time_until_available() {
+        let now = Instant::now();
+        let mut bucket = TokenBucket::new(2, 10, 4.0, now).expect("bucket should be well formed");
+        assert_eq!(bucket.time_until_available(2, now), Some(Duration::ZERO));
+        assert_eq!(bucket.time_until_available(4, now), Some(Duration::milliseconds(500)));
+        assert_eq!(bucket.time_until_available(11, now), None);
+        // The bucket is not modified.
+        assert!(bucket.acquire(2, now));
+
+        let later = now + Duration::milliseconds(500);
+        assert_eq!(bucket.time_until_available(2, later), Some(Duration::ZERO));
+        assert!(bucket.acquire(2, later));
+
+        let mut bucket = TokenBucket::new(1, 10, 0.0, now).expect("bucket should be well formed");
+        assert_eq!(bucket.time_until_available(2, now), None);
+    }
+
     #[test]
     fn zero_refill_rate() {
         let now = Instant::now();
@@ -1,7 +1,9 @@
+use crate::capture;
 use crate::config::SocketOptions;
 use crate::network_protocol::{
     Encoding, Handshake, HandshakeFailureReason, PartialEdgeInfo, PeerChainInfoV2, PeerIdOrHash,
-    PeerMessage, Ping, Pong, RawRoutedMessage, RoutedMessageBody, RoutingTableUpdate,
+    PeerMessage, PeersRequest, PeersResponse, Ping, Pong, RawRoutedMessage, RoutedMessageBody,
+    RoutingTableUpdate,
 };
 use crate::tcp;
 use crate::types::{
@@ -33,6 +35,7 @@ pub struct Connection {
     secret_key: SecretKey,
     my_peer_id: PeerId,
     peer_id: PeerId,
+    peer_handshake: HandshakeInfo,
     stream: PeerStream,
     // this is used to keep track of routed messages we've sent so that when we get a reply
     // that references one of our previously sent messages, we can determine that the message is for us
@@ -43,6 +46,30 @@ pub struct Connection {
     borsh_message_expected: bool,
 }
 
+/// Information sent by the peer in its handshake.
+#[derive(serde::Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct HandshakeInfo {
+    pub protocol_version: ProtocolVersion,
+    pub oldest_supported_version: ProtocolVersion,
+    pub height: BlockHeight,
+    pub tracked_shards: Vec<ShardId>,
+    pub archival: bool,
+    pub block_range_start: Option<BlockHeight>,
+}
+
+impl From<&Handshake> for HandshakeInfo {
+    fn from(h: &Handshake) -> Self {
+        Self {
+            protocol_version: h.protocol_version,
+            oldest_supported_version: h.oldest_supported_version,
+            height: h.sender_chain_info.height,
+            tracked_shards: h.sender_chain_info.tracked_shards.clone(),
+            archival: h.sender_chain_info.archival,
+            block_range_start: h.sender_block_range_start,
+        }
+    }
+}
+
 // The types of messages it's possible to route to a target PeerId via the connected peer as a first hop
 // These can be sent with Connection::send_routed_message(), and received in Message::Routed() from
 // Connection::recv()
@@ -90,6 +117,8 @@ pub enum DirectMessage {
     StateRequestHeader(ShardId, CryptoHash),
     StateRequestPart(ShardId, CryptoHash, u64),
     VersionedStateResponse(Box<StateResponseInfo>),
+    PeersRequest,
+    PeersResponse { peers: Vec<PeerInfo>, direct_peers: Vec<PeerInfo> },
 }
 
 impl fmt::Display for DirectMessage {
@@ -124,6 +153,13 @@ impl fmt::Debug for DirectMessage {
                 r.shard_id(),
                 r.sync_hash()
             ),
+            Self::PeersRequest => write!(f, "PeersRequest"),
+            Self::PeersResponse { peers, direct_peers } => write!(
+                f,
+                "PeersResponse(peers: {} direct_peers: {})",
+                peers.len(),
+                direct_peers.len()
+            ),
         }
     }
 }
@@ -213,6 +249,9 @@ fn new_handshake(
         // we have to set this even if we have no intention of listening since otherwise
         // the peer will drop our connection
         sender_listen_port: Some(listen_port),
+        sender_quic_port: None,
+        sender_block_range_start: None,
+        sender_compact_block_relay: false,
         sender_chain_info: PeerChainInfoV2 {
             genesis_id: GenesisId { chain_id: chain_id.to_string(), hash: genesis_hash },
             height: head_height,
@@ -253,16 +292,13 @@ impl Connection {
             target: "network",
             %peer_id, ?addr, latency=?start.elapsed(), "Connection established",
         );
-        let mut peer = Self {
-            stream: PeerStream::new(stream, recv_timeout),
-            peer_id,
-            secret_key,
-            my_peer_id,
-            route_cache: lru::LruCache::new(NonZeroUsize::new(1_000_000).unwrap()),
-            borsh_message_expected: false,
-        };
-        peer.do_handshake(
+        let mut stream = PeerStream::new(stream, recv_timeout);
+        let peer_handshake = Self::do_handshake(
+            &mut stream,
             &clock,
+            &secret_key,
+            &my_peer_id,
+            &peer_id,
             my_protocol_version.unwrap_or(PROTOCOL_VERSION),
             chain_id,
             genesis_hash,
@@ -271,7 +307,15 @@ impl Connection {
         )
         .await?;
 
-        Ok(peer)
+        Ok(Self {
+            stream,
+            peer_id,
+            peer_handshake,
+            secret_key,
+            my_peer_id,
+            route_cache: lru::LruCache::new(NonZeroUsize::new(1_000_000).unwrap()),
+            borsh_message_expected: false,
+        })
     }
 
     async fn on_accept(
@@ -297,11 +341,14 @@ impl Connection {
             Err(RecvError::IO(e)) => return Err(ConnectError::IO(e)),
         };
 
-        let (peer_id, nonce, handshake_protocol_version) = match message {
+        let (peer_id, nonce, handshake_protocol_version, peer_handshake) = match message {
             // TODO: maybe check the handshake for sanity
-            PeerMessage::Tier2Handshake(h) => {
-                (h.sender_peer_id, h.partial_edge_info.nonce, h.protocol_version)
-            }
+            PeerMessage::Tier2Handshake(h) => (
+                h.sender_peer_id.clone(),
+                h.partial_edge_info.nonce,
+                h.protocol_version,
+                HandshakeInfo::from(&h),
+            ),
             PeerMessage::HandshakeFailure(_peer_info, reason) => {
                 return Err(ConnectError::HandshakeFailure(reason));
             }
@@ -330,25 +377,29 @@ impl Connection {
             my_peer_id,
             stream,
             peer_id,
+            peer_handshake,
             route_cache: lru::LruCache::new(NonZeroUsize::new(1_000_000).unwrap()),
             borsh_message_expected,
         })
     }
 
     async fn do_handshake(
-        &mut self,
+        stream: &mut PeerStream,
         clock: &Clock,
+        secret_key: &SecretKey,
+        my_peer_id: &PeerId,
+        peer_id: &PeerId,
         protocol_version: ProtocolVersion,
         chain_id: &str,
         genesis_hash: CryptoHash,
         head_height: BlockHeight,
         tracked_shards: Vec<ShardId>,
-    ) -> Result<(), ConnectError> {
+    ) -> Result<HandshakeInfo, ConnectError> {
         let handshake = new_handshake(
-            &self.secret_key,
-            &self.my_peer_id,
-            &self.peer_id,
-            self.stream.stream.local_addr.port(),
+            secret_key,
+            my_peer_id,
+            peer_id,
+            stream.stream.local_addr.port(),
             Edge::create_fresh_nonce(&clock),
             protocol_version,
             chain_id,
@@ -358,27 +409,26 @@ impl Connection {
             false,
         );
 
-        self.stream.write_message(&handshake).await.map_err(ConnectError::IO)?;
+        stream.write_message(&handshake).await.map_err(ConnectError::IO)?;
 
         let start = Instant::now();
 
-        let (message, timestamp) = self.stream.recv_message().await?;
+        let (message, timestamp) = stream.recv_message().await?;
 
         match message {
             // TODO: maybe check the handshake for sanity
-            PeerMessage::Tier2Handshake(_) => {
+            PeerMessage::Tier2Handshake(h) => {
                 tracing::info!(
                     target: "network",
                     handshake_latency=%timestamp.signed_duration_since(start)
                 );
+                Ok(HandshakeInfo::from(&h))
             }
             PeerMessage::HandshakeFailure(_peer_info, reason) => {
-                return Err(ConnectError::HandshakeFailure(reason));
+                Err(ConnectError::HandshakeFailure(reason))
             }
-            _ => return Err(ConnectError::UnexpectedFirstMessage(Box::new(message))),
-        };
-
-        Ok(())
+            _ => Err(ConnectError::UnexpectedFirstMessage(Box::new(message))),
+        }
     }
 
     // Try to send a PeerMessage corresponding to the given DirectMessage
@@ -400,6 +450,12 @@ impl Connection {
             DirectMessage::VersionedStateResponse(request) => {
                 PeerMessage::VersionedStateResponse(*request)
             }
+            DirectMessage::PeersRequest => {
+                PeerMessage::PeersRequest(PeersRequest { max_peers: None, max_direct_peers: None })
+            }
+            DirectMessage::PeersResponse { peers, direct_peers } => {
+                PeerMessage::PeersResponse(PeersResponse { peers, direct_peers })
+            }
         };
 
         self.stream.write_message(&peer_msg).await
@@ -435,6 +491,41 @@ impl Connection {
         self.stream.write_message(&PeerMessage::Routed(Box::new(msg))).await
     }
 
+    /// Sends a message read from a capture file (see `crate::capture`), re-encoded as proto.
+    /// The frames which failed to parse when captured are sent as they are. The handshake
+    /// messages are skipped, since this connection has already performed its own handshake.
+    /// Returns whether the message has been sent.
+    pub async fn send_captured(&mut self, frame: &capture::Frame) -> io::Result<bool> {
+        let Some(msg) = frame.message() else {
+            self.stream.write_bytes(&frame.bytes).await?;
+            return Ok(true);
+        };
+        match msg.map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))? {
+            PeerMessage::Tier1Handshake(_)
+            | PeerMessage::Tier2Handshake(_)
+            | PeerMessage::Tier3Handshake(_)
+            | PeerMessage::HandshakeFailure(..) => Ok(false),
+            msg => {
+                self.stream.write_message(&msg).await?;
+                Ok(true)
+            }
+        }
+    }
+
+    /// Sends a ping to the peer and waits for the pong, dropping any other message
+    /// received in the meantime. Since the peer handles the messages of a connection
+    /// in order, all the previously sent messages have been handled once this returns.
+    pub async fn ping_and_wait(&mut self, nonce: u64) -> io::Result<()> {
+        self.send_routed_message(RoutedMessage::Ping { nonce }, self.peer_id.clone(), 2).await?;
+        loop {
+            if let (Message::Routed(RoutedMessage::Pong { nonce: n, .. }), _) = self.recv().await? {
+                if n == nonce {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
     fn target_is_for_me(&mut self, target: &PeerIdOrHash) -> bool {
         match target {
             PeerIdOrHash::PeerId(peer_id) => peer_id == &self.my_peer_id,
@@ -523,6 +614,15 @@ impl Connection {
                         timestamp,
                     ));
                 }
+                PeerMessage::PeersRequest(_) => {
+                    return Ok((Message::Direct(DirectMessage::PeersRequest), timestamp));
+                }
+                PeerMessage::PeersResponse(PeersResponse { peers, direct_peers }) => {
+                    return Ok((
+                        Message::Direct(DirectMessage::PeersResponse { peers, direct_peers }),
+                        timestamp,
+                    ));
+                }
                 _ => {}
             }
         }
@@ -531,6 +631,14 @@ impl Connection {
     pub fn peer_id(&self) -> &PeerId {
         &self.peer_id
     }
+
+    pub fn my_peer_id(&self) -> &PeerId {
+        &self.my_peer_id
+    }
+
+    pub fn peer_handshake(&self) -> &HandshakeInfo {
+        &self.peer_handshake
+    }
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -559,9 +667,12 @@ impl PeerStream {
     }
 
     async fn write_message(&mut self, msg: &PeerMessage) -> io::Result<()> {
-        let mut msg = msg.serialize(Encoding::Proto);
+        self.write_bytes(&msg.serialize(Encoding::Proto)).await
+    }
+
+    async fn write_bytes(&mut self, msg: &[u8]) -> io::Result<()> {
         let mut buf = (msg.len() as u32).to_le_bytes().to_vec();
-        buf.append(&mut msg);
+        buf.extend_from_slice(msg);
         self.stream.stream.write_all(&buf).await
     }
 
@@ -0,0 +1,242 @@
+//! Crawler of the NEAR network.
+//!
+//! Starting from the given peers, it connects to every node it learns about, performs
+//! the handshake and asks the node for its peers with a PeersRequest. The information
+//! from the handshakes (protocol versions, head height, tracked shards, archival flag)
+//! and the direct peers reported by every node are collected into a graph of the network,
+//! which can be rendered as JSON or in the DOT format of graphviz.
+//!
+//! The software version of a node is not a part of the handshake, so the crawler reports
+//! the range of the protocol versions supported by the node instead.
+use crate::raw::{Connection, DirectMessage, HandshakeInfo, Message};
+use crate::types::PeerInfo;
+use anyhow::Context as _;
+use futures::StreamExt as _;
+use futures::stream::FuturesUnordered;
+use near_async::time;
+use near_primitives::hash::CryptoHash;
+use near_primitives::network::PeerId;
+use near_primitives::types::AccountId;
+use near_primitives::version::ProtocolVersion;
+use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
+use std::fmt::Write as _;
+use std::net::SocketAddr;
+
+pub struct CrawlConfig {
+    /// Chain ID and genesis hash sent in the handshakes.
+    pub chain_id: String,
+    pub genesis_hash: CryptoHash,
+    /// Protocol version sent in the handshakes. Defaults to the current one.
+    pub protocol_version: Option<ProtocolVersion>,
+    /// Max number of nodes to connect to.
+    pub max_nodes: usize,
+    /// Max number of nodes to connect to at the same time.
+    pub concurrency: usize,
+    /// Time to wait for the handshake and then for the response to the PeersRequest.
+    pub timeout: time::Duration,
+}
+
+/// A node of the network found by the crawler.
+#[derive(serde::Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct CrawledNode {
+    pub peer_id: PeerId,
+    pub addr: Option<SocketAddr>,
+    pub account_id: Option<AccountId>,
+    /// Set if the handshake with the node succeeded.
+    pub handshake: Option<HandshakeInfo>,
+    /// Why the node could not be reached or didn't report its peers.
+    /// Not set for the nodes which the crawler didn't try to connect to.
+    pub error: Option<String>,
+    /// Peers the node is directly connected to, as reported in its PeersResponse.
+    pub direct_peers: Vec<PeerId>,
+}
+
+impl CrawledNode {
+    fn new(info: PeerInfo) -> Self {
+        Self {
+            peer_id: info.id,
+            addr: info.addr,
+            account_id: info.account_id,
+            handshake: None,
+            error: None,
+            direct_peers: vec![],
+        }
+    }
+
+    pub fn reachable(&self) -> bool {
+        self.handshake.is_some()
+    }
+}
+
+/// Graph of the network: the nodes found by the crawler, with the edges between
+/// the nodes and their direct peers.
+#[derive(serde::Serialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Graph {
+    pub nodes: Vec<CrawledNode>,
+}
+
+impl Graph {
+    /// Undirected edges of the graph, each listed once.
+    pub fn edges(&self) -> BTreeSet<(&PeerId, &PeerId)> {
+        self.nodes
+            .iter()
+            .flat_map(|n| n.direct_peers.iter().map(move |p| (&n.peer_id, p)))
+            .map(|(a, b)| if a < b { (a, b) } else { (b, a) })
+            .collect()
+    }
+
+    /// Renders the graph in the DOT format. Reachable nodes are labeled with their
+    /// protocol version, head height and tracked shards. Archival nodes are drawn
+    /// as boxes and the unreachable ones are grayed out.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("graph network {\n");
+        for node in &self.nodes {
+            let name = node
+                .account_id
+                .as_ref()
+                .map_or_else(|| node.peer_id.to_string(), |a| a.to_string());
+            let attrs = match &node.handshake {
+                Some(h) => format!(
+                    "label=\"{name}\\nv{} #{}\\nshards {:?}\"{}",
+                    h.protocol_version,
+                    h.height,
+                    h.tracked_shards,
+                    if h.archival { " shape=box" } else { "" }
+                ),
+                None => format!("label=\"{name}\" color=gray fontcolor=gray"),
+            };
+            writeln!(dot, "  \"{}\" [{attrs}];", node.peer_id).unwrap();
+        }
+        for (a, b) in self.edges() {
+            writeln!(dot, "  \"{a}\" -- \"{b}\";").unwrap();
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// Result of connecting to a node.
+struct Visit {
+    /// Peer id used by the crawler for the connection.
+    crawler_id: PeerId,
+    handshake: HandshakeInfo,
+    /// Peers from the PeersResponse, or the error if the node didn't send it.
+    peers: anyhow::Result<(Vec<PeerInfo>, Vec<PeerInfo>)>,
+}
+
+async fn visit(
+    clock: &time::Clock,
+    cfg: &CrawlConfig,
+    peer_id: PeerId,
+    addr: SocketAddr,
+) -> anyhow::Result<Visit> {
+    let timeout = cfg.timeout.unsigned_abs();
+    let mut conn = tokio::time::timeout(
+        timeout,
+        Connection::connect(
+            clock,
+            addr,
+            peer_id,
+            cfg.protocol_version,
+            &cfg.chain_id,
+            cfg.genesis_hash,
+            0,
+            vec![],
+            None,
+        ),
+    )
+    .await
+    .context("handshake timed out")??;
+    let handshake = conn.peer_handshake().clone();
+    let peers = tokio::time::timeout(timeout, async {
+        conn.send_message(DirectMessage::PeersRequest).await?;
+        loop {
+            if let (Message::Direct(DirectMessage::PeersResponse { peers, direct_peers }), _) =
+                conn.recv().await?
+            {
+                return anyhow::Ok((peers, direct_peers));
+            }
+        }
+    })
+    .await
+    .context("PeersResponse timed out")
+    .and_then(|res| res);
+    Ok(Visit { crawler_id: conn.my_peer_id().clone(), handshake, peers })
+}
+
+/// Adds the peer to the graph. Queues it to be visited once its address is known.
+fn discover(
+    nodes: &mut BTreeMap<PeerId, CrawledNode>,
+    queue: &mut VecDeque<(PeerId, SocketAddr)>,
+    info: PeerInfo,
+) {
+    let node = nodes.entry(info.id.clone()).or_insert_with(|| CrawledNode::new(info.clone()));
+    // Peers may be first learned without an address or an account.
+    if node.account_id.is_none() {
+        node.account_id = info.account_id;
+    }
+    if node.addr.is_none() {
+        if let Some(addr) = info.addr {
+            node.addr = Some(addr);
+            queue.push_back((info.id, addr));
+        }
+    }
+}
+
+/// Crawls the network starting from the `start` peers. Only the peers with known
+/// addresses are connected to. Returns the nodes sorted by peer id.
+pub async fn crawl(clock: &time::Clock, cfg: &CrawlConfig, start: Vec<PeerInfo>) -> Graph {
+    let mut nodes = BTreeMap::new();
+    let mut queue = VecDeque::new();
+    for info in start {
+        discover(&mut nodes, &mut queue, info);
+    }
+    // The crawler connects with a new peer id every time. The nodes report these
+    // connections among their peers, so they need to be excluded from the graph.
+    let mut crawler_ids = HashSet::new();
+
+    let mut visited = 0;
+    let mut in_flight = FuturesUnordered::new();
+    loop {
+        while in_flight.len() < cfg.concurrency && visited < cfg.max_nodes {
+            let Some((peer_id, addr)) = queue.pop_front() else {
+                break;
+            };
+            visited += 1;
+            in_flight
+                .push(async move { (peer_id.clone(), visit(clock, cfg, peer_id, addr).await) });
+        }
+        let Some((peer_id, result)) = in_flight.next().await else {
+            break;
+        };
+        tracing::debug!(target: "network", %peer_id, ok = result.is_ok(), "crawled node");
+        let node = nodes.get_mut(&peer_id).unwrap();
+        let visit = match result {
+            Ok(visit) => visit,
+            Err(err) => {
+                node.error = Some(format!("{err:#}"));
+                continue;
+            }
+        };
+        node.handshake = Some(visit.handshake);
+        crawler_ids.insert(visit.crawler_id);
+        let (peers, direct_peers) = match visit.peers {
+            Ok(peers) => peers,
+            Err(err) => {
+                node.error = Some(format!("{err:#}"));
+                continue;
+            }
+        };
+        node.direct_peers = direct_peers.iter().map(|p| p.id.clone()).collect();
+        for info in direct_peers.into_iter().chain(peers) {
+            if !crawler_ids.contains(&info.id) {
+                discover(&mut nodes, &mut queue, info);
+            }
+        }
+    }
+    nodes.retain(|id, _| !crawler_ids.contains(id));
+    for node in nodes.values_mut() {
+        node.direct_peers.retain(|id| !crawler_ids.contains(id));
+    }
+    Graph { nodes: nodes.into_values().collect() }
+}
@@ -1,6 +1,10 @@
 mod connection;
+mod crawl;
 
-pub use connection::{ConnectError, Connection, DirectMessage, Listener, Message, RoutedMessage};
+pub use connection::{
+    ConnectError, Connection, DirectMessage, HandshakeInfo, Listener, Message, RoutedMessage,
+};
+pub use crawl::{CrawlConfig, CrawledNode, Graph, crawl};
 
 #[cfg(test)]
 mod tests;
@@ -1,4 +1,8 @@
+use crate::capture;
+use crate::client::ClientSenderForNetworkInput;
 use crate::network_protocol::testonly as data;
+use crate::network_protocol::{Encoding, PeerMessage};
+use crate::peer_manager::testonly::Event;
 use crate::raw;
 use crate::tcp;
 use crate::testonly;
@@ -191,3 +195,143 @@ async fn test_listener() {
     // at least the handshake logic has gotten exercised somewhat
     let _ = conn.recv().await.unwrap();
 }
+
+#[tokio::test]
+async fn test_crawl() {
+    init_test_logger();
+    let mut rng = testonly::make_rng(921853233);
+    let rng = &mut rng;
+    let mut clock = time::FakeClock::default();
+    let chain = Arc::new(data::Chain::make(&mut clock, rng, 10));
+    let genesis_id = chain.genesis_id.clone();
+
+    let mut pms = vec![];
+    for _ in 0..3 {
+        let cfg = chain.make_config(rng);
+        pms.push(
+            crate::peer_manager::testonly::start(
+                clock.clock(),
+                near_store::db::TestDB::new(),
+                cfg,
+                chain.clone(),
+            )
+            .await,
+        );
+    }
+    // Connect the nodes in a line, so that the crawler needs to learn about
+    // the last node from the middle one.
+    pms[0].connect_to(&pms[1].peer_info(), tcp::Tier::T2).await;
+    pms[1].connect_to(&pms[2].peer_info(), tcp::Tier::T2).await;
+
+    let cfg = raw::CrawlConfig {
+        chain_id: genesis_id.chain_id.clone(),
+        genesis_hash: genesis_id.hash,
+        protocol_version: None,
+        max_nodes: 10,
+        concurrency: 2,
+        timeout: time::Duration::seconds(5),
+    };
+    let graph = raw::crawl(&clock.clock(), &cfg, vec![pms[0].peer_info()]).await;
+
+    let ids: Vec<_> = pms.iter().map(|pm| pm.cfg.node_id()).collect();
+    let mut want_ids = ids.clone();
+    want_ids.sort();
+    assert_eq!(graph.nodes.iter().map(|n| n.peer_id.clone()).collect::<Vec<_>>(), want_ids);
+    for node in &graph.nodes {
+        assert!(node.reachable(), "{node:?}");
+        assert_eq!(node.error, None);
+        assert_eq!(node.handshake.as_ref().unwrap().height, chain.height());
+    }
+    let mut want_edges: Vec<_> = [(&ids[0], &ids[1]), (&ids[1], &ids[2])]
+        .map(|(a, b)| if a < b { (a, b) } else { (b, a) })
+        .into();
+    want_edges.sort();
+    assert_eq!(graph.edges().into_iter().collect::<Vec<_>>(), want_edges);
+    assert_eq!(graph.to_dot().matches(" -- ").count(), 2);
+}
+
+#[tokio::test]
+async fn test_replay_capture() {
+    init_test_logger();
+    let mut rng = testonly::make_rng(33955575545);
+    let rng = &mut rng;
+    let mut clock = time::FakeClock::default();
+    let chain = Arc::new(data::Chain::make(&mut clock, rng, 10));
+
+    let cfg = chain.make_config(rng);
+    let peer_id = cfg.node_id();
+    let addr = **cfg.node_addr.as_ref().unwrap();
+    let genesis_id = chain.genesis_id.clone();
+    let pm = crate::peer_manager::testonly::start(
+        clock.clock(),
+        near_store::db::TestDB::new(),
+        cfg,
+        chain.clone(),
+    )
+    .await;
+    let mut events = pm.events.from_now();
+
+    // A captured connection: the handshake, a few state part requests and a frame
+    // which didn't parse.
+    let dir = tempfile::tempdir().unwrap();
+    let writer =
+        capture::CaptureWriter::create(dir.path(), addr, clock.now_utc(), u64::MAX).unwrap();
+    let num_parts = 5;
+    let block_hash = CryptoHash::new();
+    let mut msgs = vec![PeerMessage::Tier2Handshake(data::make_handshake(rng, &chain))];
+    msgs.extend(
+        (0..num_parts)
+            .map(|part_id| PeerMessage::StateRequestPart(ShardId::new(0), block_hash, part_id)),
+    );
+    for msg in msgs {
+        clock.advance(time::Duration::SECOND);
+        let bytes = msg.serialize(Encoding::Proto);
+        writer
+            .write(capture::Frame {
+                timestamp: clock.now_utc(),
+                encoding: Some(Encoding::Proto),
+                bytes,
+            })
+            .unwrap();
+    }
+    writer
+        .write(capture::Frame { timestamp: clock.now_utc(), encoding: None, bytes: vec![0xff; 3] })
+        .unwrap();
+    let path = writer.path().to_path_buf();
+    writer.finish().unwrap();
+
+    let mut conn = raw::Connection::connect(
+        &clock.clock(),
+        addr,
+        peer_id.clone(),
+        None,
+        &genesis_id.chain_id,
+        genesis_id.hash,
+        0,
+        vec![ShardId::new(0)],
+        Some(time::Duration::SECOND),
+    )
+    .await
+    .unwrap();
+    let frames = capture::CaptureReader::open(&path).unwrap();
+    let stats = capture::replay(&clock.clock(), &mut conn, frames, capture::Pacing::Lockstep)
+        .await
+        .unwrap();
+    assert_eq!(
+        stats,
+        capture::ReplayStats { sent: num_parts as usize + 1, unparsed: 1, skipped: 1 }
+    );
+
+    // The node has received the requests in the captured order.
+    for want_part_id in 0..num_parts {
+        let part_id = events
+            .recv_until(|ev| match ev {
+                Event::Client(ClientSenderForNetworkInput::_state_request_part(msg)) => {
+                    Some(msg.part_id)
+                }
+                _ => None,
+            })
+            .await;
+        assert_eq!(part_id, want_part_id);
+    }
+}
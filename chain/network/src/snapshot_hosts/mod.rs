@@ -55,10 +55,16 @@ pub(crate) fn priority_score(peer_id: &PeerId, shard_id: ShardId, part_id: u64)
     h.finalize().into()
 }
 
+/// Hosts whose reputation scores fall into the same tier of this width are
+/// considered equally good for serving state parts.
+const REPUTATION_TIER_WIDTH: u32 = 250;
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 struct StatePartHost {
     /// A peer host for some desired state part
     peer_id: PeerId,
+    /// Reputation tier of the host at the time it was added to the selector
+    reputation_tier: u32,
     /// Priority score computed over the peer_id, shard_id, and part_id
     score: [u8; 32],
     /// The number of times we have already queried this host for this part
@@ -70,11 +76,15 @@ struct StatePartHost {
 impl Ord for StatePartHost {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
         // std::collections:BinaryHeap used in PeerPartSelector is a max-heap.
-        // We prefer hosts with the least num_requests, after which we break
-        // ties according to the priority score and the peer_id.
+        // We prefer hosts with the least num_requests, then the hosts with
+        // a better reputation, after which we break ties according to the
+        // priority score and the peer_id. Reputations are local to the node,
+        // hence the coarse tiers: within a tier, nodes keep agreeing on which
+        // hosts serve which parts.
         self.num_requests
             .cmp(&other.num_requests)
             .reverse()
+            .then_with(|| self.reputation_tier.cmp(&other.reputation_tier))
             .then_with(|| self.score.cmp(&other.score))
             .then_with(|| self.peer_id.cmp(&other.peer_id))
     }
@@ -139,6 +149,9 @@ struct Inner {
     peer_selector: HashMap<(ShardId, u64), PartPeerSelector>,
     /// Batch size for populating the peer_selector from the hosts
     part_selection_cache_batch_size: usize,
+    /// The host from which each part has been requested most recently,
+    /// until it serves the part
+    pending_part_requests: HashMap<(ShardId, u64), PeerId>,
 }
 
 impl Inner {
@@ -176,6 +189,7 @@ impl Inner {
             self.sync_hash = Some(*sync_hash);
             self.hosts_for_shard.clear();
             self.peer_selector.clear();
+            self.pending_part_requests.clear();
 
             for (peer_id, info) in &self.hosts {
                 if info.sync_hash == *sync_hash {
@@ -209,6 +223,7 @@ impl Inner {
         sync_hash: &CryptoHash,
         shard_id: ShardId,
         part_id: u64,
+        reputation: impl Fn(&PeerId) -> u32,
     ) -> Option<PeerId> {
         self.maybe_update_sync_hash(sync_hash);
 
@@ -231,6 +246,7 @@ impl Inner {
                 // Wrap entries with `Reverse` so that we pop the *least* desirable options
                 new_peers.push(std::cmp::Reverse(StatePartHost {
                     peer_id: peer_id.clone(),
+                    reputation_tier: reputation(peer_id) / REPUTATION_TIER_WIDTH,
                     score,
                     num_requests: 0,
                 }));
@@ -244,6 +260,9 @@ impl Inner {
         }
 
         let res = selector.next();
+        if let Some(peer_id) = &res {
+            self.pending_part_requests.insert((shard_id, part_id), peer_id.clone());
+        }
         res
     }
 }
@@ -260,6 +279,7 @@ impl SnapshotHostsCache {
             hosts_for_shard: HashMap::new(),
             peer_selector: HashMap::new(),
             part_selection_cache_batch_size: config.part_selection_cache_batch_size as usize,
+            pending_part_requests: HashMap::new(),
         }))
     }
 
@@ -349,13 +369,36 @@ impl SnapshotHostsCache {
     }
 
     /// Given a state part request, selects a peer host to which the request should be sent.
+    /// `reputation` returns the reputation score of a host, see `PeerStore::reputation_score`.
     pub fn select_host_for_part(
         &self,
         sync_hash: &CryptoHash,
         shard_id: ShardId,
         part_id: u64,
+        reputation: impl Fn(&PeerId) -> u32,
     ) -> Option<PeerId> {
-        self.0.lock().select_host_for_part(sync_hash, shard_id, part_id)
+        self.0.lock().select_host_for_part(sync_hash, shard_id, part_id, reputation)
+    }
+
+    /// Returns the host from which the part has been requested and which hasn't served it yet.
+    pub fn take_pending_part_request(&self, shard_id: ShardId, part_id: u64) -> Option<PeerId> {
+        self.0.lock().pending_part_requests.remove(&(shard_id, part_id))
+    }
+
+    /// Triggered when a state part has been received from a peer.
+    /// Returns whether the part has been requested from that peer.
+    pub fn part_response_received(
+        &self,
+        shard_id: ShardId,
+        part_id: u64,
+        peer_id: &PeerId,
+    ) -> bool {
+        let mut inner = self.0.lock();
+        if inner.pending_part_requests.get(&(shard_id, part_id)) != Some(peer_id) {
+            return false;
+        }
+        inner.pending_part_requests.remove(&(shard_id, part_id));
+        true
     }
 
     /// Triggered by state sync actor after processing a state part.
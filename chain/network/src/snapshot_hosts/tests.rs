@@ -330,7 +330,7 @@ async fn run_select_peer_test(
                 assert!(err.is_none());
             }
             SelectPeerAction::CallSelect(wanted) => {
-                let peer = cache.select_host_for_part(sync_hash, ShardId::new(0), part_id);
+                let peer = cache.select_host_for_part(sync_hash, ShardId::new(0), part_id, |_| 0);
                 let wanted = match wanted {
                     Some(idx) => Some(&peers[*idx].peer_id),
                     None => None,
@@ -387,3 +387,52 @@ async fn test_select_peer() {
         .await;
     }
 }
+
+#[tokio::test]
+async fn test_select_peer_prefers_reputation() {
+    init_test_logger();
+    let mut rng = make_rng(2947294234);
+    let sync_hash = CryptoHash(rng.r#gen());
+    let shard_id = ShardId::new(0);
+    let part_id = 0;
+
+    let keys = [data::make_secret_key(&mut rng), data::make_secret_key(&mut rng)];
+    let peers: Vec<_> = keys
+        .iter()
+        .map(|key| {
+            Arc::new(SnapshotHostInfo::new(
+                PeerId::new(key.public_key()),
+                sync_hash,
+                123,
+                vec![shard_id],
+                key,
+            ))
+        })
+        .collect();
+    // Give the better reputation to the host with the worse priority score.
+    let (preferred, other) = if priority_score(&peers[0].peer_id, shard_id, part_id)
+        < priority_score(&peers[1].peer_id, shard_id, part_id)
+    {
+        (peers[0].peer_id.clone(), peers[1].peer_id.clone())
+    } else {
+        (peers[1].peer_id.clone(), peers[0].peer_id.clone())
+    };
+    let reputation = |peer_id: &PeerId| if peer_id == &preferred { 1000 } else { 0 };
+
+    let config = Config { snapshot_hosts_cache_size: 2, part_selection_cache_batch_size: 2 };
+    let cache = SnapshotHostsCache::new(config);
+    let (_res, err) = cache.insert(peers.clone()).await;
+    assert!(err.is_none());
+
+    let host = cache.select_host_for_part(&sync_hash, shard_id, part_id, reputation);
+    assert_eq!(host.as_ref(), Some(&preferred));
+    // The request is pending until the host serves the part.
+    assert!(!cache.part_response_received(shard_id, part_id, &other));
+    assert!(cache.part_response_received(shard_id, part_id, &preferred));
+    assert_eq!(cache.take_pending_part_request(shard_id, part_id), None);
+
+    // Once the preferred host has been tried, the other one is used.
+    let host = cache.select_host_for_part(&sync_hash, shard_id, part_id, reputation);
+    assert_eq!(host.as_ref(), Some(&other));
+    assert_eq!(cache.take_pending_part_request(shard_id, part_id), Some(other));
+}
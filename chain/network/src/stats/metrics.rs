@@ -204,6 +204,48 @@ pub(crate) static PEER_MESSAGE_RATE_LIMITED_BY_TYPE_TOTAL: LazyLock<IntCounterVe
         )
         .unwrap()
     });
+pub(crate) static PEER_MESSAGE_SENT_BY_CLASS_BYTES: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    try_create_int_counter_vec(
+        "near_peer_message_sent_by_class_bytes",
+        "Total data sent to peers by message classes",
+        &["class"],
+    )
+    .unwrap()
+});
+pub(crate) static PEER_MESSAGE_SENT_BY_CLASS_TOTAL: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    try_create_int_counter_vec(
+        "near_peer_message_sent_by_class_total",
+        "Number of messages sent to peers by message classes",
+        &["class"],
+    )
+    .unwrap()
+});
+pub(crate) static PEER_SEND_QUEUE_BY_CLASS_BYTES: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    try_create_int_gauge_vec(
+        "near_peer_send_queue_by_class_bytes",
+        "Size of the messages waiting to be sent to peers by message classes",
+        &["class"],
+    )
+    .unwrap()
+});
+pub(crate) static PEER_SEND_QUEUE_DELAY_BY_CLASS: LazyLock<HistogramVec> = LazyLock::new(|| {
+    try_create_histogram_vec(
+        "near_peer_send_queue_delay_by_class",
+        "Time in seconds messages wait in the send queue by message classes",
+        &["class"],
+        Some(exponential_buckets(0.0001, 2., 18).unwrap()),
+    )
+    .unwrap()
+});
+pub(crate) static PEER_SEND_THROTTLED_BY_CLASS_TOTAL: LazyLock<IntCounterVec> =
+    LazyLock::new(|| {
+        try_create_int_counter_vec(
+            "near_peer_send_throttled_by_class_total",
+            "Number of times sending a message was delayed by a byte rate limit by message classes",
+            &["class"],
+        )
+        .unwrap()
+    });
 pub(crate) static SYNC_ACCOUNTS_DATA: LazyLock<IntCounterVec> = LazyLock::new(|| {
     try_create_int_counter_vec(
         "near_sync_accounts_data",
@@ -1,8 +1,9 @@
 /// Store module defines atomic DB operations on top of schema module.
 /// All transactions should be implemented within this module,
 /// in particular schema::StoreUpdate is not exported.
+use crate::peer_manager::peer_store::reputation::PeerReputation;
 use crate::types::ConnectionInfo;
-use near_primitives::network::AnnounceAccount;
+use near_primitives::network::{AnnounceAccount, PeerId};
 use near_primitives::types::AccountId;
 use std::sync::Arc;
 
@@ -78,6 +79,28 @@ impl Store {
     }
 }
 
+// PeerStore reputations storage.
+impl Store {
+    #[tracing::instrument(
+        target = "network::store",
+        level = "trace",
+        "Store::set_peer_reputations",
+        skip_all
+    )]
+    pub fn set_peer_reputations(
+        &mut self,
+        reputations: &Vec<(PeerId, PeerReputation)>,
+    ) -> Result<(), Error> {
+        let mut update = self.0.new_update();
+        update.set::<schema::PeerReputations>(&(), reputations);
+        self.0.commit(update).map_err(Error)
+    }
+
+    pub fn get_peer_reputations(&self) -> Vec<(PeerId, PeerReputation)> {
+        self.0.get::<schema::PeerReputations>(&()).unwrap_or(Some(vec![])).unwrap_or(vec![])
+    }
+}
+
 impl From<Arc<dyn near_store::db::Database>> for Store {
     fn from(store: Arc<dyn near_store::db::Database>) -> Self {
         Self(schema::Store::from(store))
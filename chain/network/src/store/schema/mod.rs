@@ -1,3 +1,4 @@
+use crate::peer_manager::peer_store::reputation::PeerReputation;
 use crate::types as primitives;
 /// Schema module defines a type-safe access to the DB.
 /// It is a concise definition of key and value types
@@ -58,6 +59,53 @@ impl BorshRepr for ConnectionInfoRepr {
     }
 }
 
+/// A Borsh representation of the (PeerId, PeerReputation) pair.
+#[derive(BorshSerialize, BorshDeserialize, ProtocolSchema)]
+pub(super) struct PeerReputationRepr {
+    peer_id: PeerId,
+    latency_nanos: Option<u64>,
+    useful_messages: u64,
+    useless_messages: u64,
+    state_parts_served: u64,
+    state_parts_failed: u64,
+    ban_penalty: u32,
+    /// UNIX timestamp in nanos.
+    ban_penalty_updated: Option<u64>,
+}
+
+impl BorshRepr for PeerReputationRepr {
+    type T = (PeerId, PeerReputation);
+    fn to_repr((peer_id, r): &(PeerId, PeerReputation)) -> Self {
+        Self {
+            peer_id: peer_id.clone(),
+            latency_nanos: r.latency.map(|latency| latency.whole_nanoseconds() as u64),
+            useful_messages: r.useful_messages,
+            useless_messages: r.useless_messages,
+            state_parts_served: r.state_parts_served,
+            state_parts_failed: r.state_parts_failed,
+            ban_penalty: r.ban_penalty,
+            ban_penalty_updated: r.ban_penalty_updated.map(|t| t.unix_timestamp_nanos() as u64),
+        }
+    }
+
+    fn from_repr(s: Self) -> Result<(PeerId, PeerReputation), Error> {
+        let reputation = PeerReputation {
+            latency: s.latency_nanos.map(|nanos| time::Duration::nanoseconds(nanos as i64)),
+            useful_messages: s.useful_messages,
+            useless_messages: s.useless_messages,
+            state_parts_served: s.state_parts_served,
+            state_parts_failed: s.state_parts_failed,
+            ban_penalty: s.ban_penalty,
+            ban_penalty_updated: s
+                .ban_penalty_updated
+                .map(|t| time::Utc::from_unix_timestamp_nanos(t as i128))
+                .transpose()
+                .map_err(invalid_data)?,
+        };
+        Ok((s.peer_id, reputation))
+    }
+}
+
 #[derive(BorshSerialize, BorshDeserialize, ProtocolSchema)]
 pub(super) struct EdgeRepr {
     key: (PeerId, PeerId),
@@ -102,6 +150,13 @@ impl Column for RecentOutboundConnections {
     type Value = Vec<ConnectionInfoRepr>;
 }
 
+pub(super) struct PeerReputations;
+impl Column for PeerReputations {
+    const COL: DBCol = DBCol::PeerReputations;
+    type Key = Borsh<()>;
+    type Value = Vec<PeerReputationRepr>;
+}
+
 ////////////////////////////////////////////////////
 // Storage
 
@@ -5,6 +5,9 @@ use near_primitives::network::PeerId;
 use parking_lot::Mutex;
 use std::collections::HashMap;
 use std::fmt;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
 
 const LISTENER_BACKLOG: u32 = 128;
 
@@ -14,6 +17,14 @@ pub(crate) static RESERVED_LISTENER_ADDRS: std::sync::LazyLock<
     Mutex<HashMap<std::net::SocketAddr, tokio::net::TcpSocket>>,
 > = std::sync::LazyLock::new(|| Mutex::new(HashMap::new()));
 
+/// TEST-ONLY: listener addresses of the nodes of the simulated networks.
+/// ListenerAddr::listener() for these addresses accepts connections from the simulated
+/// network instead of opening a TCP listener. See `crate::testonly::sim`.
+#[cfg(test)]
+pub(crate) static SIMULATED_LISTENER_ADDRS: std::sync::LazyLock<
+    Mutex<HashMap<std::net::SocketAddr, std::sync::Weak<crate::testonly::sim::Network>>>,
+> = std::sync::LazyLock::new(|| Mutex::new(HashMap::new()));
+
 /// TCP connections established by a node belong to different logical networks (aka tiers),
 /// which serve different purpose.
 // TODO(gprusak): add a link to the design on github docs (but first write those docs).
@@ -33,15 +44,84 @@ pub enum Tier {
     T3,
 }
 
+/// Transport protocol of the connections of a tier.
+/// QUIC is supported only by TIER1, see `crate::quic`.
+#[derive(
+    serde::Serialize, serde::Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Hash,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Transport {
+    #[default]
+    Tcp,
+    Quic,
+}
+
 #[derive(Clone, Debug)]
 pub(crate) enum StreamType {
     Inbound,
     Outbound { peer_id: PeerId, tier: Tier },
 }
 
+/// TEST-ONLY: a byte stream which can be used by `Stream` in place of a TCP connection.
+#[cfg(test)]
+pub(crate) trait SimulatedConn: AsyncRead + AsyncWrite + Send + Unpin + fmt::Debug {}
+
+/// Byte stream of a `Stream`.
+#[derive(Debug)]
+pub(crate) enum Conn {
+    Tcp(tokio::net::TcpStream),
+    /// TEST-ONLY: connection over a simulated network, see `crate::testonly::sim`.
+    #[cfg(test)]
+    Simulated(Box<dyn SimulatedConn>),
+}
+
+impl AsyncRead for Conn {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        match self.get_mut() {
+            Conn::Tcp(stream) => Pin::new(stream).poll_read(cx, buf),
+            #[cfg(test)]
+            Conn::Simulated(conn) => Pin::new(conn).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for Conn {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        match self.get_mut() {
+            Conn::Tcp(stream) => Pin::new(stream).poll_write(cx, buf),
+            #[cfg(test)]
+            Conn::Simulated(conn) => Pin::new(conn).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        match self.get_mut() {
+            Conn::Tcp(stream) => Pin::new(stream).poll_flush(cx),
+            #[cfg(test)]
+            Conn::Simulated(conn) => Pin::new(conn).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        match self.get_mut() {
+            Conn::Tcp(stream) => Pin::new(stream).poll_shutdown(cx),
+            #[cfg(test)]
+            Conn::Simulated(conn) => Pin::new(conn).poll_shutdown(cx),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Stream {
-    pub(crate) stream: tokio::net::TcpStream,
+    pub(crate) stream: Conn,
     pub(crate) type_: StreamType,
     /// cached stream.local_addr()
     pub(crate) local_addr: std::net::SocketAddr,
@@ -62,6 +142,19 @@ pub(crate) struct StreamId {
     outbound: std::net::SocketAddr,
 }
 
+impl StreamId {
+    pub(crate) fn new(
+        type_: &StreamType,
+        local_addr: std::net::SocketAddr,
+        peer_addr: std::net::SocketAddr,
+    ) -> Self {
+        match type_ {
+            StreamType::Inbound => Self { inbound: local_addr, outbound: peer_addr },
+            StreamType::Outbound { .. } => Self { inbound: peer_addr, outbound: local_addr },
+        }
+    }
+}
+
 #[cfg(test)]
 pub(crate) struct Socket(tokio::net::TcpSocket);
 
@@ -88,7 +181,12 @@ impl Stream {
         if let Err(err) = stream.set_nodelay(true) {
             tracing::warn!(target: "network", "Failed to set TCP_NODELAY: {}", err);
         }
-        Ok(Self { peer_addr: stream.peer_addr()?, local_addr: stream.local_addr()?, stream, type_ })
+        Ok(Self {
+            peer_addr: stream.peer_addr()?,
+            local_addr: stream.local_addr()?,
+            stream: Conn::Tcp(stream),
+            type_,
+        })
     }
 
     pub async fn connect(
@@ -96,6 +194,10 @@ impl Stream {
         tier: Tier,
         socket_options: &SocketOptions,
     ) -> anyhow::Result<Stream> {
+        #[cfg(test)]
+        if let Some(endpoint) = &socket_options.simulated {
+            return endpoint.connect(peer_info, tier).await;
+        }
         let addr = peer_info
             .addr
             .ok_or_else(|| anyhow!("Trying to connect to peer with no public address"))?;
@@ -150,12 +252,7 @@ impl Stream {
 
     // TEST-ONLY used in reporting test events.
     pub(crate) fn id(&self) -> StreamId {
-        match self.type_ {
-            StreamType::Inbound => StreamId { inbound: self.local_addr, outbound: self.peer_addr },
-            StreamType::Outbound { .. } => {
-                StreamId { inbound: self.peer_addr, outbound: self.local_addr }
-            }
-        }
+        StreamId::new(&self.type_, self.local_addr, self.peer_addr)
     }
 }
 
@@ -226,11 +323,22 @@ impl ListenerAddr {
 
     /// Constructs a std::net::TcpListener, for usage outside of near_network.
     pub fn std_listener(&self) -> std::io::Result<std::net::TcpListener> {
-        self.listener()?.0.into_std()
+        self.tcp_listener()?.into_std()
     }
 
     /// Constructs a Listener out of ListenerAddr.
     pub(crate) fn listener(&self) -> std::io::Result<Listener> {
+        #[cfg(test)]
+        if let Some(network) = SIMULATED_LISTENER_ADDRS.lock().get(&self.0) {
+            let network = network.upgrade().ok_or_else(|| {
+                std::io::Error::new(std::io::ErrorKind::AddrNotAvailable, "network dropped")
+            })?;
+            return Ok(Listener::Simulated(network.listen(self.0)));
+        }
+        Ok(Listener::Tcp(self.tcp_listener()?))
+    }
+
+    fn tcp_listener(&self) -> std::io::Result<tokio::net::TcpListener> {
         let socket = match &self.0 {
             std::net::SocketAddr::V4(_) => tokio::net::TcpSocket::new_v4()?,
             std::net::SocketAddr::V6(_) => tokio::net::TcpSocket::new_v6()?,
@@ -240,7 +348,7 @@ impl ListenerAddr {
         }
         socket.set_reuseaddr(true)?;
         socket.bind(self.0)?;
-        Ok(Listener(socket.listen(LISTENER_BACKLOG)?))
+        socket.listen(LISTENER_BACKLOG)
     }
 
     pub(crate) fn is_ipv4(&self) -> bool {
@@ -248,11 +356,22 @@ impl ListenerAddr {
     }
 }
 
-pub(crate) struct Listener(tokio::net::TcpListener);
+pub(crate) enum Listener {
+    Tcp(tokio::net::TcpListener),
+    /// TEST-ONLY: accepts the connections of a simulated network.
+    #[cfg(test)]
+    Simulated(crate::testonly::sim::Listener),
+}
 
 impl Listener {
     pub async fn accept(&self) -> std::io::Result<Stream> {
-        let (stream, _) = self.0.accept().await?;
-        Stream::new(stream, StreamType::Inbound)
+        match self {
+            Listener::Tcp(listener) => {
+                let (stream, _) = listener.accept().await?;
+                Stream::new(stream, StreamType::Inbound)
+            }
+            #[cfg(test)]
+            Listener::Simulated(listener) => Ok(listener.accept().await),
+        }
     }
 }
@@ -6,6 +6,7 @@ use std::fmt::Debug;
 use std::hash::Hash;
 
 pub use super::actix;
+pub(crate) mod sim;
 pub mod stream;
 
 pub type Rng = rand_xorshift::XorShiftRng;
@@ -0,0 +1,445 @@
+//! Simulated network for the multi-node tests.
+//!
+//! Nodes of a simulated network talk to each other over in-memory connections plugged into
+//! `tcp::Stream` (see `tcp::SimulatedConn`), which model the latency, bandwidth and segment loss
+//! of every link, as well as network partitions which heal after a while. The conditions are
+//! described by a `Topology` file.
+//!
+//! All the delays are measured with a `FakeClock`, and the segment losses are drawn from
+//! a seeded rng, so the delivery time of every write depends only on the time of the write.
+//! `Network::run` drives the fake clock: whenever the nodes are idle, it advances the clock
+//! to the next delivery.
+//!
+//! Limitation: the nodes run on real threads, so idleness can't be proven. A node counts as idle
+//! once nothing was written to or read from the network for `IDLE_PERIOD` of real time. Work
+//! which doesn't touch the network for longer than that, e.g. processing of an already received
+//! message on a loaded machine, may observe the clock jump forward. The order of deliveries
+//! doesn't depend on it, but the time at which a node reacts does, so tests should wait for
+//! events rather than assert exact times.
+//!
+//! Usage:
+//! * `Network::new` with a topology,
+//! * `Network::configure` the `NetworkConfig` of every node before starting it,
+//! * execute the test steps waiting for the network within `Network::run`.
+use crate::config::NetworkConfig;
+use crate::network_protocol::PeerInfo;
+use crate::tcp;
+use crate::testonly::make_rng;
+use anyhow::{Context as _, anyhow};
+use futures::FutureExt as _;
+use futures::future::BoxFuture;
+use near_async::time;
+use parking_lot::Mutex;
+use rand::Rng as _;
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Weak};
+use std::task::{Context, Poll, Waker};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+mod topology;
+
+#[cfg(test)]
+mod tests;
+
+pub(crate) use topology::{Link, Topology};
+
+/// Real time without any network activity after which `Network::run` considers the nodes idle
+/// and advances the fake clock.
+const IDLE_PERIOD: std::time::Duration = std::time::Duration::from_millis(10);
+
+pub(crate) struct Network {
+    clock: time::FakeClock,
+    /// Creation time of the network, the partitions are scheduled relative to it.
+    start: time::Instant,
+    topology: Topology,
+    seed: u64,
+    /// Number of reads, writes and connections so far, used to detect that the nodes are idle.
+    activity: AtomicU64,
+    inner: Mutex<Inner>,
+}
+
+struct Inner {
+    /// Listener address of every node.
+    addrs: Vec<Option<SocketAddr>>,
+    /// Accept queues of the listening nodes.
+    listeners: HashMap<SocketAddr, tokio::sync::mpsc::UnboundedSender<tcp::Stream>>,
+    /// Directions of all the connections.
+    pipes: Vec<Weak<Mutex<Pipe>>>,
+    /// Deadlines of the connections being established.
+    timers: Vec<time::Instant>,
+    /// Port of the next outbound connection.
+    next_port: u16,
+}
+
+/// Allows a node to establish connections over the simulated network,
+/// see `config::SocketOptions::simulated`.
+#[derive(Clone)]
+pub(crate) struct Endpoint {
+    network: Arc<Network>,
+    node: usize,
+}
+
+/// Accepts the inbound connections of a node, see `tcp::Listener`.
+pub(crate) struct Listener(tokio::sync::Mutex<tokio::sync::mpsc::UnboundedReceiver<tcp::Stream>>);
+
+impl Network {
+    pub fn new(clock: &time::FakeClock, topology: Topology, seed: u64) -> Arc<Self> {
+        Arc::new(Self {
+            clock: clock.clone(),
+            start: clock.now(),
+            inner: Mutex::new(Inner {
+                addrs: vec![None; topology.nodes.len()],
+                listeners: HashMap::new(),
+                pipes: vec![],
+                timers: vec![],
+                next_port: 1,
+            }),
+            topology,
+            seed,
+            activity: AtomicU64::new(0),
+        })
+    }
+
+    /// Makes the node `name` of the topology use `cfg`. The node is reachable at
+    /// `cfg.node_addr` and establishes all its connections over the simulated network.
+    pub fn configure(self: &Arc<Self>, name: &str, cfg: &mut NetworkConfig) {
+        let node = self.topology.node(name).unwrap_or_else(|| panic!("unknown node {name}"));
+        let addr = **cfg.node_addr.as_ref().expect("node_addr is required");
+        self.inner.lock().addrs[node] = Some(addr);
+        tcp::SIMULATED_LISTENER_ADDRS.lock().insert(addr, Arc::downgrade(self));
+        cfg.socket_options.simulated = Some(Endpoint { network: self.clone(), node });
+    }
+
+    pub(crate) fn listen(&self, addr: SocketAddr) -> Listener {
+        let (send, recv) = tokio::sync::mpsc::unbounded_channel();
+        self.inner.lock().listeners.insert(addr, send);
+        Listener(tokio::sync::Mutex::new(recv))
+    }
+
+    fn node_at(&self, addr: SocketAddr) -> Option<usize> {
+        self.inner.lock().addrs.iter().position(|a| *a == Some(addr))
+    }
+
+    /// Earliest time in the future at which some data will be delivered
+    /// or a connection will be established.
+    pub fn next_deadline(&self) -> Option<time::Instant> {
+        let now = self.clock.now();
+        let mut inner = self.inner.lock();
+        inner.timers.retain(|t| *t > now);
+        inner.pipes.retain(|p| p.strong_count() > 0);
+        let deliveries = inner.pipes.iter().filter_map(|p| {
+            let pipe = p.upgrade()?;
+            let pipe = pipe.lock();
+            pipe.segments.iter().filter_map(|s| s.delivery).find(|t| *t > now)
+        });
+        deliveries.chain(inner.timers.iter().copied()).min()
+    }
+
+    /// Runs `fut` to completion. Whenever the nodes didn't touch the network for
+    /// `IDLE_PERIOD`, the fake clock is advanced to the `next_deadline()`.
+    /// See the module documentation for the limitations.
+    pub async fn run<T>(&self, fut: impl Future<Output = T>) -> T {
+        let mut fut = std::pin::pin!(fut);
+        let mut activity = self.activity.load(Ordering::SeqCst);
+        loop {
+            tokio::select! {
+                res = &mut fut => return res,
+                () = tokio::time::sleep(IDLE_PERIOD) => {
+                    let prev = std::mem::replace(&mut activity, self.activity.load(Ordering::SeqCst));
+                    if prev != activity {
+                        continue;
+                    }
+                    if let Some(t) = self.next_deadline() {
+                        self.clock.advance_until(t);
+                    }
+                }
+            }
+        }
+    }
+
+    fn record_activity(&self) {
+        self.activity.fetch_add(1, Ordering::SeqCst);
+    }
+
+    async fn sleep_until(&self, t: time::Instant) {
+        self.inner.lock().timers.push(t);
+        self.clock.clock().sleep_until(t).await;
+    }
+
+    /// If the link between nodes `a` and `b` is partitioned at `t`, returns the time
+    /// when it heals, or `None` if it never does.
+    fn partitioned(&self, a: usize, b: usize, t: time::Instant) -> Option<Option<time::Instant>> {
+        self.topology.partitioned(a, b, t - self.start).map(|end| end.map(|end| self.start + end))
+    }
+
+    /// Creates both ends of a connection between nodes `a` and `b`.
+    fn new_conn(self: &Arc<Self>, a: usize, b: usize) -> (Conn, Conn) {
+        let link = self.topology.link(a, b).clone();
+        let mut inner = self.inner.lock();
+        let mut new_pipe = || {
+            let seed = self.seed.wrapping_add(inner.pipes.len() as u64);
+            let pipe = Arc::new(Mutex::new(Pipe {
+                segments: VecDeque::new(),
+                busy_until: self.clock.now(),
+                closed: false,
+                reader_closed: false,
+                reader: None,
+                rng: make_rng(seed),
+            }));
+            inner.pipes.push(Arc::downgrade(&pipe));
+            pipe
+        };
+        let (ab, ba) = (new_pipe(), new_pipe());
+        let conn = |from, to, read: &Arc<_>, write: &Arc<_>| Conn {
+            network: self.clone(),
+            from,
+            to,
+            link: link.clone(),
+            read: Arc::clone(read),
+            write: Arc::clone(write),
+            sleep: None,
+        };
+        (conn(a, b, &ba, &ab), conn(b, a, &ab, &ba))
+    }
+}
+
+impl Drop for Network {
+    fn drop(&mut self) {
+        let mut listeners = tcp::SIMULATED_LISTENER_ADDRS.lock();
+        for addr in self.inner.get_mut().addrs.iter().flatten() {
+            listeners.remove(addr);
+        }
+    }
+}
+
+impl Endpoint {
+    /// Establishes a connection to `peer_info`, equivalent of `tcp::Stream::connect`.
+    /// It takes a round trip and fails if the link is partitioned.
+    pub async fn connect(
+        &self,
+        peer_info: &PeerInfo,
+        tier: tcp::Tier,
+    ) -> anyhow::Result<tcp::Stream> {
+        let network = &self.network;
+        let peer_addr = peer_info
+            .addr
+            .ok_or_else(|| anyhow!("Trying to connect to peer with no public address"))?;
+        let peer = network.node_at(peer_addr).context("connection refused")?;
+        network.record_activity();
+        let now = network.clock.now();
+        if network.partitioned(self.node, peer, now).is_some() {
+            anyhow::bail!("network partitioned");
+        }
+        network.sleep_until(now + network.topology.link(self.node, peer).latency() * 2).await;
+
+        let (outbound, inbound) = network.new_conn(self.node, peer);
+        let local_addr = {
+            let mut inner = network.inner.lock();
+            let ip = inner.addrs[self.node].context("node not configured")?.ip();
+            let port = inner.next_port;
+            inner.next_port = inner.next_port.checked_add(1).context("out of ports")?;
+            SocketAddr::new(ip, port)
+        };
+        let listener = network.inner.lock().listeners.get(&peer_addr).cloned();
+        listener
+            .context("connection refused")?
+            .send(tcp::Stream {
+                stream: tcp::Conn::Simulated(Box::new(inbound)),
+                type_: tcp::StreamType::Inbound,
+                local_addr: peer_addr,
+                peer_addr: local_addr,
+            })
+            .map_err(|_| anyhow!("connection refused"))?;
+        Ok(tcp::Stream {
+            stream: tcp::Conn::Simulated(Box::new(outbound)),
+            type_: tcp::StreamType::Outbound { peer_id: peer_info.id.clone(), tier },
+            local_addr,
+            peer_addr,
+        })
+    }
+}
+
+impl Listener {
+    /// Waits for the next inbound connection. Never completes once the node
+    /// started listening again on the same address.
+    pub async fn accept(&self) -> tcp::Stream {
+        match self.0.lock().await.recv().await {
+            Some(stream) => stream,
+            None => std::future::pending().await,
+        }
+    }
+}
+
+/// One direction of a connection.
+struct Pipe {
+    /// Segments in the order of delivery.
+    segments: VecDeque<Segment>,
+    /// Time at which the transmission of the last segment finishes.
+    busy_until: time::Instant,
+    /// Whether the writing end has been closed.
+    closed: bool,
+    /// Whether the reading end has been closed.
+    reader_closed: bool,
+    /// Waker of the pending read.
+    reader: Option<Waker>,
+    /// Source of the segment losses.
+    rng: crate::testonly::Rng,
+}
+
+/// Data of a single write.
+struct Segment {
+    /// Delivery time, `None` if the segment is never delivered,
+    /// because the link is partitioned forever.
+    delivery: Option<time::Instant>,
+    data: Vec<u8>,
+    /// Number of bytes already read.
+    offset: usize,
+}
+
+/// End of a connection between the nodes `from` (local) and `to` (remote).
+pub(crate) struct Conn {
+    network: Arc<Network>,
+    from: usize,
+    to: usize,
+    link: Link,
+    read: Arc<Mutex<Pipe>>,
+    write: Arc<Mutex<Pipe>>,
+    /// Timer of the pending read, waiting for the delivery of the next segment.
+    sleep: Option<(time::Instant, BoxFuture<'static, ()>)>,
+}
+
+impl std::fmt::Debug for Conn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let nodes = &self.network.topology.nodes;
+        write!(f, "sim::Conn({} -> {})", nodes[self.from], nodes[self.to])
+    }
+}
+
+impl tcp::SimulatedConn for Conn {}
+
+impl Conn {
+    /// Computes the delivery time of `data` written now and pushes it to the pipe.
+    fn push(&self, data: &[u8]) -> std::io::Result<()> {
+        let network = &self.network;
+        network.record_activity();
+        let now = network.clock.now();
+        let mut pipe = self.write.lock();
+        if pipe.reader_closed {
+            return Err(std::io::ErrorKind::BrokenPipe.into());
+        }
+        pipe.busy_until =
+            std::cmp::max(now, pipe.busy_until) + self.link.transmission_time(data.len());
+        let mut delivery = pipe.busy_until + self.link.latency();
+        while pipe.rng.gen_bool(self.link.loss) {
+            delivery += self.link.retransmission_timeout();
+        }
+        // Segments sent over a partitioned link are delivered once it heals.
+        let mut delivery = Some(delivery);
+        while let Some(t) = delivery {
+            match network.partitioned(self.from, self.to, t) {
+                Some(end) => delivery = end.map(|end| end + self.link.latency()),
+                None => break,
+            }
+        }
+        // Segments are delivered in order.
+        if let Some(last) = pipe.segments.back() {
+            delivery = match (last.delivery, delivery) {
+                (Some(last), Some(t)) => Some(std::cmp::max(last, t)),
+                _ => None,
+            };
+        }
+        pipe.segments.push_back(Segment { delivery, data: data.to_vec(), offset: 0 });
+        if let Some(waker) = pipe.reader.take() {
+            waker.wake();
+        }
+        Ok(())
+    }
+
+    fn close_write(&self) {
+        let mut pipe = self.write.lock();
+        pipe.closed = true;
+        if let Some(waker) = pipe.reader.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl AsyncRead for Conn {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let this = self.get_mut();
+        loop {
+            let now = this.network.clock.now();
+            let mut pipe = this.read.lock();
+            let Some(segment) = pipe.segments.front_mut() else {
+                if pipe.closed {
+                    return Poll::Ready(Ok(()));
+                }
+                pipe.reader = Some(cx.waker().clone());
+                return Poll::Pending;
+            };
+            match segment.delivery {
+                Some(t) if t <= now => {
+                    let n = std::cmp::min(buf.remaining(), segment.data.len() - segment.offset);
+                    buf.put_slice(&segment.data[segment.offset..segment.offset + n]);
+                    segment.offset += n;
+                    if segment.offset == segment.data.len() {
+                        pipe.segments.pop_front();
+                    }
+                    drop(pipe);
+                    this.network.record_activity();
+                    return Poll::Ready(Ok(()));
+                }
+                Some(t) => {
+                    pipe.reader = Some(cx.waker().clone());
+                    drop(pipe);
+                    if this.sleep.as_ref().is_none_or(|(deadline, _)| *deadline != t) {
+                        let clock = this.network.clock.clock();
+                        this.sleep = Some((t, async move { clock.sleep_until(t).await }.boxed()));
+                    }
+                    match this.sleep.as_mut().unwrap().1.poll_unpin(cx) {
+                        Poll::Ready(()) => this.sleep = None,
+                        Poll::Pending => return Poll::Pending,
+                    }
+                }
+                None => {
+                    pipe.reader = Some(cx.waker().clone());
+                    return Poll::Pending;
+                }
+            }
+        }
+    }
+}
+
+impl AsyncWrite for Conn {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        Poll::Ready(self.push(buf).map(|()| buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        self.close_write();
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl Drop for Conn {
+    fn drop(&mut self) {
+        self.close_write();
+        self.read.lock().reader_closed = true;
+    }
+}
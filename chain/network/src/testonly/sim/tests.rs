@@ -0,0 +1,164 @@
+use super::*;
+use crate::network_protocol::PeerInfo;
+use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _};
+
+struct Node {
+    cfg: NetworkConfig,
+    listener: tcp::Listener,
+}
+
+impl Node {
+    fn new(network: &Arc<Network>, name: &str) -> Self {
+        let mut cfg = NetworkConfig::from_seed(name, tcp::ListenerAddr::reserve_for_test());
+        network.configure(name, &mut cfg);
+        let listener = cfg.node_addr.as_ref().unwrap().listener().unwrap();
+        Self { cfg, listener }
+    }
+
+    fn peer_info(&self) -> PeerInfo {
+        PeerInfo::new(self.cfg.node_id(), **self.cfg.node_addr.as_ref().unwrap())
+    }
+
+    async fn connect(&self, to: &Node) -> anyhow::Result<tcp::Stream> {
+        tcp::Stream::connect(&to.peer_info(), tcp::Tier::T2, &self.cfg.socket_options).await
+    }
+}
+
+/// Establishes a connection from `a` to `b`, returns (outbound,inbound).
+async fn connect(network: &Network, a: &Node, b: &Node) -> (tcp::Stream, tcp::Stream) {
+    let (outbound, inbound) =
+        network.run(async { tokio::join!(a.connect(b), b.listener.accept()) }).await;
+    (outbound.unwrap(), inbound.unwrap())
+}
+
+fn ms(ms: i64) -> time::Duration {
+    time::Duration::milliseconds(ms)
+}
+
+/// Delivery times of the segments queued on the first pipe of the network.
+fn deliveries(network: &Network) -> Vec<Option<time::Instant>> {
+    let pipe = network.inner.lock().pipes[0].upgrade().unwrap();
+    let pipe = pipe.lock();
+    pipe.segments.iter().map(|s| s.delivery).collect()
+}
+
+#[test]
+fn test_topology() {
+    let topology: Topology = r#"{
+        "nodes": ["a", "b", "c"],
+        "default_link": {"latency_ms": 50},
+        "links": [{"nodes": ["c", "a"], "latency_ms": 150, "loss": 0.1}],
+        "partitions": [{"start_ms": 1000, "end_ms": 2000, "groups": [["a"], ["b"]]}]
+    }"#
+    .parse()
+    .unwrap();
+    assert_eq!(topology.link(0, 1).latency(), ms(50));
+    assert_eq!(topology.link(0, 2).latency(), ms(150));
+    assert_eq!(topology.link(2, 0).loss, 0.1);
+    assert_eq!(topology.partitioned(0, 1, ms(999)), None);
+    assert_eq!(topology.partitioned(1, 0, ms(1000)), Some(Some(ms(2000))));
+    assert_eq!(topology.partitioned(0, 1, ms(2000)), None);
+    // c is not in any group.
+    assert_eq!(topology.partitioned(0, 2, ms(1500)), None);
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("topology.json");
+    std::fs::write(&path, r#"{"nodes": ["a", "b", "c"]}"#).unwrap();
+    assert_eq!(Topology::from_file(&path).unwrap().nodes, ["a", "b", "c"]);
+    assert!(Topology::from_file(&dir.path().join("missing.json")).is_err());
+
+    for invalid in [
+        r#"{"nodes": ["a", "a"]}"#,
+        r#"{"nodes": ["a"], "links": [{"nodes": ["a", "b"]}]}"#,
+        r#"{"nodes": ["a"], "default_link": {"loss": 1.0}}"#,
+        r#"{"nodes": ["a"], "partitions": [{"start_ms": 5, "end_ms": 5, "groups": []}]}"#,
+        r#"{"nodes": ["a"], "unknown": 1}"#,
+    ] {
+        assert!(invalid.parse::<Topology>().is_err(), "{invalid}");
+    }
+}
+
+#[tokio::test]
+async fn test_latency_and_bandwidth() {
+    let clock = time::FakeClock::default();
+    let topology =
+        r#"{"nodes": ["a", "b"], "default_link": {"latency_ms": 50, "bandwidth_bytes_per_sec": 10000}}"#
+            .parse()
+            .unwrap();
+    let network = Network::new(&clock, topology, 0);
+    let a = Node::new(&network, "a");
+    let b = Node::new(&network, "b");
+
+    // Connecting takes a round trip.
+    let (mut outbound, mut inbound) = connect(&network, &a, &b).await;
+    let t = network.start + ms(100);
+    assert_eq!(clock.now(), t);
+
+    // Transmitting 1000 bytes takes 100ms, the segments queue up.
+    outbound.stream.write_all(&[1; 1000]).await.unwrap();
+    outbound.stream.write_all(&[2; 1000]).await.unwrap();
+    assert_eq!(deliveries(&network), vec![Some(t + ms(150)), Some(t + ms(250))]);
+
+    let mut buf = [0; 1500];
+    network.run(inbound.stream.read_exact(&mut buf)).await.unwrap();
+    assert_eq!(clock.now(), t + ms(250));
+    assert_eq!(buf[999..1001], [1, 2]);
+
+    // Closing the connection is observed after the data is delivered.
+    drop(outbound);
+    let mut rest = vec![];
+    network.run(inbound.stream.read_to_end(&mut rest)).await.unwrap();
+    assert_eq!(rest, [2; 500]);
+}
+
+#[tokio::test]
+async fn test_partition() {
+    let clock = time::FakeClock::default();
+    let topology = r#"{
+        "nodes": ["a", "b"],
+        "default_link": {"latency_ms": 10},
+        "partitions": [{"start_ms": 1000, "end_ms": 2000, "groups": [["a"], ["b"]]}]
+    }"#
+    .parse()
+    .unwrap();
+    let network = Network::new(&clock, topology, 0);
+    let a = Node::new(&network, "a");
+    let b = Node::new(&network, "b");
+    let (mut outbound, mut inbound) = connect(&network, &a, &b).await;
+
+    clock.advance_until(network.start + ms(1500));
+    assert!(a.connect(&b).await.is_err());
+
+    // Data sent over a partitioned link is delivered once it heals.
+    outbound.stream.write_all(b"ping").await.unwrap();
+    assert_eq!(deliveries(&network), vec![Some(network.start + ms(2010))]);
+    let mut buf = [0; 4];
+    network.run(inbound.stream.read_exact(&mut buf)).await.unwrap();
+    assert_eq!(&buf, b"ping");
+    assert_eq!(clock.now(), network.start + ms(2010));
+}
+
+#[tokio::test]
+async fn test_loss_is_deterministic() {
+    let write = |seed| async move {
+        let clock = time::FakeClock::default();
+        let topology = r#"{"nodes": ["a", "b"], "default_link": {"latency_ms": 10, "loss": 0.5}}"#
+            .parse()
+            .unwrap();
+        let network = Network::new(&clock, topology, seed);
+        let a = Node::new(&network, "a");
+        let b = Node::new(&network, "b");
+        let (mut outbound, _inbound) = connect(&network, &a, &b).await;
+        for i in 0..20 {
+            outbound.stream.write_all(&[i]).await.unwrap();
+        }
+        let t = clock.now() + ms(10);
+        deliveries(&network).into_iter().map(|d| d.unwrap() - t).collect::<Vec<_>>()
+    };
+    let delays = write(1).await;
+    assert_eq!(delays, write(1).await);
+    // Lost segments are retransmitted after 200ms and the segments are delivered in order.
+    assert!(delays.iter().any(|d| *d > time::Duration::ZERO));
+    assert!(delays.iter().all(|d| d.whole_milliseconds() % 200 == 0));
+    assert!(delays.is_sorted());
+}
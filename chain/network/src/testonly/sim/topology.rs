@@ -0,0 +1,174 @@
+//! Topology file of a simulated network.
+//!
+//! Example:
+//! ```json
+//! {
+//!   "nodes": ["a", "b", "c"],
+//!   "default_link": {"latency_ms": 50, "bandwidth_bytes_per_sec": 10000000},
+//!   "links": [{"nodes": ["a", "c"], "latency_ms": 150, "loss": 0.01}],
+//!   "partitions": [{"start_ms": 1000, "end_ms": 5000, "groups": [["a", "b"], ["c"]]}]
+//! }
+//! ```
+use anyhow::Context as _;
+use near_async::time;
+use std::collections::HashSet;
+use std::path::Path;
+
+#[derive(serde::Deserialize, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Topology {
+    /// Names of the nodes.
+    pub nodes: Vec<String>,
+    /// Conditions of the links which are not listed in `links`.
+    #[serde(default)]
+    pub default_link: Link,
+    /// Conditions of specific links.
+    #[serde(default)]
+    pub links: Vec<NamedLink>,
+    #[serde(default)]
+    pub partitions: Vec<Partition>,
+}
+
+/// Conditions of a link, the same in both directions.
+#[derive(serde::Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Link {
+    /// One way latency.
+    #[serde(default)]
+    pub latency_ms: u64,
+    /// Bandwidth in each direction. Unlimited if not set.
+    #[serde(default)]
+    pub bandwidth_bytes_per_sec: Option<u64>,
+    /// Probability of losing a segment, which is then retransmitted after
+    /// the retransmission timeout (see `Link::retransmission_timeout`).
+    #[serde(default)]
+    pub loss: f64,
+}
+
+#[derive(serde::Deserialize, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct NamedLink {
+    /// The 2 nodes connected by the link.
+    pub nodes: [String; 2],
+    #[serde(flatten)]
+    pub link: Link,
+}
+
+/// Splits the network into groups of nodes, which cannot communicate with each other
+/// between `start_ms` and `end_ms` (relative to the creation of the network). The data sent
+/// over a partitioned link is delivered once the partition heals. Nodes which are not
+/// listed in any group can communicate with everyone.
+#[derive(serde::Deserialize, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Partition {
+    pub start_ms: u64,
+    /// The partition never heals if not set.
+    #[serde(default)]
+    pub end_ms: Option<u64>,
+    pub groups: Vec<Vec<String>>,
+}
+
+impl Link {
+    pub fn latency(&self) -> time::Duration {
+        time::Duration::milliseconds(self.latency_ms as i64)
+    }
+
+    /// Time it takes to push `bytes` into the link.
+    pub fn transmission_time(&self, bytes: usize) -> time::Duration {
+        match self.bandwidth_bytes_per_sec {
+            Some(bandwidth) => time::Duration::seconds_f64(bytes as f64 / bandwidth as f64),
+            None => time::Duration::ZERO,
+        }
+    }
+
+    /// Delay after which a lost segment is retransmitted. Like the TCP RTO it is
+    /// at least 200ms and at least the round trip time.
+    pub fn retransmission_timeout(&self) -> time::Duration {
+        std::cmp::max(time::Duration::milliseconds(200), self.latency() * 2)
+    }
+}
+
+impl Partition {
+    /// Whether the partition separates nodes `a` and `b`.
+    fn separates(&self, a: &str, b: &str) -> bool {
+        let group = |n: &str| self.groups.iter().position(|g| g.iter().any(|m| m == n));
+        match (group(a), group(b)) {
+            (Some(a), Some(b)) => a != b,
+            _ => false,
+        }
+    }
+}
+
+impl Topology {
+    pub fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        json.parse().with_context(|| format!("invalid topology file {}", path.display()))
+    }
+
+    /// Index of the node with the given name.
+    pub fn node(&self, name: &str) -> Option<usize> {
+        self.nodes.iter().position(|n| n == name)
+    }
+
+    /// Conditions of the link between nodes `a` and `b`.
+    pub fn link(&self, a: usize, b: usize) -> &Link {
+        let (a, b) = (&self.nodes[a], &self.nodes[b]);
+        self.links
+            .iter()
+            .find(|l| (&l.nodes[0], &l.nodes[1]) == (a, b) || (&l.nodes[0], &l.nodes[1]) == (b, a))
+            .map_or(&self.default_link, |l| &l.link)
+    }
+
+    /// If the link between nodes `a` and `b` is partitioned at time `t` (relative to the
+    /// creation of the network), returns the time when it heals, or `None` if it never does.
+    pub fn partitioned(
+        &self,
+        a: usize,
+        b: usize,
+        t: time::Duration,
+    ) -> Option<Option<time::Duration>> {
+        let (a, b) = (&self.nodes[a], &self.nodes[b]);
+        let ms = |ms: u64| time::Duration::milliseconds(ms as i64);
+        self.partitions
+            .iter()
+            .find(|p| {
+                ms(p.start_ms) <= t && p.end_ms.is_none_or(|end| t < ms(end)) && p.separates(a, b)
+            })
+            .map(|p| p.end_ms.map(ms))
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        let mut names = HashSet::new();
+        for name in &self.nodes {
+            anyhow::ensure!(names.insert(name), "duplicate node {name}");
+        }
+        let known = |name: &String| {
+            anyhow::ensure!(names.contains(name), "unknown node {name}");
+            Ok(())
+        };
+        for link in std::iter::once(&self.default_link).chain(self.links.iter().map(|l| &l.link)) {
+            anyhow::ensure!((0. ..1.).contains(&link.loss), "loss {} not in [0,1)", link.loss);
+            anyhow::ensure!(link.bandwidth_bytes_per_sec != Some(0), "zero bandwidth");
+        }
+        for link in &self.links {
+            link.nodes.iter().try_for_each(known)?;
+        }
+        for partition in &self.partitions {
+            if let Some(end_ms) = partition.end_ms {
+                anyhow::ensure!(partition.start_ms < end_ms, "partition ends before it starts");
+            }
+            partition.groups.iter().flatten().try_for_each(known)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for Topology {
+    type Err = anyhow::Error;
+    fn from_str(json: &str) -> anyhow::Result<Self> {
+        let topology: Self = serde_json::from_str(json)?;
+        topology.validate()?;
+        Ok(topology)
+    }
+}
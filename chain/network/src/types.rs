@@ -1,6 +1,7 @@
 /// Type that belong to the network protocol.
 pub use crate::network_protocol::{
-    Disconnect, Encoding, Handshake, HandshakeFailureReason, PeerMessage, RoutingTableUpdate,
+    BlockRangeRequest, BlockRangeResponse, CompactBlockRequest, CompactBlockResponse, Disconnect,
+    Encoding, Handshake, HandshakeFailureReason, PeerMessage, RoutingTableUpdate,
     SignedAccountData,
 };
 /// Exported types, which are part of network protocol.
@@ -67,6 +68,7 @@ pub struct KnownProducer {
     Eq,
     Copy,
     ProtocolSchema,
+    strum::EnumString,
 )]
 #[borsh(use_discriminant = false)]
 pub enum ReasonForBan {
@@ -166,6 +168,9 @@ pub struct ChainInfo {
     // Peers acting on behalf of these accounts have a higher
     // priority on the NEAR network than other peers.
     pub tier1_accounts: Arc<AccountKeys>,
+    // Lowest height of the blocks served in response to BlockRangeRequest,
+    // advertised to the peers. Set only on archival nodes.
+    pub block_range_start: Option<BlockHeight>,
 }
 
 #[derive(Debug, actix::Message)]
@@ -247,6 +252,14 @@ pub enum NetworkRequests {
     Approval { approval_message: ApprovalMessage },
     /// Request block with given hash from given peer.
     BlockRequest { hash: CryptoHash, peer_id: PeerId },
+    /// Request a range of blocks from given peer.
+    BlockRangeRequest { peer_id: PeerId, request: BlockRangeRequest },
+    /// Part of the response to a BlockRangeRequest.
+    BlockRangeResponse { peer_id: PeerId, response: BlockRangeResponse },
+    /// Request the missing pieces of a compact block from given peer.
+    CompactBlockRequest { peer_id: PeerId, request: CompactBlockRequest },
+    /// Response to a CompactBlockRequest.
+    CompactBlockResponse { peer_id: PeerId, response: CompactBlockResponse },
     /// Request given block headers.
     BlockHeadersRequest { hashes: Vec<CryptoHash>, peer_id: PeerId },
     /// Request state header for given shard and given sync hash.
@@ -338,6 +351,12 @@ pub struct HighestHeightPeerInfo {
     pub tracked_shards: Vec<ShardId>,
     /// Denote if a node is running in archival mode or not.
     pub archival: bool,
+    /// Lowest height of the blocks that the peer serves in response to BlockRangeRequest,
+    /// up to `highest_block_height`. Advertised only by archival peers.
+    pub block_range_start: Option<BlockHeight>,
+    /// Reputation score of the peer, from 0 to 1000. Peers with a higher
+    /// reputation are preferred for syncing.
+    pub reputation: u32,
 }
 
 impl From<FullPeerInfo> for Option<HighestHeightPeerInfo> {
@@ -350,6 +369,8 @@ impl From<FullPeerInfo> for Option<HighestHeightPeerInfo> {
                 highest_block_hash: p.chain_info.last_block.unwrap().hash,
                 tracked_shards: p.chain_info.tracked_shards,
                 archival: p.chain_info.archival,
+                block_range_start: p.chain_info.block_range_start,
+                reputation: crate::peer_manager::peer_store::reputation::NEUTRAL_SCORE,
             })
         } else {
             None
@@ -376,6 +397,8 @@ pub struct PeerChainInfo {
     pub tracked_shards: Vec<ShardId>,
     /// Denote if a node is running in archival mode or not.
     pub archival: bool,
+    /// Lowest height of the blocks that the peer serves in response to BlockRangeRequest.
+    pub block_range_start: Option<BlockHeight>,
 }
 
 // Information about the connected peer that is shared with the rest of the system.
@@ -487,6 +487,9 @@ impl From<NearActions> for Vec<crate::models::Operation> {
                 | near_primitives::transaction::Action::UseGlobalContract(_) => {
                     // TODO(#12639): Implement global contracts support, ignored for now.
                 }
+                near_primitives::transaction::Action::UpgradeContract(_) => {
+                    // Contract upgrades are not supported yet, ignored for now.
+                }
             }
         }
         operations
@@ -507,6 +507,76 @@ pub struct ChunkDistributionUris {
     pub set: String,
 }
 
+/// Config for the validator alert engine.
+/// The engine periodically evaluates the performance of a validator in the current epoch
+/// against a set of rules and reports the rules that fire to the configured sinks.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct ValidatorAlertsConfig {
+    /// Account to monitor. If not set, the account of the node's validator key is used.
+    pub account_id: Option<AccountId>,
+    /// How often the rules are evaluated.
+    #[serde(with = "near_time::serde_duration_as_std")]
+    pub check_period: Duration,
+    /// Minimum time between two consecutive notifications for the same rule.
+    /// A rule that keeps firing is re-reported only after this interval has passed.
+    #[serde(with = "near_time::serde_duration_as_std")]
+    pub repeat_interval: Duration,
+    /// Rules to evaluate.
+    pub rules: Vec<ValidatorAlertRule>,
+    /// Destinations for fired alerts.
+    pub sinks: Vec<ValidatorAlertSink>,
+}
+
+impl Default for ValidatorAlertsConfig {
+    fn default() -> Self {
+        Self {
+            account_id: None,
+            check_period: Duration::seconds(60),
+            repeat_interval: Duration::minutes(30),
+            rules: vec![ValidatorAlertRule::OnlineRatio, ValidatorAlertRule::KickoutRisk],
+            sinks: vec![ValidatorAlertSink::Stdout],
+        }
+    }
+}
+
+/// Rule evaluated by the validator alert engine.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ValidatorAlertRule {
+    /// Fires when the produced / expected ratio of `metric` over the last `window`
+    /// blocks drops below `threshold`, a percentage from 0 to 100.
+    /// The window never spans across epochs.
+    ProductionRatio { metric: ValidatorAlertMetric, threshold: u8, window: BlockHeightDelta },
+    /// Fires when the online ratio of the validator in the current epoch is below
+    /// the online minimum threshold, i.e. the validator will not receive rewards.
+    OnlineRatio,
+    /// Fires when the validator is predicted to be kicked out at the end of the epoch.
+    KickoutRisk,
+}
+
+/// Validator duty measured by a [`ValidatorAlertRule::ProductionRatio`] rule.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ValidatorAlertMetric {
+    Blocks,
+    Chunks,
+    Endorsements,
+}
+
+/// Destination of the alerts fired by the validator alert engine.
+/// Each alert is delivered as a single JSON object.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ValidatorAlertSink {
+    /// POST the alert to the given URL.
+    Webhook { url: String },
+    /// Print the alert to stdout, one JSON object per line.
+    Stdout,
+    /// Append the alert to the given file, one JSON object per line.
+    File { path: PathBuf },
+}
+
 /// ClientConfig where some fields can be updated at runtime.
 #[derive(Clone, serde::Serialize)]
 pub struct ClientConfig {
@@ -645,6 +715,9 @@ pub struct ClientConfig {
     /// as a large number of incoming witnesses could cause denial of service.
     pub save_latest_witnesses: bool,
     pub transaction_request_handler_threads: usize,
+    /// Optional config for the validator alert engine.
+    /// If set to `None` then validator performance is not monitored.
+    pub validator_alerts: Option<ValidatorAlertsConfig>,
 }
 
 impl ClientConfig {
@@ -733,6 +806,7 @@ impl ClientConfig {
             orphan_state_witness_max_size: default_orphan_state_witness_max_size(),
             save_latest_witnesses: false,
             transaction_request_handler_threads: default_rpc_handler_thread_count(),
+            validator_alerts: None,
         }
     }
 }
@@ -19,8 +19,10 @@ pub mod log_config;
 mod log_counter;
 pub mod macros;
 pub mod metrics;
+pub mod openmetrics;
 mod opentelemetry;
 mod reload;
+pub mod span_timing;
 mod subscriber;
 pub mod testonly;
 
@@ -67,17 +67,162 @@
 //! }
 //! ```
 
+use prometheus::proto::{Metric, MetricFamily, MetricType};
 pub use prometheus::{
     self, Counter, CounterVec, Encoder, Gauge, GaugeVec, Histogram, HistogramOpts, HistogramVec,
     IntCounter, IntCounterVec, IntGauge, IntGaugeVec, Opts, Result, TextEncoder, core::MetricVec,
     core::MetricVecBuilder, exponential_buckets, linear_buckets,
 };
-use std::collections::HashSet;
-use std::sync::LazyLock;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{LazyLock, OnceLock};
 
-/// Collect all the metrics for reporting.
-pub fn gather() -> Vec<prometheus::proto::MetricFamily> {
-    prometheus::gather()
+/// Controls which series of the metric families are exported, to keep the number of series
+/// in check on nodes with many peers, shards or accounts.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct MetricsConfig {
+    /// Export rules keyed by the name of the metric family.
+    pub families: BTreeMap<String, MetricFamilyConfig>,
+    /// Serve the OpenMetrics format, which carries the exemplars, to scrapers asking for it in
+    /// the `Accept` header. Off by default, as Prometheus asks for OpenMetrics on every scrape.
+    pub openmetrics: bool,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct MetricFamilyConfig {
+    /// Don't export the metric family at all.
+    pub drop: bool,
+    /// Labels removed from the exported series. Series which only differ in these labels are
+    /// aggregated into one: counters, gauges and histograms are summed, summaries lose their
+    /// quantiles and keep the summed count and sum.
+    pub aggregate_labels: Vec<String>,
+}
+
+static METRICS_CONFIG: OnceLock<MetricsConfig> = OnceLock::new();
+
+/// Sets the export rules applied by [`gather`]. Only the first call has an effect.
+pub fn set_metrics_config(config: MetricsConfig) {
+    if METRICS_CONFIG.set(config).is_err() {
+        tracing::warn!(target: "metrics", "metrics config is already set, ignoring the new one");
+    }
+}
+
+/// Whether `/metrics` may be served in the OpenMetrics format.
+pub fn openmetrics_enabled() -> bool {
+    METRICS_CONFIG.get().is_some_and(|config| config.openmetrics)
+}
+
+/// Export rule of the metric family, if any.
+pub(crate) fn metric_family_config(name: &str) -> Option<&'static MetricFamilyConfig> {
+    METRICS_CONFIG.get()?.families.get(name)
+}
+
+/// Collect all the metrics for reporting, applying the export rules set with
+/// [`set_metrics_config`].
+pub fn gather() -> Vec<MetricFamily> {
+    let families = prometheus::gather();
+    match METRICS_CONFIG.get() {
+        Some(config) if !config.families.is_empty() => families
+            .into_iter()
+            .filter_map(|family| match config.families.get(family.get_name()) {
+                None => Some(family),
+                Some(rule) if rule.drop => None,
+                Some(rule) => Some(aggregate_labels(family, &rule.aggregate_labels)),
+            })
+            .collect(),
+        _ => families,
+    }
+}
+
+/// Removes the labels from all series of the family and merges the series which become equal.
+fn aggregate_labels(mut family: MetricFamily, labels: &[String]) -> MetricFamily {
+    if labels.is_empty() {
+        return family;
+    }
+    let metric_type = family.get_field_type();
+    let mut aggregated: Vec<Metric> = Vec::new();
+    let mut index: HashMap<Vec<(String, String)>, usize> = HashMap::new();
+    for metric in family.get_metric() {
+        let label_pairs: Vec<_> = metric
+            .get_label()
+            .iter()
+            .filter(|pair| !labels.iter().any(|label| label == pair.get_name()))
+            .cloned()
+            .collect();
+        let key = label_pairs
+            .iter()
+            .map(|pair| (pair.get_name().to_string(), pair.get_value().to_string()))
+            .collect();
+        match index.entry(key) {
+            Entry::Occupied(entry) => {
+                merge_metric(&mut aggregated[*entry.get()], metric, metric_type)
+            }
+            Entry::Vacant(entry) => {
+                let mut metric = metric.clone();
+                metric.set_label(label_pairs.into());
+                entry.insert(aggregated.len());
+                aggregated.push(metric);
+            }
+        }
+    }
+    family.set_metric(aggregated.into());
+    family
+}
+
+fn merge_metric(into: &mut Metric, from: &Metric, metric_type: MetricType) {
+    match metric_type {
+        MetricType::COUNTER => {
+            let mut counter = into.get_counter().clone();
+            counter.set_value(counter.get_value() + from.get_counter().get_value());
+            into.set_counter(counter);
+        }
+        MetricType::GAUGE => {
+            let mut gauge = into.get_gauge().clone();
+            gauge.set_value(gauge.get_value() + from.get_gauge().get_value());
+            into.set_gauge(gauge);
+        }
+        MetricType::UNTYPED => {
+            let mut untyped = into.get_untyped().clone();
+            untyped.set_value(untyped.get_value() + from.get_untyped().get_value());
+            into.set_untyped(untyped);
+        }
+        MetricType::HISTOGRAM => {
+            let mut histogram = into.get_histogram().clone();
+            let from = from.get_histogram();
+            histogram.set_sample_count(
+                histogram.get_sample_count().saturating_add(from.get_sample_count()),
+            );
+            histogram.set_sample_sum(histogram.get_sample_sum() + from.get_sample_sum());
+            // Series of a family share the buckets.
+            let buckets: Vec<_> = histogram
+                .get_bucket()
+                .iter()
+                .zip(from.get_bucket())
+                .map(|(into, from)| {
+                    let mut bucket = into.clone();
+                    bucket.set_cumulative_count(
+                        into.get_cumulative_count().saturating_add(from.get_cumulative_count()),
+                    );
+                    bucket
+                })
+                .collect();
+            histogram.set_bucket(buckets.into());
+            into.set_histogram(histogram);
+        }
+        MetricType::SUMMARY => {
+            let mut summary = into.get_summary().clone();
+            let from = from.get_summary();
+            summary.set_sample_count(
+                summary.get_sample_count().saturating_add(from.get_sample_count()),
+            );
+            summary.set_sample_sum(summary.get_sample_sum() + from.get_sample_sum());
+            // Quantiles of different series can't be combined.
+            summary.set_quantile(Vec::new().into());
+            into.set_summary(summary);
+        }
+    }
 }
 
 /// Attempts to crate an `IntCounter`, returning `Err` if the registry does not accept the counter
@@ -236,7 +381,9 @@ fn check_metric_near_prefix(name: &str) -> Result<()> {
 
 #[cfg(test)]
 mod tests {
-    use crate::metrics::check_metric_near_prefix;
+    use crate::metrics::{aggregate_labels, check_metric_near_prefix};
+    use prometheus::core::Collector;
+    use prometheus::{HistogramOpts, HistogramVec, IntCounterVec, Opts};
 
     #[test]
     fn test_near_prefix() {
@@ -245,4 +392,47 @@ mod tests {
         assert!(check_metric_near_prefix("near").is_err());
         assert!(check_metric_near_prefix("abc").is_err());
     }
+
+    #[test]
+    fn test_aggregate_labels() {
+        let counter =
+            IntCounterVec::new(Opts::new("near_test_total", "test"), &["peer_id", "type"]).unwrap();
+        counter.with_label_values(&["a", "block"]).inc_by(1);
+        counter.with_label_values(&["b", "block"]).inc_by(2);
+        counter.with_label_values(&["b", "chunk"]).inc_by(4);
+        let family = aggregate_labels(counter.collect().remove(0), &["peer_id".to_string()]);
+        let mut series: Vec<_> = family
+            .get_metric()
+            .iter()
+            .map(|metric| {
+                let labels: Vec<_> = metric
+                    .get_label()
+                    .iter()
+                    .map(|pair| format!("{}={}", pair.get_name(), pair.get_value()))
+                    .collect();
+                (labels, metric.get_counter().get_value())
+            })
+            .collect();
+        series.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            series,
+            vec![(vec!["type=block".to_string()], 3.0), (vec!["type=chunk".to_string()], 4.0)]
+        );
+
+        let histogram = HistogramVec::new(
+            HistogramOpts::new("near_test_seconds", "test").buckets(vec![1.0, 2.0]),
+            &["shard_id"],
+        )
+        .unwrap();
+        histogram.with_label_values(&["0"]).observe(0.5);
+        histogram.with_label_values(&["1"]).observe(1.5);
+        let family = aggregate_labels(histogram.collect().remove(0), &["shard_id".to_string()]);
+        assert_eq!(family.get_metric().len(), 1);
+        let histogram = family.get_metric()[0].get_histogram();
+        assert_eq!(histogram.get_sample_count(), 2);
+        assert_eq!(histogram.get_sample_sum(), 2.0);
+        let buckets: Vec<_> =
+            histogram.get_bucket().iter().map(|bucket| bucket.get_cumulative_count()).collect();
+        assert_eq!(buckets, vec![1, 2]);
+    }
 }
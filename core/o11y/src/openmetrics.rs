@@ -0,0 +1,443 @@
+//! OpenMetrics exposition of the metrics with exemplars.
+//!
+//! The `prometheus` crate only produces the Prometheus text format, which has
+//! no room for exemplars. This module encodes the gathered metrics in the
+//! OpenMetrics text format instead and attaches exemplars to histogram
+//! buckets: the OpenTelemetry trace id of the last observation which fell into
+//! the bucket. They let a latency spike on a dashboard be followed to the
+//! trace of the slow block or chunk.
+//!
+//! Exemplars are only recorded for histograms observed with
+//! [`observe_with_exemplar`] or [`start_exemplar_timer`], and only when the
+//! span of the observation is sampled by the OpenTelemetry layer.
+//!
+//! The format is only served when enabled with `metrics.openmetrics` in the
+//! config, otherwise `/metrics` always uses the Prometheus text format.
+
+use crate::metrics::{Histogram, metric_family_config};
+use opentelemetry::trace::TraceContextExt;
+use parking_lot::Mutex;
+use prometheus::core::{Collector, Metric};
+use prometheus::proto::{LabelPair, MetricFamily, MetricType};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::sync::LazyLock;
+use std::time::{Instant, SystemTime};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+/// Content type of the OpenMetrics text format.
+pub const CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+/// Labels of a series, in the order reported by the metric.
+type Labels = Vec<(String, String)>;
+
+#[derive(Clone, Debug)]
+struct Exemplar {
+    trace_id: String,
+    value: f64,
+    /// Seconds since the Unix epoch.
+    timestamp: f64,
+}
+
+/// Latest exemplar per metric family, series and bucket index. The index
+/// equal to the number of buckets is the `+Inf` bucket.
+static EXEMPLARS: LazyLock<Mutex<HashMap<String, HashMap<Labels, BTreeMap<usize, Exemplar>>>>> =
+    LazyLock::new(Default::default);
+
+/// Observes `value` in the histogram and records the trace of the current span
+/// as the exemplar of the bucket the value fell into.
+pub fn observe_with_exemplar(histogram: &Histogram, value: f64) {
+    histogram.observe(value);
+    record_exemplar(histogram, value, &tracing::Span::current());
+}
+
+/// Starts a timer which observes the elapsed seconds in the histogram when
+/// dropped, with the trace of the current span as the exemplar.
+pub fn start_exemplar_timer(histogram: Histogram) -> ExemplarTimer {
+    ExemplarTimer { histogram, span: tracing::Span::current(), start: Instant::now() }
+}
+
+/// Timer returned by [`start_exemplar_timer`].
+pub struct ExemplarTimer {
+    histogram: Histogram,
+    span: tracing::Span,
+    start: Instant,
+}
+
+impl Drop for ExemplarTimer {
+    fn drop(&mut self) {
+        let value = self.start.elapsed().as_secs_f64();
+        self.histogram.observe(value);
+        record_exemplar(&self.histogram, value, &self.span);
+    }
+}
+
+fn record_exemplar(histogram: &Histogram, value: f64, span: &tracing::Span) {
+    let context = span.context();
+    let span_ref = context.span();
+    let span_context = span_ref.span_context();
+    if !span_context.is_valid() || !span_context.is_sampled() {
+        return;
+    }
+    let Some(desc) = histogram.desc().first().copied() else {
+        return;
+    };
+    // Collecting a single histogram is cheap enough for the low frequency
+    // histograms exemplars are recorded for.
+    let metric = histogram.metric();
+    let labels = label_key(metric.get_label());
+    let buckets = metric.get_histogram().get_bucket();
+    let bucket = buckets
+        .iter()
+        .position(|bucket| value <= bucket.get_upper_bound())
+        .unwrap_or(buckets.len());
+    let timestamp = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|duration| duration.as_secs_f64())
+        .unwrap_or_default();
+    let exemplar = Exemplar { trace_id: span_context.trace_id().to_string(), value, timestamp };
+    EXEMPLARS
+        .lock()
+        .entry(desc.fq_name.clone())
+        .or_default()
+        .entry(labels)
+        .or_default()
+        .insert(bucket, exemplar);
+}
+
+fn label_key(labels: &[LabelPair]) -> Labels {
+    labels.iter().map(|pair| (pair.get_name().to_string(), pair.get_value().to_string())).collect()
+}
+
+/// Exemplars of the family's series, keyed by the labels the series are
+/// exported with after the export rules of the family dropped labels.
+fn family_exemplars(name: &str) -> HashMap<Labels, BTreeMap<usize, Exemplar>> {
+    let exemplars = EXEMPLARS.lock();
+    let Some(series) = exemplars.get(name) else {
+        return HashMap::new();
+    };
+    let aggregate_labels =
+        metric_family_config(name).map(|rule| rule.aggregate_labels.as_slice()).unwrap_or(&[]);
+    let mut result: HashMap<Labels, BTreeMap<usize, Exemplar>> = HashMap::new();
+    for (labels, buckets) in series {
+        let labels =
+            labels.iter().filter(|(label, _)| !aggregate_labels.contains(label)).cloned().collect();
+        let merged = result.entry(labels).or_default();
+        for (bucket, exemplar) in buckets {
+            match merged.get(bucket) {
+                Some(existing) if existing.timestamp >= exemplar.timestamp => {}
+                _ => {
+                    merged.insert(*bucket, exemplar.clone());
+                }
+            }
+        }
+    }
+    result
+}
+
+/// Encodes the metric families in the OpenMetrics text format.
+pub fn encode(families: &[MetricFamily]) -> String {
+    let mut out = String::new();
+    for family in families {
+        if family.get_metric().is_empty() {
+            continue;
+        }
+        let name = family.get_name();
+        let metric_type = family.get_field_type();
+        let (family_name, type_name) = match metric_type {
+            MetricType::COUNTER => match name.strip_suffix("_total") {
+                Some(family_name) => (family_name, "counter"),
+                // Counter samples must end with `_total` in OpenMetrics. Counters
+                // not following the convention are exported as `unknown` so that
+                // the series keep their names.
+                None => (name, "unknown"),
+            },
+            MetricType::GAUGE => (name, "gauge"),
+            MetricType::HISTOGRAM => (name, "histogram"),
+            MetricType::SUMMARY => (name, "summary"),
+            MetricType::UNTYPED => (name, "unknown"),
+        };
+        writeln!(out, "# TYPE {} {}", family_name, type_name).unwrap();
+        writeln!(out, "# HELP {} {}", family_name, escape_help(family.get_help())).unwrap();
+        let exemplars = match metric_type {
+            MetricType::HISTOGRAM => family_exemplars(name),
+            _ => HashMap::new(),
+        };
+        for metric in family.get_metric() {
+            let labels = metric.get_label();
+            match metric_type {
+                MetricType::COUNTER => {
+                    write_sample(&mut out, name, labels, None, metric.get_counter().get_value());
+                    out.push('\n');
+                }
+                MetricType::GAUGE => {
+                    write_sample(&mut out, name, labels, None, metric.get_gauge().get_value());
+                    out.push('\n');
+                }
+                MetricType::UNTYPED => {
+                    write_sample(&mut out, name, labels, None, metric.get_untyped().get_value());
+                    out.push('\n');
+                }
+                MetricType::HISTOGRAM => {
+                    let histogram = metric.get_histogram();
+                    let series_exemplars = exemplars.get(&label_key(labels));
+                    let bucket_name = format!("{}_bucket", name);
+                    let buckets = histogram.get_bucket();
+                    let upper_bounds = buckets
+                        .iter()
+                        .map(|bucket| (bucket.get_upper_bound(), bucket.get_cumulative_count()))
+                        .chain(std::iter::once((f64::INFINITY, histogram.get_sample_count())));
+                    for (index, (upper_bound, count)) in upper_bounds.enumerate() {
+                        write_sample(
+                            &mut out,
+                            &bucket_name,
+                            labels,
+                            Some(("le", &format_float(upper_bound))),
+                            count as f64,
+                        );
+                        if let Some(exemplar) =
+                            series_exemplars.and_then(|buckets| buckets.get(&index))
+                        {
+                            write!(
+                                out,
+                                " # {{trace_id=\"{}\"}} {} {}",
+                                exemplar.trace_id,
+                                format_float(exemplar.value),
+                                exemplar.timestamp
+                            )
+                            .unwrap();
+                        }
+                        out.push('\n');
+                    }
+                    write_sum_and_count(
+                        &mut out,
+                        name,
+                        labels,
+                        histogram.get_sample_sum(),
+                        histogram.get_sample_count(),
+                    );
+                }
+                MetricType::SUMMARY => {
+                    let summary = metric.get_summary();
+                    for quantile in summary.get_quantile() {
+                        write_sample(
+                            &mut out,
+                            name,
+                            labels,
+                            Some(("quantile", &format_float(quantile.get_quantile()))),
+                            quantile.get_value(),
+                        );
+                        out.push('\n');
+                    }
+                    write_sum_and_count(
+                        &mut out,
+                        name,
+                        labels,
+                        summary.get_sample_sum(),
+                        summary.get_sample_count(),
+                    );
+                }
+            }
+        }
+    }
+    out.push_str("# EOF\n");
+    out
+}
+
+fn write_sum_and_count(out: &mut String, name: &str, labels: &[LabelPair], sum: f64, count: u64) {
+    write_sample(out, &format!("{}_sum", name), labels, None, sum);
+    out.push('\n');
+    write_sample(out, &format!("{}_count", name), labels, None, count as f64);
+    out.push('\n');
+}
+
+/// Writes a sample without the trailing newline, so that an exemplar can follow.
+fn write_sample(
+    out: &mut String,
+    name: &str,
+    labels: &[LabelPair],
+    extra_label: Option<(&str, &str)>,
+    value: f64,
+) {
+    out.push_str(name);
+    let labels = labels
+        .iter()
+        .map(|pair| (pair.get_name(), pair.get_value()))
+        .chain(extra_label)
+        .collect::<Vec<_>>();
+    if !labels.is_empty() {
+        out.push('{');
+        for (index, (label, value)) in labels.into_iter().enumerate() {
+            if index > 0 {
+                out.push(',');
+            }
+            write!(out, "{}=\"{}\"", label, escape_label_value(value)).unwrap();
+        }
+        out.push('}');
+    }
+    write!(out, " {}", format_float(value)).unwrap();
+}
+
+fn format_float(value: f64) -> String {
+    if value == f64::INFINITY {
+        "+Inf".to_string()
+    } else if value == f64::NEG_INFINITY {
+        "-Inf".to_string()
+    } else if value.is_nan() {
+        "NaN".to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+fn escape_help(help: &str) -> String {
+    help.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+fn escape_label_value(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prometheus::{
+        Gauge, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, Opts, Registry,
+        TextEncoder,
+    };
+
+    /// Sample values of an exposition in either text format, keyed by the
+    /// sample name and labels.
+    fn parse_samples(text: &str) -> BTreeMap<String, f64> {
+        text.lines()
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                let sample = line.split(" # ").next().unwrap();
+                let (series, value) = sample.rsplit_once(' ').unwrap();
+                (series.to_string(), value.parse().unwrap())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_encode() {
+        let counter = IntCounter::with_opts(Opts::new("near_test_total", "a \"test\"\n")).unwrap();
+        counter.inc_by(3);
+        let histogram = prometheus::HistogramVec::new(
+            HistogramOpts::new("near_test_seconds", "test").buckets(vec![0.5, 1.0]),
+            &["shard_id"],
+        )
+        .unwrap();
+        histogram.with_label_values(&["0"]).observe(0.7);
+
+        let mut families = counter.collect();
+        families.extend(histogram.collect());
+        assert_eq!(
+            encode(&families),
+            "# TYPE near_test counter\n\
+             # HELP near_test a \"test\"\\n\n\
+             near_test_total 3\n\
+             # TYPE near_test_seconds histogram\n\
+             # HELP near_test_seconds test\n\
+             near_test_seconds_bucket{shard_id=\"0\",le=\"0.5\"} 0\n\
+             near_test_seconds_bucket{shard_id=\"0\",le=\"1\"} 1\n\
+             near_test_seconds_bucket{shard_id=\"0\",le=\"+Inf\"} 1\n\
+             near_test_seconds_sum{shard_id=\"0\"} 0.7\n\
+             near_test_seconds_count{shard_id=\"0\"} 1\n\
+             # EOF\n"
+        );
+    }
+
+    #[test]
+    fn test_exemplar_for_bucket() {
+        let histogram = Histogram::with_opts(
+            HistogramOpts::new("near_test_exemplar_seconds", "test").buckets(vec![0.5, 1.0]),
+        )
+        .unwrap();
+        EXEMPLARS.lock().entry("near_test_exemplar_seconds".to_string()).or_default().insert(
+            vec![],
+            BTreeMap::from([(
+                1,
+                Exemplar {
+                    trace_id: "0af7651916cd43dd8448eb211c80319c".into(),
+                    value: 0.7,
+                    timestamp: 1.5,
+                },
+            )]),
+        );
+        histogram.observe(0.7);
+        let encoded = encode(&histogram.collect());
+        assert!(
+            encoded.contains(
+                "near_test_exemplar_seconds_bucket{le=\"1\"} 1 # {trace_id=\"0af7651916cd43dd8448eb211c80319c\"} 0.7 1.5\n"
+            ),
+            "{}",
+            encoded
+        );
+    }
+
+    #[test]
+    fn test_exemplar_recorded_for_sampled_span() {
+        use opentelemetry::trace::TracerProvider as _;
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let histogram = Histogram::with_opts(
+            HistogramOpts::new("near_test_sampled_exemplar_seconds", "test")
+                .buckets(vec![0.5, 1.0]),
+        )
+        .unwrap();
+        let provider = opentelemetry_sdk::trace::TracerProvider::builder().build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+        let trace_id = tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("apply_chunk");
+            span.in_scope(|| observe_with_exemplar(&histogram, 0.7));
+            span.context().span().span_context().trace_id().to_string()
+        });
+        // Observations outside of a sampled span don't replace the exemplar.
+        observe_with_exemplar(&histogram, 0.8);
+
+        let encoded = encode(&histogram.collect());
+        let expected = format!(
+            "near_test_sampled_exemplar_seconds_bucket{{le=\"1\"}} 2 # {{trace_id=\"{}\"}} 0.7 ",
+            trace_id
+        );
+        assert!(encoded.contains(&expected), "{}", encoded);
+    }
+
+    #[test]
+    fn test_encode_matches_text_format() {
+        let registry = Registry::new();
+        let requests =
+            IntCounterVec::new(Opts::new("near_test_requests_total", "test"), &["method"]).unwrap();
+        requests.with_label_values(&["block"]).inc_by(2);
+        requests.with_label_values(&["chunk \"x\""]).inc();
+        let legacy_counter = IntCounter::new("near_test_legacy_counter", "test").unwrap();
+        legacy_counter.inc_by(5);
+        let gauge = Gauge::new("near_test_gauge", "test").unwrap();
+        gauge.set(1.5);
+        let int_gauge = IntGauge::new("near_test_int_gauge", "test").unwrap();
+        int_gauge.set(-3);
+        let histogram = HistogramVec::new(
+            HistogramOpts::new("near_test_latency_seconds", "test").buckets(vec![0.1, 0.5, 1.0]),
+            &["shard_id"],
+        )
+        .unwrap();
+        for value in [0.05, 0.7, 3.0] {
+            histogram.with_label_values(&["0"]).observe(value);
+        }
+        histogram.with_label_values(&["1"]).observe(0.3);
+        registry.register(Box::new(requests)).unwrap();
+        registry.register(Box::new(legacy_counter)).unwrap();
+        registry.register(Box::new(gauge)).unwrap();
+        registry.register(Box::new(int_gauge)).unwrap();
+        registry.register(Box::new(histogram)).unwrap();
+
+        let families = registry.gather();
+        let mut text = vec![];
+        TextEncoder::new().encode(&families, &mut text).unwrap();
+        let text_samples = parse_samples(&String::from_utf8(text).unwrap());
+        assert_eq!(text_samples.len(), 17);
+        assert_eq!(parse_samples(&encode(&families)), text_samples);
+    }
+}
@@ -0,0 +1,210 @@
+//! Wall-clock timing of tracing spans in a running node.
+//!
+//! While a recording is running, [`SpanTimer`] measures the wall-clock time
+//! threads spend inside spans and attributes it to the stack of spans it was
+//! spent in. This is not a CPU profile: time a span spends blocked on IO or a
+//! lock counts as well, and code running outside of any span doesn't show up
+//! at all. Stack frames carry the block height and shard id recorded on the
+//! spans, so a slow `apply_chunk` shows up together with the height and the
+//! shard it was applied for. Recordings are returned in the folded stacks
+//! format understood by flamegraph tools such as `inferno-flamegraph` and
+//! speedscope.
+//!
+//! Spans are only timed if they are created while a recording is running, and
+//! only up to the `DEBUG` level. When no recording is running the layer
+//! disables all spans, so it costs a single atomic load per span.
+
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::LazyLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id};
+use tracing::subscriber::Interest;
+use tracing::{Level, Subscriber};
+use tracing_subscriber::Layer;
+use tracing_subscriber::filter::{DynFilterFn, LevelFilter};
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+
+/// Span fields included in stack frames.
+const ATTRIBUTED_FIELDS: &[&str] = &["height", "block_height", "shard_id", "shard_uid"];
+
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// Microseconds spent in every stack of spans since the recording started.
+static STACKS: LazyLock<Mutex<HashMap<String, u64>>> = LazyLock::new(Default::default);
+
+/// A running recording. Only one recording can run at a time.
+pub struct Recording {
+    _private: (),
+}
+
+/// Starts a recording, or returns `None` if another one is running.
+pub fn start() -> Option<Recording> {
+    ACTIVE.compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire).ok()?;
+    STACKS.lock().clear();
+    Some(Recording { _private: () })
+}
+
+impl Recording {
+    /// Stops the recording and returns it in the folded stacks format: one line
+    /// per stack of spans, frames separated by `;`, followed by the
+    /// microseconds spent in the innermost span.
+    pub fn finish(self) -> String {
+        ACTIVE.store(false, Ordering::Release);
+        let mut stacks: Vec<_> = std::mem::take(&mut *STACKS.lock()).into_iter().collect();
+        stacks.sort();
+        let mut folded = String::new();
+        for (stack, micros) in stacks {
+            if micros > 0 {
+                writeln!(folded, "{} {}", stack, micros).unwrap();
+            }
+        }
+        folded
+    }
+}
+
+impl Drop for Recording {
+    fn drop(&mut self) {
+        ACTIVE.store(false, Ordering::Release);
+    }
+}
+
+/// Layer measuring spans for running recordings.
+pub(crate) struct SpanTimer;
+
+pub(crate) fn layer<S>() -> impl Layer<S>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    let filter =
+        DynFilterFn::new(|metadata, _| metadata.is_span() && ACTIVE.load(Ordering::Relaxed))
+            .with_callsite_filter(|metadata| {
+                if metadata.is_span() && *metadata.level() <= Level::DEBUG {
+                    Interest::sometimes()
+                } else {
+                    Interest::never()
+                }
+            })
+            .with_max_level_hint(LevelFilter::DEBUG);
+    SpanTimer.with_filter(filter)
+}
+
+struct TimedSpan {
+    /// Frames from the root span down to this one, separated by `;`.
+    stack: String,
+    entered: Option<Instant>,
+    /// Time spent in child spans since this span was entered.
+    children: Duration,
+}
+
+struct FrameVisitor<'a>(&'a mut String);
+
+impl Visit for FrameVisitor<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if ATTRIBUTED_FIELDS.contains(&field.name()) {
+            write!(self.0, " {}={:?}", field.name(), value).unwrap();
+        }
+    }
+}
+
+impl<S> Layer<S> for SpanTimer
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut frame = span.name().to_string();
+        attrs.record(&mut FrameVisitor(&mut frame));
+        // Semicolons separate frames in the folded format.
+        let frame = frame.replace(';', ",");
+        let stack = match span.parent() {
+            Some(parent) => match parent.extensions().get::<TimedSpan>() {
+                Some(parent) => format!("{};{}", parent.stack, frame),
+                None => frame,
+            },
+            None => frame,
+        };
+        span.extensions_mut().insert(TimedSpan { stack, entered: None, children: Duration::ZERO });
+    }
+
+    fn on_enter(&self, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        if let Some(timed) = span.extensions_mut().get_mut::<TimedSpan>() {
+            timed.entered = Some(Instant::now());
+            timed.children = Duration::ZERO;
+        }
+    }
+
+    fn on_exit(&self, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let busy = {
+            let mut extensions = span.extensions_mut();
+            let Some(timed) = extensions.get_mut::<TimedSpan>() else {
+                return;
+            };
+            let Some(entered) = timed.entered.take() else {
+                return;
+            };
+            let busy = entered.elapsed();
+            let self_time = busy.saturating_sub(timed.children);
+            *STACKS.lock().entry(timed.stack.clone()).or_default() += self_time.as_micros() as u64;
+            busy
+        };
+        if let Some(parent) = span.parent() {
+            if let Some(parent) = parent.extensions_mut().get_mut::<TimedSpan>() {
+                if parent.entered.is_some() {
+                    parent.children += busy;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[test]
+    fn test_recording_attributes_time_to_span_stacks() {
+        let subscriber = tracing_subscriber::registry().with(layer());
+        tracing::subscriber::with_default(subscriber, || {
+            let recording = start().unwrap();
+            assert!(start().is_none());
+            {
+                let _outer = tracing::debug_span!("apply_chunk", height = 7).entered();
+                std::thread::sleep(Duration::from_millis(10));
+                let _inner =
+                    tracing::debug_span!("apply_transactions", shard_id = 1, other = 2).entered();
+                std::thread::sleep(Duration::from_millis(20));
+            }
+            // Trace spans are never timed.
+            tracing::trace_span!("ignored").in_scope(|| {});
+            let folded = recording.finish();
+
+            let stacks: HashMap<&str, u64> = folded
+                .lines()
+                .map(|line| {
+                    let (stack, micros) = line.rsplit_once(' ').unwrap();
+                    (stack, micros.parse().unwrap())
+                })
+                .collect();
+            assert_eq!(stacks.len(), 2, "{}", folded);
+            let outer = stacks["apply_chunk height=7"];
+            let inner = stacks["apply_chunk height=7;apply_transactions shard_id=1"];
+            assert!(outer >= 10_000, "{}", folded);
+            assert!(inner >= 20_000, "{}", folded);
+        });
+        assert!(start().is_some());
+    }
+}
@@ -2,7 +2,7 @@ use crate::opentelemetry::add_opentelemetry_layer;
 use crate::reload::{
     LogLayer, SimpleLogLayer, set_default_otlp_level, set_log_layer_handle, set_otlp_layer_handle,
 };
-use crate::{OpenTelemetryLevel, log_counter};
+use crate::{OpenTelemetryLevel, log_counter, span_timing};
 use near_crypto::PublicKey;
 use near_primitives_core::types::AccountId;
 use std::path::PathBuf;
@@ -211,6 +211,7 @@ pub fn default_subscriber(
         options.log_span_events,
         subscriber,
     );
+    let subscriber = subscriber.with(span_timing::layer());
 
     #[allow(unused_mut)]
     let mut io_trace_guard = None;
@@ -274,6 +275,7 @@ pub async fn default_subscriber_with_opentelemetry(
     )
     .await;
     set_otlp_layer_handle(handle);
+    let subscriber = subscriber.with(span_timing::layer());
 
     #[allow(unused_mut)]
     let mut io_trace_guard = None;
@@ -707,3 +707,25 @@ pub fn transfer_send_fee(
         }
     }
 }
+
+/// Helper functions for computing UpgradeContract fees.
+/// The action is charged as a DeployContract of `code_len` bytes followed by a FunctionCall whose
+/// method name and arguments are `call_len` bytes long in total.
+pub fn upgrade_contract_send_fee(
+    cfg: &RuntimeFeesConfig,
+    sender_is_receiver: bool,
+    code_len: u64,
+    call_len: u64,
+) -> Gas {
+    cfg.fee(ActionCosts::deploy_contract_base).send_fee(sender_is_receiver)
+        + cfg.fee(ActionCosts::deploy_contract_byte).send_fee(sender_is_receiver) * code_len
+        + cfg.fee(ActionCosts::function_call_base).send_fee(sender_is_receiver)
+        + cfg.fee(ActionCosts::function_call_byte).send_fee(sender_is_receiver) * call_len
+}
+
+pub fn upgrade_contract_exec_fee(cfg: &RuntimeFeesConfig, code_len: u64, call_len: u64) -> Gas {
+    cfg.fee(ActionCosts::deploy_contract_base).exec_fee()
+        + cfg.fee(ActionCosts::deploy_contract_byte).exec_fee() * code_len
+        + cfg.fee(ActionCosts::function_call_base).exec_fee()
+        + cfg.fee(ActionCosts::function_call_byte).exec_fee() * call_len
+}
@@ -12,7 +12,8 @@ pub use config::{AccountCreationConfig, RuntimeConfig};
 pub use config_store::RuntimeConfigStore;
 pub use cost::{
     ActionCosts, ExtCosts, ExtCostsConfig, Fee, ParameterCost, RuntimeFeesConfig,
-    StorageUsageConfig, transfer_exec_fee, transfer_send_fee,
+    StorageUsageConfig, transfer_exec_fee, transfer_send_fee, upgrade_contract_exec_fee,
+    upgrade_contract_send_fee,
 };
 pub use parameter::Parameter;
 pub use view::{RuntimeConfigView, RuntimeFeesConfigView};
@@ -196,6 +196,7 @@ pub enum Parameter {
     AccountIdValidityRulesVersion,
     YieldTimeoutLengthInBlocks,
     MaxYieldPayloadSize,
+    MaxScheduledReceiptDelayInBlocks,
 
     // Contract runtime features
     FlatStorageReads,
@@ -235,6 +236,14 @@ pub enum Parameter {
     ActionUseGlobalContract,
     ActionUseGlobalContractPerIdentifierByte,
     SaturatingFloatToInt,
+
+    // Bulk memory and reference types proposals
+    BulkMemory,
+    ReferenceTypes,
+    WasmBulkOpUnitCost,
+
+    // Scheduled receipts
+    ScheduledReceipts,
 }
 
 #[derive(
@@ -305,6 +314,7 @@ impl Parameter {
             Parameter::AccountIdValidityRulesVersion,
             Parameter::YieldTimeoutLengthInBlocks,
             Parameter::MaxYieldPayloadSize,
+            Parameter::MaxScheduledReceiptDelayInBlocks,
             Parameter::PerReceiptStorageProofSizeLimit,
         ]
         .iter()
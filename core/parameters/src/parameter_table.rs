@@ -318,8 +318,12 @@ impl TryFrom<&ParameterTable> for RuntimeConfig {
                 vm_kind: params.get(Parameter::VmKind)?,
                 grow_mem_cost: params.get(Parameter::WasmGrowMemCost)?,
                 regular_op_cost: params.get(Parameter::WasmRegularOpCost)?,
+                bulk_op_unit_cost: params.get(Parameter::WasmBulkOpUnitCost)?,
                 discard_custom_sections: params.get(Parameter::DiscardCustomSections)?,
                 saturating_float_to_int: params.get(Parameter::SaturatingFloatToInt)?,
+                bulk_memory: params.get(Parameter::BulkMemory)?,
+                reference_types: params.get(Parameter::ReferenceTypes)?,
+                scheduled_receipts: params.get(Parameter::ScheduledReceipts)?,
                 limit_config: serde_yaml::from_value(params.yaml_map(Parameter::vm_limits()))
                     .map_err(InvalidConfigError::InvalidYaml)?,
                 fix_contract_loading_cost: params.get(Parameter::FixContractLoadingCost)?,
@@ -209,6 +209,8 @@ pub struct VMConfigView {
     pub grow_mem_cost: u32,
     /// Gas cost of a regular operation.
     pub regular_op_cost: u32,
+    /// See [VMConfig::bulk_op_unit_cost](crate::vm::Config::bulk_op_unit_cost).
+    pub bulk_op_unit_cost: u32,
 
     /// See [VMConfig::vm_kind](crate::vm::Config::vm_kind).
     pub vm_kind: crate::vm::VMKind,
@@ -216,6 +218,12 @@ pub struct VMConfigView {
     pub discard_custom_sections: bool,
     /// See [VMConfig::saturating_float_to_int](crate::vm::Config::saturating_float_to_int).
     pub saturating_float_to_int: bool,
+    /// See [VMConfig::bulk_memory](crate::vm::Config::bulk_memory).
+    pub bulk_memory: bool,
+    /// See [VMConfig::reference_types](crate::vm::Config::reference_types).
+    pub reference_types: bool,
+    /// See [VMConfig::scheduled_receipts](crate::vm::Config::scheduled_receipts).
+    pub scheduled_receipts: bool,
 
     /// See [VMConfig::storage_get_mode](crate::vm::Config::storage_get_mode).
     pub storage_get_mode: crate::vm::StorageGetMode,
@@ -239,6 +247,7 @@ impl From<crate::vm::Config> for VMConfigView {
             ext_costs: ExtCostsConfigView::from(config.ext_costs),
             grow_mem_cost: config.grow_mem_cost,
             regular_op_cost: config.regular_op_cost,
+            bulk_op_unit_cost: config.bulk_op_unit_cost,
             discard_custom_sections: config.discard_custom_sections,
             limit_config: config.limit_config,
             storage_get_mode: config.storage_get_mode,
@@ -247,6 +256,9 @@ impl From<crate::vm::Config> for VMConfigView {
             vm_kind: config.vm_kind,
             eth_implicit_accounts: config.eth_implicit_accounts,
             saturating_float_to_int: config.saturating_float_to_int,
+            bulk_memory: config.bulk_memory,
+            reference_types: config.reference_types,
+            scheduled_receipts: config.scheduled_receipts,
         }
     }
 }
@@ -257,6 +269,7 @@ impl From<VMConfigView> for crate::vm::Config {
             ext_costs: crate::ExtCostsConfig::from(view.ext_costs),
             grow_mem_cost: view.grow_mem_cost,
             regular_op_cost: view.regular_op_cost,
+            bulk_op_unit_cost: view.bulk_op_unit_cost,
             discard_custom_sections: view.discard_custom_sections,
             limit_config: view.limit_config,
             storage_get_mode: view.storage_get_mode,
@@ -265,6 +278,9 @@ impl From<VMConfigView> for crate::vm::Config {
             vm_kind: view.vm_kind,
             eth_implicit_accounts: view.eth_implicit_accounts,
             saturating_float_to_int: view.saturating_float_to_int,
+            bulk_memory: view.bulk_memory,
+            reference_types: view.reference_types,
+            scheduled_receipts: view.scheduled_receipts,
         }
     }
 }
@@ -127,6 +127,8 @@ pub struct LimitConfig {
     pub yield_timeout_length_in_blocks: u64,
     /// Maximum number of bytes for payload passed over a yield resume.
     pub max_yield_payload_size: u64,
+    /// Max number of blocks after the current one for which a receipt can be scheduled.
+    pub max_scheduled_receipt_delay_in_blocks: u64,
     /// Hard limit on the size of storage proof generated while executing a single receipt.
     pub per_receipt_storage_proof_size_limit: usize,
 }
@@ -149,6 +151,10 @@ pub struct Config {
     /// Gas cost of a regular operation.
     pub regular_op_cost: u32,
 
+    /// Gas cost of every byte of memory or table element processed by the bulk memory operations
+    /// such as `memory.copy` or `table.fill`, charged on top of `regular_op_cost`.
+    pub bulk_op_unit_cost: u32,
+
     /// The kind of the VM implementation to use
     pub vm_kind: VMKind,
 
@@ -171,6 +177,15 @@ pub struct Config {
     /// Whether to enable saturating float-to-integer wasm operators.
     pub saturating_float_to_int: bool,
 
+    /// Whether to enable the bulk memory operations wasm proposal.
+    pub bulk_memory: bool,
+
+    /// Whether to enable the reference types wasm proposal.
+    pub reference_types: bool,
+
+    /// Whether to enable the host function scheduling receipts for a future block height.
+    pub scheduled_receipts: bool,
+
     /// Describes limits for VM and Runtime.
     pub limit_config: LimitConfig,
 }
@@ -192,6 +207,7 @@ impl Config {
         };
         self.grow_mem_cost = 0;
         self.regular_op_cost = 0;
+        self.bulk_op_unit_cost = 0;
         self.limit_config.max_gas_burnt = u64::MAX;
     }
 
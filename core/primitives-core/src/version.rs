@@ -305,6 +305,15 @@ pub enum ProtocolFeature {
     /// receipts.
     ReducedGasRefunds,
     SaturatingFloatToInt,
+    /// Enables the bulk memory operations and reference types Wasm proposals.
+    /// Bulk operations are charged per byte or table element processed.
+    WasmBulkMemoryReferenceTypes,
+    /// Enables the `UpgradeContract` action, which deploys a contract and runs its migration
+    /// method atomically.
+    UpgradeContractAction,
+    /// Allows contracts to schedule receipts that are executed once the chain reaches a given
+    /// block height.
+    ScheduledReceipts,
 }
 
 impl ProtocolFeature {
@@ -406,6 +415,9 @@ impl ProtocolFeature {
             // that always enables this for mocknet (see config_mocknet function).
             ProtocolFeature::ShuffleShardAssignments => 143,
             ProtocolFeature::ExcludeExistingCodeFromWitnessForCodeLen => 148,
+            ProtocolFeature::WasmBulkMemoryReferenceTypes => 149,
+            ProtocolFeature::UpgradeContractAction => 150,
+            ProtocolFeature::ScheduledReceipts => 151,
             // Place features that are not yet in Nightly below this line.
         }
     }
@@ -425,7 +437,7 @@ pub const MIN_SUPPORTED_PROTOCOL_VERSION: ProtocolVersion = 75;
 const STABLE_PROTOCOL_VERSION: ProtocolVersion = 78;
 
 // On nightly, pick big enough version to support all features.
-const NIGHTLY_PROTOCOL_VERSION: ProtocolVersion = 149;
+const NIGHTLY_PROTOCOL_VERSION: ProtocolVersion = 151;
 
 /// Largest protocol version supported by the current binary.
 pub const PROTOCOL_VERSION: ProtocolVersion =
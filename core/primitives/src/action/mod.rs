@@ -251,6 +251,40 @@ impl fmt::Debug for FunctionCallAction {
     }
 }
 
+/// Deploys a new contract code and calls a migration method with it, atomically.
+///
+/// The migration runs with the newly deployed code, just like a `FunctionCall` following a
+/// `DeployContract` in the same receipt would. Unlike such a pair, the deploy cannot be submitted
+/// without the migration: if the migration fails, the whole receipt fails and the previous code
+/// (together with the state it was using) stays in place.
+#[serde_as]
+#[derive(
+    BorshSerialize,
+    BorshDeserialize,
+    serde::Serialize,
+    serde::Deserialize,
+    PartialEq,
+    Eq,
+    Clone,
+    ProtocolSchema,
+)]
+pub struct UpgradeContractAction {
+    /// WebAssembly binary
+    #[serde_as(as = "Base64")]
+    pub code: Vec<u8>,
+    /// Migration method to call on the new code.
+    pub migration: FunctionCallAction,
+}
+
+impl fmt::Debug for UpgradeContractAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UpgradeContractAction")
+            .field("code", &format_args!("{}", base64(&self.code)))
+            .field("migration", &self.migration)
+            .finish()
+    }
+}
+
 /// An action which stakes signer_id tokens and setup's validator public key
 #[derive(
     BorshSerialize,
@@ -315,6 +349,8 @@ pub enum Action {
     Delegate(Box<delegate::SignedDelegateAction>),
     DeployGlobalContract(DeployGlobalContractAction),
     UseGlobalContract(Box<UseGlobalContractAction>),
+    /// Sets a Wasm code to a receiver_id and calls a migration method with it atomically
+    UpgradeContract(Box<UpgradeContractAction>),
 }
 
 const _: () = assert!(
@@ -329,6 +365,7 @@ impl Action {
     pub fn get_prepaid_gas(&self) -> Gas {
         match self {
             Action::FunctionCall(a) => a.gas,
+            Action::UpgradeContract(a) => a.migration.gas,
             _ => 0,
         }
     }
@@ -336,6 +373,7 @@ impl Action {
         match self {
             Action::FunctionCall(a) => a.deposit,
             Action::Transfer(a) => a.deposit,
+            Action::UpgradeContract(a) => a.migration.deposit,
             _ => 0,
         }
     }
@@ -394,3 +432,9 @@ impl From<DeleteAccountAction> for Action {
         Self::DeleteAccount(delete_account_action)
     }
 }
+
+impl From<UpgradeContractAction> for Action {
+    fn from(upgrade_contract_action: UpgradeContractAction) -> Self {
+        Self::UpgradeContract(Box::new(upgrade_contract_action))
+    }
+}
@@ -123,7 +123,7 @@ impl Block {
         clock: near_time::Clock,
         sandbox_delta_time: Option<near_time::Duration>,
         optimistic_block: Option<OptimisticBlock>,
-    ) -> Self {
+    ) -> Result<Self, crate::validator_signer::SignerError> {
         // Collect aggregate of validators and gas usage/limits from chunks.
         let mut prev_validator_proposals = vec![];
         let mut gas_used = 0;
@@ -154,9 +154,8 @@ impl Block {
         let new_total_supply = prev.total_supply() + minted_amount.unwrap_or(0) - balance_burnt;
 
         // Use the optimistic block data if available, otherwise compute it.
-        let (time, vrf_value, vrf_proof, random_value) = optimistic_block
-            .as_ref()
-            .map(|ob| {
+        let (time, vrf_value, vrf_proof, random_value) = match &optimistic_block {
+            Some(ob) => {
                 tracing::debug!(target: "client", "Taking metadata from optimistic block");
                 (
                     ob.inner.block_timestamp,
@@ -164,11 +163,12 @@ impl Block {
                     ob.inner.vrf_proof,
                     ob.inner.random_value,
                 )
-            })
-            .unwrap_or_else(|| {
+            }
+            None => {
                 let now = clock.now_utc().unix_timestamp_nanos() as u64;
-                get_block_metadata(prev, signer, now, sandbox_delta_time)
-            });
+                get_block_metadata(prev, signer, now, sandbox_delta_time)?
+            }
+        };
 
         let last_ds_final_block =
             if height == prev.height() + 1 { prev.hash() } else { prev.last_ds_final_block() };
@@ -236,9 +236,9 @@ impl Block {
             block_merkle_root,
             prev.height(),
             chunk_endorsements_bitmap,
-        );
+        )?;
 
-        Self::new_block(header, body)
+        Ok(Self::new_block(header, body))
     }
 
     pub fn verify_total_supply(
@@ -1,27 +1,32 @@
 use std::collections::BTreeMap;
 
 use borsh::{BorshDeserialize, BorshSerialize};
-use near_primitives_core::types::{Balance, BlockHeight, Gas, ShardId};
+use near_primitives_core::types::{Balance, BlockHeight, Compute, Gas, ShardId};
+use serde_with::serde_as;
 
 use crate::bandwidth_scheduler::{
     Bandwidth, BandwidthRequest, BandwidthRequestValues, BandwidthRequests,
     BandwidthSchedulerParams, BlockBandwidthRequests,
 };
+use crate::congestion_info::CongestionInfo;
+use crate::serialize::dec_format;
 
 /// Information gathered during chunk application.
 /// Provides insight into what happened when the chunk was applied.
 /// How many transactions and receipts were processed, buffered, forwarded, etc.
 /// Useful for debugging, metrics and sanity checks.
-#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize, serde::Serialize, serde::Deserialize)]
 pub enum ChunkApplyStats {
     V0(ChunkApplyStatsV0),
+    V1(ChunkApplyStatsV1),
 }
 
 /// Information gathered during chunk application.
 /// This feature is still in development. Consider V0 as unstable, fields might be added or removed
 /// from it at any time. We will do proper versioning after stabilization when there will be other
 /// services depending on this structure.
-#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+/// Kept to read the stats stored before V1, new stats are always V1.
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize, serde::Serialize, serde::Deserialize)]
 pub struct ChunkApplyStatsV0 {
     /// Height at which the chunk was applied
     pub height: BlockHeight,
@@ -42,9 +47,42 @@ pub struct ChunkApplyStatsV0 {
     pub balance: BalanceStats,
 }
 
-impl ChunkApplyStatsV0 {
-    pub fn new(height: BlockHeight, shard_id: ShardId) -> ChunkApplyStatsV0 {
-        ChunkApplyStatsV0 {
+/// Information gathered during chunk application, V0 extended with the resources used by
+/// the chunk. Like V0, fields might still be added or removed, but only together with a new
+/// version, as the stats are persisted in `DBCol::ChunkApplyStats`.
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize, serde::Serialize, serde::Deserialize)]
+pub struct ChunkApplyStatsV1 {
+    /// Height at which the chunk was applied
+    pub height: BlockHeight,
+    /// Shard ID of the chunk
+    pub shard_id: ShardId,
+    /// Was this chunk applied as a new (non-missing) chunk or a missing one (apply_old_chunk)?
+    pub is_new_chunk: bool,
+    /// Number of new transactions in this chunk
+    pub transactions_num: u64,
+    /// Number of incoming receipts to this chunk
+    pub incoming_receipts_num: u64,
+
+    /// Receipt sink stats - forwarded receipts, buffered receipts, outgoing limits
+    pub receipt_sink: ReceiptSinkStats,
+    /// Bandwidth scheduler stats
+    pub bandwidth_scheduler: BandwidthSchedulerStats,
+    /// Balance stats - used in balance checker.
+    pub balance: BalanceStats,
+    /// Gas burnt by the transactions and receipts processed in this chunk.
+    pub gas_burnt: Gas,
+    /// Compute usage of the transactions and receipts processed in this chunk. It is higher
+    /// than `gas_burnt` when the chunk ran operations with compute costs above their gas costs.
+    pub compute_usage: Compute,
+    /// Trie reads and writes done while applying the chunk.
+    pub storage: StorageStats,
+    /// Congestion info of the shard after applying the chunk.
+    pub congestion_info: Option<CongestionInfo>,
+}
+
+impl ChunkApplyStatsV1 {
+    pub fn new(height: BlockHeight, shard_id: ShardId) -> ChunkApplyStatsV1 {
+        ChunkApplyStatsV1 {
             height: height,
             shard_id: shard_id,
             is_new_chunk: false,
@@ -53,6 +91,10 @@ impl ChunkApplyStatsV0 {
             bandwidth_scheduler: Default::default(),
             balance: Default::default(),
             receipt_sink: Default::default(),
+            gas_burnt: 0,
+            compute_usage: 0,
+            storage: Default::default(),
+            congestion_info: None,
         }
     }
 
@@ -65,8 +107,8 @@ impl ChunkApplyStatsV0 {
     }
 
     /// Dummy data for tests.
-    pub fn dummy() -> ChunkApplyStatsV0 {
-        ChunkApplyStatsV0 {
+    pub fn dummy() -> ChunkApplyStatsV1 {
+        ChunkApplyStatsV1 {
             height: 0,
             shard_id: ShardId::new(0),
             is_new_chunk: false,
@@ -75,23 +117,34 @@ impl ChunkApplyStatsV0 {
             bandwidth_scheduler: Default::default(),
             balance: Default::default(),
             receipt_sink: Default::default(),
+            gas_burnt: 0,
+            compute_usage: 0,
+            storage: Default::default(),
+            congestion_info: None,
         }
     }
 }
 
-#[derive(Debug, Clone, Default, BorshSerialize, BorshDeserialize)]
+#[serde_as]
+#[derive(
+    Debug, Clone, Default, BorshSerialize, BorshDeserialize, serde::Serialize, serde::Deserialize,
+)]
 pub struct BandwidthSchedulerStats {
     /// Scheduler params, should always be Some but there is no Default impl.
     pub params: Option<BandwidthSchedulerParams>,
     /// Bandwidth requests generated by previous chunks, used as input to bandwidth scheduler.
+    #[serde_as(as = "Vec<(_, _)>")]
     pub prev_bandwidth_requests: BTreeMap<(ShardId, ShardId), Vec<Bandwidth>>,
     /// Number of previous bandwidth requests (prev_bandwidth_requests.len()).
     pub prev_bandwidth_requests_num: u64,
     /// How long it took to run the bandwidth scheduler (in milliseconds).
+    #[serde(with = "dec_format")]
     pub time_to_run_ms: u128,
     /// Bandwidth granted by the scheduler.
+    #[serde_as(as = "Vec<(_, _)>")]
     pub granted_bandwidth: BTreeMap<(ShardId, ShardId), Bandwidth>,
     /// Bandwidth requests generated at the end of chunk application.
+    #[serde_as(as = "Vec<(_, _)>")]
     pub new_bandwidth_requests: BTreeMap<(ShardId, ShardId), Vec<Bandwidth>>,
 }
 
@@ -144,7 +197,9 @@ impl BandwidthSchedulerStats {
     }
 }
 
-#[derive(Debug, Clone, Default, BorshSerialize, BorshDeserialize)]
+#[derive(
+    Debug, Clone, Default, BorshSerialize, BorshDeserialize, serde::Serialize, serde::Deserialize,
+)]
 pub struct ReceiptSinkStats {
     /// Outgoing size and gas limits to every shard.
     pub outgoing_limits: BTreeMap<ShardId, OutgoingLimitStats>,
@@ -171,20 +226,25 @@ impl ReceiptSinkStats {
     }
 }
 
-#[derive(Debug, Clone, Default, BorshSerialize, BorshDeserialize)]
+#[derive(
+    Debug, Clone, Default, BorshSerialize, BorshDeserialize, serde::Serialize, serde::Deserialize,
+)]
 pub struct OutgoingLimitStats {
     pub size: u64,
     pub gas: Gas,
 }
 
 /// Stats about a set of receipts
-#[derive(Debug, Clone, Default, BorshSerialize, BorshDeserialize)]
+#[derive(
+    Debug, Clone, Default, BorshSerialize, BorshDeserialize, serde::Serialize, serde::Deserialize,
+)]
 pub struct ReceiptsStats {
     /// Number of receipts
     pub num: u64,
     /// Total size of receipts, as calculated by `congestion_control::compute_receipt_size`.
     pub total_size: u64,
     /// Total gas of receipts, as calculated by `compute_receipt_congestion_gas`.
+    #[serde(with = "dec_format")]
     pub total_gas: u128,
 }
 
@@ -198,17 +258,43 @@ impl ReceiptsStats {
 }
 
 /// Stats about token balance, used in balance checker.
-#[derive(Debug, Clone, Default, BorshSerialize, BorshDeserialize)]
+#[derive(
+    Debug, Clone, Default, BorshSerialize, BorshDeserialize, serde::Serialize, serde::Deserialize,
+)]
 pub struct BalanceStats {
+    #[serde(with = "dec_format")]
     pub tx_burnt_amount: Balance,
+    #[serde(with = "dec_format")]
     pub slashed_burnt_amount: Balance,
+    #[serde(with = "dec_format")]
     pub other_burnt_amount: Balance,
     /// This is a negative amount. This amount was not charged from the account that issued
     /// the transaction. It's likely due to the delayed queue of the receipts.
+    #[serde(with = "dec_format")]
     pub gas_deficit_amount: Balance,
+    #[serde(with = "dec_format")]
     pub global_actions_burnt_amount: Balance,
 }
 
+/// Stats about trie reads and writes done during chunk application.
+#[derive(
+    Debug, Clone, Default, BorshSerialize, BorshDeserialize, serde::Serialize, serde::Deserialize,
+)]
+pub struct StorageStats {
+    /// Trie nodes and values served from the shard cache. With memtries enabled trie nodes are
+    /// read from memory, so only values are read through the shard cache.
+    pub shard_cache_hits: u64,
+    /// Trie nodes and values that missed the shard cache and were read from the prefetcher
+    /// or the database.
+    pub shard_cache_misses: u64,
+    /// Number of trie keys updated or removed.
+    pub state_changes_num: u64,
+    /// Number of trie nodes and values written to the database, counting refcount increments.
+    pub trie_insertions_num: u64,
+    /// Number of trie nodes and values removed from the database, counting refcount decrements.
+    pub trie_deletions_num: u64,
+}
+
 /// Convert a bandwidth request from the bitmap representation to a list of requested values.
 fn get_requested_values(
     bandwidth_request: &BandwidthRequest,
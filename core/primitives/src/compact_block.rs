@@ -0,0 +1,224 @@
+//! Compact blocks, used to relay blocks to the peers which already know most of their
+//! contents, similarly to BIP152 in Bitcoin.
+//!
+//! A compact block carries the full block header and replaces the chunk headers with
+//! their short ids. The receiver matches the short ids against the chunk headers it
+//! already has (the chunk headers of the previous block and the chunks received from the
+//! chunk producers) and fetches the missing ones from the sender. The approvals stay in
+//! the header: they are sent only to the next block producer, so the other nodes couldn't
+//! match them anyway. The reconstructed block is checked against the chunk headers root of
+//! the header, so a short id collision results in an error rather than in an invalid block.
+use crate::block::{Block, BlockHeader};
+use crate::block_body::{BlockBody, ChunkEndorsementSignatures};
+use crate::hash::{CryptoHash, hash};
+use crate::sharding::ShardChunkHeader;
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_crypto::vrf::{Proof, Value};
+use std::collections::HashMap;
+
+/// Short id of a chunk header: the first 8 bytes of its hash salted with the block hash,
+/// so that colliding chunk headers cannot be prepared in advance.
+pub type ShortId = u64;
+
+pub fn chunk_short_id(block_hash: &CryptoHash, chunk: &ShardChunkHeader) -> ShortId {
+    let salted = [block_hash.as_ref(), chunk.chunk_hash().as_bytes()].concat();
+    u64::from_le_bytes(hash(&salted).as_ref()[..8].try_into().unwrap())
+}
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum CompactBlockError {
+    #[error("{chunks} chunk headers are missing")]
+    Missing { chunks: usize },
+    #[error("chunk headers don't match the chunk headers root")]
+    InvalidChunkHeadersRoot,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CompactBlock {
+    pub header: BlockHeader,
+    /// Short ids of the chunk headers, in the order of the shards.
+    pub chunks: Vec<ShortId>,
+    pub vrf_value: Value,
+    pub vrf_proof: Proof,
+    pub chunk_endorsements: Vec<ChunkEndorsementSignatures>,
+}
+
+impl CompactBlock {
+    /// Returns None for the blocks from before the `ChunkValidation` protocol version,
+    /// which cannot be relayed as compact blocks.
+    pub fn new(block: &Block) -> Option<Self> {
+        let Block::BlockV4(block_v4) = block else {
+            return None;
+        };
+        let BlockBody::V2(body) = &block_v4.body else {
+            return None;
+        };
+        let block_hash = block.hash();
+        Some(Self {
+            header: block.header().clone(),
+            chunks: body.chunks.iter().map(|c| chunk_short_id(block_hash, c)).collect(),
+            vrf_value: body.vrf_value,
+            vrf_proof: body.vrf_proof,
+            chunk_endorsements: body.chunk_endorsements.clone(),
+        })
+    }
+
+    pub fn hash(&self) -> &CryptoHash {
+        self.header.hash()
+    }
+
+    /// Starts the reconstruction of the block from the chunk headers known locally.
+    /// The ones which don't match any short id are ignored. The chunk headers are used
+    /// as is, so the new chunks must already have `height_included` set to the height
+    /// of the block.
+    pub fn reconstruct<'a>(
+        self,
+        known_chunks: impl IntoIterator<Item = &'a ShardChunkHeader>,
+    ) -> PartialBlock {
+        let known_chunks: HashMap<_, _> =
+            known_chunks.into_iter().map(|c| (chunk_short_id(self.hash(), c), c)).collect();
+        let chunks =
+            self.chunks.iter().map(|id| known_chunks.get(id).map(|&c| c.clone())).collect();
+        PartialBlock { compact: self, chunks }
+    }
+}
+
+/// Block being reconstructed from a compact block.
+#[derive(Debug, Clone)]
+pub struct PartialBlock {
+    compact: CompactBlock,
+    chunks: Vec<Option<ShardChunkHeader>>,
+}
+
+impl PartialBlock {
+    pub fn compact_block(&self) -> &CompactBlock {
+        &self.compact
+    }
+
+    /// Indices of the chunk headers which are still missing.
+    pub fn missing_chunks(&self) -> Vec<u64> {
+        (0..self.chunks.len()).filter(|&i| self.chunks[i].is_none()).map(|i| i as u64).collect()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.chunks.iter().all(Option::is_some)
+    }
+
+    /// Adds a chunk header fetched from a peer. Returns false if the header doesn't
+    /// match the short id at the given index.
+    pub fn add_chunk(&mut self, index: u64, chunk: ShardChunkHeader) -> bool {
+        let Some(&id) = self.compact.chunks.get(index as usize) else {
+            return false;
+        };
+        if chunk_short_id(self.compact.hash(), &chunk) != id {
+            return false;
+        }
+        self.chunks[index as usize] = Some(chunk);
+        true
+    }
+
+    pub fn into_block(self) -> Result<Block, CompactBlockError> {
+        let missing_chunks = self.missing_chunks().len();
+        if missing_chunks > 0 {
+            return Err(CompactBlockError::Missing { chunks: missing_chunks });
+        }
+        let CompactBlock { header, vrf_value, vrf_proof, chunk_endorsements, .. } = self.compact;
+        let chunks: Vec<_> = self.chunks.into_iter().map(Option::unwrap).collect();
+        if &Block::compute_chunk_headers_root(&chunks).0 != header.chunk_headers_root() {
+            return Err(CompactBlockError::InvalidChunkHeadersRoot);
+        }
+        Ok(Block::new_block(
+            header,
+            BlockBody::new(chunks, vrf_value, vrf_proof, chunk_endorsements),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::genesis::{genesis_block, genesis_chunks};
+    use crate::test_utils::{TestBlockBuilder, create_test_signer};
+    use crate::types::{ShardId, StateRoot};
+    use crate::version::PROTOCOL_VERSION;
+    use std::sync::Arc;
+
+    /// Returns a block with 4 chunks and 2 out of 3 approvals.
+    fn make_block() -> Block {
+        let clock = near_time::FakeClock::default();
+        let shard_ids: Vec<_> = (0..4).map(ShardId::new).collect();
+        let chunks = genesis_chunks(
+            vec![StateRoot::new()],
+            vec![Default::default(); shard_ids.len()],
+            &shard_ids,
+            1_000,
+            0,
+            PROTOCOL_VERSION,
+        );
+        let genesis = genesis_block(
+            PROTOCOL_VERSION,
+            chunks.into_iter().map(|c| c.take_header()).collect(),
+            clock.now_utc(),
+            0,
+            1_000,
+            1_000,
+            &vec![],
+        );
+        let signer = Arc::new(create_test_signer("test"));
+        let approvals = vec![signer.sign_bytes(b"a"), signer.sign_bytes(b"b")];
+        TestBlockBuilder::new(clock.clock(), &genesis, signer)
+            .approvals(vec![
+                Some(Box::new(approvals[0].clone())),
+                None,
+                Some(Box::new(approvals[1].clone())),
+            ])
+            .build()
+    }
+
+    #[test]
+    fn test_reconstruct_from_known() {
+        let block = make_block();
+        let compact = CompactBlock::new(&block).unwrap();
+        assert_eq!(compact.hash(), block.hash());
+        assert_eq!(compact.header.approvals(), block.header().approvals());
+        assert!(borsh::object_length(&compact).unwrap() < borsh::object_length(&block).unwrap());
+
+        let partial = compact.reconstruct(block.chunks().iter_raw());
+        assert!(partial.is_complete());
+        assert_eq!(partial.into_block().unwrap(), block);
+    }
+
+    #[test]
+    fn test_reconstruct_with_fetched() {
+        let block = make_block();
+        let compact = CompactBlock::new(&block).unwrap();
+        let chunks: Vec<_> = block.chunks().iter_raw().cloned().collect();
+
+        let mut partial = compact.reconstruct(&chunks[..2]);
+        assert_eq!(partial.missing_chunks(), vec![2, 3]);
+        assert_eq!(partial.clone().into_block(), Err(CompactBlockError::Missing { chunks: 2 }));
+
+        // Chunk headers which don't match the short ids are rejected.
+        assert!(!partial.add_chunk(2, chunks[3].clone()));
+        assert!(!partial.add_chunk(4, chunks[3].clone()));
+
+        assert!(partial.add_chunk(2, chunks[2].clone()));
+        assert!(partial.add_chunk(3, chunks[3].clone()));
+        assert!(partial.is_complete());
+        assert_eq!(partial.into_block().unwrap(), block);
+    }
+
+    /// `height_included` is not covered by the short id, but it is covered by the chunk
+    /// headers root, so a chunk header with a wrong one results in an error.
+    #[test]
+    fn test_reconstruct_wrong_height_included() {
+        let block = make_block();
+        let compact = CompactBlock::new(&block).unwrap();
+        let mut chunks: Vec<_> = block.chunks().iter_raw().cloned().collect();
+        *chunks[1].height_included_mut() += 1;
+
+        let partial = compact.reconstruct(&chunks);
+        assert!(partial.is_complete());
+        assert_eq!(partial.into_block(), Err(CompactBlockError::InvalidChunkHeadersRoot));
+    }
+}
@@ -15,6 +15,7 @@ pub mod block_body;
 pub mod block_header;
 pub mod challenge;
 pub mod chunk_apply_stats;
+pub mod compact_block;
 pub mod congestion_info;
 pub mod epoch_block_info;
 pub mod epoch_info;
@@ -35,6 +36,7 @@ pub mod sandbox;
 pub mod shard_layout;
 pub mod sharding;
 pub mod signable_message;
+pub mod signing_history;
 pub mod state;
 pub mod state_part;
 pub mod state_record;
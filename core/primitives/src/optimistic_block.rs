@@ -58,11 +58,12 @@ impl OptimisticBlock {
         signer: &crate::validator_signer::ValidatorSigner,
         now: u64,
         sandbox_delta_time: Option<near_time::Duration>,
-    ) -> Self {
+    ) -> Result<Self, crate::validator_signer::SignerError> {
         use crate::utils::get_block_metadata;
+        use crate::validator_signer::SignedMessageKind;
         let prev_block_hash = *prev_block_header.hash();
         let (time, vrf_value, vrf_proof, random_value) =
-            get_block_metadata(prev_block_header, signer, now, sandbox_delta_time);
+            get_block_metadata(prev_block_header, signer, now, sandbox_delta_time)?;
 
         let inner = OptimisticBlockInner {
             prev_block_hash,
@@ -75,9 +76,9 @@ impl OptimisticBlock {
         };
 
         let hash = hash(&borsh::to_vec(&inner).expect("Failed to serialize"));
-        let signature = signer.sign_bytes(hash.as_ref());
+        let signature = signer.sign_message(SignedMessageKind::Other, hash.as_ref())?;
 
-        Self { inner, signature, hash }
+        Ok(Self { inner, signature, hash })
     }
 
     #[cfg(all(feature = "clock", feature = "test_features"))]
@@ -89,7 +90,8 @@ impl OptimisticBlock {
         sandbox_delta_time: Option<near_time::Duration>,
         adv_type: OptimisticBlockAdvType,
     ) -> Self {
-        let original = Self::produce(prev_block_header, height, signer, now, sandbox_delta_time);
+        let original = Self::produce(prev_block_header, height, signer, now, sandbox_delta_time)
+            .expect("failed to sign the optimistic block");
         Self::alter(&original, signer, adv_type)
     }
 
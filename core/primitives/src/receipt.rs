@@ -823,6 +823,18 @@ pub struct PromiseYieldTimeout {
     pub expires_at: BlockHeight,
 }
 
+/// Metadata of the scheduled receipts queue. Scheduled receipts are grouped by
+/// the block height they are scheduled for, each group being a separate
+/// `TrieQueueIndices` queue.
+#[derive(Default, BorshSerialize, BorshDeserialize, Clone, PartialEq, Debug, ProtocolSchema)]
+pub struct ScheduledReceiptIndices {
+    /// Lowest block height which may still have scheduled receipts. Only
+    /// meaningful when `len` is not zero.
+    pub next_height: BlockHeight,
+    /// Total number of scheduled receipts over all block heights.
+    pub len: u64,
+}
+
 /// Stores indices for a persistent queue in the state trie.
 #[derive(Default, BorshSerialize, BorshDeserialize, Clone, PartialEq, Debug, ProtocolSchema)]
 pub struct TrieQueueIndices {
@@ -8,7 +8,9 @@ use crate::transaction::SignedTransaction;
 use crate::transaction::ValidatedTransaction;
 use crate::types::validator_stake::{ValidatorStake, ValidatorStakeIter, ValidatorStakeV1};
 use crate::types::{Balance, BlockHeight, Gas, MerkleHash, ShardId, StateRoot};
-use crate::validator_signer::{EmptyValidatorSigner, ValidatorSigner};
+use crate::validator_signer::{
+    EmptyValidatorSigner, SignedMessageKind, SignerError, ValidatorSigner,
+};
 use crate::version::ProtocolVersion;
 use borsh::{BorshDeserialize, BorshSerialize};
 use near_crypto::Signature;
@@ -175,6 +177,7 @@ impl ShardChunkHeaderV2 {
             Default::default(),
             &EmptyValidatorSigner::default().into(),
         )
+        .expect("the empty signer never fails")
     }
 
     pub fn init(&mut self) {
@@ -203,7 +206,7 @@ impl ShardChunkHeaderV2 {
         tx_root: CryptoHash,
         prev_validator_proposals: Vec<ValidatorStakeV1>,
         signer: &ValidatorSigner,
-    ) -> Self {
+    ) -> Result<Self, SignerError> {
         let inner = ShardChunkHeaderInnerV1 {
             prev_block_hash,
             prev_state_root,
@@ -220,8 +223,9 @@ impl ShardChunkHeaderV2 {
             prev_validator_proposals,
         };
         let hash = Self::compute_hash(&inner);
-        let signature = signer.sign_bytes(hash.as_ref());
-        Self { inner, height_included: 0, signature, hash }
+        let kind = SignedMessageKind::ChunkHeader { height, shard_id };
+        let signature = signer.sign_message(kind, hash.as_ref())?;
+        Ok(Self { inner, height_included: 0, signature, hash })
     }
 }
 
@@ -260,6 +264,7 @@ impl ShardChunkHeaderV3 {
             BandwidthRequests::empty(),
             &EmptyValidatorSigner::default().into(),
         )
+        .expect("the empty signer never fails")
     }
 
     pub fn init(&mut self) {
@@ -290,7 +295,7 @@ impl ShardChunkHeaderV3 {
         congestion_info: CongestionInfo,
         bandwidth_requests: BandwidthRequests,
         signer: &ValidatorSigner,
-    ) -> Self {
+    ) -> Result<Self, SignerError> {
         let inner = ShardChunkHeaderInner::V4(ShardChunkHeaderInnerV4 {
             prev_block_hash,
             prev_state_root,
@@ -311,10 +316,17 @@ impl ShardChunkHeaderV3 {
         Self::from_inner(inner, signer)
     }
 
-    pub fn from_inner(inner: ShardChunkHeaderInner, signer: &ValidatorSigner) -> Self {
+    pub fn from_inner(
+        inner: ShardChunkHeaderInner,
+        signer: &ValidatorSigner,
+    ) -> Result<Self, SignerError> {
         let hash = Self::compute_hash(&inner);
-        let signature = signer.sign_bytes(hash.as_ref());
-        Self { inner, height_included: 0, signature, hash }
+        let kind = SignedMessageKind::ChunkHeader {
+            height: inner.height_created(),
+            shard_id: inner.shard_id(),
+        };
+        let signature = signer.sign_message(kind, hash.as_ref())?;
+        Ok(Self { inner, height_included: 0, signature, hash })
     }
 }
 
@@ -633,6 +645,7 @@ impl ShardChunkHeaderV1 {
             Default::default(),
             &EmptyValidatorSigner::default().into(),
         )
+        .expect("the empty signer never fails")
     }
 
     pub fn init(&mut self) {
@@ -665,7 +678,7 @@ impl ShardChunkHeaderV1 {
         tx_root: CryptoHash,
         prev_validator_proposals: Vec<ValidatorStakeV1>,
         signer: &ValidatorSigner,
-    ) -> Self {
+    ) -> Result<Self, SignerError> {
         let inner = ShardChunkHeaderInnerV1 {
             prev_block_hash,
             prev_state_root,
@@ -682,8 +695,9 @@ impl ShardChunkHeaderV1 {
             prev_validator_proposals,
         };
         let hash = Self::compute_hash(&inner);
-        let signature = signer.sign_bytes(hash.as_ref());
-        Self { inner, height_included: 0, signature, hash }
+        let kind = SignedMessageKind::ChunkHeader { height, shard_id };
+        let signature = signer.sign_message(kind, hash.as_ref())?;
+        Ok(Self { inner, height_included: 0, signature, hash })
     }
 }
 
@@ -1332,7 +1346,7 @@ impl ShardChunkWithEncoding {
         bandwidth_requests: BandwidthRequests,
         signer: &ValidatorSigner,
         rs: &reed_solomon_erasure::galois_8::ReedSolomon,
-    ) -> (ShardChunkWithEncoding, Vec<MerklePath>) {
+    ) -> Result<(ShardChunkWithEncoding, Vec<MerklePath>), SignerError> {
         let signed_txs =
             validated_txs.into_iter().map(|validated_tx| validated_tx.into_signed_tx()).collect();
         let transaction_receipt = TransactionReceipt(signed_txs, prev_outgoing_receipts);
@@ -1359,14 +1373,14 @@ impl ShardChunkWithEncoding {
             congestion_info,
             bandwidth_requests,
             signer,
-        ));
+        )?);
         let encoded_shard_chunk = EncodedShardChunk::V2(EncodedShardChunkV2 { header, content });
         let shard_chunk = ShardChunk::new(
             encoded_shard_chunk.cloned_header(),
             signed_txs,
             prev_outgoing_receipts,
         );
-        (Self { shard_chunk, bytes: encoded_shard_chunk }, merkle_paths)
+        Ok((Self { shard_chunk, bytes: encoded_shard_chunk }, merkle_paths))
     }
 
     pub fn from_encoded_shard_chunk(bytes: EncodedShardChunk) -> Result<Self, std::io::Error> {
@@ -0,0 +1,223 @@
+//! Double-sign protection.
+//!
+//! A validator must never sign two different messages of the same kind for the
+//! same height. [`SigningHistory`] keeps, per message kind, the highest height
+//! signed so far together with the hash of the signed data, and refuses to sign
+//! anything below the watermark or anything different at the watermark. The
+//! history is written to disk and fsync'd before the signature is released, so
+//! it survives crashes and restarts.
+//!
+//! When a validator moves to new hardware its history has to move with it,
+//! otherwise the new host could sign over what the old one already signed. See
+//! [`SigningHistory::export`] and [`SigningHistory::import`].
+
+use crate::hash::{CryptoHash, hash};
+use crate::types::{BlockHeight, ShardId};
+use crate::validator_signer::{SignedMessageKind, SignerError};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Message kinds tracked by separate watermarks.
+#[derive(
+    serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord,
+)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum WatermarkKey {
+    Approval,
+    BlockHeader,
+    ChunkHeader { shard_id: ShardId },
+}
+
+impl WatermarkKey {
+    fn from_kind(kind: &SignedMessageKind) -> Option<Self> {
+        match kind {
+            SignedMessageKind::Approval { .. } => Some(WatermarkKey::Approval),
+            SignedMessageKind::BlockHeader { .. } => Some(WatermarkKey::BlockHeader),
+            SignedMessageKind::ChunkHeader { shard_id, .. } => {
+                Some(WatermarkKey::ChunkHeader { shard_id: *shard_id })
+            }
+            SignedMessageKind::Other => None,
+        }
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Watermark {
+    #[serde(flatten)]
+    pub key: WatermarkKey,
+    /// Highest height signed for the kind.
+    pub height: BlockHeight,
+    /// Hash of the data signed at `height`.
+    pub data_hash: CryptoHash,
+}
+
+#[derive(Debug)]
+pub struct SigningHistory {
+    path: PathBuf,
+    watermarks: BTreeMap<WatermarkKey, Watermark>,
+}
+
+impl SigningHistory {
+    /// Opens the history stored at `path`, starting with an empty one if the
+    /// file doesn't exist.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let watermarks = match std::fs::read(path) {
+            Ok(bytes) => Self::parse(&bytes)?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => vec![],
+            Err(err) => return Err(err),
+        };
+        let watermarks = watermarks.into_iter().map(|watermark| (watermark.key, watermark));
+        Ok(Self { path: path.to_path_buf(), watermarks: watermarks.collect() })
+    }
+
+    /// Parses watermarks in the format of the history file, which is also the
+    /// format of exports.
+    pub fn parse(bytes: &[u8]) -> io::Result<Vec<Watermark>> {
+        serde_json::from_slice(bytes).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    pub fn watermarks(&self) -> impl Iterator<Item = &Watermark> {
+        self.watermarks.values()
+    }
+
+    /// Serializes the history to be imported on another host.
+    pub fn export(&self) -> io::Result<Vec<u8>> {
+        let watermarks: Vec<&Watermark> = self.watermarks.values().collect();
+        Ok(serde_json::to_vec_pretty(&watermarks)?)
+    }
+
+    /// Merges watermarks exported from another host into this history. Only
+    /// ever raises watermarks: for every kind the higher of the two watermarks
+    /// is kept, and on equal heights the local one.
+    pub fn import(&mut self, watermarks: Vec<Watermark>) -> io::Result<usize> {
+        let mut raised = 0;
+        for watermark in watermarks {
+            let local = self.watermarks.get(&watermark.key);
+            if local.is_none_or(|local| local.height < watermark.height) {
+                self.watermarks.insert(watermark.key, watermark);
+                raised += 1;
+            }
+        }
+        self.persist()?;
+        Ok(raised)
+    }
+
+    /// Checks that signing `data` as a message of the given kind doesn't
+    /// conflict with anything signed before and records it. The record is
+    /// durable once this returns `Ok`.
+    pub fn check_and_record(
+        &mut self,
+        kind: &SignedMessageKind,
+        data: &[u8],
+    ) -> Result<(), SignerError> {
+        let (Some(key), Some(height)) = (WatermarkKey::from_kind(kind), kind.height()) else {
+            return Ok(());
+        };
+        let data_hash = hash(data);
+        if let Some(watermark) = self.watermarks.get(&key) {
+            if height < watermark.height
+                || (height == watermark.height && data_hash != watermark.data_hash)
+            {
+                return Err(SignerError::DoubleSign {
+                    kind: *kind,
+                    signed_height: watermark.height,
+                });
+            }
+            if height == watermark.height {
+                // The very same message, e.g. an approval sent again.
+                return Ok(());
+            }
+        }
+        let previous = self.watermarks.insert(key, Watermark { key, height, data_hash });
+        if let Err(err) = self.persist() {
+            // Without a durable record the signature must not be released.
+            match previous {
+                Some(previous) => self.watermarks.insert(key, previous),
+                None => self.watermarks.remove(&key),
+            };
+            return Err(SignerError::Unavailable(format!(
+                "failed to persist signing history: {}",
+                err
+            )));
+        }
+        Ok(())
+    }
+
+    /// Atomically replaces the history file and fsyncs it.
+    fn persist(&self) -> io::Result<()> {
+        let mut tmp_path = self.path.clone().into_os_string();
+        tmp_path.push(".tmp");
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(&self.export()?)?;
+        file.sync_all()?;
+        std::fs::rename(&tmp_path, &self.path)?;
+        if let Some(dir) = self.path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            File::open(dir)?.sync_all()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SigningHistory;
+    use crate::types::ShardId;
+    use crate::validator_signer::{SignedMessageKind, SignerError};
+
+    #[test]
+    fn test_refuses_conflicting_signatures() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.json");
+        let mut history = SigningHistory::open(&path).unwrap();
+
+        let approval = |target_height| SignedMessageKind::Approval { target_height };
+        history.check_and_record(&approval(10), b"a").unwrap();
+        // Same message again is fine, a different one at the same height is not.
+        history.check_and_record(&approval(10), b"a").unwrap();
+        assert_eq!(
+            history.check_and_record(&approval(10), b"b"),
+            Err(SignerError::DoubleSign { kind: approval(10), signed_height: 10 })
+        );
+        assert!(history.check_and_record(&approval(9), b"c").is_err());
+        history.check_and_record(&approval(11), b"c").unwrap();
+
+        // Watermarks are kept per kind and per shard.
+        let chunk = |shard_id| SignedMessageKind::ChunkHeader {
+            height: 5,
+            shard_id: ShardId::new(shard_id),
+        };
+        history.check_and_record(&chunk(0), b"x").unwrap();
+        history.check_and_record(&chunk(1), b"y").unwrap();
+        history.check_and_record(&SignedMessageKind::Other, b"z").unwrap();
+
+        // The history survives a restart.
+        let mut history = SigningHistory::open(&path).unwrap();
+        assert_eq!(history.watermarks().count(), 3);
+        assert!(history.check_and_record(&approval(11), b"d").is_err());
+        assert!(history.check_and_record(&chunk(1), b"x").is_err());
+    }
+
+    #[test]
+    fn test_import_only_raises_watermarks() {
+        let dir = tempfile::tempdir().unwrap();
+        let approval = |target_height| SignedMessageKind::Approval { target_height };
+        let block = |height| SignedMessageKind::BlockHeader { height };
+
+        let mut old_host = SigningHistory::open(&dir.path().join("old.json")).unwrap();
+        old_host.check_and_record(&approval(20), b"a").unwrap();
+        old_host.check_and_record(&block(5), b"b").unwrap();
+
+        let mut new_host = SigningHistory::open(&dir.path().join("new.json")).unwrap();
+        new_host.check_and_record(&block(8), b"c").unwrap();
+        let exported = SigningHistory::parse(&old_host.export().unwrap()).unwrap();
+        assert_eq!(new_host.import(exported).unwrap(), 1);
+
+        assert!(new_host.check_and_record(&approval(20), b"x").is_err());
+        new_host.check_and_record(&approval(21), b"x").unwrap();
+        // The lower imported block watermark didn't replace the local one.
+        assert!(new_host.check_and_record(&block(7), b"y").is_err());
+        new_host.check_and_record(&block(9), b"y").unwrap();
+    }
+}
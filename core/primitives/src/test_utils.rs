@@ -897,6 +897,7 @@ impl TestBlockBuilder {
             None,
             None,
         )
+        .unwrap()
     }
 }
 
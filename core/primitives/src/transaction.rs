@@ -1,6 +1,6 @@
 pub use crate::action::{
     Action, AddKeyAction, CreateAccountAction, DeleteAccountAction, DeleteKeyAction,
-    DeployContractAction, FunctionCallAction, StakeAction, TransferAction,
+    DeployContractAction, FunctionCallAction, StakeAction, TransferAction, UpgradeContractAction,
 };
 use crate::errors::{InvalidTxError, TxExecutionError};
 use crate::hash::{CryptoHash, hash};
@@ -2,7 +2,7 @@ use crate::types::AccountId;
 use crate::{action::GlobalContractIdentifier, hash::CryptoHash};
 use borsh::{BorshDeserialize, BorshSerialize, to_vec};
 use near_crypto::PublicKey;
-use near_primitives_core::types::ShardId;
+use near_primitives_core::types::{BlockHeight, ShardId};
 use near_schema_checker_lib::ProtocolSchema;
 use std::mem::size_of;
 
@@ -65,6 +65,15 @@ pub mod col {
     /// Global contract code instance. Values are contract blobs,
     /// the same as for `CONTRACT_CODE`.
     pub const GLOBAL_CONTRACT_CODE: u8 = 18;
+    /// Indices of the scheduled receipts queue. A singleton per shard.
+    /// (`primitives::receipt::ScheduledReceiptIndices`)
+    pub const SCHEDULED_RECEIPT_INDICES: u8 = 19;
+    /// Head and tail indices of the queue of receipts scheduled for a given
+    /// block height. (`primitives::receipt::TrieQueueIndices`)
+    pub const SCHEDULED_RECEIPT_QUEUE_INDICES: u8 = 20;
+    /// Receipts scheduled for execution at a given block height.
+    /// (`primitives::receipt::Receipt`).
+    pub const SCHEDULED_RECEIPT: u8 = 21;
 
     /// All columns except those used for the delayed receipts queue, the yielded promises
     /// queue, and the outgoing receipts buffer, which are global state for the shard.
@@ -80,7 +89,7 @@ pub mod col {
         (PROMISE_YIELD_RECEIPT, "PromiseYieldReceipt"),
     ];
 
-    pub const ALL_COLUMNS_WITH_NAMES: [(u8, &'static str); 18] = [
+    pub const ALL_COLUMNS_WITH_NAMES: [(u8, &'static str); 21] = [
         (ACCOUNT, "Account"),
         (CONTRACT_CODE, "ContractCode"),
         (ACCESS_KEY, "AccessKey"),
@@ -99,6 +108,9 @@ pub mod col {
         (BUFFERED_RECEIPT_GROUPS_QUEUE_DATA, "BufferedReceiptGroupsQueueData"),
         (BUFFERED_RECEIPT_GROUPS_QUEUE_ITEM, "BufferedReceiptGroupsQueueItem"),
         (GLOBAL_CONTRACT_CODE, "GlobalContractCode"),
+        (SCHEDULED_RECEIPT_INDICES, "ScheduledReceiptIndices"),
+        (SCHEDULED_RECEIPT_QUEUE_INDICES, "ScheduledReceiptQueueIndices"),
+        (SCHEDULED_RECEIPT, "ScheduledReceipt"),
     ];
 }
 
@@ -235,6 +247,21 @@ pub enum TrieKey {
     GlobalContractCode {
         identifier: GlobalContractCodeIdentifier,
     },
+    /// Used to store the lowest block height with pending scheduled receipts
+    /// and the total number of scheduled receipts.
+    /// NOTE: It is a singleton per shard.
+    ScheduledReceiptIndices,
+    /// Used to store head and tail indices of the queue of receipts scheduled
+    /// for execution at the given block `height`.
+    ScheduledReceiptQueueIndices {
+        height: BlockHeight,
+    },
+    /// Used to store a scheduled receipt `primitives::receipt::Receipt` for a
+    /// given block `height` and index `u64` in the queue for that height.
+    ScheduledReceipt {
+        height: BlockHeight,
+        index: u64,
+    },
 }
 
 /// Provides `len` function.
@@ -322,6 +349,15 @@ impl TrieKey {
             TrieKey::GlobalContractCode { identifier } => {
                 col::GLOBAL_CONTRACT_CODE.len() + identifier.len()
             }
+            TrieKey::ScheduledReceiptIndices => col::SCHEDULED_RECEIPT_INDICES.len(),
+            TrieKey::ScheduledReceiptQueueIndices { height } => {
+                col::SCHEDULED_RECEIPT_QUEUE_INDICES.len() + std::mem::size_of_val(height)
+            }
+            TrieKey::ScheduledReceipt { height, index } => {
+                col::SCHEDULED_RECEIPT.len()
+                    + std::mem::size_of_val(height)
+                    + std::mem::size_of_val(index)
+            }
         }
     }
 
@@ -419,6 +455,16 @@ impl TrieKey {
                 buf.push(col::GLOBAL_CONTRACT_CODE);
                 identifier.append_into(buf);
             }
+            TrieKey::ScheduledReceiptIndices => buf.push(col::SCHEDULED_RECEIPT_INDICES),
+            TrieKey::ScheduledReceiptQueueIndices { height } => {
+                buf.push(col::SCHEDULED_RECEIPT_QUEUE_INDICES);
+                buf.extend(&height.to_le_bytes());
+            }
+            TrieKey::ScheduledReceipt { height, index } => {
+                buf.push(col::SCHEDULED_RECEIPT);
+                buf.extend(&height.to_le_bytes());
+                buf.extend(&index.to_le_bytes());
+            }
         };
         debug_assert_eq!(expected_len, buf.len() - start_len);
     }
@@ -453,6 +499,9 @@ impl TrieKey {
             // Even though global contract code might be deployed under account id, it doesn't
             // correspond to the data stored for that account id, so always returning None here.
             TrieKey::GlobalContractCode { .. } => None,
+            TrieKey::ScheduledReceiptIndices => None,
+            TrieKey::ScheduledReceiptQueueIndices { .. } => None,
+            TrieKey::ScheduledReceipt { .. } => None,
         }
     }
 }
@@ -910,6 +959,21 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_key_for_scheduled_receipts_consistency() {
+        let keys = [
+            TrieKey::ScheduledReceiptIndices,
+            TrieKey::ScheduledReceiptQueueIndices { height: 42 },
+            TrieKey::ScheduledReceipt { height: 42, index: 7 },
+        ];
+        for key in keys {
+            let raw_key = key.to_vec();
+            assert_eq!(raw_key.len(), key.len());
+            assert!(trie_key_parsers::parse_account_id_from_raw_key(&raw_key).unwrap().is_none());
+            assert_eq!(key.get_account_id(), None);
+        }
+    }
+
     #[test]
     fn test_account_id_from_trie_key() {
         for account_id_str in OK_ACCOUNT_IDS {
@@ -366,6 +366,9 @@ impl StateChanges {
                 TrieKey::BufferedReceiptGroupsQueueItem { .. } => {}
                 // Global contract code is not a part of account, so ignoring it as well.
                 TrieKey::GlobalContractCode { .. } => {}
+                TrieKey::ScheduledReceiptIndices => {}
+                TrieKey::ScheduledReceiptQueueIndices { .. } => {}
+                TrieKey::ScheduledReceipt { .. } => {}
             }
         }
 
@@ -462,7 +462,10 @@ pub fn get_block_metadata(
     signer: &crate::validator_signer::ValidatorSigner,
     now: u64,
     sandbox_delta_time: Option<near_time::Duration>,
-) -> (u64, near_crypto::vrf::Value, near_crypto::vrf::Proof, CryptoHash) {
+) -> Result<
+    (u64, near_crypto::vrf::Value, near_crypto::vrf::Proof, CryptoHash),
+    crate::validator_signer::SignerError,
+> {
     #[cfg(feature = "sandbox")]
     let now = now + sandbox_delta_time.unwrap().whole_nanoseconds() as u64;
     #[cfg(not(feature = "sandbox"))]
@@ -474,9 +477,9 @@ pub fn get_block_metadata(
     };
 
     let (vrf_value, vrf_proof) =
-        signer.compute_vrf_with_proof(prev_block_header.random_value().as_ref());
+        signer.compute_vrf_with_proof(prev_block_header.random_value().as_ref())?;
     let random_value = hash(vrf_value.0.as_ref());
-    (time, vrf_value, vrf_proof, random_value)
+    Ok((time, vrf_value, vrf_proof, random_value))
 }
 
 #[cfg(test)]
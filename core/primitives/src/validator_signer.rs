@@ -1,10 +1,12 @@
 use std::fmt::Debug;
 use std::path::Path;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
+use borsh::{BorshDeserialize, BorshSerialize};
 use near_crypto::{InMemorySigner, KeyType, PublicKey, Signature, Signer};
 
-use crate::types::AccountId;
+use crate::signing_history::SigningHistory;
+use crate::types::{AccountId, BlockHeight, ShardId};
 
 /// Enum for validator signer, that holds validator id and key used for signing data.
 #[derive(Clone, Debug, PartialEq)]
@@ -13,6 +15,8 @@ pub enum ValidatorSigner {
     Empty(EmptyValidatorSigner),
     /// Default validator signer that holds data in memory.
     InMemory(InMemoryValidatorSigner),
+    /// Validator signer that delegates signing to an external signing service.
+    Remote(RemoteValidatorSigner),
 }
 
 /// Validator signer that is used to sign blocks and approvals.
@@ -22,6 +26,7 @@ impl ValidatorSigner {
         match self {
             ValidatorSigner::Empty(signer) => signer.validator_id(),
             ValidatorSigner::InMemory(signer) => signer.validator_id(),
+            ValidatorSigner::Remote(signer) => signer.validator_id(),
         }
     }
 
@@ -30,23 +35,58 @@ impl ValidatorSigner {
         match self {
             ValidatorSigner::Empty(signer) => signer.public_key(),
             ValidatorSigner::InMemory(signer) => signer.public_key(),
+            ValidatorSigner::Remote(signer) => signer.public_key(),
         }
     }
 
+    /// Signs `data` which is not a consensus message, e.g. a network message.
+    /// The recipients verify such signatures, so if the signer fails the error
+    /// is logged and an empty signature is returned.
     pub fn sign_bytes(&self, data: &[u8]) -> Signature {
+        self.sign_message(SignedMessageKind::Other, data).unwrap_or_else(|err| {
+            tracing::error!(target: "validator_signer", %err, "Failed to sign message");
+            Signature::empty(self.public_key().key_type())
+        })
+    }
+
+    /// Signs `data` which is a message of the given kind. Signers keeping a
+    /// signing history use the kind to refuse conflicting signatures. The
+    /// message must not be produced if this fails.
+    pub fn sign_message(
+        &self,
+        kind: SignedMessageKind,
+        data: &[u8],
+    ) -> Result<Signature, SignerError> {
         match self {
-            ValidatorSigner::Empty(signer) => signer.noop_signature(),
-            ValidatorSigner::InMemory(signer) => signer.sign_bytes(data),
+            ValidatorSigner::Empty(signer) => Ok(signer.noop_signature()),
+            ValidatorSigner::InMemory(signer) => signer.sign_message(kind, data),
+            ValidatorSigner::Remote(signer) => signer.sign_message(kind, data),
+        }
+    }
+
+    /// Makes the signer consult `history` before signing messages bound to a
+    /// height and refuse to sign conflicting ones. Only applies to signers
+    /// holding the key locally, a remote signer keeps its own history.
+    pub fn with_signing_history(self, history: Arc<Mutex<SigningHistory>>) -> Self {
+        match self {
+            ValidatorSigner::InMemory(signer) => {
+                ValidatorSigner::InMemory(InMemoryValidatorSigner {
+                    history: Some(history),
+                    ..signer
+                })
+            }
+            signer => signer,
         }
     }
 
     pub fn compute_vrf_with_proof(
         &self,
         data: &[u8],
-    ) -> (near_crypto::vrf::Value, near_crypto::vrf::Proof) {
+    ) -> Result<(near_crypto::vrf::Value, near_crypto::vrf::Proof), SignerError> {
         match self {
             ValidatorSigner::Empty(_) => unimplemented!(),
             ValidatorSigner::InMemory(signer) => signer.compute_vrf_with_proof(data),
+            ValidatorSigner::Remote(signer) => signer.compute_vrf_with_proof(data),
         }
     }
 
@@ -55,10 +95,69 @@ impl ValidatorSigner {
         match self {
             ValidatorSigner::Empty(_) => unimplemented!(),
             ValidatorSigner::InMemory(signer) => signer.write_to_file(path),
+            ValidatorSigner::Remote(_) => Err(std::io::Error::other(
+                "the key of a remote validator signer is not available locally",
+            )),
         }
     }
 }
 
+/// Kind of a message signed by a validator.
+///
+/// Signing twice for the same height is what makes a validator equivocate, so
+/// messages produced at most once per height carry the height. Signers use it
+/// to keep a watermark of the highest height signed per message kind.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SignedMessageKind {
+    Approval {
+        target_height: BlockHeight,
+    },
+    BlockHeader {
+        height: BlockHeight,
+    },
+    ChunkHeader {
+        height: BlockHeight,
+        shard_id: ShardId,
+    },
+    /// Any other message, not subject to double-sign protection.
+    Other,
+}
+
+impl SignedMessageKind {
+    /// Height the message is bound to, `None` if it can be signed any number
+    /// of times.
+    pub fn height(&self) -> Option<BlockHeight> {
+        match self {
+            SignedMessageKind::Approval { target_height } => Some(*target_height),
+            SignedMessageKind::BlockHeader { height } => Some(*height),
+            SignedMessageKind::ChunkHeader { height, .. } => Some(*height),
+            SignedMessageKind::Other => None,
+        }
+    }
+}
+
+#[derive(thiserror::Error, BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub enum SignerError {
+    /// The signer refused to sign as it already signed a conflicting message.
+    #[error("refused to sign {kind:?}, a conflicting message was signed at height {signed_height}")]
+    DoubleSign { kind: SignedMessageKind, signed_height: BlockHeight },
+    /// The signer could not be reached, didn't understand the request or
+    /// could not record the signature in its signing history.
+    #[error("remote signer is unavailable: {0}")]
+    Unavailable(String),
+}
+
+/// Signing service which holds the validator key outside of the node, e.g. in
+/// an HSM or a KMS.
+pub trait RemoteSigner: Send + Sync {
+    fn sign(&self, kind: SignedMessageKind, data: &[u8]) -> Result<Signature, SignerError>;
+
+    fn compute_vrf_with_proof(
+        &self,
+        data: &[u8],
+    ) -> Result<(near_crypto::vrf::Value, near_crypto::vrf::Proof), SignerError>;
+}
+
 impl From<EmptyValidatorSigner> for ValidatorSigner {
     fn from(signer: EmptyValidatorSigner) -> Self {
         ValidatorSigner::Empty(signer)
@@ -92,23 +191,32 @@ impl EmptyValidatorSigner {
 }
 
 /// Signer that keeps secret key in memory and signs locally.
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug)]
 pub struct InMemoryValidatorSigner {
     account_id: AccountId,
     signer: Arc<Signer>,
+    /// Double-sign protection. If set, conflicting messages are not signed
+    /// and the refusal is returned as an error instead.
+    history: Option<Arc<Mutex<SigningHistory>>>,
+}
+
+impl PartialEq for InMemoryValidatorSigner {
+    fn eq(&self, other: &Self) -> bool {
+        self.account_id == other.account_id && self.signer == other.signer
+    }
 }
 
 impl InMemoryValidatorSigner {
     #[cfg(feature = "rand")]
     pub fn from_random(account_id: AccountId, key_type: KeyType) -> ValidatorSigner {
         let signer = Arc::new(InMemorySigner::from_random(account_id.clone(), key_type).into());
-        ValidatorSigner::InMemory(Self { account_id, signer })
+        ValidatorSigner::InMemory(Self { account_id, signer, history: None })
     }
 
     #[cfg(feature = "rand")]
     pub fn from_seed(account_id: AccountId, key_type: KeyType, seed: &str) -> ValidatorSigner {
         let signer = Arc::new(InMemorySigner::from_seed(account_id.clone(), key_type, seed));
-        ValidatorSigner::InMemory(Self { account_id, signer })
+        ValidatorSigner::InMemory(Self { account_id, signer, history: None })
     }
 
     pub fn public_key(&self) -> PublicKey {
@@ -119,6 +227,7 @@ impl InMemoryValidatorSigner {
         ValidatorSigner::InMemory(Self {
             account_id: signer.get_account_id(),
             signer: Arc::new(signer),
+            history: None,
         })
     }
 
@@ -131,18 +240,145 @@ impl InMemoryValidatorSigner {
         &self.account_id
     }
 
-    fn sign_bytes(&self, bytes: &[u8]) -> Signature {
-        self.signer.sign(bytes)
+    fn sign_message(
+        &self,
+        kind: SignedMessageKind,
+        bytes: &[u8],
+    ) -> Result<Signature, SignerError> {
+        if let Some(history) = &self.history {
+            let mut history = history.lock().map_err(|_| {
+                SignerError::Unavailable("signing history lock is poisoned".to_string())
+            })?;
+            history.check_and_record(&kind, bytes)?;
+        }
+        Ok(self.signer.sign(bytes))
     }
 
     fn compute_vrf_with_proof(
         &self,
         data: &[u8],
-    ) -> (near_crypto::vrf::Value, near_crypto::vrf::Proof) {
-        self.signer.compute_vrf_with_proof(data)
+    ) -> Result<(near_crypto::vrf::Value, near_crypto::vrf::Proof), SignerError> {
+        Ok(self.signer.compute_vrf_with_proof(data))
     }
 
     fn write_to_file(&self, path: &Path) -> std::io::Result<()> {
         self.signer.write_to_file(path)
     }
 }
+
+/// Signer that forwards signing requests to a remote signing service.
+///
+/// If the remote signer refuses or fails to sign, the error is returned and
+/// the caller doesn't produce the message.
+#[derive(Clone)]
+pub struct RemoteValidatorSigner {
+    account_id: AccountId,
+    public_key: PublicKey,
+    signer: Arc<dyn RemoteSigner>,
+}
+
+impl RemoteValidatorSigner {
+    pub fn new(
+        account_id: AccountId,
+        public_key: PublicKey,
+        signer: Arc<dyn RemoteSigner>,
+    ) -> ValidatorSigner {
+        ValidatorSigner::Remote(Self { account_id, public_key, signer })
+    }
+
+    pub fn validator_id(&self) -> &AccountId {
+        &self.account_id
+    }
+
+    pub fn public_key(&self) -> PublicKey {
+        self.public_key.clone()
+    }
+
+    fn sign_message(&self, kind: SignedMessageKind, data: &[u8]) -> Result<Signature, SignerError> {
+        self.signer.sign(kind, data)
+    }
+
+    fn compute_vrf_with_proof(
+        &self,
+        data: &[u8],
+    ) -> Result<(near_crypto::vrf::Value, near_crypto::vrf::Proof), SignerError> {
+        self.signer.compute_vrf_with_proof(data)
+    }
+}
+
+impl Debug for RemoteValidatorSigner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RemoteValidatorSigner")
+            .field("account_id", &self.account_id)
+            .field("public_key", &self.public_key)
+            .finish()
+    }
+}
+
+impl PartialEq for RemoteValidatorSigner {
+    fn eq(&self, other: &Self) -> bool {
+        self.account_id == other.account_id && self.public_key == other.public_key
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::Approval;
+    use crate::hash::CryptoHash;
+    use crate::test_utils::create_test_signer;
+
+    struct UnavailableSigner;
+
+    impl RemoteSigner for UnavailableSigner {
+        fn sign(&self, _kind: SignedMessageKind, _data: &[u8]) -> Result<Signature, SignerError> {
+            Err(SignerError::Unavailable("timeout".to_string()))
+        }
+
+        fn compute_vrf_with_proof(
+            &self,
+            _data: &[u8],
+        ) -> Result<(near_crypto::vrf::Value, near_crypto::vrf::Proof), SignerError> {
+            Err(SignerError::Unavailable("timeout".to_string()))
+        }
+    }
+
+    #[test]
+    fn test_refused_signature_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let history = SigningHistory::open(&dir.path().join("history.json")).unwrap();
+        let signer = create_test_signer("test").with_signing_history(Arc::new(Mutex::new(history)));
+        Approval::new(CryptoHash::hash_bytes(b"a"), 0, 1, &signer).unwrap();
+        let conflicting = Approval::new(CryptoHash::hash_bytes(b"b"), 0, 1, &signer);
+        assert!(matches!(conflicting, Err(SignerError::DoubleSign { signed_height: 1, .. })));
+    }
+
+    #[test]
+    fn test_poisoned_signing_history_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let history = SigningHistory::open(&dir.path().join("history.json")).unwrap();
+        let history = Arc::new(Mutex::new(history));
+        let poison = history.clone();
+        std::thread::spawn(move || {
+            let _guard = poison.lock().unwrap();
+            panic!("poison the signing history");
+        })
+        .join()
+        .unwrap_err();
+        let signer = create_test_signer("test").with_signing_history(history);
+        let approval = Approval::new(CryptoHash::default(), 0, 1, &signer);
+        assert!(matches!(approval, Err(SignerError::Unavailable(_))));
+    }
+
+    #[test]
+    fn test_remote_signer_failure_aborts_signing() {
+        let signer = RemoteValidatorSigner::new(
+            "test".parse().unwrap(),
+            PublicKey::empty(KeyType::ED25519),
+            Arc::new(UnavailableSigner),
+        );
+        let approval = Approval::new(CryptoHash::default(), 0, 1, &signer);
+        assert!(matches!(approval, Err(SignerError::Unavailable(_))));
+        assert!(signer.compute_vrf_with_proof(b"random value").is_err());
+    }
+}
@@ -7,7 +7,7 @@ use crate::account::{AccessKey, AccessKeyPermission, Account, FunctionCallPermis
 use crate::action::delegate::{DelegateAction, SignedDelegateAction};
 use crate::action::{
     DeployGlobalContractAction, GlobalContractDeployMode, GlobalContractIdentifier,
-    UseGlobalContractAction,
+    UpgradeContractAction, UseGlobalContractAction,
 };
 use crate::bandwidth_scheduler::BandwidthRequests;
 use crate::block::{Block, BlockHeader, Tip};
@@ -263,6 +263,9 @@ pub struct KnownPeerStateView {
     pub first_seen: i64,
     pub last_seen: i64,
     pub last_attempt: Option<(i64, String)>,
+    /// Reputation score of the peer, from 0 to 1000.
+    #[serde(default)]
+    pub reputation: u32,
 }
 
 #[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
@@ -1187,6 +1190,15 @@ pub enum ActionView {
     UseGlobalContractByAccountId {
         account_id: AccountId,
     },
+    UpgradeContract {
+        #[serde_as(as = "Base64")]
+        code: Vec<u8>,
+        method_name: String,
+        args: FunctionArgs,
+        gas: Gas,
+        #[serde(with = "dec_format")]
+        deposit: Balance,
+    },
 }
 
 impl From<Action> for ActionView {
@@ -1236,6 +1248,16 @@ impl From<Action> for ActionView {
                     ActionView::UseGlobalContractByAccountId { account_id }
                 }
             },
+            Action::UpgradeContract(action) => {
+                let UpgradeContractAction { code, migration } = *action;
+                ActionView::UpgradeContract {
+                    code: hash(&code).as_ref().to_vec(),
+                    method_name: migration.method_name,
+                    args: migration.args.into(),
+                    gas: migration.gas,
+                    deposit: migration.deposit,
+                }
+            }
         }
     }
 }
@@ -1295,6 +1317,12 @@ impl TryFrom<ActionView> for Action {
                     contract_identifier: GlobalContractIdentifier::AccountId(account_id),
                 }))
             }
+            ActionView::UpgradeContract { code, method_name, args, gas, deposit } => {
+                Action::UpgradeContract(Box::new(UpgradeContractAction {
+                    code,
+                    migration: FunctionCallAction { method_name, args: args.into(), gas, deposit },
+                }))
+            }
         })
     }
 }
@@ -2187,6 +2215,53 @@ pub struct NextEpochValidatorInfo {
     pub shards: Vec<ShardId>,
 }
 
+/// Projection of the outcome of the current epoch, computed from the validator
+/// statistics collected so far as if the validators kept the same performance
+/// until the end of the epoch.
+#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Clone)]
+pub struct EpochValidatorForecast {
+    /// Height of the current epoch.
+    pub epoch_height: EpochHeight,
+    /// Epoch start block height.
+    pub epoch_start_height: BlockHeight,
+    /// Height of the block the forecast is computed at.
+    pub block_height: BlockHeight,
+    /// Estimated height of the first block of the next epoch.
+    pub estimated_next_epoch_start_height: BlockHeight,
+    /// Forecast for each validator of the current epoch.
+    pub current_validators: Vec<ValidatorForecast>,
+    /// Protocol version predicted for the epoch after next.
+    pub predicted_protocol_version: ProtocolVersion,
+    /// Validators predicted for the epoch after next, which are selected at the end of
+    /// the current epoch. The validators of the next epoch are already known and are
+    /// returned by the `validators` method.
+    ///
+    /// The shard assignment depends on the randomness of the last block of the current
+    /// epoch, so the predicted shards are likely to differ from the final ones.
+    pub predicted_validators: Vec<NextEpochValidatorInfo>,
+}
+
+/// Projected outcome of the current epoch for a single validator.
+#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Clone)]
+pub struct ValidatorForecast {
+    pub account_id: AccountId,
+    #[serde(with = "dec_format")]
+    pub stake: Balance,
+    pub num_produced_blocks: NumBlocks,
+    pub num_expected_blocks: NumBlocks,
+    pub num_produced_chunks: NumBlocks,
+    pub num_expected_chunks: NumBlocks,
+    pub num_produced_endorsements: NumBlocks,
+    pub num_expected_endorsements: NumBlocks,
+    /// Online ratio (between 0 and 1) used to compute the reward of the validator.
+    pub online_ratio: f64,
+    /// Reward the validator is expected to receive at the end of the epoch.
+    #[serde(with = "dec_format")]
+    pub expected_reward: Balance,
+    /// Reason the validator is expected to be kicked out at the end of the epoch, if any.
+    pub kickout_reason: Option<ValidatorKickoutReason>,
+}
+
 #[derive(
     PartialEq,
     Eq,
@@ -0,0 +1,81 @@
+use crate::RemoteSignerConfig;
+use crate::protocol::{
+    PROTOCOL_VERSION, Request, RequestBody, Response, read_message, write_message,
+};
+use crate::transport::{self, Stream};
+use near_crypto::Signature;
+use near_crypto::vrf::{Proof, Value};
+use near_primitives::validator_signer::{
+    RemoteSigner, RemoteValidatorSigner, SignedMessageKind, SignerError, ValidatorSigner,
+};
+use parking_lot::Mutex;
+use std::sync::Arc;
+
+/// Node side of the remote signer protocol.
+pub struct RemoteSignerClient {
+    config: RemoteSignerConfig,
+    /// Connection to the signer, established lazily and dropped on any error.
+    stream: Mutex<Option<Stream>>,
+}
+
+impl RemoteSignerClient {
+    /// Connects to the signer and returns a validator signer for the key the
+    /// signer holds.
+    pub fn connect(config: RemoteSignerConfig) -> anyhow::Result<ValidatorSigner> {
+        let client = Self { config, stream: Mutex::new(None) };
+        match client.request(RequestBody::Hello)? {
+            Response::Hello { version, account_id, public_key } => {
+                tracing::info!(
+                    target: "remote_signer",
+                    version, %account_id, %public_key, "Connected to remote signer"
+                );
+                Ok(RemoteValidatorSigner::new(account_id, public_key, Arc::new(client)))
+            }
+            Response::Error(err) => Err(err.into()),
+            _ => anyhow::bail!("unexpected response to hello"),
+        }
+    }
+
+    fn request(&self, body: RequestBody) -> anyhow::Result<Response> {
+        let request = Request { version: PROTOCOL_VERSION, body };
+        let mut stream = self.stream.lock();
+        // Retry once on a fresh connection, the signer may have been restarted
+        // since the last request. Repeating a request is safe as signing the
+        // same message again is never considered a double sign.
+        let mut result = Err(anyhow::anyhow!("no attempt made"));
+        for _ in 0..2 {
+            if stream.is_none() {
+                *stream = Some(transport::connect(&self.config.endpoint, self.config.timeout)?);
+            }
+            let conn = stream.as_mut().unwrap();
+            result = write_message(conn, &request)
+                .and_then(|()| read_message::<Response>(conn))
+                .map_err(anyhow::Error::from);
+            if result.is_ok() {
+                break;
+            }
+            *stream = None;
+        }
+        result
+    }
+}
+
+impl RemoteSigner for RemoteSignerClient {
+    fn sign(&self, kind: SignedMessageKind, data: &[u8]) -> Result<Signature, SignerError> {
+        match self.request(RequestBody::Sign { kind, data: data.to_vec() }) {
+            Ok(Response::Signature(signature)) => Ok(signature),
+            Ok(Response::Error(err)) => Err(err),
+            Ok(_) => Err(SignerError::Unavailable("unexpected response".to_string())),
+            Err(err) => Err(SignerError::Unavailable(format!("{:#}", err))),
+        }
+    }
+
+    fn compute_vrf_with_proof(&self, data: &[u8]) -> Result<(Value, Proof), SignerError> {
+        match self.request(RequestBody::ComputeVrf { data: data.to_vec() }) {
+            Ok(Response::Vrf { value, proof }) => Ok((value, proof)),
+            Ok(Response::Error(err)) => Err(err),
+            Ok(_) => Err(SignerError::Unavailable("unexpected response".to_string())),
+            Err(err) => Err(SignerError::Unavailable(format!("{:#}", err))),
+        }
+    }
+}
@@ -0,0 +1,53 @@
+//! Remote signing of validator messages.
+//!
+//! Lets a validator node keep its validator key outside of the node host. The
+//! node uses a [`RemoteSignerClient`] which forwards every signature request
+//! over a Unix socket or a mutually authenticated TLS connection to a signing
+//! service speaking the protocol defined in [`protocol`]. The crate also ships
+//! `near-local-signer`, a reference signing service holding the key in a local
+//! file, which is meant for testing and as a template for HSM or KMS backed
+//! implementations.
+
+pub mod client;
+pub mod protocol;
+pub mod server;
+mod transport;
+
+pub use client::RemoteSignerClient;
+pub use near_primitives::signing_history::SigningHistory;
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Configuration of the connection to a remote signer, the `remote_signer`
+/// section of `config.json`.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+pub struct RemoteSignerConfig {
+    pub endpoint: SignerEndpoint,
+    /// Timeout for connecting to the signer and for each request. Signing is
+    /// on the critical path of block production, so keep it short.
+    #[serde(default = "default_timeout")]
+    pub timeout: Duration,
+}
+
+fn default_timeout() -> Duration {
+    Duration::from_millis(500)
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SignerEndpoint {
+    /// Signer listening on a Unix domain socket on the same host.
+    Unix { path: PathBuf },
+    /// Signer listening on a TCP address, authenticated with mutual TLS. All
+    /// certificates and the key are PEM files.
+    Tls {
+        addr: String,
+        /// Name the signer certificate is issued for.
+        server_name: String,
+        /// CA certificate both sides' certificates are verified against.
+        ca_cert: PathBuf,
+        cert: PathBuf,
+        key: PathBuf,
+    },
+}
@@ -0,0 +1,59 @@
+use anyhow::Context;
+use clap::Parser;
+use near_crypto::InMemorySigner;
+use near_remote_signer::server::{self, LocalSigner};
+use near_remote_signer::{SignerEndpoint, SigningHistory};
+use std::path::PathBuf;
+
+/// Reference remote signer for validator keys.
+///
+/// Holds the validator key from a `validator_key.json` style file and signs
+/// requests of a node configured with a matching `remote_signer` section.
+/// Refuses to sign conflicting messages based on the signing history it keeps.
+#[derive(clap::Parser)]
+struct Cli {
+    /// Validator key file.
+    #[clap(long)]
+    key_file: PathBuf,
+    /// File keeping the highest signed height per message kind.
+    #[clap(long)]
+    history_file: PathBuf,
+    /// Listen on this Unix domain socket.
+    #[clap(long, conflicts_with = "tls_addr", required_unless_present = "tls_addr")]
+    unix_socket: Option<PathBuf>,
+    /// Listen on this TCP address with mutual TLS.
+    #[clap(long, requires_all = ["ca_cert", "cert", "key"])]
+    tls_addr: Option<String>,
+    /// CA certificate client certificates are verified against.
+    #[clap(long)]
+    ca_cert: Option<PathBuf>,
+    /// Certificate of the signer.
+    #[clap(long)]
+    cert: Option<PathBuf>,
+    /// Private key of the signer certificate.
+    #[clap(long)]
+    key: Option<PathBuf>,
+}
+
+fn main() -> anyhow::Result<()> {
+    let env_filter = near_o11y::EnvFilterBuilder::from_env().finish().unwrap();
+    let _subscriber = near_o11y::default_subscriber(env_filter, &Default::default()).global();
+
+    let cli = Cli::parse();
+    let signer = InMemorySigner::from_file(&cli.key_file)
+        .with_context(|| format!("can't load key from {}", cli.key_file.display()))?;
+    let history = SigningHistory::open(&cli.history_file)?;
+    let endpoint = match (cli.unix_socket, cli.tls_addr) {
+        (Some(path), _) => SignerEndpoint::Unix { path },
+        (None, Some(addr)) => SignerEndpoint::Tls {
+            addr,
+            // Only used by clients to verify the signer.
+            server_name: String::new(),
+            ca_cert: cli.ca_cert.unwrap(),
+            cert: cli.cert.unwrap(),
+            key: cli.key.unwrap(),
+        },
+        (None, None) => unreachable!("clap requires one of the endpoints"),
+    };
+    server::serve(&endpoint, LocalSigner::new(signer, history))
+}
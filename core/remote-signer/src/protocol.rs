@@ -0,0 +1,102 @@
+//! Wire protocol between a validator node and its signing service.
+//!
+//! The node sends a [`Request`] and waits for a [`Response`] before sending the
+//! next one. Both are borsh encoded and prefixed with their length as a
+//! little-endian `u32`. Every request carries the protocol version the node
+//! speaks, a signer answers requests of a version it doesn't support with an
+//! error. After connecting, the node sends [`RequestBody::Hello`] to learn the
+//! signer's version and the validator key it holds.
+//!
+//! Version 1 messages:
+//!
+//! | Request                 | Response                               |
+//! |-------------------------|----------------------------------------|
+//! | `Hello`                 | `Hello { version, account_id, key }`   |
+//! | `Sign { kind, data }`   | `Signature` or `Error`                 |
+//! | `ComputeVrf { data }`   | `Vrf { value, proof }` or `Error`      |
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_crypto::vrf::{Proof, Value};
+use near_crypto::{PublicKey, Signature};
+use near_primitives::types::AccountId;
+use near_primitives::validator_signer::{SignedMessageKind, SignerError};
+use std::io::{self, Read, Write};
+
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Upper bound on a message size. Messages signed by validators, including
+/// state witness parts, are well below it.
+const MAX_MESSAGE_SIZE: usize = 64 * 1024 * 1024;
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct Request {
+    pub version: u32,
+    pub body: RequestBody,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub enum RequestBody {
+    Hello,
+    Sign { kind: SignedMessageKind, data: Vec<u8> },
+    ComputeVrf { data: Vec<u8> },
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub enum Response {
+    Hello { version: u32, account_id: AccountId, public_key: PublicKey },
+    Signature(Signature),
+    Vrf { value: Value, proof: Proof },
+    Error(SignerError),
+}
+
+pub fn write_message<T: BorshSerialize>(writer: &mut impl Write, message: &T) -> io::Result<()> {
+    let bytes = borsh::to_vec(message)?;
+    let len = u32::try_from(bytes.len())
+        .ok()
+        .filter(|&len| len as usize <= MAX_MESSAGE_SIZE)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "message too large"))?;
+    writer.write_all(&len.to_le_bytes())?;
+    writer.write_all(&bytes)?;
+    writer.flush()
+}
+
+pub fn read_message<T: BorshDeserialize>(reader: &mut impl Read) -> io::Result<T> {
+    let mut len = [0u8; 4];
+    reader.read_exact(&mut len)?;
+    let len = u32::from_le_bytes(len) as usize;
+    if len > MAX_MESSAGE_SIZE {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "message too large"));
+    }
+    let mut bytes = vec![0u8; len];
+    reader.read_exact(&mut bytes)?;
+    T::try_from_slice(&bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_roundtrip() {
+        let request = Request {
+            version: PROTOCOL_VERSION,
+            body: RequestBody::Sign {
+                kind: SignedMessageKind::Approval { target_height: 42 },
+                data: vec![1, 2, 3],
+            },
+        };
+        let mut buf = Vec::new();
+        write_message(&mut buf, &request).unwrap();
+        assert_eq!(&buf[..4], &(buf.len() as u32 - 4).to_le_bytes());
+
+        let decoded: Request = read_message(&mut buf.as_slice()).unwrap();
+        assert_eq!(decoded.version, PROTOCOL_VERSION);
+        match decoded.body {
+            RequestBody::Sign { kind, data } => {
+                assert_eq!(kind, SignedMessageKind::Approval { target_height: 42 });
+                assert_eq!(data, vec![1, 2, 3]);
+            }
+            body => panic!("unexpected request {:?}", body),
+        }
+    }
+}
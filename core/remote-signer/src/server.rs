@@ -0,0 +1,125 @@
+//! Signer side of the remote signer protocol, backed by a key held in memory.
+
+use crate::SignerEndpoint;
+use crate::protocol::{
+    PROTOCOL_VERSION, Request, RequestBody, Response, read_message, write_message,
+};
+use crate::transport::{Listener, Stream};
+use near_crypto::Signer;
+use near_primitives::signing_history::SigningHistory;
+use near_primitives::validator_signer::SignerError;
+use parking_lot::Mutex;
+use std::sync::Arc;
+
+pub struct LocalSigner {
+    signer: Signer,
+    history: SigningHistory,
+}
+
+impl LocalSigner {
+    pub fn new(signer: Signer, history: SigningHistory) -> Self {
+        Self { signer, history }
+    }
+
+    pub fn handle(&mut self, request: Request) -> Response {
+        if request.version != PROTOCOL_VERSION {
+            return Response::Error(SignerError::Unavailable(format!(
+                "unsupported protocol version {}, the signer speaks {}",
+                request.version, PROTOCOL_VERSION
+            )));
+        }
+        match request.body {
+            RequestBody::Hello => Response::Hello {
+                version: PROTOCOL_VERSION,
+                account_id: self.signer.get_account_id(),
+                public_key: self.signer.public_key(),
+            },
+            RequestBody::Sign { kind, data } => match self.history.check_and_record(&kind, &data) {
+                Ok(()) => Response::Signature(self.signer.sign(&data)),
+                Err(err) => {
+                    tracing::warn!(target: "remote_signer", %err, "Refused to sign");
+                    Response::Error(err)
+                }
+            },
+            RequestBody::ComputeVrf { data } => {
+                let (value, proof) = self.signer.compute_vrf_with_proof(&data);
+                Response::Vrf { value, proof }
+            }
+        }
+    }
+}
+
+/// Serves signing requests from nodes connecting to `endpoint` until an
+/// error occurs on the listener. Every connection is served by its own thread.
+pub fn serve(endpoint: &SignerEndpoint, signer: LocalSigner) -> anyhow::Result<()> {
+    let listener = Listener::bind(endpoint)?;
+    tracing::info!(target: "remote_signer", ?endpoint, "Listening for signing requests");
+    let signer = Arc::new(Mutex::new(signer));
+    loop {
+        let stream = match listener.accept() {
+            Ok(stream) => stream,
+            Err(err) => {
+                tracing::warn!(target: "remote_signer", ?err, "Failed to accept connection");
+                continue;
+            }
+        };
+        let signer = signer.clone();
+        std::thread::spawn(move || serve_connection(stream, &signer));
+    }
+}
+
+fn serve_connection(mut stream: Stream, signer: &Mutex<LocalSigner>) {
+    loop {
+        let request: Request = match read_message(&mut stream) {
+            Ok(request) => request,
+            Err(err) => {
+                if err.kind() != std::io::ErrorKind::UnexpectedEof {
+                    tracing::warn!(target: "remote_signer", ?err, "Failed to read request");
+                }
+                return;
+            }
+        };
+        let response = signer.lock().handle(request);
+        if let Err(err) = write_message(&mut stream, &response) {
+            tracing::warn!(target: "remote_signer", ?err, "Failed to write response");
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LocalSigner;
+    use crate::RemoteSignerClient;
+    use crate::{RemoteSignerConfig, SignerEndpoint};
+    use near_crypto::{InMemorySigner, KeyType};
+    use near_primitives::signing_history::SigningHistory;
+    use near_primitives::validator_signer::{SignedMessageKind, SignerError};
+
+    #[test]
+    fn test_remote_signer_over_unix_socket() {
+        let dir = tempfile::tempdir().unwrap();
+        let endpoint = SignerEndpoint::Unix { path: dir.path().join("signer.sock") };
+        let key = InMemorySigner::from_seed("test".parse().unwrap(), KeyType::ED25519, "test");
+        let history = SigningHistory::open(&dir.path().join("history.json")).unwrap();
+        let local_signer = LocalSigner::new(key.clone(), history);
+        let listener = crate::transport::Listener::bind(&endpoint).unwrap();
+        std::thread::spawn(move || {
+            let signer = parking_lot::Mutex::new(local_signer);
+            let stream = listener.accept().unwrap();
+            super::serve_connection(stream, &signer);
+        });
+
+        let config = RemoteSignerConfig { endpoint, timeout: std::time::Duration::from_secs(5) };
+        let validator_signer = RemoteSignerClient::connect(config).unwrap();
+        assert_eq!(validator_signer.validator_id(), &key.get_account_id());
+        assert_eq!(validator_signer.public_key(), key.public_key());
+
+        let kind = SignedMessageKind::Approval { target_height: 7 };
+        let signature = validator_signer.sign_message(kind, b"approval").unwrap();
+        assert!(signature.verify(b"approval", &key.public_key()));
+        // A conflicting approval is refused.
+        let err = validator_signer.sign_message(kind, b"other approval").unwrap_err();
+        assert!(matches!(err, SignerError::DoubleSign { .. }), "{err}");
+    }
+}
@@ -0,0 +1,121 @@
+use crate::SignerEndpoint;
+use anyhow::Context;
+use openssl::ssl::{SslAcceptor, SslConnector, SslFiletype, SslMethod, SslStream, SslVerifyMode};
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::time::Duration;
+
+/// Connection between a node and a signer.
+pub(crate) enum Stream {
+    Unix(UnixStream),
+    Tls(Box<SslStream<TcpStream>>),
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Stream::Unix(stream) => stream.read(buf),
+            Stream::Tls(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Stream::Unix(stream) => stream.write(buf),
+            Stream::Tls(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Stream::Unix(stream) => stream.flush(),
+            Stream::Tls(stream) => stream.flush(),
+        }
+    }
+}
+
+/// Connects to the signer at `endpoint`, applying `timeout` to the connection
+/// and to every read and write on it.
+pub(crate) fn connect(endpoint: &SignerEndpoint, timeout: Duration) -> anyhow::Result<Stream> {
+    match endpoint {
+        SignerEndpoint::Unix { path } => {
+            let stream = UnixStream::connect(path)
+                .with_context(|| format!("can't connect to {}", path.display()))?;
+            stream.set_read_timeout(Some(timeout))?;
+            stream.set_write_timeout(Some(timeout))?;
+            Ok(Stream::Unix(stream))
+        }
+        SignerEndpoint::Tls { addr, server_name, ca_cert, cert, key } => {
+            let mut builder = SslConnector::builder(SslMethod::tls_client())?;
+            builder.set_ca_file(ca_cert)?;
+            builder.set_certificate_chain_file(cert)?;
+            builder.set_private_key_file(key, SslFiletype::PEM)?;
+            builder.check_private_key()?;
+            let connector = builder.build();
+
+            let socket_addr = addr
+                .to_socket_addrs()?
+                .next()
+                .with_context(|| format!("can't resolve {}", addr))?;
+            let stream = TcpStream::connect_timeout(&socket_addr, timeout)
+                .with_context(|| format!("can't connect to {}", addr))?;
+            stream.set_read_timeout(Some(timeout))?;
+            stream.set_write_timeout(Some(timeout))?;
+            stream.set_nodelay(true)?;
+            let stream = connector
+                .connect(server_name, stream)
+                .map_err(|err| anyhow::anyhow!("TLS handshake with {} failed: {}", addr, err))?;
+            Ok(Stream::Tls(Box::new(stream)))
+        }
+    }
+}
+
+/// Accepts connections from nodes on the signer side.
+pub(crate) enum Listener {
+    Unix(UnixListener),
+    Tls(TcpListener, SslAcceptor),
+}
+
+impl Listener {
+    pub(crate) fn bind(endpoint: &SignerEndpoint) -> anyhow::Result<Self> {
+        match endpoint {
+            SignerEndpoint::Unix { path } => {
+                // A socket left over by a previous run would make bind fail.
+                if path.exists() {
+                    std::fs::remove_file(path)?;
+                }
+                let listener = UnixListener::bind(path)
+                    .with_context(|| format!("can't listen on {}", path.display()))?;
+                Ok(Listener::Unix(listener))
+            }
+            SignerEndpoint::Tls { addr, ca_cert, cert, key, .. } => {
+                let mut builder = SslAcceptor::mozilla_intermediate_v5(SslMethod::tls_server())?;
+                builder.set_ca_file(ca_cert)?;
+                builder.set_certificate_chain_file(cert)?;
+                builder.set_private_key_file(key, SslFiletype::PEM)?;
+                builder.check_private_key()?;
+                // Only nodes holding a certificate issued by the CA may sign.
+                builder.set_verify(SslVerifyMode::PEER | SslVerifyMode::FAIL_IF_NO_PEER_CERT);
+                let listener =
+                    TcpListener::bind(addr).with_context(|| format!("can't listen on {}", addr))?;
+                Ok(Listener::Tls(listener, builder.build()))
+            }
+        }
+    }
+
+    pub(crate) fn accept(&self) -> anyhow::Result<Stream> {
+        match self {
+            Listener::Unix(listener) => Ok(Stream::Unix(listener.accept()?.0)),
+            Listener::Tls(listener, acceptor) => {
+                let (stream, peer) = listener.accept()?;
+                let stream = acceptor.accept(stream).map_err(|err| {
+                    anyhow::anyhow!("TLS handshake with {} failed: {}", peer, err)
+                })?;
+                Ok(Stream::Tls(Box::new(stream)))
+            }
+        }
+    }
+}
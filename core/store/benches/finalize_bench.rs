@@ -115,7 +115,7 @@ fn create_benchmark_receipts() -> Vec<Receipt> {
 }
 
 fn create_chunk_header(height: u64, shard_id: ShardId) -> ShardChunkHeader {
-    ShardChunkHeader::V3(ShardChunkHeaderV3::new(
+    let header = ShardChunkHeaderV3::new(
         CryptoHash::default(),
         CryptoHash::default(),
         CryptoHash::default(),
@@ -132,7 +132,9 @@ fn create_chunk_header(height: u64, shard_id: ShardId) -> ShardChunkHeader {
         Default::default(),
         BandwidthRequests::empty(),
         &validator_signer(),
-    ))
+    )
+    .unwrap();
+    ShardChunkHeader::V3(header)
 }
 
 fn create_action_receipt(
@@ -203,6 +205,7 @@ fn create_encoded_shard_chunk(
         &validator_signer(),
         &rs,
     )
+    .unwrap()
 }
 
 fn encoded_chunk_to_partial_encoded_chunk(
@@ -14,6 +14,7 @@ use parking_lot::{Mutex, MutexGuard, RwLock};
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::num::NonZeroUsize;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 pub(crate) struct BoundedQueue<T> {
     queue: VecDeque<T>,
@@ -377,6 +378,11 @@ pub struct TrieCachingStorage {
     // Counters tracking operations happening inside the shard cache.
     // Stored here to avoid overhead of looking them up on hot paths.
     metrics: TrieCacheInnerMetrics,
+
+    /// Shard cache hits and misses of reads through this storage. Unlike the metrics above they
+    /// are not shared with other storages, so they can be attributed to a single chunk.
+    shard_cache_hits: AtomicU64,
+    shard_cache_misses: AtomicU64,
 }
 
 struct TrieCacheInnerMetrics {
@@ -430,9 +436,19 @@ impl TrieCachingStorage {
             shard_cache,
             prefetch_api,
             metrics,
+            shard_cache_hits: AtomicU64::new(0),
+            shard_cache_misses: AtomicU64::new(0),
         }
     }
 
+    /// Returns the number of shard cache hits and misses of reads through this storage.
+    pub fn shard_cache_hits_and_misses(&self) -> (u64, u64) {
+        (
+            self.shard_cache_hits.load(Ordering::Relaxed),
+            self.shard_cache_misses.load(Ordering::Relaxed),
+        )
+    }
+
     /// Reads value if it is not in shard cache. Handles dropping the cache
     /// lock. Either waits for prefetcher to fetch it or reads it from DB.
     /// It is responsibility of caller to release the prefetch slot later.
@@ -511,11 +527,13 @@ impl TrieStorage for TrieCachingStorage {
         self.metrics.shard_cache_current_total_size.set(guard.current_total_size() as i64);
         if let Some(val) = guard.get(hash) {
             self.metrics.shard_cache_hits.inc();
+            self.shard_cache_hits.fetch_add(1, Ordering::Relaxed);
             near_o11y::io_trace!(count: "shard_cache_hit");
             return Ok(val);
         }
 
         self.metrics.shard_cache_misses.inc();
+        self.shard_cache_misses.fetch_add(1, Ordering::Relaxed);
         near_o11y::io_trace!(count: "shard_cache_miss");
         let val = match self.read_for_shard_cache_miss(guard, hash) {
             Ok(val) => val,
@@ -13,7 +13,7 @@ use near_primitives::errors::StorageError;
 use near_primitives::hash::CryptoHash;
 use near_primitives::receipt::{
     BufferedReceiptIndices, DelayedReceiptIndices, PromiseYieldIndices, PromiseYieldTimeout,
-    Receipt, ReceiptEnum, ReceivedData,
+    Receipt, ReceiptEnum, ReceivedData, ScheduledReceiptIndices, TrieQueueIndices,
 };
 use near_primitives::trie_key::{TrieKey, trie_key_parsers};
 use near_primitives::types::{AccountId, BlockHeight, StateRoot};
@@ -219,6 +219,50 @@ pub fn has_promise_yield_receipt(
     )
 }
 
+pub fn get_scheduled_receipt_indices(
+    trie: &dyn TrieAccess,
+) -> Result<ScheduledReceiptIndices, StorageError> {
+    Ok(get(trie, &TrieKey::ScheduledReceiptIndices)?.unwrap_or_default())
+}
+
+pub fn set_scheduled_receipt_indices(
+    state_update: &mut TrieUpdate,
+    scheduled_receipt_indices: &ScheduledReceiptIndices,
+) {
+    set(state_update, TrieKey::ScheduledReceiptIndices, scheduled_receipt_indices);
+}
+
+// Enqueues given receipt to the queue of receipts scheduled for block `height`
+pub fn enqueue_scheduled_receipt(
+    state_update: &mut TrieUpdate,
+    scheduled_receipt_indices: &mut ScheduledReceiptIndices,
+    height: BlockHeight,
+    receipt: &Receipt,
+) -> Result<(), StorageError> {
+    let queue_indices_key = TrieKey::ScheduledReceiptQueueIndices { height };
+    let mut queue_indices: TrieQueueIndices =
+        get(state_update, &queue_indices_key)?.unwrap_or_default();
+    set(
+        state_update,
+        TrieKey::ScheduledReceipt { height, index: queue_indices.next_available_index },
+        receipt,
+    );
+    queue_indices.next_available_index = queue_indices
+        .next_available_index
+        .checked_add(1)
+        .expect("Next available index for scheduled receipt queue exceeded the integer limit");
+    set(state_update, queue_indices_key, &queue_indices);
+
+    if scheduled_receipt_indices.len == 0 || height < scheduled_receipt_indices.next_height {
+        scheduled_receipt_indices.next_height = height;
+    }
+    scheduled_receipt_indices.len = scheduled_receipt_indices
+        .len
+        .checked_add(1)
+        .expect("Number of scheduled receipts exceeded the integer limit");
+    Ok(())
+}
+
 pub fn get_buffered_receipt_indices(
     trie: &dyn TrieAccess,
 ) -> Result<BufferedReceiptIndices, StorageError> {
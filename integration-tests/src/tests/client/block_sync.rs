@@ -6,11 +6,12 @@ use near_async::time::Clock;
 use near_chain::Provenance;
 use near_chain::test_utils::wait_for_all_blocks_in_processing;
 use near_chain_configs::Genesis;
-use near_client::sync::block::BlockSync;
+use near_client::sync::block::{BLOCK_RANGE_REQUEST_COUNT, BlockSync};
 use near_crypto::{KeyType, PublicKey};
 use near_network::test_utils::MockPeerManagerAdapter;
 use near_network::types::{
-    HighestHeightPeerInfo, NetworkRequests, PeerInfo, PeerManagerMessageRequest,
+    BlockRangeRequest, BlockRangeResponse, HighestHeightPeerInfo, NetworkRequests, PeerInfo,
+    PeerManagerMessageRequest,
 };
 use near_o11y::testonly::TracingCapture;
 use near_primitives::hash::CryptoHash;
@@ -44,6 +45,24 @@ fn check_hashes_from_network_adapter(
     assert_eq!(collected_hashes, expected_hashes.into_iter().collect::<HashSet<_>>());
 }
 
+fn collect_block_range_requests(
+    network_adapter: &MockPeerManagerAdapter,
+) -> Vec<BlockRangeRequest> {
+    let mut network_request = network_adapter.requests.write();
+    let mut requests: Vec<_> = network_request
+        .drain(..)
+        .map(|request| match request {
+            PeerManagerMessageRequest::NetworkRequests(NetworkRequests::BlockRangeRequest {
+                request,
+                ..
+            }) => request,
+            _ => panic!("unexpected network request {:?}", request),
+        })
+        .collect();
+    requests.sort_by_key(|request| request.start_height);
+    requests
+}
+
 fn create_highest_height_peer_infos(num_peers: usize) -> Vec<HighestHeightPeerInfo> {
     (0..num_peers)
         .map(|_| HighestHeightPeerInfo {
@@ -57,6 +76,8 @@ fn create_highest_height_peer_infos(num_peers: usize) -> Vec<HighestHeightPeerIn
             highest_block_hash: Default::default(),
             tracked_shards: vec![],
             archival: false,
+            block_range_start: None,
+            reputation: 0,
         })
         .collect()
 }
@@ -188,3 +209,80 @@ fn test_block_sync_archival() {
         blocks.iter().take(max_block_requests).map(|b| *b.hash()).collect::<HashSet<_>>()
     );
 }
+
+#[test]
+fn test_block_sync_archival_block_ranges() {
+    let network_adapter = Arc::new(MockPeerManagerAdapter::default());
+    let block_fetch_horizon = 10;
+    let max_block_requests = 2;
+    let mut block_sync = BlockSync::new(
+        Clock::real(),
+        network_adapter.as_multi_sender(),
+        block_fetch_horizon,
+        true,
+        true,
+    );
+    let mut env = test_env_with_epoch_length(5);
+    let mut blocks = vec![];
+    for i in 1..141 {
+        let block = env.clients[0].produce_block(i).unwrap().unwrap();
+        blocks.push(block.clone());
+        env.process_block(0, block, Provenance::PRODUCED);
+    }
+    let block_headers = blocks.iter().map(|b| b.header().clone()).collect::<Vec<_>>();
+    env.clients[1].chain.sync_block_headers(block_headers).unwrap();
+    let mut peer_infos = create_highest_height_peer_infos(2);
+    for peer in &mut peer_infos {
+        peer.archival = true;
+        peer.highest_block_height = 140;
+    }
+    // Only the first peer serves block ranges, so that all of them are requested from it.
+    peer_infos[0].block_range_start = Some(0);
+    let peer_id = peer_infos[0].peer_info.id.clone();
+    let range = |start_height| BlockRangeRequest { start_height, count: BLOCK_RANGE_REQUEST_COUNT };
+
+    // Up to max_block_requests ranges are requested at once, instead of the separate blocks.
+    block_sync.block_sync(&env.clients[1].chain, &peer_infos, max_block_requests).unwrap();
+    assert_eq!(collect_block_range_requests(&network_adapter), vec![range(0), range(50)]);
+    block_sync.block_sync(&env.clients[1].chain, &peer_infos, max_block_requests).unwrap();
+    assert_eq!(collect_block_range_requests(&network_adapter), vec![]);
+
+    // Invalid responses are rejected.
+    let chain = &env.clients[1].chain;
+    let response = |start_height, blocks: &[_], last| BlockRangeResponse {
+        start_height,
+        blocks: blocks.to_vec(),
+        last,
+    };
+    let other_peer = PeerId::new(PublicKey::empty(KeyType::SECP256K1));
+    for (from_peer, invalid) in [
+        (&other_peer, response(0, &blocks[0..10], false)),
+        (&peer_id, response(100, &blocks[99..110], false)),
+        (&peer_id, response(50, &blocks[0..10], false)),
+        (&peer_id, response(0, &[blocks[0].clone(), blocks[2].clone()], false)),
+    ] {
+        assert!(block_sync.receive_block_range_response(chain, from_peer, &invalid).is_err());
+    }
+
+    // A valid response is accepted once.
+    let valid = response(0, &blocks[0..49], true);
+    block_sync.receive_block_range_response(chain, &peer_id, &valid).unwrap();
+    assert!(block_sync.receive_block_range_response(chain, &peer_id, &valid).is_err());
+
+    // The range is not requested again while its blocks are waiting to be processed,
+    // nor while they are being processed.
+    block_sync.block_sync(&env.clients[1].chain, &peer_infos, max_block_requests).unwrap();
+    assert_eq!(collect_block_range_requests(&network_adapter), vec![]);
+    for block in &blocks[0..10] {
+        env.process_block(1, block.clone(), Provenance::NONE);
+    }
+    block_sync.block_sync(&env.clients[1].chain, &peer_infos, max_block_requests).unwrap();
+    assert_eq!(collect_block_range_requests(&network_adapter), vec![]);
+
+    // Once the blocks are processed, the next range is requested.
+    for block in &blocks[10..49] {
+        env.process_block(1, block.clone(), Provenance::NONE);
+    }
+    block_sync.block_sync(&env.clients[1].chain, &peer_infos, max_block_requests).unwrap();
+    assert_eq!(collect_block_range_requests(&network_adapter), vec![range(100)]);
+}
@@ -162,7 +162,8 @@ fn receive_network_block() {
                 Clock::real(),
                 None,
                 None,
-            );
+            )
+            .unwrap();
             actor_handles.client_actor.do_send(
                 BlockResponse { block, peer_id: PeerInfo::random().id, was_requested: false }
                     .with_span_context(),
@@ -250,7 +251,8 @@ fn produce_block_with_approvals() {
                 Clock::real(),
                 None,
                 None,
-            );
+            )
+            .unwrap();
             actor_handles.client_actor.do_send(
                 BlockResponse {
                     block: block.clone(),
@@ -273,7 +275,8 @@ fn produce_block_with_approvals() {
                     block.header().height(),
                     10, // the height at which "test1" is producing
                     &signer,
-                );
+                )
+                .unwrap();
                 actor_handles
                     .client_actor
                     .do_send(BlockApproval(approval, PeerInfo::random().id).with_span_context());
@@ -358,7 +361,8 @@ fn invalid_blocks_common(is_requested: bool) {
                 Clock::real(),
                 None,
                 None,
-            );
+            )
+            .unwrap();
             // Send block with invalid chunk mask
             let mut block = valid_block.clone();
             block.mut_header().set_chunk_mask(vec![]);
@@ -515,6 +519,7 @@ fn client_sync_headers() {
                             last_block: Some(BlockInfo { height: 5, hash: hash(&[5]) }),
                             tracked_shards: vec![],
                             archival: false,
+                            block_range_start: None,
                         },
                     },
                     received_bytes_per_sec: 0,
@@ -534,6 +539,8 @@ fn client_sync_headers() {
                     highest_block_hash: hash(&[5]),
                     tracked_shards: vec![],
                     archival: false,
+                    block_range_start: None,
+                    reputation: 0,
                 }],
                 sent_bytes_per_sec: 0,
                 received_bytes_per_sec: 0,
@@ -1486,11 +1493,13 @@ fn test_reject_block_headers_during_epoch_sync() {
     let highest_height = sync_client.config.epoch_sync.epoch_sync_horizon + 1;
     let highest_height_peers = vec![HighestHeightPeerInfo {
         archival: false,
+        block_range_start: None,
         genesis_id: GenesisId::default(),
         highest_block_hash: *blocks.last().unwrap().hash(),
         highest_block_height: blocks.len() as u64,
         tracked_shards: vec![],
         peer_info: PeerInfo::random(),
+        reputation: 0,
     }];
 
     // Running epoch sync, sets SyncStatus::EpochSync
@@ -1949,7 +1958,7 @@ fn test_validate_chunk_extra() {
         let outcome_root = Block::compute_outcome_root(block.chunks().iter_deprecated());
         block.mut_header().set_prev_outcome_root(outcome_root);
         let endorsement =
-            ChunkEndorsement::new(EpochId::default(), &chunk_header, &validator_signer);
+            ChunkEndorsement::new(EpochId::default(), &chunk_header, &validator_signer).unwrap();
         block.set_chunk_endorsements(vec![vec![Some(Box::new(endorsement.signature()))]]);
         let body_hash = block.compute_block_body_hash().unwrap();
         block.mut_header().set_block_body_hash(body_hash);
@@ -95,7 +95,8 @@ fn query_status_not_crash() {
                 Clock::real(),
                 None,
                 None,
-            );
+            )
+            .unwrap();
             let timestamp = next_block.header().timestamp();
             next_block
                 .mut_header()
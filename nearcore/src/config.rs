@@ -3,6 +3,7 @@ use crate::dyn_config::LOG_CONFIG_FILENAME;
 use anyhow::{Context, anyhow, bail};
 use bytesize::ByteSize;
 use near_async::time::{Clock, Duration};
+use near_chain::consensus_log::ConsensusLogConfig;
 use near_chain::runtime::NightshadeRuntime;
 use near_chain_configs::test_utils::{
     TESTING_INIT_BALANCE, TESTING_INIT_STAKE, add_account_with_key, add_protocol_account,
@@ -17,8 +18,8 @@ use near_chain_configs::{
     MIN_BLOCK_PRODUCTION_DELAY, MIN_GAS_PRICE, MutableConfigValue, MutableValidatorSigner,
     NEAR_BASE, NUM_BLOCK_PRODUCER_SEATS, NUM_BLOCKS_PER_YEAR, PROTOCOL_REWARD_RATE,
     PROTOCOL_UPGRADE_STAKE_THRESHOLD, ReshardingConfig, StateSyncConfig,
-    TRANSACTION_VALIDITY_PERIOD, TrackedShardsConfig, default_chunk_wait_mult,
-    default_enable_multiline_logging, default_epoch_sync,
+    TRANSACTION_VALIDITY_PERIOD, TrackedShardsConfig, ValidatorAlertsConfig,
+    default_chunk_wait_mult, default_enable_multiline_logging, default_epoch_sync,
     default_header_sync_expected_height_per_second, default_header_sync_initial_timeout,
     default_header_sync_progress_timeout, default_header_sync_stall_ban_timeout,
     default_log_summary_period, default_orphan_state_witness_max_size,
@@ -36,10 +37,12 @@ use near_epoch_manager::EpochManagerHandle;
 #[cfg(feature = "json_rpc")]
 use near_jsonrpc::RpcConfig;
 use near_network::config::NetworkConfig;
+use near_network::dns_seeds::DnsSeed;
 use near_network::tcp;
 use near_o11y::log_config::LogConfig;
 use near_primitives::hash::CryptoHash;
 use near_primitives::shard_layout::ShardLayout;
+use near_primitives::signing_history::SigningHistory;
 use near_primitives::test_utils::create_test_signer;
 use near_primitives::types::{
     AccountId, AccountInfo, Balance, BlockHeight, BlockHeightDelta, Gas, NumSeats, NumShards,
@@ -57,11 +60,12 @@ use near_store::{StateSnapshotConfig, Store, TrieConfig};
 use near_telemetry::TelemetryConfig;
 use near_vm_runner::{ContractRuntimeCache, FilesystemContractRuntimeCache};
 use num_rational::Rational32;
+use std::collections::HashMap;
 use std::fs;
 use std::fs::File;
 use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::{Arc, LazyLock, Mutex};
 use tracing::{info, warn};
 
 /// Millinear, 1/1000 of NEAR.
@@ -111,10 +115,18 @@ pub const MINIMUM_STAKE_DIVISOR: u64 = 10;
 pub const CONFIG_FILENAME: &str = "config.json";
 pub const NODE_KEY_FILE: &str = "node_key.json";
 pub const VALIDATOR_KEY_FILE: &str = "validator_key.json";
+pub const SIGNING_HISTORY_FILE: &str = "signing_history.json";
 
 pub const NETWORK_LEGACY_TELEMETRY_URL: &str = "https://explorer.{}.near.org/api/nodes";
 pub const NETWORK_TELEMETRY_URL: &str = "https://telemetry.nearone.org/nodes";
 
+/// DNS seeds (name and public key) of the mainnet boot nodes, see `near_network::dns_seeds`.
+/// Empty until the seeds are operated.
+pub const MAINNET_DNS_SEEDS: &[(&str, &str)] = &[];
+/// DNS seeds (name and public key) of the testnet boot nodes, see `near_network::dns_seeds`.
+/// Empty until the seeds are operated.
+pub const TESTNET_DNS_SEEDS: &[(&str, &str)] = &[];
+
 fn default_doomslug_step_period() -> Duration {
     Duration::milliseconds(10)
 }
@@ -237,6 +249,14 @@ pub struct Config {
     pub genesis_file: String,
     pub genesis_records_file: Option<String>,
     pub validator_key_file: String,
+    /// Sign with a validator key held by an external signing service instead of
+    /// `validator_key_file`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remote_signer: Option<near_remote_signer::RemoteSignerConfig>,
+    /// File with the highest heights the validator key signed approvals, block
+    /// headers and chunk headers for. Consulted before every such signature to
+    /// make sure the validator never signs conflicting messages.
+    pub signing_history_file: String,
     pub node_key_file: String,
     #[cfg(feature = "json_rpc")]
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -366,6 +386,16 @@ pub struct Config {
     /// as a large number of incoming witnesses could cause denial of service.
     pub save_latest_witnesses: bool,
     pub transaction_request_handler_threads: usize,
+    /// Optional config for the validator alert engine.
+    ///
+    /// If set, the node periodically evaluates the performance of the validator
+    /// against the configured rules and reports the alerts to the configured sinks.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validator_alerts: Option<ValidatorAlertsConfig>,
+    /// Journal of consensus decisions read by `neard consensus-log`.
+    pub consensus_log: ConsensusLogConfig,
+    /// Rules dropping or aggregating labels of the exported metric families.
+    pub metrics: near_o11y::metrics::MetricsConfig,
 }
 
 fn is_false(value: &bool) -> bool {
@@ -377,6 +407,8 @@ impl Default for Config {
             genesis_file: GENESIS_CONFIG_FILENAME.to_string(),
             genesis_records_file: None,
             validator_key_file: VALIDATOR_KEY_FILE.to_string(),
+            remote_signer: None,
+            signing_history_file: SIGNING_HISTORY_FILE.to_string(),
             node_key_file: NODE_KEY_FILE.to_string(),
             #[cfg(feature = "json_rpc")]
             rpc: Some(RpcConfig::default()),
@@ -421,6 +453,9 @@ impl Default for Config {
             max_loaded_contracts: 256,
             save_latest_witnesses: false,
             transaction_request_handler_threads: 4,
+            validator_alerts: None,
+            consensus_log: ConsensusLogConfig::default(),
+            metrics: near_o11y::metrics::MetricsConfig::default(),
         }
     }
 }
@@ -616,11 +651,20 @@ impl NearConfig {
                 orphan_state_witness_max_size: config.orphan_state_witness_max_size,
                 save_latest_witnesses: config.save_latest_witnesses,
                 transaction_request_handler_threads: config.transaction_request_handler_threads,
+                validator_alerts: config.validator_alerts,
             },
             #[cfg(feature = "tx_generator")]
             tx_generator: config.tx_generator,
             network_config: NetworkConfig::new(
-                config.network,
+                near_network::config_json::Config {
+                    dns_seeds: Some(
+                        config
+                            .network
+                            .dns_seeds
+                            .unwrap_or_else(|| default_dns_seeds(&genesis.config.chain_id)),
+                    ),
+                    ..config.network
+                },
                 network_key_pair.secret_key,
                 validator_signer.clone(),
                 config.archive,
@@ -781,6 +825,22 @@ fn generate_or_load_keys(
     Ok(())
 }
 
+/// DNS seeds used by the nodes of the chain unless `network.dns_seeds` is set in the config.
+pub fn default_dns_seeds(chain_id: &str) -> Vec<DnsSeed> {
+    let seeds: &[(&str, &str)] = match chain_id {
+        near_primitives::chains::MAINNET => MAINNET_DNS_SEEDS,
+        near_primitives::chains::TESTNET => TESTNET_DNS_SEEDS,
+        _ => &[],
+    };
+    seeds
+        .iter()
+        .map(|(name, public_key)| DnsSeed {
+            name: name.to_string(),
+            public_key: public_key.parse().expect("invalid public key of a DNS seed"),
+        })
+        .collect()
+}
+
 fn set_block_production_delay(chain_id: &str, fast: bool, config: &mut Config) {
     match chain_id {
         near_primitives::chains::MAINNET => {
@@ -1409,6 +1469,45 @@ pub fn load_validator_key(validator_file: &Path) -> anyhow::Result<Option<Arc<Va
     }
 }
 
+/// Loads the validator signer configured in `config`: a remote signer if one is
+/// configured and the validator key file otherwise.
+pub fn load_validator_signer(
+    dir: &Path,
+    config: &Config,
+) -> anyhow::Result<Option<Arc<ValidatorSigner>>> {
+    match &config.remote_signer {
+        Some(remote_signer) => {
+            let signer = near_remote_signer::RemoteSignerClient::connect(remote_signer.clone())
+                .context("Failed connecting to the remote signer")?;
+            Ok(Some(Arc::new(signer)))
+        }
+        None => {
+            let Some(signer) = load_validator_key(&dir.join(&config.validator_key_file))? else {
+                return Ok(None);
+            };
+            let history = open_signing_history(&dir.join(&config.signing_history_file))?;
+            Ok(Some(Arc::new(Arc::unwrap_or_clone(signer).with_signing_history(history))))
+        }
+    }
+}
+
+/// Opens the signing history at `path`. The history is opened once per process
+/// and shared by all signers using it, including ones hot loaded later, so that
+/// they all see each other's signatures.
+pub fn open_signing_history(path: &Path) -> anyhow::Result<Arc<Mutex<SigningHistory>>> {
+    static HISTORIES: LazyLock<Mutex<HashMap<PathBuf, Arc<Mutex<SigningHistory>>>>> =
+        LazyLock::new(Default::default);
+    let mut histories = HISTORIES.lock().unwrap();
+    if let Some(history) = histories.get(path) {
+        return Ok(history.clone());
+    }
+    let history = SigningHistory::open(path)
+        .with_context(|| format!("Failed opening signing history {}", path.display()))?;
+    let history = Arc::new(Mutex::new(history));
+    histories.insert(path.to_path_buf(), history.clone());
+    Ok(history)
+}
+
 pub fn load_config(
     dir: &Path,
     genesis_validation: GenesisValidationMode,
@@ -1422,8 +1521,7 @@ pub fn load_config(
         validation_errors.push_errors(e)
     };
 
-    let validator_file: PathBuf = dir.join(&config.validator_key_file);
-    let validator_signer = match load_validator_key(&validator_file) {
+    let validator_signer = match load_validator_signer(dir, &config) {
         Ok(validator_signer) => validator_signer,
         Err(e) => {
             validation_errors.push_validator_key_file_error(e.to_string());
@@ -1523,9 +1621,19 @@ mod tests {
     use tempfile::tempdir;
 
     use crate::config::{
-        CONFIG_FILENAME, Config, create_localnet_configs, generate_or_load_key, init_configs,
+        CONFIG_FILENAME, Config, create_localnet_configs, default_dns_seeds, generate_or_load_key,
+        init_configs,
     };
 
+    #[test]
+    fn test_default_dns_seeds() {
+        for chain_id in [near_primitives::chains::MAINNET, near_primitives::chains::TESTNET] {
+            // Panics if any of the public keys is invalid.
+            default_dns_seeds(chain_id);
+        }
+        assert_eq!(default_dns_seeds("localnet"), vec![]);
+    }
+
     #[test]
     fn test_old_tracked_config_fields_are_parsed() {
         let json_data = json!({
@@ -28,6 +28,7 @@ fn test_config_duration_all_std() {
     // because there are Vec's. So it's best-effort.
     let config = Config {
         chunk_distribution_network: Some(Default::default()),
+        validator_alerts: Some(Default::default()),
         store: StoreConfig { path: Some(Default::default()), ..Default::default() },
         cold_store: Some(StoreConfig { path: Some(Default::default()), ..Default::default() }),
         enable_multiline_logging: Some(Default::default()),
@@ -65,6 +66,7 @@ fn test_config_duration_all_std() {
                     received_messages_rate_limits: Some(
                         near_network::MessagesLimitsOverrideConfig::default(),
                     ),
+                    sent_messages_limits: Some(near_network::SendLimitsOverrideConfig::default()),
                 },
                 ..Default::default()
             },
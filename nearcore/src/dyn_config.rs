@@ -110,9 +110,13 @@ fn read_validator_key(
     config: &Config,
 ) -> Result<Option<Arc<ValidatorSigner>>, UpdatableConfigLoaderError> {
     let validator_file: PathBuf = home_dir.join(&config.validator_key_file);
-    match crate::config::load_validator_key(&validator_file) {
+    match crate::config::load_validator_signer(home_dir, config) {
         Ok(Some(validator_signer)) => {
-            tracing::info!(target: "neard", "Hot loading validator key {}.", validator_file.display());
+            if config.remote_signer.is_some() {
+                tracing::info!(target: "neard", "Hot loading validator key from the remote signer.");
+            } else {
+                tracing::info!(target: "neard", "Hot loading validator key {}.", validator_file.display());
+            }
             Ok(Some(validator_signer))
         }
         Ok(None) => {
@@ -247,6 +247,9 @@ pub fn start_with_config_and_synchronization(
     config_updater: Option<ConfigUpdater>,
 ) -> anyhow::Result<NearNode> {
     let storage = open_storage(home_dir, &mut config)?;
+    near_chain::consensus_log::init(home_dir, &config.config.consensus_log)
+        .context("failed to open the consensus log")?;
+    near_o11y::metrics::set_metrics_config(config.config.metrics.clone());
     let db_metrics_arbiter = if config.client_config.enable_statistics_export {
         let period = config.client_config.log_summary_period;
         let db_metrics_arbiter_handle = spawn_db_metrics_loop(&storage, period)?;
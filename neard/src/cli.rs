@@ -22,15 +22,17 @@ use near_ping::PingCommand;
 use near_primitives::epoch_manager::EpochConfigStore;
 use near_primitives::hash::CryptoHash;
 use near_primitives::merkle::compute_root_from_path;
-use near_primitives::types::{Gas, NumSeats, NumShards, ProtocolVersion, ShardId};
+use near_primitives::signing_history::SigningHistory;
+use near_primitives::types::{BlockHeight, Gas, NumSeats, NumShards, ProtocolVersion, ShardId};
 use near_replay_archive_tool::ReplayArchiveCommand;
 use near_state_parts::cli::StatePartsCommand;
 use near_state_parts_dump_check::cli::StatePartsDumpCheckCommand;
-use near_state_viewer::StateViewerSubCommand;
+use near_state_viewer::{ContractsCommand, StateViewerSubCommand};
 use near_store::db::RocksDB;
 use near_store::{Mode, ShardUId};
 use near_undo_block::cli::UndoBlockCommand;
 use serde_json::Value;
+use std::collections::BTreeMap;
 use std::fs::File;
 use std::io::BufReader;
 use std::net::SocketAddr;
@@ -156,6 +158,18 @@ impl NeardCmd {
             NeardSubCommand::DumpEpochConfigs(cmd) => {
                 cmd.run(&home_dir)?;
             }
+            NeardSubCommand::Contracts(cmd) => {
+                cmd.run(&home_dir, genesis_validation)?;
+            }
+            NeardSubCommand::SigningHistory(cmd) => {
+                cmd.run(&home_dir)?;
+            }
+            NeardSubCommand::ConsensusLog(cmd) => {
+                cmd.run(&home_dir)?;
+            }
+            NeardSubCommand::Network(cmd) => {
+                cmd.run()?;
+            }
         };
         Ok(())
     }
@@ -268,6 +282,18 @@ pub(super) enum NeardSubCommand {
 
     /// Dump hard-coded epoch configs into JSON files
     DumpEpochConfigs(DumpEpochConfigsCommand),
+
+    /// Precompile contracts in state and export or import the compiled contract cache
+    Contracts(ContractsCommand),
+
+    /// Export or import the validator signing history when moving a validator to new hardware
+    SigningHistory(SigningHistoryCommand),
+
+    /// Print a timeline of consensus decisions recorded in the consensus log
+    ConsensusLog(ConsensusLogCommand),
+
+    /// Network debugging tools
+    Network(NetworkCommand),
 }
 
 #[allow(unused)]
@@ -876,6 +902,312 @@ impl ValidateConfigCommand {
     }
 }
 
+#[derive(clap::Parser)]
+pub(super) struct SigningHistoryCommand {
+    #[clap(subcommand)]
+    subcmd: SigningHistorySubCommand,
+}
+
+#[derive(clap::Subcommand)]
+enum SigningHistorySubCommand {
+    /// Write the signing history of this node to a file.
+    Export {
+        #[clap(long)]
+        output: PathBuf,
+    },
+    /// Merge a signing history exported on another host into the history of
+    /// this node. Only raises watermarks. The node must be stopped, otherwise
+    /// it overwrites the imported history with its own on the next signature.
+    Import {
+        #[clap(long)]
+        input: PathBuf,
+    },
+}
+
+impl SigningHistoryCommand {
+    pub(super) fn run(self, home_dir: &Path) -> anyhow::Result<()> {
+        let config =
+            nearcore::config::Config::from_file(&home_dir.join(nearcore::config::CONFIG_FILENAME))?;
+        let path = home_dir.join(&config.signing_history_file);
+        let mut history = SigningHistory::open(&path)
+            .with_context(|| format!("failed to open signing history {}", path.display()))?;
+        match self.subcmd {
+            SigningHistorySubCommand::Export { output } => {
+                std::fs::write(&output, history.export()?)
+                    .with_context(|| format!("failed to write {}", output.display()))?;
+                println!(
+                    "Exported {} watermarks to {}",
+                    history.watermarks().count(),
+                    output.display()
+                );
+            }
+            SigningHistorySubCommand::Import { input } => {
+                let bytes = std::fs::read(&input)
+                    .with_context(|| format!("failed to read {}", input.display()))?;
+                let raised = history.import(SigningHistory::parse(&bytes)?)?;
+                println!("Raised {} watermarks in {}", raised, path.display());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(clap::Parser)]
+pub(super) struct ConsensusLogCommand {
+    /// First height of the timeline.
+    #[clap(long)]
+    from_height: BlockHeight,
+    /// Last height of the timeline, inclusive. Defaults to `from_height`.
+    #[clap(long)]
+    to_height: Option<BlockHeight>,
+    /// Directory of the consensus log. Defaults to the one in `config.json`.
+    #[clap(long)]
+    dir: Option<PathBuf>,
+}
+
+impl ConsensusLogCommand {
+    pub(super) fn run(self, home_dir: &Path) -> anyhow::Result<()> {
+        let dir = match self.dir {
+            Some(dir) => dir,
+            None => {
+                let config = nearcore::config::Config::from_file(
+                    &home_dir.join(nearcore::config::CONFIG_FILENAME),
+                )?;
+                home_dir.join(config.consensus_log.dir)
+            }
+        };
+        let to_height = self.to_height.unwrap_or(self.from_height);
+        let entries = near_chain::consensus_log::read_journal(&dir)
+            .with_context(|| format!("failed to read consensus log in {}", dir.display()))?;
+
+        let mut timeline: BTreeMap<BlockHeight, Vec<_>> = BTreeMap::new();
+        for entry in entries {
+            let height = entry.event.height();
+            if (self.from_height..=to_height).contains(&height) {
+                timeline.entry(height).or_default().push(entry);
+            }
+        }
+        for (height, mut entries) in timeline {
+            entries.sort_by_key(|entry| entry.timestamp);
+            println!("#{}", height);
+            for entry in entries {
+                println!("  {}  {}", entry.timestamp.format("%Y-%m-%d %H:%M:%S%.3f"), entry.event);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(clap::Parser)]
+pub(super) struct NetworkCommand {
+    #[clap(subcommand)]
+    subcmd: NetworkSubCommand,
+}
+
+#[derive(clap::Subcommand)]
+enum NetworkSubCommand {
+    /// Connects to a NEAR node and sends it the messages recorded in a capture file
+    /// (see `network.experimental.capture_dir`), in the captured order.
+    ReplayCapture(ReplayCaptureCommand),
+    /// Crawls the network starting from the given nodes and prints the graph of the nodes
+    /// with their protocol versions, heads, tracked shards, archival flags and reachability.
+    Crawl(CrawlCommand),
+}
+
+impl NetworkCommand {
+    pub(super) fn run(self) -> anyhow::Result<()> {
+        match self.subcmd {
+            NetworkSubCommand::ReplayCapture(cmd) => cmd.run(),
+            NetworkSubCommand::Crawl(cmd) => cmd.run(),
+        }
+    }
+}
+
+#[derive(clap::Parser)]
+struct ReplayCaptureCommand {
+    /// Capture file to replay.
+    #[clap(long)]
+    capture: PathBuf,
+    /// Node to send the messages to, in the form [public key]@[socket addr].
+    #[clap(long)]
+    peer: String,
+    /// Replay the messages with their captured relative timing, sped up by the given
+    /// factor (0 sends them without delays). By default the messages are replayed in
+    /// lockstep instead: every message is followed by a ping, and the next one is sent
+    /// only once the node has answered, so that the node handles them one at a time
+    /// regardless of the network timing.
+    #[clap(long)]
+    speed: Option<f64>,
+    /// Chain ID to use in the handshake. Defaults to the one of the captured handshake.
+    #[clap(long)]
+    chain_id: Option<String>,
+    /// Genesis hash to use in the handshake. Defaults to the one of the captured handshake.
+    #[clap(long)]
+    genesis_hash: Option<CryptoHash>,
+}
+
+impl ReplayCaptureCommand {
+    fn run(self) -> anyhow::Result<()> {
+        let pacing = match self.speed {
+            None => near_network::capture::Pacing::Lockstep,
+            Some(speed) if speed >= 0.0 && speed.is_finite() => {
+                near_network::capture::Pacing::Timed { speed }
+            }
+            Some(speed) => anyhow::bail!("--speed should be a non-negative number, got {speed}"),
+        };
+        let peer = near_network::types::PeerInfo::from_str(&self.peer)
+            .map_err(|err| anyhow::anyhow!("could not parse --peer {}: {:?}", self.peer, err))?;
+        let Some(peer_addr) = peer.addr else {
+            anyhow::bail!("--peer should be in the form [public key]@[socket addr]");
+        };
+        let open_capture = || {
+            near_network::capture::CaptureReader::open(&self.capture)
+                .with_context(|| format!("failed to open {}", self.capture.display()))
+        };
+        let chain_info = near_network::capture::handshake_chain_info(open_capture()?)
+            .with_context(|| format!("failed to read {}", self.capture.display()))?;
+        let chain_id = match (self.chain_id.clone(), &chain_info) {
+            (Some(chain_id), _) => chain_id,
+            (None, Some(info)) => info.genesis_id.chain_id.clone(),
+            (None, None) => anyhow::bail!("no handshake in the capture, --chain-id is required"),
+        };
+        let genesis_hash = match (self.genesis_hash, &chain_info) {
+            (Some(genesis_hash), _) => genesis_hash,
+            (None, Some(info)) => info.genesis_id.hash,
+            (None, None) => {
+                anyhow::bail!("no handshake in the capture, --genesis-hash is required")
+            }
+        };
+        let (head_height, tracked_shards) = match chain_info {
+            Some(info) => (info.height, info.tracked_shards),
+            None => (0, vec![]),
+        };
+        let frames = open_capture()?;
+
+        let runtime = tokio::runtime::Runtime::new()?;
+        runtime.block_on(async move {
+            let clock = near_time::Clock::real();
+            let mut conn = near_network::raw::Connection::connect(
+                &clock,
+                peer_addr,
+                peer.id.clone(),
+                None,
+                &chain_id,
+                genesis_hash,
+                head_height,
+                tracked_shards,
+                None,
+            )
+            .await
+            .with_context(|| format!("failed to connect to {}", peer_addr))?;
+
+            let stats = near_network::capture::replay(&clock, &mut conn, frames, pacing)
+                .await
+                .with_context(|| format!("failed to replay {}", self.capture.display()))?;
+            println!(
+                "Sent {} messages to {} ({} of which failed to parse when captured), skipped {} handshake messages",
+                stats.sent, peer.id, stats.unparsed, stats.skipped
+            );
+            anyhow::Ok(())
+        })
+    }
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy)]
+enum CrawlOutputFormat {
+    Json,
+    /// The DOT format of graphviz.
+    Dot,
+}
+
+#[derive(clap::Parser)]
+struct CrawlCommand {
+    /// Nodes to start crawling from, in the form [public key]@[socket addr], comma separated.
+    #[clap(long, required = true, use_value_delimiter = true, value_delimiter = ',')]
+    peers: Vec<String>,
+    /// Chain ID to use in the handshakes.
+    #[clap(long)]
+    chain_id: String,
+    /// Genesis hash to use in the handshakes. Required if --chain-id is not "mainnet" or "testnet".
+    #[clap(long)]
+    genesis_hash: Option<CryptoHash>,
+    /// Protocol version to advertise in the handshakes. Defaults to the current one.
+    #[clap(long)]
+    protocol_version: Option<ProtocolVersion>,
+    /// Max number of nodes to connect to.
+    #[clap(long, default_value_t = 1000)]
+    max_nodes: usize,
+    /// Number of nodes to connect to at the same time.
+    #[clap(long, default_value_t = 50)]
+    concurrency: usize,
+    /// Seconds to wait for the handshake and then for the list of peers of a node.
+    #[clap(long, default_value_t = 10)]
+    timeout_seconds: u64,
+    #[clap(long, value_enum, default_value_t = CrawlOutputFormat::Json)]
+    format: CrawlOutputFormat,
+    /// File to write the graph to. Printed to stdout if not set.
+    #[clap(long)]
+    output: Option<PathBuf>,
+}
+
+impl CrawlCommand {
+    fn run(self) -> anyhow::Result<()> {
+        if self.concurrency == 0 {
+            anyhow::bail!("--concurrency should be positive");
+        }
+        let genesis_hash = match self.genesis_hash {
+            Some(genesis_hash) => genesis_hash,
+            None => match near_ping::cli::CHAIN_INFO.iter().find(|i| i.chain_id == self.chain_id) {
+                Some(info) => info.genesis_hash,
+                None => anyhow::bail!(
+                    "--genesis-hash not given, and genesis hash for --chain-id {} not known",
+                    self.chain_id
+                ),
+            },
+        };
+        let peers = self
+            .peers
+            .iter()
+            .map(|peer| {
+                near_network::types::PeerInfo::from_str(peer)
+                    .map_err(|err| anyhow::anyhow!("could not parse --peers {}: {:?}", peer, err))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        if peers.iter().any(|peer| peer.addr.is_none()) {
+            anyhow::bail!("--peers should be in the form [public key]@[socket addr]");
+        }
+        let cfg = near_network::raw::CrawlConfig {
+            chain_id: self.chain_id,
+            genesis_hash,
+            protocol_version: self.protocol_version,
+            max_nodes: self.max_nodes,
+            concurrency: self.concurrency,
+            timeout: near_time::Duration::seconds(self.timeout_seconds as i64),
+        };
+
+        let runtime = tokio::runtime::Runtime::new()?;
+        let graph =
+            runtime.block_on(near_network::raw::crawl(&near_time::Clock::real(), &cfg, peers));
+        let reachable = graph.nodes.iter().filter(|node| node.reachable()).count();
+        eprintln!(
+            "Found {} nodes, {} reachable, {} connections",
+            graph.nodes.len(),
+            reachable,
+            graph.edges().len()
+        );
+        let out = match self.format {
+            CrawlOutputFormat::Json => serde_json::to_string_pretty(&graph)?,
+            CrawlOutputFormat::Dot => graph.to_dot(),
+        };
+        match self.output {
+            Some(path) => std::fs::write(&path, out)
+                .with_context(|| format!("failed to write {}", path.display()))?,
+            None => println!("{}", out),
+        }
+        Ok(())
+    }
+}
+
 #[cfg(target_os = "linux")]
 fn normalize_whitespace(s: &str) -> String {
     s.split_whitespace().collect::<Vec<_>>().join(" ")
@@ -258,12 +258,12 @@ fn normalize_config(mut config: wasm_smith::Config) -> wasm_smith::Config {
     config.available_imports = Some(rs_contract().into());
     config.max_memories = 1;
     config.max_tables = 1;
-    config.bulk_memory_enabled = false;
+    config.bulk_memory_enabled = true;
     config.exceptions_enabled = false;
     config.gc_enabled = false;
     config.memory64_enabled = false;
     config.multi_value_enabled = false;
-    config.reference_types_enabled = false;
+    config.reference_types_enabled = true;
     config.relaxed_simd_enabled = false;
     config.saturating_float_to_int_enabled = true;
     config.sign_extension_ops_enabled = false;
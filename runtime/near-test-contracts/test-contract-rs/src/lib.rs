@@ -69,6 +69,11 @@ extern "C" {
     fn promise_and(promise_idx_ptr: u64, promise_idx_count: u64) -> u64;
     fn promise_batch_create(account_id_len: u64, account_id_ptr: u64) -> u64;
     fn promise_batch_then(promise_index: u64, account_id_len: u64, account_id_ptr: u64) -> u64;
+    fn promise_batch_create_scheduled(
+        account_id_len: u64,
+        account_id_ptr: u64,
+        block_height: u64,
+    ) -> u64;
     // #######################
     // # Promise API actions #
     // #######################
@@ -598,11 +603,7 @@ pub unsafe fn fibonacci() {
 }
 
 fn fib(n: u8) -> u64 {
-    if n < 2 {
-        n as u64
-    } else {
-        fib(n - 2) + fib(n - 1)
-    }
+    if n < 2 { n as u64 } else { fib(n - 2) + fib(n - 1) }
 }
 
 #[unsafe(no_mangle)]
@@ -681,11 +682,7 @@ fn internal_recurse(n: u64) -> u64 {
         n
     } else {
         let a = internal_recurse(n - 1) + 1;
-        if a % 2 == 1 {
-            (a + n) / 2
-        } else {
-            a
-        }
+        if a % 2 == 1 { (a + n) / 2 } else { a }
     }
 }
 
@@ -1225,6 +1222,39 @@ fn do_ripemd() {
     }
 }
 
+/// Schedules a `write_block_height` call on the current account for the block
+/// height given as input.
+#[cfg(feature = "nightly")]
+#[unsafe(no_mangle)]
+pub unsafe fn call_promise_batch_create_scheduled() {
+    input(0);
+    let data = [0u8; size_of::<u64>()];
+    assert!(register_len(0) == data.len() as u64);
+    read_register(0, data.as_ptr() as u64);
+    let block_height = u64::from_le_bytes(data);
+
+    current_account_id(0);
+    let account_id = vec![0u8; register_len(0) as usize];
+    read_register(0, account_id.as_ptr() as u64);
+
+    let promise_index = promise_batch_create_scheduled(
+        account_id.len() as u64,
+        account_id.as_ptr() as u64,
+        block_height,
+    );
+    let method_name = b"write_block_height";
+    let amount = 0u128;
+    promise_batch_action_function_call(
+        promise_index,
+        method_name.len() as u64,
+        method_name.as_ptr() as u64,
+        0,
+        0,
+        &amount as *const u128 as u64,
+        10_000_000_000_000,
+    );
+}
+
 #[unsafe(no_mangle)]
 pub unsafe fn noop() {}
 
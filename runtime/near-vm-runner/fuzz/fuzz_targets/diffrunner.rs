@@ -4,22 +4,33 @@ use near_parameters::RuntimeConfigStore;
 use near_parameters::vm::VMKind;
 use near_primitives::version::PROTOCOL_VERSION;
 use near_test_contracts::ArbitraryModule;
-use near_vm_runner::ContractCode;
 use near_vm_runner::internal::VMKindExt;
 use near_vm_runner::logic::VMOutcome;
-use near_vm_runner::logic::errors::FunctionCallError;
+use near_vm_runner::logic::errors::{CompilationError, FunctionCallError};
 use near_vm_runner::logic::mocks::mock_external::MockedExternal;
+use near_vm_runner::{ContractCode, ContractRuntimeCache, MockContractRuntimeCache};
 use near_vm_runner_fuzz::{create_context, find_entry_point};
 use std::sync::Arc;
 
 libfuzzer_sys::fuzz_target!(|module: ArbitraryModule| {
     let code = ContractCode::new(module.0.to_bytes(), None);
-    let near_vm = run_fuzz(&code, VMKind::NearVm);
-    let wasmtime = run_fuzz(&code, VMKind::Wasmtime);
+    let near_vm = run_fuzz(&code, VMKind::NearVm, None);
+    let wasmtime = run_fuzz(&code, VMKind::Wasmtime, None);
     assert_eq!(near_vm, wasmtime);
+
+    // Loading the precompiled artifact from the cache must not change the outcome either.
+    let cache = MockContractRuntimeCache::default();
+    let wasmtime_compiled = run_fuzz(&code, VMKind::Wasmtime, Some(&cache));
+    let wasmtime_cached = run_fuzz(&code, VMKind::Wasmtime, Some(&cache));
+    assert_eq!(wasmtime, wasmtime_compiled);
+    assert_eq!(wasmtime, wasmtime_cached);
 });
 
-fn run_fuzz(code: &ContractCode, vm_kind: VMKind) -> VMOutcome {
+fn run_fuzz(
+    code: &ContractCode,
+    vm_kind: VMKind,
+    cache: Option<&dyn ContractRuntimeCache>,
+) -> VMOutcome {
     let mut fake_external = MockedExternal::with_code(code.clone_for_tests());
     let method_name = find_entry_point(code).unwrap_or_else(|| "main".to_string());
     let mut context = create_context(vec![]);
@@ -27,25 +38,37 @@ fn run_fuzz(code: &ContractCode, vm_kind: VMKind) -> VMOutcome {
     let config_store = RuntimeConfigStore::new(None);
     let config = config_store.get_config(PROTOCOL_VERSION);
     let fees = Arc::clone(&config.fees);
-    let wasm_config = near_parameters::vm::Config::clone(&config.wasm_config);
+    let mut wasm_config = near_parameters::vm::Config::clone(&config.wasm_config);
+    wasm_config.vm_kind = vm_kind;
     let gas_counter = context.make_gas_counter(&wasm_config);
     let res = vm_kind
         .runtime(wasm_config.into())
         .unwrap()
-        .prepare(&fake_external, None, gas_counter, &method_name)
+        .prepare(&fake_external, cache, gas_counter, &method_name)
         .run(&mut fake_external, &context, fees);
 
-    // Remove the VMError message details as they can differ between runtimes
-    // TODO: maybe there's actually things we could check for equality here too?
+    // Gas, logs and the kind of the error must match exactly. Only the messages of errors that
+    // are produced by the compilers or linkers themselves differ between the runtimes.
     match res {
         Ok(mut outcome) => {
-            if outcome.aborted.is_some() {
-                outcome.logs = vec!["[censored]".to_owned()];
-                outcome.aborted =
-                    Some(FunctionCallError::LinkError { msg: "[censored]".to_owned() });
-            }
+            outcome.aborted = outcome.aborted.map(censor_error_message);
             outcome
         }
         Err(err) => panic!("fatal error: {err:?}"),
     }
 }
+
+fn censor_error_message(err: FunctionCallError) -> FunctionCallError {
+    match err {
+        FunctionCallError::CompilationError(
+            CompilationError::WasmerCompileError { .. }
+            | CompilationError::WasmtimeCompileError { .. },
+        ) => FunctionCallError::CompilationError(CompilationError::WasmtimeCompileError {
+            msg: "[censored]".to_owned(),
+        }),
+        FunctionCallError::LinkError { .. } => {
+            FunctionCallError::LinkError { msg: "[censored]".to_owned() }
+        }
+        err => err,
+    }
+}
@@ -584,6 +584,108 @@ pub fn precompile_contract(
     runtime.precompile(code, cache)
 }
 
+/// Magic bytes at the start of an exported contract cache archive.
+const CONTRACT_CACHE_ARCHIVE_MAGIC: [u8; 8] = *b"NEARCCA\0";
+
+/// Header of an exported contract cache archive.
+///
+/// The header captures everything other than the contract code hash that goes into
+/// [`get_contract_cache_key`]. An archive can only be imported by a node whose header compares
+/// equal, which ensures that the keys in the archive are meaningful to the importing node as well.
+#[derive(Debug, Clone, BorshSerialize)]
+struct ContractCacheArchiveHeader {
+    magic: [u8; 8],
+    version: u32,
+    vm_kind: VMKind,
+    vm_config_non_crypto_hash: u64,
+    vm_hash: u64,
+}
+
+impl ContractCacheArchiveHeader {
+    const VERSION: u32 = 1;
+
+    fn new(config: &Config) -> Self {
+        Self {
+            magic: CONTRACT_CACHE_ARCHIVE_MAGIC,
+            version: Self::VERSION,
+            vm_kind: config.vm_kind,
+            vm_config_non_crypto_hash: config.non_crypto_hash(),
+            vm_hash: vm_hash(config.vm_kind),
+        }
+    }
+}
+
+/// Writes the compiled artifacts of the contracts with the given code hashes into `writer`.
+///
+/// The archive contains the entries keyed by [`get_contract_cache_key`] and can be loaded into
+/// the cache of another node with [`import_contract_cache`], provided the other node runs the
+/// same VM with the same configuration. Code hashes that do not have an entry in the `cache` are
+/// skipped.
+///
+/// Returns the number of entries written.
+pub fn export_contract_cache(
+    cache: &dyn ContractRuntimeCache,
+    config: &Config,
+    code_hashes: impl IntoIterator<Item = CryptoHash>,
+    writer: &mut impl std::io::Write,
+) -> std::io::Result<usize> {
+    let _span = tracing::debug_span!(target: "vm", "export_contract_cache").entered();
+    borsh::to_writer(&mut *writer, &ContractCacheArchiveHeader::new(config))?;
+    let mut exported = 0;
+    for code_hash in code_hashes {
+        let key = get_contract_cache_key(code_hash, config);
+        let Some(value) = cache.get(&key)? else {
+            continue;
+        };
+        borsh::to_writer(&mut *writer, &Some((key, value)))?;
+        exported += 1;
+    }
+    borsh::to_writer(&mut *writer, &None::<(CryptoHash, CompiledContractInfo)>)?;
+    writer.flush()?;
+    Ok(exported)
+}
+
+/// Loads an archive written by [`export_contract_cache`] into the `cache`.
+///
+/// Fails with [`std::io::ErrorKind::InvalidData`] if the archive has been produced by a node
+/// running a different VM or configuration than `config`. Entries already present in the cache
+/// are left untouched.
+///
+/// Returns the number of entries added to the cache.
+pub fn import_contract_cache(
+    cache: &dyn ContractRuntimeCache,
+    config: &Config,
+    reader: &mut impl std::io::Read,
+) -> std::io::Result<usize> {
+    let _span = tracing::debug_span!(target: "vm", "import_contract_cache").entered();
+    let expected_header = borsh::to_vec(&ContractCacheArchiveHeader::new(config))?;
+    let mut header = vec![0; expected_header.len()];
+    reader.read_exact(&mut header)?;
+    if header[..CONTRACT_CACHE_ARCHIVE_MAGIC.len()] != CONTRACT_CACHE_ARCHIVE_MAGIC {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "not a contract cache archive",
+        ));
+    }
+    if header != expected_header {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "contract cache archive was produced with a different VM kind, version or config",
+        ));
+    }
+    let mut imported = 0;
+    while let Some((key, value)) =
+        Option::<(CryptoHash, CompiledContractInfo)>::deserialize_reader(reader)?
+    {
+        if cache.has(&key)? {
+            continue;
+        }
+        cache.put(&key, value)?;
+        imported += 1;
+    }
+    Ok(imported)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
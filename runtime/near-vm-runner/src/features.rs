@@ -4,9 +4,10 @@ use opts::*;
 
 #[allow(dead_code)]
 mod opts {
-    pub(super) const REFERENCE_TYPES: bool = false;
+    // The singlepass compiler does not support functions and blocks returning multiple values,
+    // so multi-value is not part of the `WasmBulkMemoryReferenceTypes` feature and stays
+    // rejected until the singlepass codegen learns to return values in more than one register.
     pub(super) const MULTI_VALUE: bool = false;
-    pub(super) const BULK_MEMORY: bool = false;
     pub(super) const SIMD: bool = false;
     pub(super) const THREADS: bool = false;
     pub(super) const TAIL_CALL: bool = false;
@@ -27,12 +28,18 @@ mod opts {
 #[allow(unused)]
 pub struct WasmFeatures {
     saturating_float_to_int: bool,
+    bulk_memory: bool,
+    reference_types: bool,
 }
 
 impl WasmFeatures {
     #[allow(unused)]
     pub fn new(config: &vm::Config) -> Self {
-        Self { saturating_float_to_int: config.saturating_float_to_int }
+        Self {
+            saturating_float_to_int: config.saturating_float_to_int,
+            bulk_memory: config.bulk_memory,
+            reference_types: config.reference_types,
+        }
     }
 }
 
@@ -44,11 +51,11 @@ impl From<WasmFeatures> for finite_wasm::wasmparser::WasmFeatures {
             mutable_global: true,
             sign_extension: SIGN_EXTENSION,
             saturating_float_to_int: f.saturating_float_to_int,
+            bulk_memory: f.bulk_memory,
+            reference_types: f.reference_types,
 
-            reference_types: REFERENCE_TYPES,
             // wasmer singlepass compiler requires multi_value return values to be disabled.
             multi_value: MULTI_VALUE,
-            bulk_memory: BULK_MEMORY,
             simd: SIMD,
             threads: THREADS,
             tail_call: TAIL_CALL,
@@ -71,12 +78,12 @@ impl From<WasmFeatures> for near_vm_types::Features {
         Self {
             mutable_global: true,
             saturating_float_to_int: f.saturating_float_to_int,
+            bulk_memory: f.bulk_memory,
+            reference_types: f.reference_types,
 
             sign_extension: SIGN_EXTENSION,
             threads: THREADS,
-            reference_types: REFERENCE_TYPES,
             simd: SIMD,
-            bulk_memory: BULK_MEMORY,
             multi_value: MULTI_VALUE,
             tail_call: TAIL_CALL,
             multi_memory: MULTI_MEMORY,
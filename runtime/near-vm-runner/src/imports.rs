@@ -165,6 +165,11 @@ imports! {
     promise_and<[promise_idx_ptr: u64, promise_idx_count: u64] -> [u64]>,
     promise_batch_create<[account_id_len: u64, account_id_ptr: u64] -> [u64]>,
     promise_batch_then<[promise_index: u64, account_id_len: u64, account_id_ptr: u64] -> [u64]>,
+    #[scheduled_receipts] promise_batch_create_scheduled<[
+        account_id_len: u64,
+        account_id_ptr: u64,
+        block_height: u64
+    ] -> [u64]>,
     // #######################
     // # Promise API actions #
     // #######################
@@ -25,8 +25,10 @@ pub use crate::logic::with_ext_cost_counter;
 pub use cache::FilesystemContractRuntimeCache;
 pub use cache::{
     CompiledContract, CompiledContractInfo, ContractRuntimeCache, MockContractRuntimeCache,
-    NoContractRuntimeCache, get_contract_cache_key, precompile_contract,
+    NoContractRuntimeCache, export_contract_cache, get_contract_cache_key, import_contract_cache,
+    precompile_contract,
 };
+pub use errors::ContractPrecompilatonResult;
 #[cfg(feature = "metrics")]
 pub use metrics::{report_metrics, reset_metrics};
 pub use near_primitives_core::code::ContractCode;
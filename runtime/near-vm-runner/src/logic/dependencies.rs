@@ -3,7 +3,7 @@ use super::VMLogicError;
 use super::types::ReceiptIndex;
 use near_crypto::PublicKey;
 use near_primitives_core::hash::CryptoHash;
-use near_primitives_core::types::{AccountId, Balance, Gas, GasWeight, Nonce};
+use near_primitives_core::types::{AccountId, Balance, BlockHeight, Gas, GasWeight, Nonce};
 use std::borrow::Cow;
 
 /// Representation of the address slice of guest memory.
@@ -296,6 +296,18 @@ pub trait External {
         receiver_id: AccountId,
     ) -> Result<(ReceiptIndex, CryptoHash), VMLogicError>;
 
+    /// Create an action receipt which is held back until the chain reaches `block_height`.
+    ///
+    /// # Arguments
+    ///
+    /// * `receiver_id` - account id of the receiver of the receipt created
+    /// * `block_height` - height of the block in which the receipt is released
+    fn create_scheduled_action_receipt(
+        &mut self,
+        receiver_id: AccountId,
+        block_height: BlockHeight,
+    ) -> Result<ReceiptIndex, VMLogicError>;
+
     /// Creates a receipt under the specified `data_id` containing given `data`.
     ///
     /// This function shall return `Ok(true)` if the data dependency of the yield receipt has been
@@ -276,6 +276,13 @@ pub enum HostError {
     RecordedStorageExceeded {
         limit: ByteSize,
     },
+    /// Receipts can only be scheduled for a block height after the current one, and at most
+    /// `max_scheduled_receipt_delay_in_blocks` after it.
+    ScheduledReceiptHeight {
+        height: u64,
+        block_height: u64,
+        max_delay: u64,
+    },
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -519,6 +526,10 @@ impl std::fmt::Display for HostError {
                 "Size of the recorded trie storage proof has exceeded the allowed limit ({})",
                 limit
             ),
+            ScheduledReceiptHeight { height, block_height, max_delay } => write!(
+                f,
+                "Cannot schedule a receipt for block height {height}, it must be greater than the current block height {block_height} and at most {max_delay} blocks after it"
+            ),
         }
     }
 }
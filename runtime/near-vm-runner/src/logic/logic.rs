@@ -1977,6 +1977,64 @@ bls12381_p2_decompress_base + bls12381_p2_decompress_element * num_elements`
         self.checked_push_promise(Promise::Receipt(new_receipt_idx))
     }
 
+    /// Creates a new promise towards given `account_id` without any actions attached to it. The
+    /// promise is not sent right away, instead it is kept in the scheduled receipts queue of the
+    /// current shard and released in the first block with height at least `block_height`.
+    ///
+    /// The scheduled receipt is stored in the state until it is released, so the current account
+    /// is charged storage staking for it.
+    ///
+    /// # Errors
+    ///
+    /// * If `account_id_len + account_id_ptr` points outside the memory of the guest or host
+    /// returns `MemoryAccessViolation`.
+    /// * If called as view function returns `ProhibitedInView`.
+    /// * If `block_height` is not greater than the current block height, or is more than
+    ///   `max_scheduled_receipt_delay_in_blocks` after it, returns `ScheduledReceiptHeight`.
+    /// * If the total number of promises exceeds `max_promises_per_function_call_action` limit
+    ///   returns `NumPromisesExceeded`.
+    ///
+    /// # Returns
+    ///
+    /// Index of the new promise that uniquely identifies it within the current execution of the
+    /// method.
+    ///
+    /// # Cost
+    ///
+    /// `burnt_gas := base + cost of reading and decoding the account id + dispatch cost of the receipt`.
+    /// `used_gas := burnt_gas + exec cost of the receipt`.
+    pub fn promise_batch_create_scheduled(
+        &mut self,
+        account_id_len: u64,
+        account_id_ptr: u64,
+        block_height: u64,
+    ) -> Result<u64> {
+        self.result_state.gas_counter.pay_base(base)?;
+        if self.context.is_view() {
+            return Err(HostError::ProhibitedInView {
+                method_name: "promise_batch_create_scheduled".to_string(),
+            }
+            .into());
+        }
+        let max_delay = self.config.limit_config.max_scheduled_receipt_delay_in_blocks;
+        if block_height <= self.context.block_height
+            || block_height - self.context.block_height > max_delay
+        {
+            return Err(HostError::ScheduledReceiptHeight {
+                height: block_height,
+                block_height: self.context.block_height,
+                max_delay,
+            }
+            .into());
+        }
+        let account_id = self.read_and_parse_account_id(account_id_ptr, account_id_len)?;
+        let sir = account_id == self.context.current_account_id;
+        self.pay_gas_for_new_receipt(sir, &[])?;
+        let new_receipt_idx = self.ext.create_scheduled_action_receipt(account_id, block_height)?;
+
+        self.checked_push_promise(Promise::Receipt(new_receipt_idx))
+    }
+
     /// Creates a new promise towards given `account_id` without any actions attached, that is
     /// executed after promise pointed by `promise_idx` is complete.
     ///
@@ -3,7 +3,7 @@ use crate::logic::dependencies::{Result, StorageAccessTracker};
 use crate::logic::types::ReceiptIndex;
 use crate::logic::{External, ValuePtr};
 use near_primitives_core::hash::{CryptoHash, hash};
-use near_primitives_core::types::{AccountId, Balance, Gas, GasWeight};
+use near_primitives_core::types::{AccountId, Balance, BlockHeight, Gas, GasWeight};
 use std::collections::HashMap;
 use std::sync::Arc;
 
@@ -72,6 +72,10 @@ pub enum MockAction {
         data_id: CryptoHash,
         data: Vec<u8>,
     },
+    CreateScheduledReceipt {
+        receiver_id: AccountId,
+        block_height: BlockHeight,
+    },
 }
 
 #[derive(Default, Clone)]
@@ -200,6 +204,16 @@ impl External for MockedExternal {
         Ok((index as u64, data_id))
     }
 
+    fn create_scheduled_action_receipt(
+        &mut self,
+        receiver_id: AccountId,
+        block_height: BlockHeight,
+    ) -> Result<ReceiptIndex, crate::logic::VMLogicError> {
+        let index = self.action_log.len();
+        self.action_log.push(MockAction::CreateScheduledReceipt { receiver_id, block_height });
+        Ok(index as u64)
+    }
+
     fn submit_promise_resume_data(
         &mut self,
         data_id: CryptoHash,
@@ -630,3 +630,63 @@ fn test_promise_batch_then() {
         ]"#]]
     .assert_eq(&serde_json::to_string_pretty(&vm_receipts(&logic_builder.ext)).unwrap());
 }
+
+#[test]
+fn test_promise_batch_create_scheduled() {
+    let mut logic_builder = VMLogicBuilder::default();
+    let max_delay = logic_builder.config.limit_config.max_scheduled_receipt_delay_in_blocks;
+    let mut logic = logic_builder.build();
+
+    let account_id = logic.internal_mem_write(b"rick.test");
+    logic
+        .promise_batch_create_scheduled(account_id.len, account_id.ptr, 10)
+        .expect_err("shouldn't accept the current block height");
+    logic
+        .promise_batch_create_scheduled(account_id.len, account_id.ptr, 10 + max_delay + 1)
+        .expect_err("shouldn't accept a block height too far in the future");
+    let index = logic
+        .promise_batch_create_scheduled(account_id.len, account_id.ptr, 15)
+        .expect("should create a scheduled promise");
+    let method_name = logic.internal_mem_write(b"tick");
+    let args = logic.internal_mem_write(b"{}");
+    let amount = logic.internal_mem_write(&0u128.to_le_bytes());
+    logic
+        .promise_batch_action_function_call(
+            index,
+            method_name.len,
+            method_name.ptr,
+            args.len,
+            args.ptr,
+            amount.ptr,
+            0,
+        )
+        .expect("should add an action to the scheduled promise");
+    expect_test::expect![[r#"
+        [
+          {
+            "CreateScheduledReceipt": {
+              "receiver_id": "rick.test",
+              "block_height": 15
+            }
+          },
+          {
+            "FunctionCallWeight": {
+              "receipt_index": 0,
+              "method_name": [
+                116,
+                105,
+                99,
+                107
+              ],
+              "args": [
+                123,
+                125
+              ],
+              "attached_deposit": 0,
+              "prepaid_gas": 0,
+              "gas_weight": 0
+            }
+          }
+        ]"#]]
+    .assert_eq(&serde_json::to_string_pretty(&vm_receipts(&logic_builder.ext)).unwrap());
+}
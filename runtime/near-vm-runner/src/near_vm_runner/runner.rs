@@ -106,7 +106,8 @@ impl NearVM {
     pub(crate) fn new_for_target(config: Arc<Config>, target: near_vm_compiler::Target) -> Self {
         // We only support singlepass compiler at the moment.
         assert_eq!(VM_CONFIG.compiler, NearVmCompiler::Singlepass);
-        let compiler = Singlepass::new();
+        let mut compiler = Singlepass::new();
+        compiler.bulk_op_unit_cost(u64::from(config.bulk_op_unit_cost));
         // We only support universal engine at the moment.
         assert_eq!(VM_CONFIG.engine, NearVmEngine::Universal);
 
@@ -4,6 +4,7 @@
 use crate::logic::errors::PrepareError;
 use near_parameters::vm::{Config, VMKind};
 
+mod bulk_op_gas;
 mod prepare_v2;
 
 /// Loads the given module given in `original_code`, performs some checks on it and
@@ -0,0 +1,301 @@
+//! Per-unit gas accounting of the bulk memory and table operations for the runtimes that rely on
+//! the finite-wasm instrumentation.
+//!
+//! NearVm charges `bulk_op_unit_cost` for every byte or table element processed by operations
+//! such as `memory.fill` directly in the generated machine code. finite-wasm can only charge a
+//! static cost per instruction, so for the other runtimes every bulk operation in the instrumented
+//! module is replaced with a call to a generated helper function. The helper charges for the
+//! number of units processed through the `finite_wasm_gas` import and then performs the operation.
+//!
+//! The helpers are appended after all other functions so that none of the existing function
+//! indices change. The static cost of the operation itself has already been accounted for by the
+//! finite-wasm analysis of the original instruction.
+
+use crate::logic::errors::PrepareError;
+use finite_wasm::wasmparser as wp;
+use wasm_encoder::{Encode, Instruction, SectionId, ValType};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum BulkOp {
+    MemoryInit { mem: u32, data_index: u32 },
+    MemoryCopy { dst_mem: u32, src_mem: u32 },
+    MemoryFill { mem: u32 },
+    TableInit { table: u32, elem_index: u32 },
+    TableCopy { dst_table: u32, src_table: u32 },
+    TableFill { table: u32 },
+    TableGrow { table: u32 },
+}
+
+impl BulkOp {
+    fn from_operator(op: &wp::Operator) -> Option<Self> {
+        Some(match *op {
+            wp::Operator::MemoryInit { mem, data_index } => Self::MemoryInit { mem, data_index },
+            wp::Operator::MemoryCopy { dst_mem, src_mem } => Self::MemoryCopy { dst_mem, src_mem },
+            wp::Operator::MemoryFill { mem } => Self::MemoryFill { mem },
+            wp::Operator::TableInit { table, elem_index } => Self::TableInit { table, elem_index },
+            wp::Operator::TableCopy { dst_table, src_table } => {
+                Self::TableCopy { dst_table, src_table }
+            }
+            wp::Operator::TableFill { table } => Self::TableFill { table },
+            wp::Operator::TableGrow { table } => Self::TableGrow { table },
+            _ => return None,
+        })
+    }
+
+    /// Parameters and results of the helper, matching the operands and results of the operation.
+    fn signature(self, tables: &[ValType]) -> Result<(Vec<ValType>, Vec<ValType>), PrepareError> {
+        let table_ty =
+            |table: u32| tables.get(table as usize).copied().ok_or(PrepareError::Deserialization);
+        Ok(match self {
+            Self::MemoryInit { .. }
+            | Self::MemoryCopy { .. }
+            | Self::MemoryFill { .. }
+            | Self::TableInit { .. }
+            | Self::TableCopy { .. } => (vec![ValType::I32; 3], vec![]),
+            Self::TableFill { table } => {
+                (vec![ValType::I32, table_ty(table)?, ValType::I32], vec![])
+            }
+            Self::TableGrow { table } => (vec![table_ty(table)?, ValType::I32], vec![ValType::I32]),
+        })
+    }
+
+    /// Index of the operand specifying the number of bytes or elements processed.
+    fn count_operand(self) -> u32 {
+        match self {
+            Self::TableGrow { .. } => 1,
+            _ => 2,
+        }
+    }
+
+    fn instruction(self) -> Instruction<'static> {
+        match self {
+            Self::MemoryInit { mem, data_index } => Instruction::MemoryInit { mem, data_index },
+            Self::MemoryCopy { dst_mem, src_mem } => Instruction::MemoryCopy { src_mem, dst_mem },
+            Self::MemoryFill { mem } => Instruction::MemoryFill(mem),
+            Self::TableInit { table, elem_index } => Instruction::TableInit { elem_index, table },
+            Self::TableCopy { dst_table, src_table } => {
+                Instruction::TableCopy { src_table, dst_table }
+            }
+            Self::TableFill { table } => Instruction::TableFill(table),
+            Self::TableGrow { table } => Instruction::TableGrow(table),
+        }
+    }
+}
+
+/// Information about the instrumented module gathered before rewriting it.
+struct ModuleInfo {
+    type_count: u32,
+    imported_function_count: u32,
+    defined_function_count: u32,
+    gas_function: Option<u32>,
+    tables: Vec<ValType>,
+    /// Distinct bulk operations used in the module, in order of the helpers generated for them.
+    bulk_ops: Vec<BulkOp>,
+}
+
+impl ModuleInfo {
+    fn collect(code: &[u8]) -> Result<Self, PrepareError> {
+        let mut info = Self {
+            type_count: 0,
+            imported_function_count: 0,
+            defined_function_count: 0,
+            gas_function: None,
+            tables: vec![],
+            bulk_ops: vec![],
+        };
+        for payload in wp::Parser::new(0).parse_all(code) {
+            match payload.map_err(|_| PrepareError::Deserialization)? {
+                wp::Payload::TypeSection(reader) => info.type_count = reader.count(),
+                wp::Payload::ImportSection(reader) => {
+                    for import in reader {
+                        let import = import.map_err(|_| PrepareError::Deserialization)?;
+                        if let wp::TypeRef::Func(_) = import.ty {
+                            if import.module == "internal" && import.name == "finite_wasm_gas" {
+                                info.gas_function = Some(info.imported_function_count);
+                            }
+                            info.imported_function_count += 1;
+                        }
+                    }
+                }
+                wp::Payload::FunctionSection(reader) => {
+                    info.defined_function_count = reader.count()
+                }
+                wp::Payload::TableSection(reader) => {
+                    for table in reader {
+                        let table = table.map_err(|_| PrepareError::Deserialization)?;
+                        info.tables.push(convert_ref_type(table.ty.element_type)?);
+                    }
+                }
+                wp::Payload::CodeSectionEntry(body) => {
+                    let mut reader =
+                        body.get_operators_reader().map_err(|_| PrepareError::Deserialization)?;
+                    while !reader.eof() {
+                        let op = reader.read().map_err(|_| PrepareError::Deserialization)?;
+                        if let Some(op) = BulkOp::from_operator(&op) {
+                            if !info.bulk_ops.contains(&op) {
+                                info.bulk_ops.push(op);
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(info)
+    }
+
+    fn helper_index(&self, op: BulkOp) -> u32 {
+        let position = self.bulk_ops.iter().position(|o| *o == op).expect("op was collected");
+        self.imported_function_count + self.defined_function_count + position as u32
+    }
+}
+
+fn convert_ref_type(ty: wp::RefType) -> Result<ValType, PrepareError> {
+    if ty == wp::RefType::FUNCREF {
+        Ok(ValType::FUNCREF)
+    } else if ty == wp::RefType::EXTERNREF {
+        Ok(ValType::EXTERNREF)
+    } else {
+        Err(PrepareError::Deserialization)
+    }
+}
+
+/// Skips over the item count at the start of a vector section payload.
+fn section_items(payload: &[u8]) -> &[u8] {
+    let count_len = payload.iter().position(|b| b & 0x80 == 0).map_or(payload.len(), |p| p + 1);
+    &payload[count_len..]
+}
+
+fn write_section(output: &mut Vec<u8>, id: SectionId, payload: &[u8]) {
+    id.encode(output);
+    payload.encode(output);
+}
+
+/// Makes bulk memory and table operations in the instrumented `code` charge `unit_cost` gas for
+/// every byte or element processed.
+pub(super) fn instrument(code: Vec<u8>, unit_cost: u32) -> Result<Vec<u8>, PrepareError> {
+    let info = ModuleInfo::collect(&code)?;
+    if info.bulk_ops.is_empty() {
+        return Ok(code);
+    }
+    // The bulk operations have a non-zero static cost, so finite-wasm must have imported the gas
+    // function.
+    let gas_function = info.gas_function.ok_or(PrepareError::Serialization)?;
+    let helper_count = info.bulk_ops.len() as u32;
+    let mut signatures = Vec::with_capacity(info.bulk_ops.len());
+    for op in &info.bulk_ops {
+        signatures.push(op.signature(&info.tables)?);
+    }
+
+    let mut output = Vec::with_capacity(code.len());
+    let mut code_section = Vec::new();
+    let mut remaining_bodies = 0;
+    for payload in wp::Parser::new(0).parse_all(&code) {
+        let payload = payload.map_err(|_| PrepareError::Deserialization)?;
+        let (id, range) = match payload {
+            wp::Payload::Version { range, .. } => {
+                output.extend(&code[range]);
+                continue;
+            }
+            wp::Payload::TypeSection(reader) => {
+                let mut section = Vec::new();
+                (info.type_count + helper_count).encode(&mut section);
+                section.extend(section_items(&code[reader.range()]));
+                for (params, results) in &signatures {
+                    section.push(0x60);
+                    params.as_slice().encode(&mut section);
+                    results.as_slice().encode(&mut section);
+                }
+                write_section(&mut output, SectionId::Type, &section);
+                continue;
+            }
+            wp::Payload::FunctionSection(reader) => {
+                let mut section = Vec::new();
+                (info.defined_function_count + helper_count).encode(&mut section);
+                section.extend(section_items(&code[reader.range()]));
+                for type_index in info.type_count..info.type_count + helper_count {
+                    type_index.encode(&mut section);
+                }
+                write_section(&mut output, SectionId::Function, &section);
+                continue;
+            }
+            wp::Payload::CodeSectionStart { count, .. } => {
+                (count + helper_count).encode(&mut code_section);
+                remaining_bodies = count;
+                continue;
+            }
+            wp::Payload::CodeSectionEntry(body) => {
+                rewrite_body(&code, &info, &body, &mut code_section)?;
+                remaining_bodies -= 1;
+                if remaining_bodies == 0 {
+                    for (op, (params, _)) in info.bulk_ops.iter().zip(&signatures) {
+                        helper_body(*op, params.len() as u32, gas_function, unit_cost)
+                            .encode(&mut code_section);
+                    }
+                    write_section(&mut output, SectionId::Code, &code_section);
+                }
+                continue;
+            }
+            wp::Payload::End(_) => continue,
+            wp::Payload::ImportSection(reader) => (SectionId::Import, reader.range()),
+            wp::Payload::TableSection(reader) => (SectionId::Table, reader.range()),
+            wp::Payload::MemorySection(reader) => (SectionId::Memory, reader.range()),
+            wp::Payload::GlobalSection(reader) => (SectionId::Global, reader.range()),
+            wp::Payload::ExportSection(reader) => (SectionId::Export, reader.range()),
+            wp::Payload::StartSection { range, .. } => (SectionId::Start, range),
+            wp::Payload::ElementSection(reader) => (SectionId::Element, reader.range()),
+            wp::Payload::DataCountSection { range, .. } => (SectionId::DataCount, range),
+            wp::Payload::DataSection(reader) => (SectionId::Data, reader.range()),
+            wp::Payload::CustomSection(reader) => (SectionId::Custom, reader.range()),
+            _ => return Err(PrepareError::Deserialization),
+        };
+        write_section(&mut output, id, &code[range]);
+    }
+    Ok(output)
+}
+
+/// Copies the function body over, replacing the bulk operations with calls to their helpers.
+fn rewrite_body(
+    code: &[u8],
+    info: &ModuleInfo,
+    body: &wp::FunctionBody,
+    output: &mut Vec<u8>,
+) -> Result<(), PrepareError> {
+    let range = body.range();
+    let mut reader = body.get_operators_reader().map_err(|_| PrepareError::Deserialization)?;
+    let mut rewritten = Vec::with_capacity(range.len());
+    let mut copied_up_to = range.start;
+    while !reader.eof() {
+        let offset = reader.original_position();
+        let op = reader.read().map_err(|_| PrepareError::Deserialization)?;
+        if let Some(op) = BulkOp::from_operator(&op) {
+            rewritten.extend(&code[copied_up_to..offset]);
+            Instruction::Call(info.helper_index(op)).encode(&mut rewritten);
+            copied_up_to = reader.original_position();
+        }
+    }
+    rewritten.extend(&code[copied_up_to..range.end]);
+    rewritten.as_slice().encode(output);
+    Ok(())
+}
+
+fn helper_body(
+    op: BulkOp,
+    param_count: u32,
+    gas_function: u32,
+    unit_cost: u32,
+) -> wasm_encoder::Function {
+    let mut function = wasm_encoder::Function::new(Vec::<(u32, ValType)>::new());
+    // The product of two 32-bit values always fits into 64 bits.
+    function.instruction(&Instruction::LocalGet(op.count_operand()));
+    function.instruction(&Instruction::I64ExtendI32U);
+    function.instruction(&Instruction::I64Const(i64::from(unit_cost)));
+    function.instruction(&Instruction::I64Mul);
+    function.instruction(&Instruction::Call(gas_function));
+    for param in 0..param_count {
+        function.instruction(&Instruction::LocalGet(param));
+    }
+    function.instruction(&op.instruction());
+    function.instruction(&Instruction::End);
+    function
+}
@@ -294,7 +294,10 @@ pub(crate) fn prepare_contract(
             tracing::error!(?err, ?kind, "Instrumentation failed");
             PrepareError::Serialization
         })?;
-    Ok(res)
+    if config.bulk_op_unit_cost == 0 {
+        return Ok(res);
+    }
+    super::bulk_op_gas::instrument(res, config.bulk_op_unit_cost)
 }
 
 // TODO: refactor to avoid copy-paste with the ones currently defined in near_vm_runner
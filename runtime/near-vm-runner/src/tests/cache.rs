@@ -107,6 +107,49 @@ fn test_does_not_cache_io_error() {
     })
 }
 
+#[test]
+fn test_export_import_contract_cache() {
+    with_vm_variants(&test_vm_config(), |vm_kind: VMKind| {
+        let config = Arc::new(Config { vm_kind, ..test_vm_config() });
+        let code = ContractCode::new(near_test_contracts::trivial_contract().to_vec(), None);
+        let source = MockContractRuntimeCache::default();
+        crate::precompile_contract(&code, Arc::clone(&config), Some(&source))
+            .expect("cache error")
+            .expect("compilation error");
+        assert_eq!(source.len(), 1);
+
+        let missing = CryptoHash::hash_bytes(b"missing");
+        let mut archive = vec![];
+        let exported =
+            crate::export_contract_cache(&source, &config, [*code.hash(), missing], &mut archive)
+                .unwrap();
+        assert_eq!(exported, 1);
+
+        let target = MockContractRuntimeCache::default();
+        let imported =
+            crate::import_contract_cache(&target, &config, &mut archive.as_slice()).unwrap();
+        assert_eq!(imported, 1);
+        let key = crate::get_contract_cache_key(*code.hash(), &config);
+        assert_eq!(target.get(&key).unwrap(), source.get(&key).unwrap());
+
+        // Importing the same archive again does not add anything new.
+        let imported =
+            crate::import_contract_cache(&target, &config, &mut archive.as_slice()).unwrap();
+        assert_eq!(imported, 0);
+
+        // A node with a different VM configuration must refuse the archive.
+        let mut other_config = Config::clone(&config);
+        other_config.regular_op_cost += 1;
+        let err = crate::import_contract_cache(
+            &MockContractRuntimeCache::default(),
+            &other_config,
+            &mut archive.as_slice(),
+        )
+        .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    })
+}
+
 fn make_cached_contract_call_vm(
     config: Arc<Config>,
     cache: &dyn ContractRuntimeCache,
@@ -36,6 +36,7 @@ static REFERENCE_TYPES: &str = r#"
 
 static BULK_MEMORY: &str = r#"
 (module
+  (memory 1)
   (func $entry (result i32) i32.const 0)
   (func (export "memory.copy") (param i32 i32 i32)
     local.get 0
@@ -95,12 +96,14 @@ static EXPECTED_UNSUPPORTED: &[(&str, &str)] = &[
     // ("module_linking", MODULE_LINKING),
     ("tail_call", TAIL_CALL),
     ("multi_value", MULTI_VALUE),
-    ("bulk_memory", BULK_MEMORY),
-    ("reference_types", REFERENCE_TYPES),
     ("threads", THREADS),
     ("simd", SIMD),
 ];
 
+/// Proposals that are only accepted when enabled in the config.
+static CONFIG_GATED: &[(&str, &str)] =
+    &[("bulk_memory", BULK_MEMORY), ("reference_types", REFERENCE_TYPES)];
+
 #[test]
 #[cfg(feature = "prepare")]
 fn ensure_fails_verification() {
@@ -115,6 +118,32 @@ fn ensure_fails_verification() {
     });
 }
 
+#[test]
+#[cfg(feature = "prepare")]
+fn config_gated_verification() {
+    for enabled in [false, true] {
+        let config = near_parameters::vm::Config {
+            bulk_memory: enabled,
+            reference_types: enabled,
+            ..test_vm_config()
+        };
+        with_vm_variants(&config, |kind| {
+            for (feature_name, wat) in CONFIG_GATED {
+                let wasm = wat::parse_str(wat).expect("parsing test wat should succeed");
+                let result = crate::prepare::prepare_contract(&wasm, &config, kind);
+                assert_eq!(
+                    result.is_ok(),
+                    enabled,
+                    "unexpected preparation result for {feature_name} feature: {result:?}"
+                );
+            }
+            // Multi-value is not enabled together with the other proposals.
+            let wasm = wat::parse_str(MULTI_VALUE).expect("parsing test wat should succeed");
+            assert!(crate::prepare::prepare_contract(&wasm, &config, kind).is_err());
+        });
+    }
+}
+
 #[test]
 fn ensure_fails_execution() {
     for (_feature_name, wat) in EXPECTED_UNSUPPORTED {
@@ -154,3 +183,47 @@ fn extension_saturating_float_to_int() {
             "#]],
         ]);
 }
+
+#[test]
+#[cfg(feature = "prepare")]
+fn bulk_memory_charged_per_byte() {
+    use crate::logic::mocks::mock_external::MockedExternal;
+    use crate::runner::VMKindExt;
+    use near_parameters::RuntimeFeesConfig;
+    use std::sync::Arc;
+
+    let config = near_parameters::vm::Config {
+        bulk_memory: true,
+        reference_types: true,
+        bulk_op_unit_cost: 100,
+        ..test_vm_config()
+    };
+    with_vm_variants(&config, |kind| {
+        let config = Arc::new(near_parameters::vm::Config { vm_kind: kind, ..config.clone() });
+        let used_gas = |len: u32| {
+            let wat = format!(
+                r#"(module
+                    (memory 1)
+                    (table 2048 funcref)
+                    (func (export "main")
+                        (memory.fill (i32.const 0) (i32.const 42) (i32.const {len}))
+                        (table.fill (i32.const 0) (ref.null func) (i32.const {len})))
+                )"#
+            );
+            let code = crate::ContractCode::new(wat::parse_str(wat).unwrap(), None);
+            let mut fake_external = MockedExternal::with_code(code);
+            let context = super::create_context(vec![]);
+            let gas_counter = context.make_gas_counter(&config);
+            let runtime = kind.runtime(config.clone()).unwrap();
+            let outcome = runtime
+                .prepare(&fake_external, None, gas_counter, "main")
+                .run(&mut fake_external, &context, Arc::new(RuntimeFeesConfig::test()))
+                .expect("execution failed");
+            assert!(outcome.aborted.is_none(), "{:?}", outcome.aborted);
+            outcome.used_gas
+        };
+        // Both lengths take two bytes to encode, so the contracts only differ in the number of
+        // bytes and elements filled.
+        assert_eq!(used_gas(2048) - used_gas(1024), 2 * 1024 * 100);
+    });
+}
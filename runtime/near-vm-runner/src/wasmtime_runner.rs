@@ -131,12 +131,26 @@ pub(crate) fn default_wasmtime_config(c: &Config) -> wasmtime::Config {
     let features = crate::features::WasmFeatures::new(c);
     let mut config = wasmtime::Config::from(features);
     config.max_wasm_stack(1024 * 1024 * 1024); // wasm stack metering is implemented by instrumentation, we don't want wasmtime to trap before that
+    // NearVm canonicalizes NaNs produced by floating point operations, do the same for the
+    // results to be observably identical.
+    config.cranelift_nan_canonicalization(true);
     config
 }
 
+#[derive(Hash)]
+struct WasmtimeConfig {
+    seed: u32,
+    /// Compiled artifacts contain machine code for the architecture of the node and must not be
+    /// loaded by a node running on a different one, e.g. when importing a contract cache archive.
+    target_arch: &'static str,
+}
+
+// Bump the seed whenever the wasmtime version or the code generation settings change.
+const WASMTIME_CONFIG: WasmtimeConfig =
+    WasmtimeConfig { seed: 65, target_arch: std::env::consts::ARCH };
+
 pub(crate) fn wasmtime_vm_hash() -> u64 {
-    // TODO: take into account compiler and engine used to compile the contract.
-    64
+    crate::utils::stable_hash(&WASMTIME_CONFIG)
 }
 
 pub(crate) struct WasmtimeVM {
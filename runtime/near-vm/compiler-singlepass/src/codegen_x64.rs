@@ -420,6 +420,32 @@ impl<'a> FuncGen<'a> {
         self.machine.release_temp_gpr(current_burnt_reg);
     }
 
+    /// Emit a gas charge proportional to the number of units processed by a bulk operation.
+    ///
+    /// `count` is the location of the `i32` operand specifying the number of bytes or table
+    /// elements the operation will process. Must be called before the registers of the operand
+    /// are released.
+    fn emit_gas_per_unit(&mut self, count: Location) {
+        let cost_per_unit = self.config.bulk_op_unit_cost;
+        if cost_per_unit == 0 {
+            return;
+        }
+        let cost_reg = self.machine.acquire_temp_gpr().unwrap();
+        // A 32-bit move zero-extends the operand into the full register.
+        self.assembler.emit_mov(Size::S32, count, Location::GPR(cost_reg));
+        let unit_cost_reg = self.machine.acquire_temp_gpr().unwrap();
+        self.assembler.emit_mov(
+            Size::S64,
+            Location::Imm64(cost_per_unit),
+            Location::GPR(unit_cost_reg),
+        );
+        self.assembler.emit_imul(Size::S64, Location::GPR(unit_cost_reg), Location::GPR(cost_reg));
+        self.machine.release_temp_gpr(unit_cost_reg);
+        self.assembler.emit_jmp(Condition::Overflow, self.special_labels.integer_overflow);
+        self.emit_gas(Location::GPR(cost_reg));
+        self.machine.release_temp_gpr(cost_reg);
+    }
+
     fn emit_trap(&mut self, code: TrapCode) {
         let label = self.assembler.get_label();
         self.assembler.emit_label(label);
@@ -5080,6 +5106,7 @@ impl<'a> FuncGen<'a> {
                 let len = self.value_stack.pop().unwrap();
                 let src = self.value_stack.pop().unwrap();
                 let dst = self.value_stack.pop().unwrap();
+                self.emit_gas_per_unit(len);
                 self.machine.release_locations_only_regs(&[len, src, dst]);
 
                 self.assembler.emit_mov(
@@ -5130,6 +5157,7 @@ impl<'a> FuncGen<'a> {
                 let len = self.value_stack.pop().unwrap();
                 let src_pos = self.value_stack.pop().unwrap();
                 let dst_pos = self.value_stack.pop().unwrap();
+                self.emit_gas_per_unit(len);
                 self.machine.release_locations_only_regs(&[len, src_pos, dst_pos]);
 
                 let memory_index = MemoryIndex::new(src_mem as usize);
@@ -5164,6 +5192,7 @@ impl<'a> FuncGen<'a> {
                 let len = self.value_stack.pop().unwrap();
                 let val = self.value_stack.pop().unwrap();
                 let dst = self.value_stack.pop().unwrap();
+                self.emit_gas_per_unit(len);
                 self.machine.release_locations_only_regs(&[len, val, dst]);
 
                 let memory_index = MemoryIndex::new(mem as usize);
@@ -7459,6 +7488,7 @@ impl<'a> FuncGen<'a> {
                 let table_index = TableIndex::new(index as _);
                 let delta = self.value_stack.pop().unwrap();
                 let init_value = self.value_stack.pop().unwrap();
+                self.emit_gas_per_unit(delta);
                 self.machine.release_locations_only_regs(&[delta, init_value]);
 
                 self.assembler.emit_mov(
@@ -7469,7 +7499,7 @@ impl<'a> FuncGen<'a> {
                             if self.module.local_table_index(table_index).is_some() {
                                 VMBuiltinFunctionIndex::get_table_grow_index()
                             } else {
-                                VMBuiltinFunctionIndex::get_imported_table_get_index()
+                                VMBuiltinFunctionIndex::get_imported_table_grow_index()
                             },
                         ) as i32,
                     ),
@@ -7498,6 +7528,7 @@ impl<'a> FuncGen<'a> {
                 let len = self.value_stack.pop().unwrap();
                 let src = self.value_stack.pop().unwrap();
                 let dest = self.value_stack.pop().unwrap();
+                self.emit_gas_per_unit(len);
                 self.machine.release_locations_only_regs(&[len, src, dest]);
 
                 self.assembler.emit_mov(
@@ -7529,6 +7560,7 @@ impl<'a> FuncGen<'a> {
                 let len = self.value_stack.pop().unwrap();
                 let val = self.value_stack.pop().unwrap();
                 let dest = self.value_stack.pop().unwrap();
+                self.emit_gas_per_unit(len);
                 self.machine.release_locations_only_regs(&[len, val, dest]);
 
                 self.assembler.emit_mov(
@@ -7557,6 +7589,7 @@ impl<'a> FuncGen<'a> {
                 let len = self.value_stack.pop().unwrap();
                 let src = self.value_stack.pop().unwrap();
                 let dest = self.value_stack.pop().unwrap();
+                self.emit_gas_per_unit(len);
                 self.machine.release_locations_only_regs(&[len, src, dest]);
 
                 self.assembler.emit_mov(
@@ -24,6 +24,9 @@ pub(crate) struct Intrinsic {
 pub struct Singlepass {
     pub(crate) enable_nan_canonicalization: bool,
     pub(crate) enable_stack_check: bool,
+    /// Gas charged for every byte of memory or element of a table processed by the bulk memory
+    /// operations (`memory.copy`, `memory.fill`, `table.grow`, etc.)
+    pub(crate) bulk_op_unit_cost: u64,
     /// Compiler intrinsics.
     pub(crate) intrinsics: Vec<Intrinsic>,
 }
@@ -35,6 +38,7 @@ impl Singlepass {
         Self {
             enable_nan_canonicalization: true,
             enable_stack_check: false,
+            bulk_op_unit_cost: 0,
             intrinsics: vec![Intrinsic {
                 kind: IntrinsicKind::Gas,
                 name: "gas".to_string(),
@@ -55,6 +59,16 @@ impl Singlepass {
         self
     }
 
+    /// Set the gas charged for every unit processed by the bulk memory operations.
+    ///
+    /// The operations that process a dynamic number of bytes or table elements (`memory.copy`,
+    /// `memory.fill`, `memory.init`, `table.copy`, `table.fill`, `table.init` and `table.grow`)
+    /// charge this amount for every unit on top of the static cost of the instruction.
+    pub fn bulk_op_unit_cost(&mut self, cost: u64) -> &mut Self {
+        self.bulk_op_unit_cost = cost;
+        self
+    }
+
     fn enable_nan_canonicalization(&mut self) {
         self.enable_nan_canonicalization = true;
     }
@@ -247,6 +247,14 @@ pub enum Cost {
     /// attempt to cause slow loads and stores. The total time spent in the
     /// runtime is divided by the number of executed instructions.
     WasmInstruction,
+    /// Estimates `wasm_config.bulk_op_unit_cost` which is charged for every
+    /// byte or table element processed by bulk memory operations such as
+    /// `memory.fill` or `table.copy`, on top of `regular_op_cost`.
+    ///
+    /// Estimation: Run a contract that fills a MiB of memory many times with
+    /// `memory.fill` and subtract the cost of the same contract filling zero
+    /// bytes. Divide the difference by the number of bytes filled.
+    WasmBulkOpUnit,
 
     // # Reading and writing memory
     // The hosting runtime sometimes copies data between in and out of WASM
@@ -213,6 +213,7 @@ static ALL_COSTS: &[(Cost, fn(&mut EstimatorContext) -> GasCost)] = &[
     (Cost::ActionDelegateExec, action_costs::delegate_exec),
     (Cost::HostFunctionCall, host_function_call),
     (Cost::WasmInstruction, wasm_instruction),
+    (Cost::WasmBulkOpUnit, wasm_bulk_op_unit),
     (Cost::DataReceiptCreationBase, data_receipt_creation_base),
     (Cost::DataReceiptCreationPerByte, data_receipt_creation_per_byte),
     (Cost::ReadMemoryBase, read_memory_base),
@@ -942,6 +943,57 @@ fn wasm_instruction(ctx: &mut EstimatorContext) -> GasCost {
     per_instruction
 }
 
+fn wasm_bulk_op_unit(ctx: &mut EstimatorContext) -> GasCost {
+    let vm_kind = ctx.config.vm_kind;
+    let n_iters = 10;
+    let n_fills = 100;
+    let fill_len = 1024 * 1024;
+
+    let config_store = RuntimeConfigStore::new(None);
+    let mut config =
+        near_parameters::vm::Config::clone(&config_store.get_config(PROTOCOL_VERSION).wasm_config);
+    config.bulk_memory = true;
+    let config = Arc::new(config);
+    let fees = Arc::new(RuntimeFeesConfig::test());
+    let cache = MockContractRuntimeCache::default();
+
+    let measure = |len: u64| {
+        let wat = format!(
+            r#"(module
+                (memory 16)
+                (func (export "main")
+                    (local $i i32)
+                    (loop $fill
+                        (memory.fill (i32.const 0) (i32.const 42) (i32.const {len}))
+                        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+                        (br_if $fill (i32.lt_u (local.get $i) (i32.const {n_fills}))))))"#
+        );
+        let code = ContractCode::new(wat::parse_str(wat).unwrap(), None);
+        let mut fake_external = MockedExternal::with_code(code);
+        let mut run = || {
+            let context = create_context(vec![]);
+            let gas_counter = context.make_gas_counter(&config);
+            let vm_result = vm_kind
+                .runtime(config.clone())
+                .unwrap()
+                .prepare(&fake_external, Some(&cache), gas_counter, "main")
+                .run(&mut fake_external, &context, Arc::clone(&fees))
+                .expect("fatal_error");
+            assert!(vm_result.aborted.is_none());
+        };
+        run();
+        let start = GasCost::measure(ctx.config.metric);
+        for _ in 0..n_iters {
+            run();
+        }
+        start.elapsed()
+    };
+
+    let base = measure(0);
+    let total = measure(fill_len);
+    total.saturating_sub(&base, &NonNegativeTolerance::PER_MILLE) / (n_iters * n_fills * fill_len)
+}
+
 fn read_memory_base(ctx: &mut EstimatorContext) -> GasCost {
     fn_cost(ctx, "read_memory_10b_10k", ExtCosts::read_memory_base, 10_000)
 }
@@ -17,7 +17,7 @@ use near_primitives::receipt::{
 };
 use near_primitives::transaction::{
     Action, AddKeyAction, DeleteAccountAction, DeleteKeyAction, DeployContractAction,
-    FunctionCallAction, StakeAction,
+    FunctionCallAction, StakeAction, UpgradeContractAction,
 };
 use near_primitives::types::validator_stake::ValidatorStake;
 use near_primitives::types::{
@@ -39,6 +39,7 @@ use near_vm_runner::logic::{VMContext, VMOutcome};
 use near_vm_runner::{ContractCode, ContractRuntimeCache};
 use near_vm_runner::{PreparedContract, precompile_contract};
 use near_wallet_contract::{wallet_contract, wallet_contract_magic_bytes};
+use std::collections::BTreeMap;
 use std::sync::Arc;
 
 /// Runs given function call with given context / apply state.
@@ -266,10 +267,15 @@ pub(crate) fn action_function_call(
         let mut promise_yield_indices = get_promise_yield_indices(state_update).unwrap_or_default();
         let initial_promise_yield_indices = promise_yield_indices.clone();
 
+        // Scheduled receipts are tracked by their index in `result.new_receipts`
+        let receipt_offset = result.new_receipts.len();
+        let mut scheduled_receipts = BTreeMap::new();
+
         let mut new_receipts: Vec<_> = receipt_manager
             .action_receipts
             .into_iter()
-            .map(|receipt| {
+            .enumerate()
+            .map(|(receipt_index, receipt)| {
                 // If the newly created receipt is a PromiseYield, enqueue a timeout for it
                 if receipt.is_promise_yield {
                     enqueue_promise_yield_timeout(
@@ -281,6 +287,9 @@ pub(crate) fn action_function_call(
                             + config.wasm_config.limit_config.yield_timeout_length_in_blocks,
                     );
                 }
+                if let Some(height) = receipt.scheduled_at {
+                    scheduled_receipts.insert(receipt_offset + receipt_index, height);
+                }
 
                 let new_action_receipt = ActionReceipt {
                     signer_id: action_receipt.signer_id.clone(),
@@ -329,15 +338,33 @@ pub(crate) fn action_function_call(
             set_promise_yield_indices(state_update, &promise_yield_indices);
         }
 
+        // Scheduled receipts are kept in the state until released, the account pays for storing
+        // them.
+        let storage_usage =
+            scheduled_receipts.keys().fold(outcome.storage_usage, |usage, index| {
+                let receipt = &new_receipts[index - receipt_offset];
+                usage.saturating_add(scheduled_receipt_storage_usage(receipt, config))
+            });
+
         account.set_amount(outcome.balance);
-        account.set_storage_usage(outcome.storage_usage);
+        account.set_storage_usage(storage_usage);
         result.result = Ok(outcome.return_data);
         result.new_receipts.extend(new_receipts);
+        result.scheduled_receipts.append(&mut scheduled_receipts);
     }
 
     Ok(())
 }
 
+/// Storage usage charged to the predecessor of a scheduled receipt until it is released.
+pub(crate) fn scheduled_receipt_storage_usage(
+    receipt: &Receipt,
+    config: &RuntimeConfig,
+) -> StorageUsage {
+    borsh::object_length(receipt).unwrap() as u64
+        + config.fees.storage_usage_config.num_extra_bytes_record
+}
+
 pub(crate) fn action_stake(
     account: &mut Account,
     result: &mut ActionResult,
@@ -589,6 +616,58 @@ pub(crate) fn action_deploy_contract(
     Ok(())
 }
 
+/// Deploys the new code and calls the migration method with it.
+///
+/// A failure of the migration is reported through `result` like for any other function call,
+/// which makes the whole receipt, including the deploy, roll back.
+pub(crate) fn action_upgrade_contract(
+    state_update: &mut TrieUpdate,
+    apply_state: &ApplyState,
+    account: &mut Account,
+    receipt: &Receipt,
+    action_receipt: &ActionReceipt,
+    promise_results: Arc<[near_vm_runner::logic::types::PromiseResult]>,
+    result: &mut ActionResult,
+    account_id: &AccountId,
+    upgrade_contract: &UpgradeContractAction,
+    action_hash: &CryptoHash,
+    config: &RuntimeConfig,
+    is_last_action: bool,
+    epoch_info_provider: &dyn EpochInfoProvider,
+    get_contract: impl FnOnce(CryptoHash) -> Box<dyn PreparedContract>,
+) -> Result<(), RuntimeError> {
+    let _span = tracing::debug_span!(target: "runtime", "action_upgrade_contract").entered();
+    let deploy_contract = DeployContractAction { code: upgrade_contract.code.clone() };
+    action_deploy_contract(
+        state_update,
+        account,
+        account_id,
+        &deploy_contract,
+        Arc::clone(&config.wasm_config),
+        apply_state.cache.as_deref(),
+        apply_state.current_protocol_version,
+    )?;
+    let code_hash = state_update.get_account_contract_hash(account.contract().as_ref())?;
+    let contract = get_contract(code_hash);
+    action_function_call(
+        state_update,
+        apply_state,
+        account,
+        receipt,
+        action_receipt,
+        promise_results,
+        result,
+        account_id,
+        &upgrade_contract.migration,
+        action_hash,
+        code_hash,
+        config,
+        is_last_action,
+        epoch_info_provider,
+        contract,
+    )
+}
+
 pub(crate) fn action_delete_account(
     state_update: &mut TrieUpdate,
     account: &mut Option<Account>,
@@ -981,7 +1060,8 @@ pub(crate) fn check_actor_permissions(
         | Action::AddKey(_)
         | Action::DeleteKey(_)
         | Action::DeployGlobalContract(_)
-        | Action::UseGlobalContract(_) => {
+        | Action::UseGlobalContract(_)
+        | Action::UpgradeContract(_) => {
             if actor_id != account_id {
                 return Err(ActionErrorKind::ActorNoPermission {
                     account_id: account_id.clone(),
@@ -1064,7 +1144,8 @@ pub(crate) fn check_account_existence(
         | Action::DeleteAccount(_)
         | Action::Delegate(_)
         | Action::DeployGlobalContract(_)
-        | Action::UseGlobalContract(_) => {
+        | Action::UseGlobalContract(_)
+        | Action::UpgradeContract(_) => {
             if account.is_none() {
                 return Err(ActionErrorKind::AccountDoesNotExist {
                     account_id: account_id.clone(),
@@ -9,7 +9,10 @@ use num_bigint::BigUint;
 use num_traits::cast::ToPrimitive;
 use num_traits::pow::Pow;
 // Just re-exporting RuntimeConfig for backwards compatibility.
-use near_parameters::{ActionCosts, RuntimeConfig, transfer_exec_fee, transfer_send_fee};
+use near_parameters::{
+    ActionCosts, RuntimeConfig, transfer_exec_fee, transfer_send_fee, upgrade_contract_exec_fee,
+    upgrade_contract_send_fee,
+};
 pub use near_primitives::num_rational::Rational32;
 use near_primitives::transaction::{Action, DeployContractAction, Transaction};
 use near_primitives::types::{AccountId, Balance, Compute, Gas};
@@ -144,6 +147,15 @@ pub fn total_send_fees(
                     + fees.fee(ActionCosts::use_global_contract_byte).send_fee(sender_is_receiver)
                         * num_bytes
             }
+            UpgradeContract(action) => {
+                let migration = &action.migration;
+                upgrade_contract_send_fee(
+                    fees,
+                    sender_is_receiver,
+                    action.code.len() as u64,
+                    migration.method_name.as_bytes().len() as u64 + migration.args.len() as u64,
+                )
+            }
         };
         result = safe_add_gas(result, delta)?;
     }
@@ -235,6 +247,14 @@ pub fn exec_fee(config: &RuntimeConfig, action: &Action, receiver_id: &AccountId
             fees.fee(ActionCosts::use_global_contract_base).exec_fee()
                 + fees.fee(ActionCosts::use_global_contract_byte).exec_fee() * num_bytes
         }
+        UpgradeContract(action) => {
+            let migration = &action.migration;
+            upgrade_contract_exec_fee(
+                fees,
+                action.code.len() as u64,
+                migration.method_name.as_bytes().len() as u64 + migration.args.len() as u64,
+            )
+        }
     }
 }
 
@@ -354,6 +354,14 @@ impl<'a> External for RuntimeExt<'a> {
             .map(|receipt_index| (receipt_index, input_data_id))
     }
 
+    fn create_scheduled_action_receipt(
+        &mut self,
+        receiver_id: AccountId,
+        block_height: BlockHeight,
+    ) -> Result<ReceiptIndex, VMLogicError> {
+        self.receipt_manager.create_scheduled_action_receipt(receiver_id, block_height)
+    }
+
     fn submit_promise_resume_data(
         &mut self,
         data_id: CryptoHash,
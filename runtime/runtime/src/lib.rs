@@ -28,7 +28,7 @@ use near_parameters::{ActionCosts, RuntimeConfig};
 pub use near_primitives;
 use near_primitives::account::Account;
 use near_primitives::bandwidth_scheduler::{BandwidthRequests, BlockBandwidthRequests};
-use near_primitives::chunk_apply_stats::ChunkApplyStatsV0;
+use near_primitives::chunk_apply_stats::ChunkApplyStatsV1;
 use near_primitives::congestion_info::{BlockCongestionInfo, CongestionInfo};
 use near_primitives::errors::{
     ActionError, ActionErrorKind, EpochError, IntegerOverflowError, InvalidTxError, RuntimeError,
@@ -37,7 +37,8 @@ use near_primitives::errors::{
 use near_primitives::hash::CryptoHash;
 use near_primitives::receipt::{
     ActionReceipt, DataReceipt, PromiseYieldIndices, PromiseYieldTimeout, Receipt, ReceiptEnum,
-    ReceiptOrStateStoredReceipt, ReceiptV0, ReceivedData,
+    ReceiptOrStateStoredReceipt, ReceiptV0, ReceivedData, ScheduledReceiptIndices,
+    TrieQueueIndices,
 };
 use near_primitives::sandbox::state_patch::SandboxStatePatch;
 use near_primitives::state_record::StateRecord;
@@ -63,10 +64,12 @@ use near_store::trie::AccessOptions;
 use near_store::trie::receipts_column_helper::DelayedReceiptQueue;
 use near_store::trie::update::TrieUpdateResult;
 use near_store::{
-    PartialStorage, StorageError, Trie, TrieAccess, TrieChanges, TrieUpdate, get, get_account,
-    get_postponed_receipt, get_promise_yield_receipt, get_pure, get_received_data,
-    has_received_data, remove_postponed_receipt, remove_promise_yield_receipt, set, set_access_key,
-    set_account, set_postponed_receipt, set_promise_yield_receipt, set_received_data,
+    PartialStorage, StorageError, Trie, TrieAccess, TrieChanges, TrieUpdate,
+    enqueue_scheduled_receipt, get, get_account, get_postponed_receipt, get_promise_yield_receipt,
+    get_pure, get_received_data, get_scheduled_receipt_indices, has_received_data,
+    remove_postponed_receipt, remove_promise_yield_receipt, set, set_access_key, set_account,
+    set_postponed_receipt, set_promise_yield_receipt, set_received_data,
+    set_scheduled_receipt_indices,
 };
 use near_vm_runner::ContractCode;
 use near_vm_runner::ContractRuntimeCache;
@@ -77,7 +80,7 @@ pub use near_vm_runner::with_ext_cost_counter;
 use pipelining::ReceiptPreparationPipeline;
 use rayon::prelude::*;
 use std::cmp::max;
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::sync::Arc;
 use tracing::{debug, instrument};
 use verifier::ValidateReceiptMode;
@@ -102,6 +105,10 @@ mod verifier;
 
 const EXPECT_ACCOUNT_EXISTS: &str = "account exists, checked above";
 
+/// Max number of block heights checked for scheduled receipts when applying a chunk. Every
+/// height costs a trie read, even if no receipts are scheduled for it.
+const MAX_SCHEDULED_RECEIPT_HEIGHTS_PER_CHUNK: u64 = 64;
+
 #[derive(Debug)]
 pub struct ApplyState {
     /// Points to a phase of the chain lifecycle that we want to run apply for.
@@ -199,7 +206,7 @@ pub struct ApplyResult {
     pub outgoing_receipts: Vec<Receipt>,
     pub outcomes: Vec<ExecutionOutcomeWithId>,
     pub state_changes: Vec<RawStateChangesWithTrieKey>,
-    pub stats: ChunkApplyStatsV0,
+    pub stats: ChunkApplyStatsV1,
     pub processed_delayed_receipts: Vec<Receipt>,
     pub processed_yield_timeouts: Vec<PromiseYieldTimeout>,
     pub proof: Option<PartialStorage>,
@@ -222,6 +229,9 @@ pub struct ActionResult {
     pub result: Result<ReturnData, ActionError>,
     pub logs: Vec<LogEntry>,
     pub new_receipts: Vec<Receipt>,
+    /// Indices into `new_receipts` of the receipts to put into the scheduled receipts queue
+    /// instead of sending them, mapped to the block height they are scheduled for.
+    pub scheduled_receipts: BTreeMap<usize, BlockHeight>,
     pub validator_proposals: Vec<ValidatorStake>,
     pub profile: Box<ProfileDataV3>,
 }
@@ -250,10 +260,19 @@ impl ActionResult {
             *receipt_index += self.new_receipts.len() as u64;
         }
         if self.result.is_ok() {
+            // Shifting local receipt indices to be global receipt indices.
+            let receipt_offset = self.new_receipts.len();
+            self.scheduled_receipts.extend(
+                next_result
+                    .scheduled_receipts
+                    .into_iter()
+                    .map(|(receipt_index, height)| (receipt_index + receipt_offset, height)),
+            );
             self.new_receipts.append(&mut next_result.new_receipts);
             self.validator_proposals.append(&mut next_result.validator_proposals);
         } else {
             self.new_receipts.clear();
+            self.scheduled_receipts.clear();
             self.validator_proposals.clear();
         }
         Ok(())
@@ -270,6 +289,7 @@ impl Default for ActionResult {
             result: Ok(ReturnData::None),
             logs: vec![],
             new_receipts: vec![],
+            scheduled_receipts: BTreeMap::new(),
             validator_proposals: vec![],
             profile: Default::default(),
         }
@@ -358,7 +378,7 @@ impl Runtime {
         apply_state: &ApplyState,
         validated_tx: &ValidatedTransaction,
         transaction_cost: &TransactionCost,
-        stats: &mut ChunkApplyStatsV0,
+        stats: &mut ChunkApplyStatsV1,
     ) -> Result<(Receipt, ExecutionOutcomeWithId), InvalidTxError> {
         let span = tracing::Span::current();
         metrics::TRANSACTION_PROCESSED_TOTAL.inc();
@@ -447,7 +467,7 @@ impl Runtime {
         action_index: usize,
         actions: &[Action],
         epoch_info_provider: &dyn EpochInfoProvider,
-        stats: &mut ChunkApplyStatsV0,
+        stats: &mut ChunkApplyStatsV1,
     ) -> Result<ActionResult, RuntimeError> {
         let _span = tracing::debug_span!(
             target: "runtime",
@@ -553,6 +573,28 @@ impl Runtime {
                     contract,
                 )?;
             }
+            Action::UpgradeContract(upgrade_contract) => {
+                let account = account.as_mut().expect(EXPECT_ACCOUNT_EXISTS);
+                let is_last_action = action_index + 1 == actions.len();
+                action_upgrade_contract(
+                    state_update,
+                    apply_state,
+                    account,
+                    receipt,
+                    action_receipt,
+                    promise_results,
+                    &mut result,
+                    account_id,
+                    upgrade_contract,
+                    action_hash,
+                    &apply_state.config,
+                    is_last_action,
+                    epoch_info_provider,
+                    |code_hash| {
+                        preparation_pipeline.get_contract(receipt, code_hash, action_index, None)
+                    },
+                )?;
+            }
             Action::Transfer(TransferAction { deposit }) => {
                 action_transfer_or_implicit_account_creation(
                     account,
@@ -632,7 +674,7 @@ impl Runtime {
         receipt: &Receipt,
         receipt_sink: &mut ReceiptSink,
         validator_proposals: &mut Vec<ValidatorStake>,
-        stats: &mut ChunkApplyStatsV0,
+        stats: &mut ChunkApplyStatsV1,
         epoch_info_provider: &dyn EpochInfoProvider,
     ) -> Result<ExecutionOutcomeWithId, RuntimeError> {
         let _span = tracing::debug_span!(
@@ -894,6 +936,14 @@ impl Runtime {
             };
         }
 
+        // Fetch metadata for the scheduled receipts queue
+        let has_scheduled_receipts = !result.scheduled_receipts.is_empty();
+        let mut scheduled_receipt_indices = if has_scheduled_receipts {
+            get_scheduled_receipt_indices(state_update)?
+        } else {
+            ScheduledReceiptIndices::default()
+        };
+
         // Generating receipt IDs
         let receipt_ids = result
             .new_receipts
@@ -907,12 +957,22 @@ impl Runtime {
                     ReceiptEnum::Action(_) | ReceiptEnum::PromiseYield(_)
                 );
 
-                let res = receipt_sink.forward_or_buffer_receipt(
-                    new_receipt,
-                    apply_state,
-                    state_update,
-                    epoch_info_provider,
-                );
+                // Scheduled receipts are held in the state until their block height is reached
+                let res = match result.scheduled_receipts.get(&receipt_index) {
+                    Some(&height) => enqueue_scheduled_receipt(
+                        state_update,
+                        &mut scheduled_receipt_indices,
+                        height,
+                        &new_receipt,
+                    )
+                    .map_err(RuntimeError::from),
+                    None => receipt_sink.forward_or_buffer_receipt(
+                        new_receipt,
+                        apply_state,
+                        state_update,
+                        epoch_info_provider,
+                    ),
+                };
                 if let Err(e) = res {
                     Some(Err(e))
                 } else if is_action {
@@ -923,6 +983,11 @@ impl Runtime {
             })
             .collect::<Result<_, _>>()?;
 
+        // Commit metadata for the scheduled receipts queue
+        if has_scheduled_receipts {
+            set_scheduled_receipt_indices(state_update, &scheduled_receipt_indices);
+        }
+
         let status = match result.result {
             Ok(ReturnData::ReceiptIndex(receipt_index)) => ExecutionStatus::SuccessReceiptId(
                 apply_state.create_receipt_id(receipt.receipt_id(), receipt_index as usize),
@@ -2075,6 +2140,11 @@ impl Runtime {
         let promise_yield_result =
             resolve_promise_yield_timeouts(processing_state, receipt_sink, compute_limit)?;
 
+        // Release receipts scheduled for this block height or earlier
+        if ProtocolFeature::ScheduledReceipts.enabled(processing_state.protocol_version) {
+            release_scheduled_receipts(processing_state, receipt_sink, compute_limit)?;
+        }
+
         let shard_id_str = processing_state.apply_state.shard_id.to_string();
         if processing_state.total.compute >= compute_limit {
             metrics::CHUNK_RECEIPTS_LIMITED_BY
@@ -2206,6 +2276,16 @@ impl Runtime {
             .with_label_values(&[shard_id_str.as_str()])
             .observe(chunk_recorded_size_upper_bound / f64::max(1.0, chunk_recorded_size));
         metrics::report_recorded_column_sizes(&trie, &apply_state);
+        if let Some(storage) = trie.internal_get_storage_as_caching_storage() {
+            (stats.storage.shard_cache_hits, stats.storage.shard_cache_misses) =
+                storage.shard_cache_hits_and_misses();
+        }
+        stats.storage.state_changes_num = state_changes.len().try_into().unwrap();
+        stats.storage.trie_insertions_num = trie_changes.insertions().len().try_into().unwrap();
+        stats.storage.trie_deletions_num = trie_changes.deletions().len().try_into().unwrap();
+        stats.gas_burnt = processing_state.total.gas;
+        stats.compute_usage = processing_state.total.compute;
+        stats.congestion_info = Some(own_congestion_info);
         let proof = trie.recorded_storage();
         let processed_yield_timeouts = promise_yield_result.processed_yield_timeouts;
         let bandwidth_scheduler_state_hash =
@@ -2432,6 +2512,104 @@ fn resolve_promise_yield_timeouts(
     })
 }
 
+/// Forwards receipts which were scheduled for the current block height or
+/// earlier and releases the storage staked by their predecessors.
+///
+/// Every released receipt is charged the send fee of an action receipt towards
+/// the chunk limits, so that many receipts scheduled for the same height are
+/// released over several chunks. At most `MAX_SCHEDULED_RECEIPT_HEIGHTS_PER_CHUNK`
+/// heights are checked in one chunk.
+///
+/// The scheduled receipts queue is copied to all children on resharding, so a
+/// receipt is only released by the shard its predecessor account belongs to and
+/// is dropped from the queues of the other shards.
+fn release_scheduled_receipts(
+    processing_state: &mut ApplyProcessingReceiptState,
+    receipt_sink: &mut ReceiptSink,
+    compute_limit: u64,
+) -> Result<(), RuntimeError> {
+    let state_update = &mut processing_state.state_update;
+    let total = &mut processing_state.total;
+    let apply_state = processing_state.apply_state;
+    let epoch_info_provider = processing_state.epoch_info_provider;
+    let shard_layout = epoch_info_provider.shard_layout(&apply_state.epoch_id)?;
+    let receipt_fee = apply_state.config.fees.fee(ActionCosts::new_action_receipt);
+
+    let mut scheduled_receipt_indices = get_scheduled_receipt_indices(state_update)?;
+    let initial_scheduled_receipt_indices = scheduled_receipt_indices.clone();
+    let mut num_heights_checked = 0;
+    while scheduled_receipt_indices.len > 0
+        && scheduled_receipt_indices.next_height <= apply_state.block_height
+    {
+        if total.compute >= compute_limit
+            || state_update.trie.check_proof_size_limit_exceed()
+            || num_heights_checked >= MAX_SCHEDULED_RECEIPT_HEIGHTS_PER_CHUNK
+        {
+            break;
+        }
+        num_heights_checked += 1;
+
+        let height = scheduled_receipt_indices.next_height;
+        let queue_indices_key = TrieKey::ScheduledReceiptQueueIndices { height };
+        let mut queue_indices: TrieQueueIndices =
+            get(state_update, &queue_indices_key)?.unwrap_or_default();
+        while queue_indices.len() > 0 {
+            if total.compute >= compute_limit || state_update.trie.check_proof_size_limit_exceed() {
+                break;
+            }
+
+            let receipt_key =
+                TrieKey::ScheduledReceipt { height, index: queue_indices.first_index };
+            let receipt: Receipt = get(state_update, &receipt_key)?.ok_or_else(|| {
+                StorageError::StorageInconsistentState(format!(
+                    "Scheduled receipt #{} for height {} should be in the state",
+                    queue_indices.first_index, height
+                ))
+            })?;
+            state_update.remove(receipt_key);
+            // Math checked above: the queue is not empty
+            queue_indices.first_index += 1;
+            scheduled_receipt_indices.len -= 1;
+
+            let predecessor_id = receipt.predecessor_id();
+            let sir = predecessor_id == receipt.receiver_id();
+            let release_gas = receipt_fee.send_fee(sir);
+            total.add(release_gas, release_gas)?;
+            if shard_layout.account_id_to_shard_id(predecessor_id) != apply_state.shard_id {
+                continue;
+            }
+            if let Some(mut account) = get_account(state_update, predecessor_id)? {
+                let storage_usage = scheduled_receipt_storage_usage(&receipt, &apply_state.config);
+                account.set_storage_usage(account.storage_usage().saturating_sub(storage_usage));
+                set_account(state_update, predecessor_id.clone(), &account);
+            }
+            receipt_sink.forward_or_buffer_receipt(
+                receipt,
+                apply_state,
+                state_update,
+                epoch_info_provider,
+            )?;
+        }
+
+        if queue_indices.len() > 0 {
+            // Ran out of compute or proof size, continue in the next chunk.
+            set(state_update, queue_indices_key, &queue_indices);
+            break;
+        }
+        if !queue_indices.is_default() {
+            state_update.remove(queue_indices_key);
+        }
+        scheduled_receipt_indices.next_height = height.checked_add(1).ok_or_else(|| {
+            RuntimeError::UnexpectedIntegerOverflow("release_scheduled_receipts".into())
+        })?;
+    }
+
+    if scheduled_receipt_indices != initial_scheduled_receipt_indices {
+        set_scheduled_receipt_indices(state_update, &scheduled_receipt_indices);
+    }
+    Ok(())
+}
+
 struct TotalResourceGuard {
     gas: u64,
     compute: u64,
@@ -2473,7 +2651,7 @@ struct ApplyProcessingState<'a> {
     state_update: TrieUpdate,
     epoch_info_provider: &'a dyn EpochInfoProvider,
     total: TotalResourceGuard,
-    stats: ChunkApplyStatsV0,
+    stats: ChunkApplyStatsV1,
 }
 
 impl<'a> ApplyProcessingState<'a> {
@@ -2493,7 +2671,7 @@ impl<'a> ApplyProcessingState<'a> {
             gas: 0,
             compute: 0,
         };
-        let stats = ChunkApplyStatsV0::new(apply_state.block_height, apply_state.shard_id);
+        let stats = ChunkApplyStatsV1::new(apply_state.block_height, apply_state.shard_id);
         Self {
             protocol_version,
             apply_state,
@@ -2542,7 +2720,7 @@ struct ApplyProcessingReceiptState<'a> {
     state_update: TrieUpdate,
     epoch_info_provider: &'a dyn EpochInfoProvider,
     total: TotalResourceGuard,
-    stats: ChunkApplyStatsV0,
+    stats: ChunkApplyStatsV1,
     outcomes: Vec<ExecutionOutcomeWithId>,
     metrics: ApplyMetrics,
     local_receipts: VecDeque<Receipt>,
@@ -2660,7 +2838,7 @@ pub mod estimator {
     use crate::congestion_control::ReceiptSinkV2;
     use crate::pipelining::ReceiptPreparationPipeline;
     use near_primitives::bandwidth_scheduler::BandwidthSchedulerParams;
-    use near_primitives::chunk_apply_stats::{ChunkApplyStatsV0, ReceiptSinkStats};
+    use near_primitives::chunk_apply_stats::{ChunkApplyStatsV1, ReceiptSinkStats};
     use near_primitives::congestion_info::CongestionInfo;
     use near_primitives::errors::RuntimeError;
     use near_primitives::receipt::Receipt;
@@ -2679,7 +2857,7 @@ pub mod estimator {
         receipt: &Receipt,
         outgoing_receipts: &mut Vec<Receipt>,
         validator_proposals: &mut Vec<ValidatorStake>,
-        stats: &mut ChunkApplyStatsV0,
+        stats: &mut ChunkApplyStatsV1,
         epoch_info_provider: &dyn EpochInfoProvider,
     ) -> Result<ExecutionOutcomeWithId, RuntimeError> {
         // TODO(congestion_control - edit runtime config parameters for limitless estimator runs
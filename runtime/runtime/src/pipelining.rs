@@ -109,9 +109,9 @@ impl ReceiptPreparationPipeline {
     /// for the most part it is best to submit work with limited look-ahead.
     ///
     /// Returns `true` if the receipt is interesting and that pipelining has acted on it in some
-    /// way. Currently `true` is returned for any receipts containing `Action::DeployContract` (in
-    /// which case no further processing for the receiver account will be done), and
-    /// `Action::FunctionCall` (provided the account has not been blocked.)
+    /// way. Currently `true` is returned for any receipts containing `Action::DeployContract` or
+    /// `Action::UpgradeContract` (in which case no further processing for the receiver account
+    /// will be done), and `Action::FunctionCall` (provided the account has not been blocked.)
     pub(crate) fn submit(
         &mut self,
         receipt: &Receipt,
@@ -135,7 +135,9 @@ impl ReceiptPreparationPipeline {
         for (action_index, action) in actions.iter().enumerate() {
             let account_id = account_id.clone();
             match action {
-                Action::DeployContract(_) | Action::UseGlobalContract(_) => {
+                Action::DeployContract(_)
+                | Action::UseGlobalContract(_)
+                | Action::UpgradeContract(_) => {
                     // FIXME: instead of blocking these accounts, move the handling of
                     // deploy action into here, so that the necessary data dependencies can be
                     // established.
@@ -276,8 +278,10 @@ impl ReceiptPreparationPipeline {
                 panic!("attempting to get_contract with a non-action receipt!?")
             }
         };
-        let Action::FunctionCall(function_call) = action else {
-            panic!("referenced receipt action is not a function call!");
+        let function_call = match action {
+            Action::FunctionCall(function_call) => &**function_call,
+            Action::UpgradeContract(upgrade_contract) => &upgrade_contract.migration,
+            _ => panic!("referenced receipt action is not a function call!"),
         };
         let key = PrepareTaskKey { receipt_id: receipt.get_hash(), action_index };
         let Some(task) = self.map.get(&key) else {
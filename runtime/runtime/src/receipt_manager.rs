@@ -7,7 +7,7 @@ use near_primitives::errors::RuntimeError;
 use near_primitives::receipt::DataReceiver;
 use near_primitives_core::account::{AccessKey, AccessKeyPermission, FunctionCallPermission};
 use near_primitives_core::hash::CryptoHash;
-use near_primitives_core::types::{AccountId, Balance, Gas, GasWeight, Nonce};
+use near_primitives_core::types::{AccountId, Balance, BlockHeight, Gas, GasWeight, Nonce};
 use near_vm_runner::logic::HostError;
 use near_vm_runner::logic::VMLogicError;
 use std::collections::HashMap;
@@ -36,6 +36,9 @@ pub struct ActionReceiptMetadata {
     pub actions: Vec<Action>,
     /// Indicates whether the receipt should have type Action or PromiseYield
     pub is_promise_yield: bool,
+    /// If present, the receipt is held in the scheduled receipts queue until the chain reaches
+    /// the given block height
+    pub scheduled_at: Option<BlockHeight>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -121,6 +124,7 @@ impl ReceiptManager {
             input_data_ids,
             actions: vec![],
             is_promise_yield: false,
+            scheduled_at: None,
         };
         let new_receipt_index = self.action_receipts.len() as ReceiptIndex;
         self.action_receipts.push(new_receipt);
@@ -147,6 +151,7 @@ impl ReceiptManager {
             input_data_ids: vec![input_data_id],
             actions: vec![],
             is_promise_yield: true,
+            scheduled_at: None,
         };
         let new_receipt_index = self.action_receipts.len();
         self.action_receipts.push(new_receipt);
@@ -154,6 +159,33 @@ impl ReceiptManager {
         Ok(new_receipt_index as ReceiptIndex)
     }
 
+    /// Special case of create_receipt used by scheduled receipts.
+    ///
+    /// The receipt has no input data dependencies. Instead of being sent right away, it is put
+    /// into the scheduled receipts queue and forwarded once the chain reaches `block_height`.
+    ///
+    /// # Arguments
+    ///
+    /// * `receiver_id` - account id of the receiver of the receipt created
+    /// * `block_height` - height of the block in which the receipt is released
+    pub(super) fn create_scheduled_action_receipt(
+        &mut self,
+        receiver_id: AccountId,
+        block_height: BlockHeight,
+    ) -> Result<ReceiptIndex, VMLogicError> {
+        let new_receipt = ActionReceiptMetadata {
+            receiver_id,
+            output_data_receivers: vec![],
+            input_data_ids: vec![],
+            actions: vec![],
+            is_promise_yield: false,
+            scheduled_at: Some(block_height),
+        };
+        let new_receipt_index = self.action_receipts.len() as ReceiptIndex;
+        self.action_receipts.push(new_receipt);
+        Ok(new_receipt_index)
+    }
+
     /// Creates a PromiseResume receipt.
     ///
     /// Should only be used to resolve dependencies created by `create_yielded_action_receipt`.
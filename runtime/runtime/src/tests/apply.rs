@@ -13,7 +13,7 @@ use near_o11y::testonly::init_test_logger;
 use near_parameters::{ActionCosts, RuntimeConfig};
 use near_primitives::account::AccessKey;
 use near_primitives::action::delegate::{DelegateAction, NonDelegateAction, SignedDelegateAction};
-use near_primitives::action::{Action, DeleteAccountAction};
+use near_primitives::action::{Action, DeleteAccountAction, UpgradeContractAction};
 use near_primitives::apply::ApplyChunkReason;
 use near_primitives::bandwidth_scheduler::BlockBandwidthRequests;
 use near_primitives::congestion_info::{
@@ -824,6 +824,44 @@ fn test_apply_surplus_gas_for_transfer() {
     assert_eq!(result.outgoing_receipts.len(), expected_receipts);
 }
 
+#[test]
+fn test_apply_stats_resource_usage() {
+    let initial_balance = to_yocto(1_000_000);
+    let initial_locked = to_yocto(500_000);
+    let small_transfer = to_yocto(10_000);
+    let gas_limit = 10u64.pow(15);
+    let (runtime, tries, root, apply_state, _, epoch_info_provider) = setup_runtime(
+        vec![alice_account(), bob_account()],
+        initial_balance,
+        initial_locked,
+        gas_limit,
+    );
+
+    let receipts = generate_receipts(small_transfer, 3);
+    let result = runtime
+        .apply(
+            tries.get_trie_for_shard(ShardUId::single_shard(), root),
+            &None,
+            &apply_state,
+            &receipts,
+            SignedValidPeriodTransactions::empty(),
+            &epoch_info_provider,
+            Default::default(),
+        )
+        .unwrap();
+
+    let stats = &result.stats;
+    let outcomes_gas_burnt: Gas =
+        result.outcomes.iter().map(|outcome| outcome.outcome.gas_burnt).sum();
+    assert!(outcomes_gas_burnt > 0);
+    assert!(stats.gas_burnt >= outcomes_gas_burnt);
+    assert!(stats.compute_usage >= stats.gas_burnt);
+    assert_eq!(stats.storage.state_changes_num, result.state_changes.len() as u64);
+    assert_eq!(stats.storage.trie_insertions_num, result.trie_changes.insertions().len() as u64);
+    assert_eq!(stats.storage.trie_deletions_num, result.trie_changes.deletions().len() as u64);
+    assert_eq!(stats.congestion_info, result.congestion_info);
+}
+
 #[test]
 fn test_apply_deficit_gas_for_function_call_covered() {
     let initial_balance = to_yocto(1_000_000);
@@ -2205,6 +2243,240 @@ fn test_deploy_and_call_in_same_receipt_with_failed_call() {
     assert_eq!(apply_result.contract_updates.contract_deploy_hashes(), HashSet::new());
 }
 
+fn apply_upgrade_contract(method_name: &str) -> (ContractCode, Option<CryptoHash>) {
+    let (runtime, tries, root, mut apply_state, signers, epoch_info_provider) = setup_runtime(
+        vec![alice_account(), bob_account()],
+        to_yocto(1_000_000),
+        to_yocto(500_000),
+        1,
+    );
+    apply_state.config = Arc::new(RuntimeConfig::free());
+
+    let contract_code = ContractCode::new(near_test_contracts::rs_contract().to_vec(), None);
+    let receipt = create_receipt_with_actions(
+        alice_account(),
+        signers[0].clone(),
+        vec![Action::UpgradeContract(Box::new(UpgradeContractAction {
+            code: contract_code.code().to_vec(),
+            migration: FunctionCallAction {
+                method_name: method_name.to_string(),
+                args: b"first".to_vec(),
+                gas: 1,
+                deposit: 0,
+            },
+        }))],
+    );
+
+    let apply_result = runtime
+        .apply(
+            tries.get_trie_for_shard(ShardUId::single_shard(), root),
+            &None,
+            &apply_state,
+            &[receipt],
+            SignedValidPeriodTransactions::empty(),
+            &epoch_info_provider,
+            Default::default(),
+        )
+        .unwrap();
+    let mut store_update = tries.store_update();
+    let root =
+        tries.apply_all(&apply_result.trie_changes, ShardUId::single_shard(), &mut store_update);
+    store_update.commit().unwrap();
+
+    let state_update = tries.new_trie_update(ShardUId::single_shard(), root);
+    let account = get_account(&state_update, &alice_account()).unwrap().unwrap();
+    (contract_code, account.local_contract_hash())
+}
+
+// Tests that the upgrade deploys the code and runs the migration with it.
+#[test]
+fn test_upgrade_contract() {
+    let (contract_code, contract_hash) = apply_upgrade_contract("ext_sha256");
+    assert_eq!(contract_hash, Some(*contract_code.hash()));
+}
+
+// Tests that the deploy is rolled back when the migration fails.
+#[test]
+fn test_upgrade_contract_with_failed_migration() {
+    let (contract_code, contract_hash) = apply_upgrade_contract("missing_migration");
+    assert_ne!(contract_hash, Some(*contract_code.hash()));
+}
+
+// Tests that a scheduled receipt is held in the state until its block height is reached and
+// that the storage it occupies is staked by the scheduling account in the meantime.
+#[cfg(feature = "nightly")]
+#[test]
+fn test_scheduled_receipt() {
+    let (runtime, tries, mut root, mut apply_state, signers, epoch_info_provider) =
+        setup_runtime(vec![alice_account()], to_yocto(1_000_000), to_yocto(500_000), 10u64.pow(15));
+    apply_state.config = Arc::new(RuntimeConfig::free());
+    let shard_uid = ShardUId::single_shard();
+    let scheduled_height = apply_state.block_height + 2;
+
+    let receipt = create_receipt_with_actions(
+        alice_account(),
+        signers[0].clone(),
+        vec![
+            Action::DeployContract(DeployContractAction {
+                code: near_test_contracts::nightly_rs_contract().to_vec(),
+            }),
+            Action::FunctionCall(Box::new(FunctionCallAction {
+                method_name: "call_promise_batch_create_scheduled".to_string(),
+                args: scheduled_height.to_le_bytes().to_vec(),
+                gas: MAX_ATTACHED_GAS,
+                deposit: 0,
+            })),
+        ],
+    );
+    let mut receipts = vec![receipt];
+    let mut scheduled_receipt_id = None;
+    let mut storage_usage = vec![];
+    for _ in 0..3 {
+        let apply_result = runtime
+            .apply(
+                tries.get_trie_for_shard(shard_uid, root),
+                &None,
+                &apply_state,
+                &std::mem::take(&mut receipts),
+                SignedValidPeriodTransactions::empty(),
+                &epoch_info_provider,
+                Default::default(),
+            )
+            .unwrap();
+        root = commit_apply_result(&apply_result, &mut apply_state, &tries, shard_uid);
+
+        let outgoing_receipt_ids = apply_result
+            .outgoing_receipts
+            .iter()
+            .map(|receipt| *receipt.receipt_id())
+            .collect::<Vec<_>>();
+        if let Some(scheduled_receipt_id) = scheduled_receipt_id {
+            assert_eq!(
+                outgoing_receipt_ids.contains(&scheduled_receipt_id),
+                apply_state.block_height == scheduled_height,
+            );
+        } else {
+            let outcome = &apply_result.outcomes[0].outcome;
+            assert_matches!(outcome.status, ExecutionStatus::SuccessValue(_));
+            let mut held_receipt_ids = outcome
+                .receipt_ids
+                .iter()
+                .filter(|receipt_id| !outgoing_receipt_ids.contains(receipt_id));
+            scheduled_receipt_id = held_receipt_ids.next().copied();
+            assert!(scheduled_receipt_id.is_some());
+            assert_eq!(held_receipt_ids.next(), None);
+        }
+
+        let state_update = tries.new_trie_update(shard_uid, root);
+        let account = get_account(&state_update, &alice_account()).unwrap().unwrap();
+        storage_usage.push(account.storage_usage());
+        let indices = near_store::get_scheduled_receipt_indices(&state_update).unwrap();
+        if apply_state.block_height < scheduled_height {
+            assert_eq!(
+                indices,
+                near_primitives::receipt::ScheduledReceiptIndices {
+                    next_height: scheduled_height,
+                    len: 1
+                }
+            );
+        } else {
+            assert_eq!(indices.len, 0);
+            let released_receipt = apply_result
+                .outgoing_receipts
+                .iter()
+                .find(|receipt| Some(*receipt.receipt_id()) == scheduled_receipt_id)
+                .unwrap();
+            assert_eq!(
+                storage_usage[0] - storage_usage[2],
+                crate::actions::scheduled_receipt_storage_usage(
+                    released_receipt,
+                    &apply_state.config
+                )
+            );
+        }
+        apply_state.block_height += 1;
+    }
+    assert_eq!(storage_usage[0], storage_usage[1]);
+}
+
+// Tests that releasing scheduled receipts is limited by the chunk compute limit and by the number
+// of block heights checked in one chunk.
+#[cfg(feature = "nightly")]
+#[test]
+fn test_scheduled_receipts_release_limits() {
+    use near_primitives::receipt::ScheduledReceiptIndices;
+
+    let (runtime, tries, mut root, mut apply_state, signers, epoch_info_provider) =
+        setup_runtime(vec![alice_account()], to_yocto(1_000_000), to_yocto(500_000), 10u64.pow(15));
+    let shard_uid = ShardUId::single_shard();
+    let first_height = apply_state.block_height + 1;
+    let last_height = first_height + crate::MAX_SCHEDULED_RECEIPT_HEIGHTS_PER_CHUNK + 10;
+
+    let receipts = (1..=3)
+        .map(|deposit| {
+            create_receipt_with_actions(
+                alice_account(),
+                signers[0].clone(),
+                vec![Action::Transfer(TransferAction { deposit })],
+            )
+        })
+        .collect::<Vec<_>>();
+    let mut state_update = tries.new_trie_update(shard_uid, root);
+    let mut indices = near_store::get_scheduled_receipt_indices(&state_update).unwrap();
+    for (height, receipt) in [first_height, first_height, last_height].into_iter().zip(&receipts) {
+        near_store::enqueue_scheduled_receipt(&mut state_update, &mut indices, height, receipt)
+            .unwrap();
+    }
+    near_store::set_scheduled_receipt_indices(&mut state_update, &indices);
+    state_update.commit(StateChangeCause::InitialState);
+    let trie_changes = state_update.finalize().unwrap().trie_changes;
+    let mut store_update = tries.store_update();
+    root = tries.apply_all(&trie_changes, shard_uid, &mut store_update);
+    store_update.commit().unwrap();
+
+    let gas_limit = apply_state.gas_limit;
+    // Every released receipt is charged the send fee, so only one fits into the first chunk.
+    let send_fee = apply_state.config.fees.fee(ActionCosts::new_action_receipt).send_fee(true);
+    apply_state.block_height = last_height;
+    let expected_indices = [
+        ScheduledReceiptIndices { next_height: first_height, len: 2 },
+        // The remaining heights can't all be checked in one chunk.
+        ScheduledReceiptIndices {
+            next_height: first_height + crate::MAX_SCHEDULED_RECEIPT_HEIGHTS_PER_CHUNK,
+            len: 1,
+        },
+        ScheduledReceiptIndices { next_height: last_height + 1, len: 0 },
+    ];
+    for (i, (receipt, expected_indices)) in receipts.iter().zip(expected_indices).enumerate() {
+        apply_state.gas_limit = if i == 0 { Some(send_fee) } else { gas_limit };
+        let apply_result = runtime
+            .apply(
+                tries.get_trie_for_shard(shard_uid, root),
+                &None,
+                &apply_state,
+                &[],
+                SignedValidPeriodTransactions::empty(),
+                &epoch_info_provider,
+                Default::default(),
+            )
+            .unwrap();
+        root = commit_apply_result(&apply_result, &mut apply_state, &tries, shard_uid);
+
+        let released_receipt_ids = apply_result
+            .outgoing_receipts
+            .iter()
+            .map(|receipt| *receipt.receipt_id())
+            .collect::<Vec<_>>();
+        assert_eq!(released_receipt_ids, vec![*receipt.receipt_id()]);
+        let state_update = tries.new_trie_update(shard_uid, root);
+        assert_eq!(
+            near_store::get_scheduled_receipt_indices(&state_update).unwrap(),
+            expected_indices
+        );
+        apply_state.block_height += 1;
+    }
+}
+
 // Tests the case in which a function call is made to an account with no contract deployed.
 #[test]
 fn test_call_account_without_contract() {
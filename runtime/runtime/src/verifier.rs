@@ -4,8 +4,8 @@ use crate::near_primitives::account::Account;
 use near_crypto::key_conversion::is_valid_staking_key;
 use near_parameters::RuntimeConfig;
 use near_primitives::account::{AccessKey, AccessKeyPermission};
-use near_primitives::action::DeployGlobalContractAction;
 use near_primitives::action::delegate::SignedDelegateAction;
+use near_primitives::action::{DeployGlobalContractAction, UpgradeContractAction};
 use near_primitives::errors::{
     ActionsValidationError, InvalidAccessKeyError, InvalidTxError, ReceiptValidationError,
 };
@@ -417,6 +417,9 @@ pub fn validate_action(
         Action::DeleteKey(_) => Ok(()),
         Action::DeleteAccount(a) => validate_delete_action(a),
         Action::Delegate(a) => validate_delegate_action(limit_config, a, current_protocol_version),
+        Action::UpgradeContract(a) => {
+            validate_upgrade_contract_action(limit_config, a, current_protocol_version)
+        }
     }
 }
 
@@ -470,6 +473,30 @@ fn validate_use_global_contract_action(
     check_global_contracts_enabled(current_protocol_version)
 }
 
+/// Validates `UpgradeContractAction`. Checks that the contract size doesn't exceed the limit and
+/// the migration is a valid function call.
+fn validate_upgrade_contract_action(
+    limit_config: &LimitConfig,
+    action: &UpgradeContractAction,
+    current_protocol_version: ProtocolVersion,
+) -> Result<(), ActionsValidationError> {
+    if !ProtocolFeature::UpgradeContractAction.enabled(current_protocol_version) {
+        return Err(ActionsValidationError::UnsupportedProtocolFeature {
+            protocol_feature: "UpgradeContractAction".to_owned(),
+            version: current_protocol_version,
+        });
+    }
+
+    if action.code.len() as u64 > limit_config.max_contract_size {
+        return Err(ActionsValidationError::ContractSizeExceeded {
+            size: action.code.len() as u64,
+            limit: limit_config.max_contract_size,
+        });
+    }
+
+    validate_function_call_action(limit_config, &action.migration)
+}
+
 /// Validates `FunctionCallAction`. Checks that the method name length doesn't exceed the limit and
 /// the length of the arguments doesn't exceed the limit.
 fn validate_function_call_action(
@@ -1934,6 +1961,29 @@ mod tests {
         .expect("valid action");
     }
 
+    #[test]
+    fn test_validate_action_upgrade_contract() {
+        let action = Action::UpgradeContract(Box::new(UpgradeContractAction {
+            code: vec![1; 5],
+            migration: FunctionCallAction {
+                method_name: "migrate".to_string(),
+                args: vec![],
+                gas: 100,
+                deposit: 0,
+            },
+        }));
+        let protocol_version = ProtocolFeature::UpgradeContractAction.protocol_version();
+        validate_action(&test_limit_config(), &action, protocol_version).expect("valid action");
+        assert_eq!(
+            validate_action(&test_limit_config(), &action, protocol_version - 1)
+                .expect_err("expected an error"),
+            ActionsValidationError::UnsupportedProtocolFeature {
+                protocol_feature: "UpgradeContractAction".to_owned(),
+                version: protocol_version - 1,
+            },
+        );
+    }
+
     #[test]
     fn test_delegate_action_must_be_only_one() {
         let signed_delegate_action = SignedDelegateAction {
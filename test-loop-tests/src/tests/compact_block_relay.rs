@@ -0,0 +1,135 @@
+use std::sync::Arc;
+
+use itertools::Itertools as _;
+use near_async::time::Duration;
+use near_chain_configs::test_genesis::{TestEpochConfigBuilder, ValidatorsSpec};
+use near_network::types::NetworkRequests;
+use near_o11y::testonly::init_test_logger;
+use near_primitives::shard_layout::ShardLayout;
+use near_primitives::types::AccountId;
+use parking_lot::RwLock;
+
+use crate::setup::builder::TestLoopBuilder;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ResponseMode {
+    /// Compact block responses are delivered as is.
+    Honest,
+    /// Chunk headers in the compact block responses are moved to the wrong indices.
+    Tampered,
+    /// Compact block responses are never delivered.
+    Dropped,
+}
+
+#[derive(Default, Debug)]
+struct Counters {
+    compact_block_requests: usize,
+    compact_block_responses: usize,
+    block_requests: usize,
+}
+
+/// Runs 4 block producers and a non-validator node, relaying the blocks as compact blocks
+/// between all of them. The block producers know the chunk headers of the new blocks
+/// from the chunk producers and reconstruct the blocks locally, while the other node has to
+/// request the chunk headers from the sender.
+fn run_compact_block_relay(mode: ResponseMode) -> Counters {
+    init_test_logger();
+
+    let block_producers = ["test1", "test2", "test3", "test4"];
+    let validators_spec = ValidatorsSpec::desired_roles(&block_producers, &[]);
+    let genesis = TestLoopBuilder::new_genesis_builder()
+        .epoch_length(100)
+        .shard_layout(ShardLayout::single_shard())
+        .validators_spec(validators_spec)
+        .build();
+    let epoch_config_store = TestEpochConfigBuilder::build_store_from_genesis(&genesis);
+    let clients: Vec<AccountId> =
+        block_producers.iter().chain(&["observer"]).map(|a| a.parse().unwrap()).collect_vec();
+    let mut env = TestLoopBuilder::new()
+        .genesis(genesis)
+        .epoch_config_store(epoch_config_store)
+        .clients(clients)
+        .build()
+        .warmup();
+    env.shared_state.network_shared_state.enable_compact_block_relay();
+
+    let counters = Arc::new(RwLock::new(Counters::default()));
+    for node in &env.node_datas {
+        let counters = counters.clone();
+        let peer_actor_handle = node.peer_manager_sender.actor_handle();
+        let peer_actor = env.test_loop.data.get_mut(&peer_actor_handle);
+        peer_actor.register_override_handler(Box::new(move |request| -> Option<NetworkRequests> {
+            let mut counters = counters.write();
+            match request {
+                NetworkRequests::CompactBlockRequest { .. } => {
+                    counters.compact_block_requests += 1;
+                    Some(request)
+                }
+                NetworkRequests::CompactBlockResponse { peer_id, mut response } => {
+                    counters.compact_block_responses += 1;
+                    match mode {
+                        ResponseMode::Honest => {}
+                        ResponseMode::Tampered => {
+                            for (index, _) in &mut response.chunks {
+                                *index += 1;
+                            }
+                        }
+                        ResponseMode::Dropped => return None,
+                    }
+                    Some(NetworkRequests::CompactBlockResponse { peer_id, response })
+                }
+                NetworkRequests::BlockRequest { .. } => {
+                    counters.block_requests += 1;
+                    Some(request)
+                }
+                _ => Some(request),
+            }
+        }));
+    }
+
+    let start_height = {
+        let client_actor_handle = env.node_datas[0].client_sender.actor_handle();
+        env.test_loop.data.get(&client_actor_handle).client.chain.head().unwrap().height
+    };
+    let client_actor_handles =
+        env.node_datas.iter().map(|data| data.client_sender.actor_handle()).collect_vec();
+    env.test_loop.run_until(
+        |test_loop_data| {
+            client_actor_handles.iter().all(|handle| {
+                let client = &test_loop_data.get(handle).client;
+                client.chain.head().unwrap().height >= start_height + 10
+            })
+        },
+        Duration::seconds(30),
+    );
+
+    let counters = std::mem::take(&mut *counters.write());
+    env.shutdown_and_drain_remaining_events(Duration::seconds(10));
+    counters
+}
+
+#[test]
+fn test_compact_block_relay() {
+    let counters = run_compact_block_relay(ResponseMode::Honest);
+    assert!(counters.compact_block_requests > 0, "{counters:?}");
+    assert!(counters.compact_block_responses > 0, "{counters:?}");
+    // Every compact block is reconstructed, by the block producers from the chunk headers
+    // they got from the chunk producers and by the other node from the responses.
+    assert_eq!(counters.block_requests, 0, "{counters:?}");
+}
+
+/// If the response doesn't match the compact block, the full block is requested.
+#[test]
+fn test_compact_block_relay_tampered_response() {
+    let counters = run_compact_block_relay(ResponseMode::Tampered);
+    assert!(counters.compact_block_responses > 0, "{counters:?}");
+    assert!(counters.block_requests > 0, "{counters:?}");
+}
+
+/// If no response arrives in time, the full block is requested.
+#[test]
+fn test_compact_block_relay_no_response() {
+    let counters = run_compact_block_relay(ResponseMode::Dropped);
+    assert!(counters.compact_block_requests > 0, "{counters:?}");
+    assert!(counters.block_requests > 0, "{counters:?}");
+}
@@ -13,9 +13,10 @@ use near_async::{MultiSend, MultiSenderFrom};
 use near_chain::BlockHeader;
 use near_client::{BlockApproval, BlockResponse, SetNetworkInfo};
 use near_network::client::{
-    BlockHeadersRequest, BlockHeadersResponse, BlockRequest, ChunkEndorsementMessage,
-    EpochSyncRequestMessage, EpochSyncResponseMessage, OptimisticBlockMessage, ProcessTxRequest,
-    ProcessTxResponse,
+    BlockHeadersRequest, BlockHeadersResponse, BlockRangeRequestMessage, BlockRangeResponseMessage,
+    BlockRequest, ChunkEndorsementMessage, CompactBlockMessage, CompactBlockRequestMessage,
+    CompactBlockResponseMessage, EpochSyncRequestMessage, EpochSyncResponseMessage,
+    OptimisticBlockMessage, ProcessTxRequest, ProcessTxResponse,
 };
 use near_network::shards_manager::ShardsManagerRequestFromNetwork;
 use near_network::state_witness::{
@@ -29,6 +30,7 @@ use near_network::types::{
     PeerManagerMessageRequest, PeerManagerMessageResponse, SetChainInfo, StateSyncEvent,
     Tier3Request,
 };
+use near_primitives::compact_block::CompactBlock;
 use near_primitives::genesis::GenesisId;
 use near_primitives::hash::CryptoHash;
 use near_primitives::network::PeerId;
@@ -45,6 +47,9 @@ pub struct ClientSenderForTestLoopNetwork {
     pub epoch_sync_request: Sender<EpochSyncRequestMessage>,
     pub epoch_sync_response: Sender<EpochSyncResponseMessage>,
     pub optimistic_block_receiver: Sender<OptimisticBlockMessage>,
+    pub block_range_response: Sender<BlockRangeResponseMessage>,
+    pub compact_block: Sender<CompactBlockMessage>,
+    pub compact_block_response: Sender<CompactBlockResponseMessage>,
     pub network_info: AsyncSender<SetNetworkInfo, ()>,
 }
 
@@ -58,6 +63,8 @@ pub struct TxRequestHandleSenderForTestLoopNetwork {
 pub struct ViewClientSenderForTestLoopNetwork {
     pub block_headers_request: AsyncSender<BlockHeadersRequest, ActixResult<BlockHeadersRequest>>,
     pub block_request: AsyncSender<BlockRequest, ActixResult<BlockRequest>>,
+    pub block_range_request: Sender<BlockRangeRequestMessage>,
+    pub compact_block_request: Sender<CompactBlockRequestMessage>,
 }
 
 /// This message is used to allow TestLoopPeerManagerActor to construct NetworkInfo for each
@@ -176,6 +183,8 @@ impl TestLoopPeerManagerActor {
                     highest_block_height: header.height(),
                     tracked_shards: vec![],
                     peer_info: peer_info.clone(),
+                    block_range_start: None,
+                    reputation: 0,
                 })
                 .collect(),
             ..NetworkInfo::default()
@@ -201,6 +210,7 @@ struct TestLoopNetworkSharedStateInner {
     drop_events_senders: Arc<OneClientSenders>,
     route_back: HashMap<CryptoHash, PeerId>,
     disallowed_peer_links: HashMap<PeerId, HashSet<PeerId>>,
+    compact_block_relay: bool,
 }
 
 /// Senders available for the networking layer, for one node in the test loop.
@@ -248,6 +258,7 @@ impl TestLoopNetworkSharedState {
             drop_events_senders: to_drop_events_senders(unreachable_actor_sender),
             route_back: HashMap::new(),
             disallowed_peer_links: HashMap::new(),
+            compact_block_relay: false,
         };
         Self(Arc::new(Mutex::new(inner)))
     }
@@ -293,6 +304,16 @@ impl TestLoopNetworkSharedState {
         guard.disallowed_peer_links = HashMap::new();
     }
 
+    /// Relays the blocks as compact blocks between all peers.
+    pub fn enable_compact_block_relay(&self) {
+        let mut guard = self.0.lock();
+        guard.compact_block_relay = true;
+    }
+
+    fn compact_block_relay(&self) -> bool {
+        self.0.lock().compact_block_relay
+    }
+
     fn account_to_peer_id(&self, account_id: &AccountId) -> PeerId {
         let guard = self.0.lock();
         guard.account_to_peer_id.get(account_id).unwrap().clone()
@@ -396,6 +417,8 @@ fn network_message_to_client_handler(
     Box::new(move |request| match request {
         NetworkRequests::Block { block } => {
             let my_peer_id = shared_state.account_to_peer_id(&my_account_id);
+            let compact_block =
+                if shared_state.compact_block_relay() { CompactBlock::new(&block) } else { None };
             for account_id in shared_state.accounts() {
                 if account_id == my_account_id {
                     continue;
@@ -403,12 +426,20 @@ fn network_message_to_client_handler(
 
                 let senders = shared_state.senders_for_account(&my_account_id, &account_id);
 
-                let future = senders.client_sender.send_async(BlockResponse {
-                    block: block.clone(),
-                    peer_id: my_peer_id.clone(),
-                    was_requested: false,
-                });
-                drop(future);
+                match &compact_block {
+                    Some(compact_block) => senders.client_sender.send(CompactBlockMessage {
+                        from_peer: my_peer_id.clone(),
+                        compact_block: compact_block.clone(),
+                    }),
+                    None => {
+                        let future = senders.client_sender.send_async(BlockResponse {
+                            block: block.clone(),
+                            peer_id: my_peer_id.clone(),
+                            was_requested: false,
+                        });
+                        drop(future);
+                    }
+                }
 
                 senders.peer_manager_sender.send(TestLoopNetworkBlockInfo {
                     peer: PeerInfo {
@@ -488,6 +519,22 @@ fn network_message_to_client_handler(
                 .send(EpochSyncResponseMessage { from_peer: my_peer_id, proof });
             None
         }
+        NetworkRequests::BlockRangeResponse { peer_id, response } => {
+            let my_peer_id = shared_state.account_to_peer_id(&my_account_id);
+            shared_state
+                .senders_for_peer(&my_peer_id, &peer_id)
+                .client_sender
+                .send(BlockRangeResponseMessage { from_peer: my_peer_id, response });
+            None
+        }
+        NetworkRequests::CompactBlockResponse { peer_id, response } => {
+            let my_peer_id = shared_state.account_to_peer_id(&my_account_id);
+            shared_state
+                .senders_for_peer(&my_peer_id, &peer_id)
+                .client_sender
+                .send(CompactBlockResponseMessage { from_peer: my_peer_id, response });
+            None
+        }
         NetworkRequests::StateRequestPart { .. } => None,
         _ => Some(request),
     })
@@ -535,6 +582,22 @@ fn network_message_to_view_client_handler(
             });
             None
         }
+        NetworkRequests::BlockRangeRequest { peer_id, request } => {
+            let my_peer_id = shared_state.account_to_peer_id(&my_account_id);
+            shared_state
+                .senders_for_peer(&my_peer_id, &peer_id)
+                .view_client_sender
+                .send(BlockRangeRequestMessage { from_peer: my_peer_id, request });
+            None
+        }
+        NetworkRequests::CompactBlockRequest { peer_id, request } => {
+            let my_peer_id = shared_state.account_to_peer_id(&my_account_id);
+            shared_state
+                .senders_for_peer(&my_peer_id, &peer_id)
+                .view_client_sender
+                .send(CompactBlockRequestMessage { from_peer: my_peer_id, request });
+            None
+        }
         _ => Some(request),
     })
 }
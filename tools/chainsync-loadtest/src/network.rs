@@ -267,6 +267,11 @@ impl Network {
             epoch_sync_request: noop().into_sender(),
             epoch_sync_response: noop().into_sender(),
             optimistic_block_receiver: noop().into_sender(),
+            block_range_request: noop().into_sender(),
+            block_range_response: noop().into_sender(),
+            compact_block: noop().into_sender(),
+            compact_block_request: noop().into_sender(),
+            compact_block_response: noop().into_sender(),
         }
     }
 }
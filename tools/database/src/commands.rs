@@ -8,6 +8,7 @@ use crate::compact::RunCompactionCommand;
 use crate::drop_column::DropColumnCommand;
 use crate::make_snapshot::MakeSnapshotCommand;
 use crate::memtrie::{LoadMemTrieCommand, SplitShardTrieCommand};
+use crate::replay_io_trace::ReplayIoTraceCommand;
 use crate::run_migrations::RunMigrationsCommand;
 use crate::set_version::SetVersionCommand;
 use crate::state_perf::StatePerfCommand;
@@ -43,6 +44,10 @@ enum SubCommand {
     /// Make snapshot of the database
     MakeSnapshot(MakeSnapshotCommand),
 
+    /// Replay reads of an IO trace against the database and report latencies
+    /// and cache hit rates
+    ReplayIoTrace(ReplayIoTraceCommand),
+
     /// Run migrations
     RunMigrations(RunMigrationsCommand),
 
@@ -86,6 +91,7 @@ impl DatabaseCommand {
                 let near_config = load_config(home, genesis_validation);
                 cmd.run(home, &near_config.config.store, near_config.config.archival_config())
             }
+            SubCommand::ReplayIoTrace(cmd) => cmd.run(home),
             SubCommand::RunMigrations(cmd) => cmd.run(home, genesis_validation),
             SubCommand::StatePerf(cmd) => cmd.run(home),
             SubCommand::LoadMemTrie(cmd) => cmd.run(home, genesis_validation),
@@ -0,0 +1,270 @@
+use crate::utils::resolve_column;
+use anyhow::Context;
+use clap::Parser;
+use near_primitives::hash::CryptoHash;
+use near_primitives::shard_layout::ShardUId;
+use near_store::{DBCol, NodeStorage, Store, StoreConfig, TrieCache, TrieConfig};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Replays the database reads of an IO trace recorded with `--record-io-trace`
+/// against the database of the node and reports read latencies and cache hit
+/// rates. Meant for evaluating `StoreConfig` and trie cache changes offline
+/// against real access patterns.
+///
+/// The trace records reads that reached the database, so reads served by the
+/// trie cache of the traced node are not part of it. Writes in the trace are
+/// skipped, the database is opened read-only.
+#[derive(Parser)]
+pub(crate) struct ReplayIoTraceCommand {
+    /// IO trace to replay.
+    #[clap(long)]
+    trace: PathBuf,
+
+    /// JSON file with `StoreConfig` fields overriding the `store` section of
+    /// `config.json`, e.g. `{"trie_cache": {"default_max_bytes": 1000000000}}`.
+    #[clap(long)]
+    store_config: Option<PathBuf>,
+
+    /// Serve `State` reads from a simulated trie cache configured with the
+    /// `trie_cache` store config, reading from the database on misses only.
+    #[clap(long)]
+    simulate_trie_cache: bool,
+
+    /// Replay at most that many reads.
+    #[clap(long)]
+    limit: Option<usize>,
+}
+
+impl ReplayIoTraceCommand {
+    pub(crate) fn run(&self, home: &Path) -> anyhow::Result<()> {
+        let store_config = self.load_store_config(home)?;
+        let db_path = store_config.path.clone().unwrap_or_else(|| home.join("data"));
+        let rocksdb = near_store::db::RocksDB::open(
+            &db_path,
+            &store_config,
+            near_store::Mode::ReadOnly,
+            near_store::Temperature::Hot,
+        )?;
+        let store = NodeStorage::new(Arc::new(rocksdb)).get_hot_store();
+        let trie_config = TrieConfig::from_store_config(&store_config);
+
+        let trace = std::fs::File::open(&self.trace)
+            .with_context(|| format!("failed to open {}", self.trace.display()))?;
+        let mut replay = Replay::new(store, self.simulate_trie_cache.then_some(trie_config));
+        for line in std::io::BufReader::new(trace).lines() {
+            if self.limit.is_some_and(|limit| replay.reads >= limit) {
+                break;
+            }
+            match parse_line(&line?) {
+                Some(TraceOp::Get { col, key }) => replay.get(col, &key)?,
+                Some(TraceOp::Write) => replay.skipped_writes += 1,
+                None => {}
+            }
+        }
+        print!("{}", replay.report());
+        Ok(())
+    }
+
+    fn load_store_config(&self, home: &Path) -> anyhow::Result<StoreConfig> {
+        let config = nearcore::config::Config::from_file_skip_validation(
+            &home.join(nearcore::config::CONFIG_FILENAME),
+        )?;
+        let Some(path) = &self.store_config else {
+            return Ok(config.store);
+        };
+        let mut store_config = serde_json::to_value(&config.store)?;
+        let overrides: serde_json::Value = serde_json::from_slice(&std::fs::read(path)?)
+            .with_context(|| format!("failed to parse {}", path.display()))?;
+        let (Some(store_config_fields), serde_json::Value::Object(overrides)) =
+            (store_config.as_object_mut(), overrides)
+        else {
+            anyhow::bail!("{} must contain a JSON object", path.display());
+        };
+        store_config_fields.extend(overrides);
+        Ok(serde_json::from_value(store_config)?)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum TraceOp {
+    Get { col: DBCol, key: Vec<u8> },
+    Write,
+}
+
+/// Parses a database operation line of the trace, e.g.
+/// `GET State "AAAAAAAAAAA..." size=42`. Returns `None` for other lines.
+fn parse_line(line: &str) -> Option<TraceOp> {
+    let line = line.trim_start();
+    let (op, rest) = line.split_once(' ')?;
+    match op {
+        "GET" => {}
+        "INSERT" | "SET" | "UPDATE_RC" | "DELETE" | "DELETE_ALL" => return Some(TraceOp::Write),
+        _ => return None,
+    }
+    let (col, key) = rest.split_once(' ')?;
+    let col = resolve_column(col).ok()?;
+    // Keys are formatted with `near_fmt::StorageKey` and quoted.
+    let key = match key.rsplit_once(" size=") {
+        Some((key, size)) if size.parse::<u64>().is_ok() => key,
+        _ => key,
+    };
+    let key = key.strip_prefix('"')?.strip_suffix('"')?.replace("\\\"", "\"").replace("\\\\", "\\");
+    let key = near_fmt::Bytes::from_str(&key).ok()?;
+    Some(TraceOp::Get { col, key })
+}
+
+#[derive(Default)]
+struct ColumnStats {
+    latencies: Vec<Duration>,
+    found: usize,
+    block_cache_hits: u64,
+    block_reads: u64,
+    trie_cache_hits: usize,
+}
+
+impl ColumnStats {
+    fn percentile(sorted: &[Duration], percentile: f64) -> Duration {
+        if sorted.is_empty() {
+            return Duration::ZERO;
+        }
+        let index = ((sorted.len() - 1) as f64 * percentile / 100.0).round() as usize;
+        sorted[index]
+    }
+}
+
+struct Replay {
+    store: Store,
+    trie_config: Option<TrieConfig>,
+    trie_caches: HashMap<ShardUId, TrieCache>,
+    perf_context: rocksdb::perf::PerfContext,
+    stats: HashMap<DBCol, ColumnStats>,
+    reads: usize,
+    skipped_writes: usize,
+}
+
+impl Replay {
+    fn new(store: Store, trie_config: Option<TrieConfig>) -> Self {
+        rocksdb::perf::set_perf_stats(rocksdb::perf::PerfStatsLevel::EnableTime);
+        Self {
+            store,
+            trie_config,
+            trie_caches: HashMap::new(),
+            perf_context: rocksdb::perf::PerfContext::default(),
+            stats: HashMap::new(),
+            reads: 0,
+            skipped_writes: 0,
+        }
+    }
+
+    fn get(&mut self, col: DBCol, key: &[u8]) -> anyhow::Result<()> {
+        self.reads += 1;
+        let stats = self.stats.entry(col).or_default();
+
+        let trie_cache = match (&self.trie_config, col) {
+            (Some(trie_config), DBCol::State) if key.len() == 40 => {
+                let shard_uid =
+                    ShardUId::try_from(&key[..8]).map_err(|err| anyhow::anyhow!(err))?;
+                let hash = CryptoHash::try_from(&key[8..]).map_err(|err| anyhow::anyhow!(err))?;
+                let cache = self
+                    .trie_caches
+                    .entry(shard_uid)
+                    .or_insert_with(|| TrieCache::new(trie_config, shard_uid, false));
+                if cache.get(&hash).is_some() {
+                    stats.trie_cache_hits += 1;
+                    stats.found += 1;
+                    return Ok(());
+                }
+                Some((cache, hash))
+            }
+            _ => None,
+        };
+
+        self.perf_context.reset();
+        let start = Instant::now();
+        let value = self.store.get(col, key)?;
+        stats.latencies.push(start.elapsed());
+        stats.block_cache_hits += self.perf_context.metric(rocksdb::PerfMetric::BlockCacheHitCount);
+        stats.block_reads += self.perf_context.metric(rocksdb::PerfMetric::BlockReadCount);
+        if let Some(value) = value {
+            stats.found += 1;
+            if let Some((cache, hash)) = trie_cache {
+                cache.update_cache(vec![(&hash, Some(&*value))]);
+            }
+        }
+        Ok(())
+    }
+
+    fn report(&mut self) -> String {
+        let mut out = String::new();
+        writeln!(out, "replayed {} reads, skipped {} writes", self.reads, self.skipped_writes)
+            .unwrap();
+        let mut stats: Vec<_> = self.stats.iter_mut().collect();
+        stats.sort_by_key(|(col, _)| <&str>::from(**col));
+        for (col, stats) in stats {
+            let reads = stats.latencies.len() + stats.trie_cache_hits;
+            stats.latencies.sort_unstable();
+            let latencies = &stats.latencies;
+            let total: Duration = latencies.iter().sum();
+            let mean = total.checked_div(latencies.len() as u32).unwrap_or_default();
+            writeln!(
+                out,
+                "{col}: reads {reads}, found {:.1}%, db reads {}, mean {mean:?}, p50 {:?}, p90 {:?}, p99 {:?}, p99.9 {:?}, max {:?}",
+                100.0 * stats.found as f64 / reads as f64,
+                latencies.len(),
+                ColumnStats::percentile(latencies, 50.0),
+                ColumnStats::percentile(latencies, 90.0),
+                ColumnStats::percentile(latencies, 99.0),
+                ColumnStats::percentile(latencies, 99.9),
+                latencies.last().copied().unwrap_or_default(),
+            )
+            .unwrap();
+            let block_accesses = stats.block_cache_hits + stats.block_reads;
+            if block_accesses > 0 {
+                writeln!(
+                    out,
+                    "  block cache hit rate {:.1}% ({} hits, {} block reads)",
+                    100.0 * stats.block_cache_hits as f64 / block_accesses as f64,
+                    stats.block_cache_hits,
+                    stats.block_reads,
+                )
+                .unwrap();
+            }
+            if self.trie_config.is_some() && *col == DBCol::State {
+                writeln!(
+                    out,
+                    "  trie cache hit rate {:.1}% ({} hits)",
+                    100.0 * stats.trie_cache_hits as f64 / reads as f64,
+                    stats.trie_cache_hits,
+                )
+                .unwrap();
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TraceOp, parse_line};
+    use near_store::DBCol;
+
+    #[test]
+    fn test_parse_line() {
+        assert_eq!(
+            parse_line("  GET State \"AAAAAAAAAAA=\" size=42"),
+            Some(TraceOp::Get { col: DBCol::State, key: vec![0; 8] })
+        );
+        assert_eq!(
+            parse_line("GET DbVersion \"'VERSION'\""),
+            Some(TraceOp::Get { col: DBCol::DbVersion, key: b"VERSION".to_vec() })
+        );
+        assert_eq!(parse_line("    SET State \"AAAAAAAAAAA=\" size=3"), Some(TraceOp::Write));
+        assert_eq!(parse_line("apply num_transactions=3"), None);
+        assert_eq!(parse_line("GET NoSuchColumn \"AAAAAAAAAAA=\""), None);
+    }
+}
@@ -253,6 +253,7 @@ export interface KnownPeerStateView {
     first_seen: number;
     last_seen: number;
     last_attempt: [number, string] | null;
+    reputation: number;
 }
 
 export interface SyncStatusResponse {
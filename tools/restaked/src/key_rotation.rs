@@ -0,0 +1,132 @@
+//! Validator key rotation.
+//!
+//! Rotating a validator key takes a few epochs: the new key is first proposed
+//! on chain together with the stake and only becomes the active validator key
+//! once the validator set with that proposal is in effect. Until then the node
+//! has to keep signing with the old key. The rotation therefore goes through
+//! these steps:
+//!
+//!  1. Generate a new key and store it next to the validator key with the
+//!     `.next` suffix.
+//!  2. Propose the new key on chain, either with a stake transaction or through
+//!     `update_staking_key` of the staking pool.
+//!  3. Once the next validator set lists the new key, schedule the key swap
+//!     for the epoch switch. The node is then polled about once per block and
+//!     as soon as it is in the new epoch, the validator key file is replaced
+//!     with the new key and `neard` gets SIGHUP. The node hot loads the key
+//!     through its updatable configs without a restart.
+
+use anyhow::Context;
+use near_crypto::{KeyFile, KeyType, PublicKey, SecretKey};
+use near_o11y::tracing::info;
+use near_primitives::types::{AccountId, EpochHeight};
+use std::path::{Path, PathBuf};
+
+const STATE_FILENAME: &str = "restaked_state.json";
+
+/// Persistent state of the key rotation, kept in the node home directory.
+#[derive(serde::Serialize, serde::Deserialize, Default)]
+struct RotationState {
+    /// Epoch in which the last rotation completed.
+    last_rotation_epoch: Option<EpochHeight>,
+}
+
+pub(crate) struct KeyRotation {
+    /// Rotate the key every this many epochs.
+    period: EpochHeight,
+    key_path: PathBuf,
+    pending_key_path: PathBuf,
+    state_path: PathBuf,
+    state: RotationState,
+    neard_pid: Option<i32>,
+}
+
+impl KeyRotation {
+    pub(crate) fn new(
+        home_dir: &Path,
+        key_path: PathBuf,
+        period: EpochHeight,
+        neard_pid: Option<i32>,
+    ) -> anyhow::Result<Self> {
+        let mut pending_key_path = key_path.clone().into_os_string();
+        pending_key_path.push(".next");
+        let state_path = home_dir.join(STATE_FILENAME);
+        let state = match std::fs::read_to_string(&state_path) {
+            Ok(data) => serde_json::from_str(&data)
+                .with_context(|| format!("can't parse {}", state_path.display()))?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => RotationState::default(),
+            Err(err) => return Err(err.into()),
+        };
+        Ok(Self {
+            period,
+            key_path,
+            pending_key_path: pending_key_path.into(),
+            state_path,
+            state,
+            neard_pid,
+        })
+    }
+
+    /// The key waiting to become the validator key, if a rotation is ongoing.
+    pub(crate) fn pending_key(&self) -> anyhow::Result<Option<KeyFile>> {
+        match KeyFile::from_file(&self.pending_key_path) {
+            Ok(key_file) => Ok(Some(key_file)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => {
+                Err(err).with_context(|| format!("can't read {}", self.pending_key_path.display()))
+            }
+        }
+    }
+
+    /// Whether a new rotation has to be started in the given epoch.
+    pub(crate) fn is_due(&mut self, epoch_height: EpochHeight) -> anyhow::Result<bool> {
+        match self.state.last_rotation_epoch {
+            Some(last) => Ok(epoch_height >= last + self.period),
+            None => {
+                // Count the period from the first epoch the daemon saw.
+                self.state.last_rotation_epoch = Some(epoch_height);
+                self.save_state()?;
+                Ok(false)
+            }
+        }
+    }
+
+    /// Generates the next validator key and stores it as pending.
+    pub(crate) fn start(&self, account_id: AccountId) -> anyhow::Result<PublicKey> {
+        let secret_key = SecretKey::from_random(KeyType::ED25519);
+        let public_key = secret_key.public_key();
+        let key_file = KeyFile { account_id, public_key: public_key.clone(), secret_key };
+        key_file
+            .write_to_file(&self.pending_key_path)
+            .with_context(|| format!("can't write {}", self.pending_key_path.display()))?;
+        info!(target: "restaked", %public_key, "Generated the next validator key");
+        Ok(public_key)
+    }
+
+    /// Makes the pending key the validator key of the node.
+    pub(crate) fn complete(&mut self, epoch_height: EpochHeight) -> anyhow::Result<()> {
+        let mut backup_path = self.key_path.clone().into_os_string();
+        backup_path.push(format!(".{}", epoch_height));
+        std::fs::copy(&self.key_path, &backup_path)
+            .with_context(|| format!("can't back up {}", self.key_path.display()))?;
+        std::fs::rename(&self.pending_key_path, &self.key_path)
+            .with_context(|| format!("can't replace {}", self.key_path.display()))?;
+        self.state.last_rotation_epoch = Some(epoch_height);
+        self.save_state()?;
+        info!(target: "restaked", epoch_height, "Validator key rotated");
+
+        if let Some(pid) = self.neard_pid {
+            nix::sys::signal::kill(nix::unistd::Pid::from_raw(pid), nix::sys::signal::SIGHUP)
+                .context("can't signal neard to reload the validator key")?;
+        } else {
+            info!(target: "restaked", "Send SIGHUP to neard to load the new validator key");
+        }
+        Ok(())
+    }
+
+    fn save_state(&self) -> anyhow::Result<()> {
+        let data = serde_json::to_string_pretty(&self.state)?;
+        std::fs::write(&self.state_path, data)
+            .with_context(|| format!("can't write {}", self.state_path.display()))
+    }
+}
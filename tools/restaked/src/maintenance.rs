@@ -0,0 +1,85 @@
+use near_o11y::tracing::{info, warn};
+use near_primitives::types::{BlockHeight, EpochHeight};
+use std::process::Command;
+
+/// Maintenance windows of the current epoch, as reported by
+/// `EXPERIMENTAL_maintenance_windows`. In these windows the validator is
+/// neither a block nor a chunk producer, so the node can be restarted or
+/// upgraded without missing any production.
+#[derive(Default)]
+pub(crate) struct MaintenanceSchedule {
+    epoch_height: Option<EpochHeight>,
+    windows: Vec<(BlockHeight, BlockHeight)>,
+    /// Start of the last window the maintenance command was run for.
+    last_started: Option<BlockHeight>,
+}
+
+impl MaintenanceSchedule {
+    /// Whether the schedule has to be refetched for the given epoch.
+    pub(crate) fn is_stale(&self, epoch_height: EpochHeight) -> bool {
+        self.epoch_height != Some(epoch_height)
+    }
+
+    pub(crate) fn update(
+        &mut self,
+        epoch_height: EpochHeight,
+        windows: Vec<(BlockHeight, BlockHeight)>,
+    ) {
+        info!(target: "restaked", epoch_height, ?windows, "Maintenance windows for the epoch");
+        self.epoch_height = Some(epoch_height);
+        self.windows = windows;
+    }
+
+    /// Returns the window `height` falls into if it spans at least
+    /// `min_blocks` blocks and hasn't been started yet.
+    pub(crate) fn window_to_start(
+        &mut self,
+        height: BlockHeight,
+        min_blocks: u64,
+    ) -> Option<(BlockHeight, BlockHeight)> {
+        let window =
+            self.windows.iter().copied().find(|&(start, end)| {
+                start <= height && height < end && end - start >= min_blocks
+            })?;
+        if self.last_started == Some(window.0) {
+            return None;
+        }
+        self.last_started = Some(window.0);
+        Some(window)
+    }
+}
+
+/// Runs the operator provided maintenance command in the background.
+pub(crate) fn run_command(command: &str, (start, end): (BlockHeight, BlockHeight)) {
+    info!(target: "restaked", start, end, command, "Starting maintenance");
+    let result = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .env("NEAR_MAINTENANCE_START", start.to_string())
+        .env("NEAR_MAINTENANCE_END", end.to_string())
+        .spawn();
+    if let Err(err) = result {
+        warn!(target: "restaked", ?err, command, "Failed to run the maintenance command");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MaintenanceSchedule;
+
+    #[test]
+    fn test_window_started_once() {
+        let mut schedule = MaintenanceSchedule::default();
+        assert!(schedule.is_stale(7));
+        schedule.update(7, vec![(100, 105), (200, 400)]);
+        assert!(!schedule.is_stale(7));
+
+        // Too short.
+        assert_eq!(schedule.window_to_start(101, 50), None);
+        // Outside of any window.
+        assert_eq!(schedule.window_to_start(150, 50), None);
+        assert_eq!(schedule.window_to_start(210, 50), Some((200, 400)));
+        assert_eq!(schedule.window_to_start(250, 50), None);
+        assert_eq!(schedule.window_to_start(400, 50), None);
+    }
+}
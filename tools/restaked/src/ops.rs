@@ -0,0 +1,605 @@
+use crate::Cli;
+use crate::key_rotation::KeyRotation;
+use crate::maintenance::{self, MaintenanceSchedule};
+use crate::restake::{self, current_validator, needs_restake};
+use crate::rpc::{ChainRpc, OpsRpc};
+use crate::seat_price::{SeatPosition, SeatPrice};
+use anyhow::Context;
+use near_crypto::{InMemorySigner, KeyFile, PublicKey, Signer};
+use near_o11y::tracing::{info, warn};
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::{AccountId, Balance, BlockHeight, EpochHeight};
+use near_primitives::views::{EpochValidatorInfo, StatusSyncInfo};
+use nearcore::config::{CONFIG_FILENAME, Config};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// How often the node is polled while waiting for the epoch switch at which
+/// the validator key is swapped. Roughly once per block, so that the node
+/// signs the first blocks and chunks of the epoch with the new key.
+const KEY_SWAP_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Transactions the daemon sends, each of them at most once per epoch.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+enum TxKind {
+    /// Restake or `ping` of the staking pool.
+    Restake,
+    /// Proposal of the next validator key.
+    KeyProposal,
+}
+
+/// Validator key swap waiting for the epoch switch.
+struct KeySwap {
+    /// Epoch in which the pending key was seen in the next validator set.
+    epoch_height: EpochHeight,
+    epoch_start_height: BlockHeight,
+}
+
+pub(crate) struct ValidatorOps<R = OpsRpc> {
+    rpc: R,
+    /// Account the validator key belongs to.
+    account_id: AccountId,
+    /// Key the node currently validates with.
+    public_key: PublicKey,
+    /// Signs the transactions sent by the daemon.
+    signer: Signer,
+    staking_pool: Option<AccountId>,
+    /// Stake to propose, 0 means the last seen stake.
+    stake_amount: Balance,
+    last_stake_amount: Balance,
+    seat_price_margin_percent: u8,
+    key_rotation: Option<KeyRotation>,
+    maintenance: MaintenanceSchedule,
+    maintenance_command: Option<String>,
+    min_maintenance_blocks: u64,
+    /// Epoch in which each kind of transaction was last sent, to send at most
+    /// one per epoch and not race with transactions which are still in flight.
+    last_tx_epoch: HashMap<TxKind, EpochHeight>,
+    last_seat_price_epoch: Option<EpochHeight>,
+    key_swap: Option<KeySwap>,
+    wait_period: Duration,
+    /// When the checks were last run.
+    last_check: Option<Instant>,
+}
+
+impl ValidatorOps {
+    pub(crate) fn new(cli: Cli) -> anyhow::Result<Self> {
+        let config =
+            Config::from_file(&cli.home.join(CONFIG_FILENAME)).context("can't load config")?;
+        let key_path = cli.home.join(&config.validator_key_file);
+        let key_file = KeyFile::from_file(&key_path)
+            .with_context(|| format!("failed to open key file at {}", key_path.display()))?;
+        let signer_path = cli.signer_key.unwrap_or_else(|| key_path.clone());
+        let signer = InMemorySigner::from_file(&signer_path).with_context(|| {
+            format!("failed to initialize signer from key file at {}", signer_path.display())
+        })?;
+        match &cli.staking_pool {
+            // Pool methods are called by the owner, which may be any account.
+            Some(_) => {}
+            None => anyhow::ensure!(
+                signer.get_account_id() == key_file.account_id,
+                "Only can stake for the same account as given signer key"
+            ),
+        }
+
+        let key_rotation = match cli.rotate_key_epochs {
+            Some(period) => {
+                anyhow::ensure!(
+                    signer_path != key_path,
+                    "Key rotation requires a separate --signer-key, the validator key is replaced"
+                );
+                Some(KeyRotation::new(&cli.home, key_path, period, cli.neard_pid)?)
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            rpc: OpsRpc::new(&cli.rpc_url),
+            account_id: key_file.account_id,
+            public_key: key_file.public_key,
+            signer,
+            staking_pool: cli.staking_pool,
+            stake_amount: cli.stake_amount,
+            last_stake_amount: cli.stake_amount,
+            seat_price_margin_percent: cli.seat_price_margin_percent,
+            key_rotation,
+            maintenance: MaintenanceSchedule::default(),
+            maintenance_command: cli.maintenance_command,
+            min_maintenance_blocks: cli.min_maintenance_blocks,
+            last_tx_epoch: HashMap::new(),
+            last_seat_price_epoch: None,
+            key_swap: None,
+            wait_period: Duration::from_secs(cli.wait_period),
+            last_check: None,
+        })
+    }
+}
+
+impl<R: ChainRpc> ValidatorOps<R> {
+    /// Time to wait before the next `tick`.
+    pub(crate) fn poll_interval(&self) -> Duration {
+        if self.key_swap.is_some() {
+            self.wait_period.min(KEY_SWAP_POLL_INTERVAL)
+        } else {
+            self.wait_period
+        }
+    }
+
+    /// Swaps the validator key if the node switched to the epoch it was
+    /// scheduled for, and runs all the checks once per wait period.
+    pub(crate) async fn tick(&mut self) -> anyhow::Result<()> {
+        let sync_info = self.rpc.sync_info().await?;
+        if sync_info.syncing {
+            info!(target: "restaked", "Node is syncing, skipping the checks");
+            return Ok(());
+        }
+        self.check_key_swap(&sync_info)?;
+        if self.last_check.is_some_and(|last_check| last_check.elapsed() < self.wait_period) {
+            return Ok(());
+        }
+        self.last_check = Some(Instant::now());
+
+        let block_hash = sync_info.latest_block_hash;
+        let height = sync_info.latest_block_height;
+        let validators = self.rpc.validators().await?;
+
+        if let Some(stake) = current_validator(&validators, &self.account_id).map(|v| v.stake) {
+            self.last_stake_amount = stake;
+        }
+        if self.key_rotation.is_some() {
+            self.check_key_rotation(&validators, block_hash).await?;
+        }
+        self.check_restake(&validators, block_hash).await?;
+        self.check_seat_price(&validators).await?;
+        self.check_maintenance(validators.epoch_height, height).await?;
+        Ok(())
+    }
+
+    fn stake(&self) -> Balance {
+        if self.stake_amount == 0 { self.last_stake_amount } else { self.stake_amount }
+    }
+
+    /// Sends a transaction unless one of the same kind was already sent in
+    /// this epoch. A failed send is retried on the next check.
+    async fn send_once_per_epoch(
+        &mut self,
+        kind: TxKind,
+        epoch_height: EpochHeight,
+        receiver_id: AccountId,
+        action: near_primitives::action::Action,
+        block_hash: CryptoHash,
+    ) -> anyhow::Result<()> {
+        if self.last_tx_epoch.get(&kind) == Some(&epoch_height) {
+            return Ok(());
+        }
+        let tx_hash =
+            self.rpc.sign_and_send(&self.signer, receiver_id, vec![action], block_hash).await?;
+        self.last_tx_epoch.insert(kind, epoch_height);
+        info!(target: "restaked", %tx_hash, "Transaction executed");
+        Ok(())
+    }
+
+    async fn check_restake(
+        &mut self,
+        validators: &EpochValidatorInfo,
+        block_hash: CryptoHash,
+    ) -> anyhow::Result<()> {
+        let epoch_height = validators.epoch_height;
+        if let Some(pool) = self.staking_pool.clone() {
+            // The pool restakes and distributes rewards on `ping`, it is cheap
+            // enough to do it once every epoch.
+            info!(target: "restaked", %pool, epoch_height, "Pinging the staking pool");
+            let action = restake::staking_pool_call("ping", serde_json::json!({}));
+            return self
+                .send_once_per_epoch(TxKind::Restake, epoch_height, pool, action, block_hash)
+                .await;
+        }
+        if !needs_restake(validators, &self.account_id) {
+            return Ok(());
+        }
+        let amount = self.stake();
+        if amount == 0 {
+            warn!(target: "restaked", "Not a validator and no --stake-amount given, can't restake");
+            return Ok(());
+        }
+        // Already kicked out or getting kicked out.
+        let public_key = self.staking_key()?;
+        info!(
+            target: "restaked",
+            "Sending staking transaction {} -> {}", self.account_id, amount
+        );
+        let action = restake::stake_action(public_key, amount);
+        let account_id = self.account_id.clone();
+        self.send_once_per_epoch(TxKind::Restake, epoch_height, account_id, action, block_hash)
+            .await
+    }
+
+    /// Key to propose with the stake: the pending key while a rotation is
+    /// ongoing, as proposing the old key would revert the rotation.
+    fn staking_key(&self) -> anyhow::Result<PublicKey> {
+        let pending = match &self.key_rotation {
+            Some(key_rotation) => key_rotation.pending_key()?,
+            None => None,
+        };
+        Ok(pending.map_or_else(|| self.public_key.clone(), |key_file| key_file.public_key))
+    }
+
+    async fn check_key_rotation(
+        &mut self,
+        validators: &EpochValidatorInfo,
+        block_hash: CryptoHash,
+    ) -> anyhow::Result<()> {
+        let epoch_height = validators.epoch_height;
+        let key_rotation = self.key_rotation.as_mut().unwrap();
+        let Some(pending) = key_rotation.pending_key()? else {
+            if key_rotation.is_due(epoch_height)? {
+                key_rotation.start(self.account_id.clone())?;
+            }
+            return Ok(());
+        };
+
+        let current = current_validator(validators, &self.account_id);
+        let next = validators.next_validators.iter().find(|v| v.account_id == self.account_id);
+        let next_has_key = next.is_some_and(|next| next.public_key == pending.public_key);
+        let swap_now = match current {
+            Some(current) => current.public_key == pending.public_key,
+            // Not validating right now, the key can be swapped right away.
+            None => next_has_key,
+        };
+        if swap_now {
+            key_rotation.complete(epoch_height)?;
+            self.public_key = pending.public_key;
+            self.key_swap = None;
+            return Ok(());
+        }
+        if next_has_key {
+            // The node has to keep signing with the old key until the end of
+            // the epoch, the key is swapped once the node is past it.
+            if self.key_swap.is_none() {
+                info!(
+                    target: "restaked",
+                    epoch_height, "Scheduled the validator key swap for the epoch switch"
+                );
+            }
+            self.key_swap =
+                Some(KeySwap { epoch_height, epoch_start_height: validators.epoch_start_height });
+            return Ok(());
+        }
+
+        let proposed = validators.current_proposals.iter().any(|proposal| {
+            proposal.account_id() == &self.account_id
+                && proposal.clone().into_validator_stake().public_key() == &pending.public_key
+        });
+        if proposed {
+            return Ok(());
+        }
+        info!(target: "restaked", public_key = %pending.public_key, "Proposing the next validator key");
+        let (receiver_id, action) = match self.staking_pool.clone() {
+            Some(pool) => {
+                let args = serde_json::json!({ "stake_public_key": pending.public_key });
+                (pool, restake::staking_pool_call("update_staking_key", args))
+            }
+            None => {
+                (self.account_id.clone(), restake::stake_action(pending.public_key, self.stake()))
+            }
+        };
+        self.send_once_per_epoch(TxKind::KeyProposal, epoch_height, receiver_id, action, block_hash)
+            .await
+    }
+
+    /// Swaps the validator key once the node is in the epoch in which the
+    /// pending key is the validator key.
+    fn check_key_swap(&mut self, sync_info: &StatusSyncInfo) -> anyhow::Result<()> {
+        let Some(key_swap) = &self.key_swap else {
+            return Ok(());
+        };
+        match sync_info.epoch_start_height {
+            Some(epoch_start_height) if epoch_start_height > key_swap.epoch_start_height => {}
+            _ => return Ok(()),
+        }
+        let epoch_height = key_swap.epoch_height + 1;
+        self.key_swap = None;
+        let key_rotation = self.key_rotation.as_mut().unwrap();
+        let Some(pending) = key_rotation.pending_key()? else {
+            return Ok(());
+        };
+        key_rotation.complete(epoch_height)?;
+        self.public_key = pending.public_key;
+        Ok(())
+    }
+
+    async fn check_seat_price(&mut self, validators: &EpochValidatorInfo) -> anyhow::Result<()> {
+        let epoch_height = validators.epoch_height;
+        if self.last_seat_price_epoch == Some(epoch_height) {
+            return Ok(());
+        }
+        self.last_seat_price_epoch = Some(epoch_height);
+        let ordered = self.rpc.validators_ordered().await?;
+        let Some(seat_price) = SeatPrice::estimate(ordered, &self.account_id) else {
+            return Ok(());
+        };
+        let stake = validators
+            .next_validators
+            .iter()
+            .find(|v| v.account_id == self.account_id)
+            .map_or_else(|| self.stake(), |v| v.stake);
+        match seat_price.position(stake, self.seat_price_margin_percent) {
+            SeatPosition::Safe => info!(
+                target: "restaked",
+                seat_price = seat_price.price, stake, rank = ?seat_price.rank,
+                num_seats = seat_price.num_seats, "Stake is above the seat price"
+            ),
+            SeatPosition::AtRisk => warn!(
+                target: "restaked",
+                seat_price = seat_price.price, stake, rank = ?seat_price.rank,
+                num_seats = seat_price.num_seats, "Stake is close to the seat price"
+            ),
+            SeatPosition::Below => warn!(
+                target: "restaked",
+                seat_price = seat_price.price, stake, num_seats = seat_price.num_seats,
+                "Stake is below the seat price, the validator won't get a seat"
+            ),
+        }
+        Ok(())
+    }
+
+    async fn check_maintenance(
+        &mut self,
+        epoch_height: EpochHeight,
+        height: BlockHeight,
+    ) -> anyhow::Result<()> {
+        if self.maintenance.is_stale(epoch_height) {
+            let windows = self.rpc.maintenance_windows(self.account_id.clone()).await?;
+            self.maintenance.update(epoch_height, windows);
+        }
+        let Some(command) = &self.maintenance_command else {
+            return Ok(());
+        };
+        if let Some(window) = self.maintenance.window_to_start(height, self.min_maintenance_blocks)
+        {
+            maintenance::run_command(command, window);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_crypto::{KeyType, SecretKey};
+    use near_primitives::action::Action;
+    use near_primitives::views::validator_stake_view::ValidatorStakeView;
+    use near_primitives::views::{CurrentEpochValidatorInfo, NextEpochValidatorInfo};
+    use std::cell::{Cell, RefCell};
+    use std::path::{Path, PathBuf};
+
+    const STAKE: Balance = 1_000;
+
+    struct MockRpc {
+        height: BlockHeight,
+        epoch_start_height: BlockHeight,
+        validators: Option<EpochValidatorInfo>,
+        /// Receivers and actions of the sent transactions.
+        sent: RefCell<Vec<(AccountId, Action)>>,
+        /// If set, sending the transactions fails.
+        fail_sends: Cell<bool>,
+    }
+
+    impl ChainRpc for MockRpc {
+        async fn sync_info(&self) -> anyhow::Result<StatusSyncInfo> {
+            Ok(StatusSyncInfo {
+                latest_block_hash: CryptoHash::default(),
+                latest_block_height: self.height,
+                latest_state_root: CryptoHash::default(),
+                latest_block_time: near_time::Utc::UNIX_EPOCH,
+                syncing: false,
+                earliest_block_hash: None,
+                earliest_block_height: None,
+                earliest_block_time: None,
+                epoch_id: None,
+                epoch_start_height: Some(self.epoch_start_height),
+            })
+        }
+
+        async fn validators(&self) -> anyhow::Result<EpochValidatorInfo> {
+            Ok(self.validators.clone().unwrap())
+        }
+
+        async fn validators_ordered(&self) -> anyhow::Result<Vec<ValidatorStakeView>> {
+            Ok(vec![])
+        }
+
+        async fn maintenance_windows(
+            &self,
+            _account_id: AccountId,
+        ) -> anyhow::Result<Vec<(BlockHeight, BlockHeight)>> {
+            Ok(vec![])
+        }
+
+        async fn sign_and_send(
+            &self,
+            _signer: &Signer,
+            receiver_id: AccountId,
+            actions: Vec<Action>,
+            _block_hash: CryptoHash,
+        ) -> anyhow::Result<CryptoHash> {
+            if self.fail_sends.get() {
+                anyhow::bail!("failed to send the transaction");
+            }
+            let mut sent = self.sent.borrow_mut();
+            sent.extend(actions.into_iter().map(|action| (receiver_id.clone(), action)));
+            Ok(CryptoHash::default())
+        }
+    }
+
+    fn validator_id() -> AccountId {
+        "validator".parse().unwrap()
+    }
+
+    /// Validator set in which the validator has `current_key` in the current
+    /// epoch and `next_key` in the next one.
+    fn validators(
+        epoch_height: EpochHeight,
+        epoch_start_height: BlockHeight,
+        current_key: PublicKey,
+        next_key: PublicKey,
+    ) -> EpochValidatorInfo {
+        EpochValidatorInfo {
+            current_validators: vec![CurrentEpochValidatorInfo {
+                account_id: validator_id(),
+                public_key: current_key,
+                is_slashed: false,
+                stake: STAKE,
+                shards_produced: vec![],
+                num_produced_blocks: 0,
+                num_expected_blocks: 0,
+                num_produced_chunks: 0,
+                num_expected_chunks: 0,
+                num_produced_chunks_per_shard: vec![],
+                num_expected_chunks_per_shard: vec![],
+                num_produced_endorsements: 0,
+                num_expected_endorsements: 0,
+                num_produced_endorsements_per_shard: vec![],
+                num_expected_endorsements_per_shard: vec![],
+                shards_endorsed: vec![],
+            }],
+            next_validators: vec![NextEpochValidatorInfo {
+                account_id: validator_id(),
+                public_key: next_key,
+                stake: STAKE,
+                shards: vec![],
+            }],
+            current_fishermen: vec![],
+            next_fishermen: vec![],
+            current_proposals: vec![],
+            prev_epoch_kickout: vec![],
+            epoch_start_height,
+            epoch_height,
+        }
+    }
+
+    /// Validator ops with key rotation enabled and the validator key stored in
+    /// `home`.
+    fn test_ops(home: &Path, staking_pool: Option<AccountId>) -> ValidatorOps<MockRpc> {
+        let secret_key = SecretKey::from_random(KeyType::ED25519);
+        let public_key = secret_key.public_key();
+        let key_file =
+            KeyFile { account_id: validator_id(), public_key: public_key.clone(), secret_key };
+        key_file.write_to_file(&key_path(home)).unwrap();
+        let key_rotation = KeyRotation::new(home, key_path(home), 1, None).unwrap();
+        ValidatorOps {
+            rpc: MockRpc {
+                height: 0,
+                epoch_start_height: 0,
+                validators: None,
+                sent: RefCell::new(vec![]),
+                fail_sends: Cell::new(false),
+            },
+            account_id: validator_id(),
+            public_key,
+            signer: InMemorySigner::test_signer(&"owner".parse().unwrap()),
+            staking_pool,
+            stake_amount: STAKE,
+            last_stake_amount: STAKE,
+            seat_price_margin_percent: 10,
+            key_rotation: Some(key_rotation),
+            maintenance: MaintenanceSchedule::default(),
+            maintenance_command: None,
+            min_maintenance_blocks: 100,
+            last_tx_epoch: HashMap::new(),
+            last_seat_price_epoch: None,
+            key_swap: None,
+            wait_period: Duration::ZERO,
+            last_check: None,
+        }
+    }
+
+    fn key_path(home: &Path) -> PathBuf {
+        home.join("validator_key.json")
+    }
+
+    fn tick(ops: &mut ValidatorOps<MockRpc>) {
+        actix::System::new().block_on(ops.tick()).unwrap();
+    }
+
+    /// Methods called by the sent transactions since the last call.
+    fn take_sent(ops: &ValidatorOps<MockRpc>) -> Vec<(String, String)> {
+        let sent = std::mem::take(&mut *ops.rpc.sent.borrow_mut());
+        sent.into_iter()
+            .map(|(receiver_id, action)| match action {
+                Action::FunctionCall(call) => (receiver_id.to_string(), call.method_name),
+                Action::Stake(_) => (receiver_id.to_string(), "stake".to_string()),
+                action => panic!("unexpected action {action:?}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_key_swapped_at_epoch_switch() {
+        let home = tempfile::tempdir().unwrap();
+        let mut ops = test_ops(home.path(), None);
+        ops.wait_period = Duration::from_secs(60);
+        let old_key = ops.public_key.clone();
+        let new_key = ops.key_rotation.as_ref().unwrap().start(validator_id()).unwrap();
+
+        // The next validator set has the new key, the swap waits for the epoch switch.
+        ops.rpc.height = 150;
+        ops.rpc.epoch_start_height = 100;
+        ops.rpc.validators = Some(validators(5, 100, old_key.clone(), new_key.clone()));
+        tick(&mut ops);
+        assert_eq!(ops.poll_interval(), KEY_SWAP_POLL_INTERVAL);
+        ops.rpc.height = 199;
+        tick(&mut ops);
+        assert_eq!(KeyFile::from_file(&key_path(home.path())).unwrap().public_key, old_key);
+        assert_eq!(ops.public_key, old_key);
+
+        // The first block of the next epoch.
+        ops.rpc.height = 200;
+        ops.rpc.epoch_start_height = 200;
+        tick(&mut ops);
+        assert_eq!(KeyFile::from_file(&key_path(home.path())).unwrap().public_key, new_key);
+        assert_eq!(ops.public_key, new_key);
+        assert!(ops.key_rotation.as_ref().unwrap().pending_key().unwrap().is_none());
+        assert_eq!(ops.poll_interval(), Duration::from_secs(60));
+        assert_eq!(take_sent(&ops), vec![]);
+    }
+
+    #[test]
+    fn test_key_proposal_and_ping_in_same_epoch() {
+        let home = tempfile::tempdir().unwrap();
+        let mut ops = test_ops(home.path(), Some("pool".parse().unwrap()));
+        let old_key = ops.public_key.clone();
+        ops.key_rotation.as_ref().unwrap().start(validator_id()).unwrap();
+
+        ops.rpc.validators = Some(validators(5, 100, old_key.clone(), old_key.clone()));
+        tick(&mut ops);
+        let expected = vec![
+            ("pool".to_string(), "update_staking_key".to_string()),
+            ("pool".to_string(), "ping".to_string()),
+        ];
+        assert_eq!(take_sent(&ops), expected);
+        // At most one transaction of each kind per epoch.
+        tick(&mut ops);
+        assert_eq!(take_sent(&ops), vec![]);
+
+        ops.rpc.validators = Some(validators(6, 200, old_key.clone(), old_key));
+        tick(&mut ops);
+        assert_eq!(take_sent(&ops), expected);
+    }
+
+    #[test]
+    fn test_failed_ping_retried_in_same_epoch() {
+        let home = tempfile::tempdir().unwrap();
+        let mut ops = test_ops(home.path(), Some("pool".parse().unwrap()));
+        let key = ops.public_key.clone();
+        ops.rpc.validators = Some(validators(5, 100, key.clone(), key));
+
+        ops.rpc.fail_sends.set(true);
+        assert!(actix::System::new().block_on(ops.tick()).is_err());
+        assert_eq!(take_sent(&ops), vec![]);
+
+        ops.rpc.fail_sends.set(false);
+        tick(&mut ops);
+        assert_eq!(take_sent(&ops), vec![("pool".to_string(), "ping".to_string())]);
+    }
+}
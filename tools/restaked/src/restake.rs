@@ -0,0 +1,57 @@
+use near_chain_configs::BLOCK_PRODUCER_KICKOUT_THRESHOLD;
+use near_crypto::PublicKey;
+use near_primitives::action::{Action, FunctionCallAction, StakeAction};
+use near_primitives::types::{AccountId, Balance, Gas};
+use near_primitives::views::{CurrentEpochValidatorInfo, EpochValidatorInfo};
+
+/// Gas attached to staking pool calls.
+const STAKING_POOL_GAS: Gas = 50_000_000_000_000;
+
+/// Returns true if given validator might get kicked out.
+pub(crate) fn maybe_kicked_out(validator_info: &CurrentEpochValidatorInfo) -> bool {
+    validator_info.num_produced_blocks * 100
+        < validator_info.num_expected_blocks * u64::from(BLOCK_PRODUCER_KICKOUT_THRESHOLD)
+}
+
+/// Returns true if the validator is already kicked out or is getting kicked
+/// out and has no pending proposal which would bring it back.
+pub(crate) fn needs_restake(validators: &EpochValidatorInfo, account_id: &AccountId) -> bool {
+    // Check:
+    //  - don't already have a proposal
+    //  - too many missing blocks in current validators
+    //  - missing in next validators
+    if validators.current_proposals.iter().any(|proposal| proposal.account_id() == account_id) {
+        return false;
+    }
+    current_validator(validators, account_id).is_some_and(maybe_kicked_out)
+        || !validators
+            .next_validators
+            .iter()
+            .any(|validator_info| &validator_info.account_id == account_id)
+}
+
+pub(crate) fn current_validator<'a>(
+    validators: &'a EpochValidatorInfo,
+    account_id: &AccountId,
+) -> Option<&'a CurrentEpochValidatorInfo> {
+    validators
+        .current_validators
+        .iter()
+        .filter(|validator_info| &validator_info.account_id == account_id)
+        .next_back()
+}
+
+/// Action sending a stake proposal from the validator account itself.
+pub(crate) fn stake_action(public_key: PublicKey, stake: Balance) -> Action {
+    Action::Stake(Box::new(StakeAction { stake, public_key }))
+}
+
+/// Call of a staking pool method with the given JSON arguments.
+pub(crate) fn staking_pool_call(method_name: &str, args: serde_json::Value) -> Action {
+    Action::FunctionCall(Box::new(FunctionCallAction {
+        method_name: method_name.to_string(),
+        args: args.to_string().into_bytes(),
+        gas: STAKING_POOL_GAS,
+        deposit: 0,
+    }))
+}
@@ -0,0 +1,129 @@
+use anyhow::Context;
+use near_crypto::{PublicKey, Signer};
+use near_jsonrpc_client_internal::JsonRpcClient;
+use near_jsonrpc_primitives::types::maintenance::RpcMaintenanceWindowsRequest;
+use near_jsonrpc_primitives::types::query::{QueryResponseKind, RpcQueryRequest};
+use near_jsonrpc_primitives::types::validator::RpcValidatorsOrderedRequest;
+use near_primitives::action::Action;
+use near_primitives::hash::CryptoHash;
+use near_primitives::transaction::{SignedTransaction, Transaction, TransactionV0};
+use near_primitives::types::{AccountId, BlockHeight, BlockReference, Finality};
+use near_primitives::views::validator_stake_view::ValidatorStakeView;
+use near_primitives::views::{EpochValidatorInfo, QueryRequest, StatusSyncInfo, TxExecutionStatus};
+
+/// Node and chain queries the daemon makes. Implemented over JSON RPC by
+/// `OpsRpc`, tests use a mock.
+pub(crate) trait ChainRpc {
+    async fn sync_info(&self) -> anyhow::Result<StatusSyncInfo>;
+
+    async fn validators(&self) -> anyhow::Result<EpochValidatorInfo>;
+
+    async fn validators_ordered(&self) -> anyhow::Result<Vec<ValidatorStakeView>>;
+
+    async fn maintenance_windows(
+        &self,
+        account_id: AccountId,
+    ) -> anyhow::Result<Vec<(BlockHeight, BlockHeight)>>;
+
+    /// Signs a transaction with the given actions and waits until it is
+    /// executed.
+    async fn sign_and_send(
+        &self,
+        signer: &Signer,
+        receiver_id: AccountId,
+        actions: Vec<Action>,
+        block_hash: CryptoHash,
+    ) -> anyhow::Result<CryptoHash>;
+}
+
+/// Thin wrapper around the JSON RPC client with the calls the daemon needs.
+pub(crate) struct OpsRpc {
+    client: JsonRpcClient,
+}
+
+impl OpsRpc {
+    pub(crate) fn new(rpc_url: &str) -> Self {
+        Self { client: near_jsonrpc_client_internal::new_client(rpc_url) }
+    }
+
+    async fn access_key_nonce(
+        &self,
+        account_id: AccountId,
+        public_key: PublicKey,
+    ) -> anyhow::Result<u64> {
+        // Transactions are only awaited until they are executed, the next one
+        // sent right after has to see the nonce at the optimistic head.
+        let request = RpcQueryRequest {
+            block_reference: BlockReference::Finality(Finality::None),
+            request: QueryRequest::ViewAccessKey { account_id, public_key },
+        };
+        let response = self
+            .client
+            .query(request)
+            .await
+            .map_err(|err| anyhow::anyhow!("query access key: {:?}", err))?;
+        match response.kind {
+            QueryResponseKind::AccessKey(access_key) => Ok(access_key.nonce),
+            kind => anyhow::bail!("unexpected response for access key query: {:?}", kind),
+        }
+    }
+}
+
+impl ChainRpc for OpsRpc {
+    async fn sync_info(&self) -> anyhow::Result<StatusSyncInfo> {
+        let status =
+            self.client.status().await.map_err(|err| anyhow::anyhow!("status: {:?}", err))?;
+        Ok(status.sync_info)
+    }
+
+    async fn validators(&self) -> anyhow::Result<EpochValidatorInfo> {
+        self.client.validators(None).await.map_err(|err| anyhow::anyhow!("validators: {:?}", err))
+    }
+
+    async fn validators_ordered(&self) -> anyhow::Result<Vec<ValidatorStakeView>> {
+        self.client
+            .EXPERIMENTAL_validators_ordered(RpcValidatorsOrderedRequest { block_id: None })
+            .await
+            .map_err(|err| anyhow::anyhow!("EXPERIMENTAL_validators_ordered: {:?}", err))
+    }
+
+    async fn maintenance_windows(
+        &self,
+        account_id: AccountId,
+    ) -> anyhow::Result<Vec<(BlockHeight, BlockHeight)>> {
+        self.client
+            .EXPERIMENTAL_maintenance_windows(RpcMaintenanceWindowsRequest { account_id })
+            .await
+            .map_err(|err| anyhow::anyhow!("EXPERIMENTAL_maintenance_windows: {:?}", err))
+    }
+
+    async fn sign_and_send(
+        &self,
+        signer: &Signer,
+        receiver_id: AccountId,
+        actions: Vec<Action>,
+        block_hash: CryptoHash,
+    ) -> anyhow::Result<CryptoHash> {
+        let signer_id = signer.get_account_id();
+        let public_key = signer.public_key();
+        let nonce = self
+            .access_key_nonce(signer_id.clone(), public_key.clone())
+            .await
+            .with_context(|| format!("can't fetch nonce for {}", signer_id))?;
+        let transaction = Transaction::V0(TransactionV0 {
+            signer_id,
+            public_key,
+            nonce: nonce + 1,
+            receiver_id,
+            block_hash,
+            actions,
+        });
+        let (hash, _) = transaction.get_hash_and_size();
+        let signed_transaction = SignedTransaction::new(signer.sign(hash.as_ref()), transaction);
+        self.client
+            .send_tx(signed_transaction, TxExecutionStatus::ExecutedOptimistic)
+            .await
+            .map_err(|err| anyhow::anyhow!("send_tx: {:?}", err))?;
+        Ok(hash)
+    }
+}
@@ -0,0 +1,86 @@
+use near_primitives::types::{AccountId, Balance};
+use near_primitives::views::validator_stake_view::ValidatorStakeView;
+
+/// Where the validator stands relative to the seat price.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum SeatPosition {
+    /// Comfortably above the seat price.
+    Safe,
+    /// Above the seat price, but within the configured margin.
+    AtRisk,
+    /// Below the seat price.
+    Below,
+}
+
+/// Seat price estimate derived from `EXPERIMENTAL_validators_ordered`.
+#[derive(Debug)]
+pub(crate) struct SeatPrice {
+    /// Smallest stake which still got a block producer seat.
+    pub price: Balance,
+    /// Number of seated block producers.
+    pub num_seats: usize,
+    /// Rank of the validator in the ordered list, if it has a seat.
+    pub rank: Option<usize>,
+}
+
+impl SeatPrice {
+    /// Estimates the seat price as the lowest stake among the seated block
+    /// producers. Returns `None` if the list is empty.
+    pub(crate) fn estimate(
+        ordered: Vec<ValidatorStakeView>,
+        account_id: &AccountId,
+    ) -> Option<Self> {
+        let stakes: Vec<(AccountId, Balance)> = ordered
+            .into_iter()
+            .map(|view| {
+                let stake = view.into_validator_stake();
+                (stake.account_id().clone(), stake.stake())
+            })
+            .collect();
+        let price = stakes.iter().map(|(_, stake)| *stake).min()?;
+        let rank = stakes.iter().position(|(id, _)| id == account_id);
+        Some(Self { price, num_seats: stakes.len(), rank })
+    }
+
+    /// Classifies `stake` against the seat price, treating stakes less than
+    /// `margin_percent` above the price as at risk.
+    pub(crate) fn position(&self, stake: Balance, margin_percent: u8) -> SeatPosition {
+        if stake < self.price {
+            return SeatPosition::Below;
+        }
+        let margin = self.price / 100 * Balance::from(margin_percent);
+        if stake < self.price.saturating_add(margin) {
+            SeatPosition::AtRisk
+        } else {
+            SeatPosition::Safe
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_crypto::{KeyType, PublicKey};
+    use near_primitives::types::validator_stake::ValidatorStake;
+
+    fn view(account_id: &str, stake: Balance) -> ValidatorStakeView {
+        ValidatorStake::new(account_id.parse().unwrap(), PublicKey::empty(KeyType::ED25519), stake)
+            .into()
+    }
+
+    #[test]
+    fn test_seat_price_position() {
+        let me: AccountId = "me".parse().unwrap();
+        let seat_price =
+            SeatPrice::estimate(vec![view("a", 3_000), view("me", 1_050), view("b", 1_000)], &me)
+                .unwrap();
+        assert_eq!(seat_price.price, 1_000);
+        assert_eq!(seat_price.num_seats, 3);
+        assert_eq!(seat_price.rank, Some(1));
+        assert_eq!(seat_price.position(1_050, 10), SeatPosition::AtRisk);
+        assert_eq!(seat_price.position(1_200, 10), SeatPosition::Safe);
+        assert_eq!(seat_price.position(900, 10), SeatPosition::Below);
+
+        assert!(SeatPrice::estimate(vec![], &me).is_none());
+    }
+}
@@ -178,7 +178,12 @@ fn apply_block_from_range(
             for tx in chunk.to_transactions() {
                 for action in tx.transaction.actions() {
                     has_contracts = has_contracts
-                        || matches!(action, Action::FunctionCall(_) | Action::DeployContract(_));
+                        || matches!(
+                            action,
+                            Action::FunctionCall(_)
+                                | Action::DeployContract(_)
+                                | Action::UpgradeContract(_)
+                        );
                 }
             }
             if !has_contracts {
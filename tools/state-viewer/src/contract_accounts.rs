@@ -28,6 +28,8 @@ pub(crate) struct ContractAccountSummary {
 /// streaming, not all fields are available.
 pub(crate) struct ContractAccount {
     pub(crate) account_id: AccountId,
+    /// Hash of the deployed WASM code, always available.
+    pub(crate) code_hash: CryptoHash,
     pub(crate) info: ContractInfo,
 }
 
@@ -138,6 +140,7 @@ pub(crate) enum ActionType {
     Delegate,
     DeployGlobalContract,
     UseGlobalContract,
+    UpgradeContract,
 }
 
 impl ContractAccount {
@@ -186,6 +189,7 @@ impl ContractAccount {
         };
         Ok(Self {
             account_id,
+            code_hash: value_hash,
             info: ContractInfo { code_size: code.map(|bytes| bytes.len()), ..Default::default() },
         })
     }
@@ -348,6 +352,7 @@ fn try_find_actions_spawned_by_receipt(
                                         ActionType::DeployGlobalContract
                                     }
                                     Action::UseGlobalContract(_) => ActionType::UseGlobalContract,
+                                    Action::UpgradeContract(_) => ActionType::UpgradeContract,
                                 };
                                 entry
                                     .actions
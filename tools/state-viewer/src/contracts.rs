@@ -0,0 +1,259 @@
+//! Commands to populate, export and import the compiled contract cache of a
+//! node ahead of time.
+//!
+//! A node normally compiles a contract only when it is deployed or called for
+//! the first time. A node that has just synced state therefore spends a lot of
+//! time compiling contracts when it starts processing chunks. These commands
+//! allow compiling all contracts in state before starting the node, or copying
+//! the compiled artifacts over from another node running the same VM.
+
+use crate::contract_accounts::{ContractAccount, ContractAccountFilter};
+use crate::util::load_trie;
+use anyhow::Context;
+use near_chain::types::RuntimeAdapter;
+use near_chain_configs::GenesisValidationMode;
+use near_epoch_manager::EpochManagerAdapter;
+use near_primitives::hash::CryptoHash;
+use near_primitives::shard_layout::ShardUId;
+use near_primitives::types::StateRoot;
+use near_store::adapter::StoreAdapter;
+use near_store::trie::AccessOptions;
+use near_store::{Mode, NodeStorage, Store, Trie, TrieDBStorage};
+use near_vm_runner::{ContractCode, ContractPrecompilatonResult, ContractRuntimeCache};
+use nearcore::load_config;
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Number of contracts loaded into memory and compiled at once.
+const PRECOMPILE_BATCH_SIZE: usize = 256;
+
+#[derive(clap::Parser)]
+pub struct ContractsCommand {
+    #[clap(subcommand)]
+    subcmd: ContractsSubCommand,
+}
+
+#[derive(clap::Subcommand)]
+#[clap(subcommand_required = true, arg_required_else_help = true)]
+enum ContractsSubCommand {
+    /// Compile every contract deployed in the current state and store the
+    /// result in the compiled contract cache.
+    Precompile(PrecompileCmd),
+    /// Write the compiled artifacts of all contracts deployed in the current
+    /// state into a file.
+    ExportCache(ExportCacheCmd),
+    /// Load compiled artifacts written by `export-cache` into the compiled
+    /// contract cache. The archive must have been produced by a node running
+    /// the same VM kind, VM version and VM configuration.
+    ImportCache(ImportCacheCmd),
+}
+
+#[derive(clap::Parser)]
+struct PrecompileCmd {
+    /// Number of threads to compile contracts with. Defaults to the number of
+    /// available CPUs.
+    #[clap(long)]
+    threads: Option<usize>,
+}
+
+#[derive(clap::Parser)]
+struct ExportCacheCmd {
+    /// File to write the archive to.
+    #[clap(long)]
+    output: PathBuf,
+}
+
+#[derive(clap::Parser)]
+struct ImportCacheCmd {
+    /// Archive written by `export-cache`.
+    #[clap(long)]
+    input: PathBuf,
+}
+
+impl ContractsCommand {
+    pub fn run(
+        self,
+        home_dir: &Path,
+        genesis_validation: GenesisValidationMode,
+    ) -> anyhow::Result<()> {
+        let near_config = load_config(home_dir, genesis_validation)
+            .with_context(|| format!("Error loading config from {:?}", home_dir))?;
+        let storage = NodeStorage::opener(
+            home_dir,
+            &near_config.config.store,
+            near_config.config.archival_config(),
+        )
+        .open_in_mode(Mode::ReadOnly)?;
+        let store = storage.get_hot_store();
+        let (epoch_manager, runtime, state_roots, header) =
+            load_trie(store.clone(), home_dir, &near_config);
+        let epoch_id = header.epoch_id();
+        let shard_layout = epoch_manager.get_shard_layout(epoch_id)?;
+        let protocol_version = epoch_manager.get_epoch_protocol_version(epoch_id)?;
+        let vm_config = Arc::clone(&runtime.get_runtime_config(protocol_version).wasm_config);
+        let shards = state_roots
+            .into_iter()
+            .enumerate()
+            .map(|(shard_index, state_root)| {
+                Ok((shard_layout.get_shard_uid(shard_index)?, state_root))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let contracts = StateContracts { store, shards };
+        let cache = runtime.compiled_contract_cache();
+        match self.subcmd {
+            ContractsSubCommand::Precompile(cmd) => cmd.run(&contracts, cache, vm_config),
+            ContractsSubCommand::ExportCache(cmd) => cmd.run(&contracts, cache, &vm_config),
+            ContractsSubCommand::ImportCache(cmd) => cmd.run(cache, &vm_config),
+        }
+    }
+}
+
+impl PrecompileCmd {
+    fn run(
+        self,
+        contracts: &StateContracts,
+        cache: &dyn ContractRuntimeCache,
+        vm_config: Arc<near_vm_runner::logic::Config>,
+    ) -> anyhow::Result<()> {
+        let pool =
+            rayon::ThreadPoolBuilder::new().num_threads(self.threads.unwrap_or(0)).build()?;
+        let compiled = AtomicUsize::new(0);
+        let already_cached = AtomicUsize::new(0);
+        let invalid = AtomicUsize::new(0);
+        let failed = AtomicUsize::new(0);
+        for (shard_uid, code_hashes) in contracts.code_hashes()? {
+            eprintln!("Compiling {} contracts of shard {shard_uid}", code_hashes.len());
+            let code_hashes = code_hashes.into_iter().collect::<Vec<_>>();
+            for batch in code_hashes.chunks(PRECOMPILE_BATCH_SIZE) {
+                let codes = contracts.codes(shard_uid, batch)?;
+                pool.install(|| {
+                    codes.into_par_iter().for_each(|code| {
+                        let result = near_vm_runner::precompile_contract(
+                            &code,
+                            Arc::clone(&vm_config),
+                            Some(cache),
+                        );
+                        let counter = match result {
+                            Ok(Ok(ContractPrecompilatonResult::ContractAlreadyInCache)) => {
+                                &already_cached
+                            }
+                            Ok(Ok(_)) => &compiled,
+                            // The compilation failure is cached just like a successful result,
+                            // so there is nothing to retry for such contracts.
+                            Ok(Err(_)) => &invalid,
+                            Err(err) => {
+                                eprintln!("failed to cache contract {}: {err}", code.hash());
+                                &failed
+                            }
+                        };
+                        counter.fetch_add(1, Ordering::Relaxed);
+                    })
+                });
+            }
+        }
+        println!(
+            "Compiled {} contracts, {} were already cached, {} are invalid, {} failed",
+            compiled.into_inner(),
+            already_cached.into_inner(),
+            invalid.into_inner(),
+            failed.into_inner()
+        );
+        Ok(())
+    }
+}
+
+impl ExportCacheCmd {
+    fn run(
+        self,
+        contracts: &StateContracts,
+        cache: &dyn ContractRuntimeCache,
+        vm_config: &near_vm_runner::logic::Config,
+    ) -> anyhow::Result<()> {
+        let code_hashes =
+            contracts.code_hashes()?.into_iter().flat_map(|(_, code_hashes)| code_hashes);
+        let mut writer = BufWriter::new(File::create(&self.output)?);
+        let exported =
+            near_vm_runner::export_contract_cache(cache, vm_config, code_hashes, &mut writer)?;
+        println!("Exported {exported} compiled contracts to {}", self.output.display());
+        Ok(())
+    }
+}
+
+impl ImportCacheCmd {
+    fn run(
+        self,
+        cache: &dyn ContractRuntimeCache,
+        vm_config: &near_vm_runner::logic::Config,
+    ) -> anyhow::Result<()> {
+        let mut reader = BufReader::new(File::open(&self.input)?);
+        let imported = near_vm_runner::import_contract_cache(cache, vm_config, &mut reader)
+            .with_context(|| format!("failed to import {}", self.input.display()))?;
+        println!("Imported {imported} compiled contracts from {}", self.input.display());
+        Ok(())
+    }
+}
+
+/// Contracts deployed in the state of all shards at a given block.
+struct StateContracts {
+    store: Store,
+    shards: Vec<(ShardUId, StateRoot)>,
+}
+
+impl StateContracts {
+    fn trie(&self, shard_uid: ShardUId, state_root: StateRoot) -> Trie {
+        // Use simple non-caching storage, we don't expect many duplicate lookups while iterating.
+        let storage = TrieDBStorage::new(self.store.trie_store(), shard_uid);
+        Trie::new(Arc::new(storage), state_root, None)
+    }
+
+    /// Returns the hashes of the distinct contract codes deployed in each shard.
+    ///
+    /// Code deployed in multiple shards is only listed for the first of them.
+    fn code_hashes(&self) -> anyhow::Result<Vec<(ShardUId, HashSet<CryptoHash>)>> {
+        let mut seen = HashSet::new();
+        let mut result = vec![];
+        for &(shard_uid, state_root) in &self.shards {
+            let trie = self.trie(shard_uid, state_root);
+            let mut code_hashes = HashSet::new();
+            for contract in ContractAccount::in_trie(trie, ContractAccountFilter::default())? {
+                match contract {
+                    Ok(contract) if seen.insert(contract.code_hash) => {
+                        code_hashes.insert(contract.code_hash);
+                    }
+                    Ok(_) => {}
+                    Err(err) => eprintln!("skipping contract due to {err}"),
+                }
+            }
+            result.push((shard_uid, code_hashes));
+        }
+        Ok(result)
+    }
+
+    /// Loads the contract codes with the given hashes from the state of the shard.
+    fn codes(
+        &self,
+        shard_uid: ShardUId,
+        code_hashes: &[CryptoHash],
+    ) -> anyhow::Result<Vec<ContractCode>> {
+        let (_, state_root) = self
+            .shards
+            .iter()
+            .find(|(uid, _)| *uid == shard_uid)
+            .with_context(|| format!("unknown shard {shard_uid}"))?;
+        let trie = self.trie(shard_uid, *state_root);
+        code_hashes
+            .iter()
+            .map(|code_hash| {
+                let code = trie
+                    .retrieve_value(code_hash, AccessOptions::DEFAULT)
+                    .with_context(|| format!("failed loading contract code {code_hash}"))?;
+                Ok(ContractCode::new(code, Some(*code_hash)))
+            })
+            .collect()
+    }
+}
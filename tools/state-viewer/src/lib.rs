@@ -6,6 +6,7 @@ pub mod cli;
 mod commands;
 mod congestion_control;
 mod contract_accounts;
+mod contracts;
 mod epoch_info;
 mod latest_witnesses;
 pub mod progress_reporter;
@@ -24,4 +25,5 @@ pub use apply_chunk::apply_chunk as apply_chunk_fn;
 pub use apply_chunk::apply_receipt;
 pub use apply_chunk::apply_tx;
 pub use cli::StateViewerSubCommand;
+pub use contracts::ContractsCommand;
 pub use state_dump::state_dump;